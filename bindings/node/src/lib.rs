@@ -0,0 +1,147 @@
+//! Node.js bindings for dlsite-gamebox.
+//!
+//! Results are converted to JS objects through their serde representation.
+
+use dlsite_gamebox::{
+    client::{circle::CircleQuery, ranking::RankingTerm, search::SearchProductQuery},
+    interface::genre::GenreCode,
+    library::{self, LibraryItem, WorkMetadata},
+};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+
+fn to_napi_err(e: dlsite_gamebox::DlsiteError) -> Error {
+    Error::new(Status::GenericFailure, e.to_string())
+}
+
+fn to_js<T: serde::Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+}
+
+/// Search options.
+#[napi(object)]
+pub struct SearchOptions {
+    pub keyword: Option<String>,
+    pub genre: Option<Vec<u32>>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Asynchronous DLsite client.
+#[napi(js_name = "DlsiteClient")]
+pub struct JsDlsiteClient {
+    inner: dlsite_gamebox::DlsiteClient,
+}
+
+#[napi]
+impl JsDlsiteClient {
+    #[napi(constructor)]
+    pub fn new(base_url: Option<String>) -> Self {
+        let inner = match base_url {
+            Some(url) => dlsite_gamebox::DlsiteClient::new(&url),
+            None => dlsite_gamebox::DlsiteClient::default(),
+        };
+        Self { inner }
+    }
+
+    /// Search products. Resolves to an object with `products`, `count` and `query_path`.
+    #[napi]
+    pub async fn search(&self, options: Option<SearchOptions>) -> Result<Value> {
+        let query = match options {
+            Some(o) => SearchProductQuery {
+                keyword: o.keyword,
+                genre: o
+                    .genre
+                    .map(|g| g.into_iter().map(GenreCode::from).collect()),
+                page: o.page,
+                per_page: o.per_page,
+                ..Default::default()
+            },
+            None => SearchProductQuery::default(),
+        };
+        let result = self
+            .inner
+            .search()
+            .search_product(&query)
+            .await
+            .map_err(to_napi_err)?;
+        to_js(&result)
+    }
+
+    /// Get full product information by scraping the product page.
+    #[napi]
+    pub async fn product(&self, id: String) -> Result<Value> {
+        let product = self
+            .inner
+            .product()
+            .get_all(&id)
+            .await
+            .map_err(to_napi_err)?;
+        to_js(&product)
+    }
+
+    /// Get compact product metadata using the product api.
+    #[napi]
+    pub async fn product_metadata(&self, id: String) -> Result<Value> {
+        let product = self
+            .inner
+            .product_api()
+            .get(&id)
+            .await
+            .map_err(to_napi_err)?;
+        to_js(&WorkMetadata::from(&product))
+    }
+
+    /// List products of a circle.
+    #[napi]
+    pub async fn circle(&self, id: String, page: Option<u32>) -> Result<Value> {
+        let result = self
+            .inner
+            .circle()
+            .get_circle(
+                &id,
+                &CircleQuery {
+                    page,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(to_napi_err)?;
+        to_js(&result)
+    }
+
+    /// Get a ranking. `term` is one of `day`, `week`, `month`, `year` or `total`.
+    #[napi]
+    pub async fn ranking(&self, term: Option<String>, genre: Option<u32>) -> Result<Value> {
+        let term: RankingTerm = match term {
+            Some(term) => term.parse().map_err(|_| {
+                Error::new(Status::InvalidArg, format!("Invalid term: {}", term))
+            })?,
+            None => RankingTerm::Day,
+        };
+        let entries = match genre {
+            Some(genre) => self.inner.ranking().by_genre(genre, term).await,
+            None => self.inner.ranking().get(term).await,
+        }
+        .map_err(to_napi_err)?;
+        to_js(&entries)
+    }
+
+    /// Fetch metadata of library items returned by `scan`.
+    #[napi(ts_args_type = "items: Array<{ id: string, path: string }>, concurrency?: number")]
+    pub async fn enrich(&self, items: Value, concurrency: Option<u32>) -> Result<Value> {
+        let items: Vec<LibraryItem> = serde_json::from_value(items)
+            .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+        let items =
+            library::enrich(&self.inner, items, concurrency.unwrap_or(4) as usize).await;
+        to_js(&items)
+    }
+}
+
+/// Find works in a local library directory.
+#[napi(ts_return_type = "Array<{ id: string, path: string }>")]
+pub fn scan(dir: String) -> Result<Value> {
+    let items = library::scan(&dir).map_err(|e| Error::from_reason(e.to_string()))?;
+    to_js(&items)
+}
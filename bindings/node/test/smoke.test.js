@@ -0,0 +1,29 @@
+// Smoke tests of the native module. They don't access DLsite.
+const assert = require("node:assert");
+const fs = require("node:fs");
+const os = require("node:os");
+const path = require("node:path");
+const test = require("node:test");
+
+const { DlsiteClient, scan } = require("..");
+
+test("scan finds works", () => {
+  const root = fs.mkdtempSync(path.join(os.tmpdir(), "dlsite-node-"));
+  fs.mkdirSync(path.join(root, "voice", "RJ403038 title"), { recursive: true });
+  fs.mkdirSync(path.join(root, "games"));
+  fs.writeFileSync(path.join(root, "games", "[rj01017217].zip"), "");
+
+  const items = scan(root);
+  assert.deepStrictEqual(
+    items.map((item) => item.id),
+    ["RJ01017217", "RJ403038"],
+  );
+
+  fs.rmSync(root, { recursive: true });
+  assert.throws(() => scan(root));
+});
+
+test("invalid ranking term is rejected", async () => {
+  const client = new DlsiteClient();
+  await assert.rejects(client.ranking("forever"), /Invalid term: forever/);
+});
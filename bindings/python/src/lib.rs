@@ -0,0 +1,164 @@
+//! Python bindings for dlsite-gamebox.
+//!
+//! Results are converted to Python objects through their serde representation.
+
+use dlsite_gamebox::{
+    client::{
+        circle::CircleQuery,
+        ranking::RankingTerm,
+        search::SearchProductQuery,
+    },
+    interface::genre::GenreCode,
+    library::{self, WorkMetadata},
+};
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+create_exception!(dlsite, DlsiteError, PyException);
+
+fn to_py_err(e: dlsite_gamebox::DlsiteError) -> PyErr {
+    DlsiteError::new_err(e.to_string())
+}
+
+fn to_py<T: serde::Serialize>(value: &T) -> PyResult<PyObject> {
+    Python::with_gil(|py| Ok(pythonize::pythonize(py, value)?.unbind()))
+}
+
+/// Asynchronous DLsite client.
+#[pyclass(name = "DlsiteClient")]
+struct PyDlsiteClient {
+    inner: dlsite_gamebox::DlsiteClient,
+}
+
+#[pymethods]
+impl PyDlsiteClient {
+    #[new]
+    #[pyo3(signature = (base_url = "https://www.dlsite.com/maniax"))]
+    fn new(base_url: &str) -> Self {
+        Self {
+            inner: dlsite_gamebox::DlsiteClient::new(base_url),
+        }
+    }
+
+    /// Search products. Returns a dict with `products`, `count` and `query_path`.
+    #[pyo3(signature = (keyword = None, genre = None, page = None, per_page = None))]
+    fn search<'py>(
+        &self,
+        py: Python<'py>,
+        keyword: Option<String>,
+        genre: Option<Vec<u32>>,
+        page: Option<u32>,
+        per_page: Option<u32>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let result = client
+                .search()
+                .search_product(&SearchProductQuery {
+                    keyword,
+                    genre: genre.map(|g| g.into_iter().map(GenreCode::from).collect()),
+                    page,
+                    per_page,
+                    ..Default::default()
+                })
+                .await
+                .map_err(to_py_err)?;
+            to_py(&result)
+        })
+    }
+
+    /// Get full product information by scraping the product page.
+    fn product<'py>(&self, py: Python<'py>, id: String) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let product = client.product().get_all(&id).await.map_err(to_py_err)?;
+            to_py(&product)
+        })
+    }
+
+    /// Get compact product metadata using the product api.
+    fn product_metadata<'py>(&self, py: Python<'py>, id: String) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let product = client.product_api().get(&id).await.map_err(to_py_err)?;
+            to_py(&WorkMetadata::from(&product))
+        })
+    }
+
+    /// List products of a circle.
+    #[pyo3(signature = (id, page = None))]
+    fn circle<'py>(
+        &self,
+        py: Python<'py>,
+        id: String,
+        page: Option<u32>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let result = client
+                .circle()
+                .get_circle(
+                    &id,
+                    &CircleQuery {
+                        page,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(to_py_err)?;
+            to_py(&result)
+        })
+    }
+
+    /// Get a ranking. `term` is one of `day`, `week`, `month`, `year` or `total`.
+    #[pyo3(signature = (term = "day", genre = None))]
+    fn ranking<'py>(
+        &self,
+        py: Python<'py>,
+        term: &str,
+        genre: Option<u32>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let term: RankingTerm = term
+            .parse()
+            .map_err(|_| DlsiteError::new_err(format!("Invalid term: {}", term)))?;
+        let client = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let entries = match genre {
+                Some(genre) => client.ranking().by_genre(genre, term).await,
+                None => client.ranking().get(term).await,
+            }
+            .map_err(to_py_err)?;
+            to_py(&entries)
+        })
+    }
+
+    /// Scan a library directory and fetch metadata of every work found.
+    #[pyo3(signature = (items_dir, concurrency = 4))]
+    fn enrich<'py>(
+        &self,
+        py: Python<'py>,
+        items_dir: String,
+        concurrency: usize,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let items = library::scan(&items_dir)?;
+        let client = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let items = library::enrich(&client, items, concurrency).await;
+            to_py(&items)
+        })
+    }
+}
+
+/// Find works in a local library directory.
+#[pyfunction]
+fn scan(py: Python<'_>, dir: String) -> PyResult<PyObject> {
+    let items = py.allow_threads(|| library::scan(&dir))?;
+    Ok(pythonize::pythonize(py, &items)?.unbind())
+}
+
+#[pymodule]
+fn dlsite(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDlsiteClient>()?;
+    m.add_function(wrap_pyfunction!(scan, m)?)?;
+    m.add("DlsiteError", m.py().get_type::<DlsiteError>())?;
+    Ok(())
+}
@@ -0,0 +1,27 @@
+"""Smoke tests of the extension module. They don't access DLsite."""
+
+import pytest
+
+import dlsite
+
+
+def test_scan(tmp_path):
+    (tmp_path / "voice" / "RJ403038 title").mkdir(parents=True)
+    (tmp_path / "games").mkdir()
+    (tmp_path / "games" / "[rj01017217].zip").write_bytes(b"")
+
+    items = dlsite.scan(str(tmp_path))
+
+    assert [item["id"] for item in items] == ["RJ01017217", "RJ403038"]
+
+
+def test_scan_missing_dir(tmp_path):
+    with pytest.raises(OSError):
+        dlsite.scan(str(tmp_path / "missing"))
+
+
+def test_invalid_ranking_term():
+    client = dlsite.DlsiteClient()
+    # The term is validated before the coroutine is created
+    with pytest.raises(dlsite.DlsiteError, match="Invalid term: forever"):
+        client.ranking(term="forever")
@@ -0,0 +1,237 @@
+//! UniFFI bindings for dlsite-gamebox.
+//!
+//! Only a curated subset of the crate is exported, using flat records which map cleanly to
+//! Kotlin data classes and Swift structs.
+
+use std::sync::Arc;
+
+use dlsite_gamebox::{
+    client::search::{SearchProductItem, SearchProductQuery},
+    interface::genre::GenreCode,
+    library::{self, EnrichedItem, LibraryItem, WorkMetadata},
+};
+
+uniffi::setup_scaffolding!();
+
+/// Errors returned to foreign code.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum DlsiteError {
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+    #[error("IO error: {0}")]
+    Io(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<dlsite_gamebox::DlsiteError> for DlsiteError {
+    fn from(e: dlsite_gamebox::DlsiteError) -> Self {
+        match e {
+            e if e.is_not_found() => DlsiteError::NotFound(e.to_string()),
+            dlsite_gamebox::DlsiteError::RateLimit { .. } => DlsiteError::RateLimited(e.to_string()),
+            e => DlsiteError::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for DlsiteError {
+    fn from(e: std::io::Error) -> Self {
+        DlsiteError::Io(e.to_string())
+    }
+}
+
+#[derive(uniffi::Record)]
+pub struct SearchItem {
+    pub id: String,
+    pub title: String,
+    pub circle_id: String,
+    pub circle_name: String,
+    pub work_type: String,
+    pub price: i32,
+    pub price_sale: Option<i32>,
+    pub dl_count: Option<i32>,
+    pub rating: Option<f32>,
+    pub thumbnail_url: String,
+}
+
+impl From<SearchProductItem> for SearchItem {
+    fn from(item: SearchProductItem) -> Self {
+        Self {
+            id: item.id,
+            title: item.title,
+            circle_id: item.circle_id,
+            circle_name: item.circle_name,
+            work_type: item.work_type.to_string(),
+            price: item.price_original,
+            price_sale: item.price_sale,
+            dl_count: item.dl_count,
+            rating: item.rating,
+            thumbnail_url: item.thumbnail_url,
+        }
+    }
+}
+
+#[derive(uniffi::Record)]
+pub struct SearchPage {
+    pub items: Vec<SearchItem>,
+    /// Total number of matching products
+    pub count: i32,
+}
+
+#[derive(uniffi::Record)]
+pub struct WorkInfo {
+    pub id: String,
+    pub title: String,
+    pub circle_id: Option<String>,
+    pub circle_name: String,
+    pub work_type: String,
+    pub genres: Vec<String>,
+    pub creators: Vec<String>,
+    pub price: i64,
+    pub rating: Option<f32>,
+    pub released_at: Option<String>,
+}
+
+impl From<WorkMetadata> for WorkInfo {
+    fn from(m: WorkMetadata) -> Self {
+        Self {
+            id: m.id,
+            title: m.title,
+            circle_id: m.circle_id,
+            circle_name: m.circle_name,
+            work_type: m.work_type.to_string(),
+            genres: m.genres.into_iter().map(|g| g.name).collect(),
+            creators: m.creators,
+            price: m.price,
+            rating: m.rating,
+            released_at: m.released_at,
+        }
+    }
+}
+
+#[derive(uniffi::Record)]
+pub struct LibraryEntry {
+    pub id: String,
+    pub path: String,
+}
+
+impl From<LibraryItem> for LibraryEntry {
+    fn from(item: LibraryItem) -> Self {
+        Self {
+            id: item.id,
+            path: item.path.display().to_string(),
+        }
+    }
+}
+
+#[derive(uniffi::Record)]
+pub struct EnrichedEntry {
+    pub entry: LibraryEntry,
+    pub info: Option<WorkInfo>,
+    pub error: Option<String>,
+}
+
+impl From<EnrichedItem> for EnrichedEntry {
+    fn from(item: EnrichedItem) -> Self {
+        Self {
+            entry: item.item.into(),
+            info: item.metadata.map(Into::into),
+            error: item.error,
+        }
+    }
+}
+
+/// Find works in a local library directory.
+#[uniffi::export]
+pub fn scan_library(dir: String) -> Result<Vec<LibraryEntry>, DlsiteError> {
+    Ok(library::scan(dir)?.into_iter().map(Into::into).collect())
+}
+
+/// Asynchronous DLsite client.
+#[derive(uniffi::Object)]
+pub struct DlsiteClient {
+    inner: dlsite_gamebox::DlsiteClient,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl DlsiteClient {
+    #[uniffi::constructor]
+    pub fn new(base_url: Option<String>) -> Arc<Self> {
+        let inner = match base_url {
+            Some(url) => dlsite_gamebox::DlsiteClient::new(&url),
+            None => dlsite_gamebox::DlsiteClient::default(),
+        };
+        Arc::new(Self { inner })
+    }
+
+    /// Search products by keyword and genre IDs.
+    pub async fn search(
+        &self,
+        keyword: Option<String>,
+        genres: Vec<u32>,
+        page: Option<u32>,
+    ) -> Result<SearchPage, DlsiteError> {
+        let result = self
+            .inner
+            .search()
+            .search_product(&SearchProductQuery {
+                keyword,
+                genre: (!genres.is_empty())
+                    .then(|| genres.into_iter().map(GenreCode::from).collect()),
+                page,
+                ..Default::default()
+            })
+            .await?;
+        Ok(SearchPage {
+            items: result.products.into_iter().map(Into::into).collect(),
+            count: result.count,
+        })
+    }
+
+    /// Get product metadata using the product api.
+    pub async fn product(&self, id: String) -> Result<WorkInfo, DlsiteError> {
+        let product = self.inner.product_api().get(&id).await?;
+        Ok(WorkMetadata::from(&product).into())
+    }
+
+    /// Fetch metadata of library entries found by [`scan_library`].
+    pub async fn enrich(&self, entries: Vec<LibraryEntry>) -> Vec<EnrichedEntry> {
+        let items = entries
+            .into_iter()
+            .map(|e| LibraryItem {
+                id: e.id,
+                path: e.path.into(),
+            })
+            .collect();
+        library::enrich(&self.inner, items, 4)
+            .await
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scan_library, DlsiteError};
+
+    #[test]
+    fn scan() {
+        let root = std::env::temp_dir().join(format!("dlsite-uniffi-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("voice/RJ403038 title")).unwrap();
+
+        let entries = scan_library(root.display().to_string()).unwrap();
+        let ids: Vec<_> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["RJ403038"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(matches!(
+            scan_library(root.display().to_string()),
+            Err(DlsiteError::Io(_))
+        ));
+    }
+}
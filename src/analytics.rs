@@ -0,0 +1,236 @@
+//! Offline statistics over sets of fetched works.
+//!
+//! [`genre_matrix`] counts how often genres appear together in a set of works (e.g. the
+//! products of a search, or a library enriched with [`crate::library::enrich`]) and how
+//! strongly they are correlated, for heatmaps and other visualizations. Nothing is requested
+//! from DLsite.
+//!
+//! # Example
+//! ```
+//! use dlsite_gamebox::{analytics, library::WorkMetadata};
+//!
+//! fn print_pairs(works: &[WorkMetadata]) {
+//!     let matrix = analytics::genre_matrix(works);
+//!     for (a, b, count) in matrix.pairs() {
+//!         println!(
+//!             "{} + {}: {} works (phi {:.2})",
+//!             a.name,
+//!             b.name,
+//!             count,
+//!             matrix.correlation(&a.id, &b.id).unwrap_or_default()
+//!         );
+//!     }
+//! }
+//! ```
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{client::product::Product, interface::genre::Genre, library::WorkMetadata};
+
+/// A work with genres, which can be analyzed by [`genre_matrix`].
+pub trait Genres {
+    fn genres(&self) -> &[Genre];
+}
+
+impl Genres for WorkMetadata {
+    fn genres(&self) -> &[Genre] {
+        &self.genres
+    }
+}
+
+impl Genres for Product {
+    fn genres(&self) -> &[Genre] {
+        &self.genre
+    }
+}
+
+/// Genre co-occurrence counts, see [`genre_matrix`].
+///
+/// Rows and columns follow the order of [`GenreMatrix::genres`], most frequent genre first.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GenreMatrix {
+    /// Number of analyzed works
+    pub works: usize,
+    pub genres: Vec<Genre>,
+    /// Number of works having each genre
+    pub counts: Vec<u32>,
+    /// Number of works having both genres. The diagonal equals `counts`.
+    pub co_occurrence: Vec<Vec<u32>>,
+}
+
+/// Count genre co-occurrences over `items`.
+///
+/// A genre listed twice on the same work is counted once. Ties in frequency are ordered by
+/// genre ID.
+pub fn genre_matrix<'a, T, I>(items: I) -> GenreMatrix
+where
+    T: Genres + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let mut genres: Vec<Genre> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut works_genres: Vec<Vec<usize>> = vec![];
+    for item in items {
+        let mut ids: Vec<usize> = item
+            .genres()
+            .iter()
+            .map(|genre| {
+                *index.entry(genre.id.clone()).or_insert_with(|| {
+                    genres.push(genre.clone());
+                    genres.len() - 1
+                })
+            })
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        works_genres.push(ids);
+    }
+
+    let mut counts = vec![0u32; genres.len()];
+    for ids in &works_genres {
+        for &i in ids {
+            counts[i] += 1;
+        }
+    }
+
+    // Most frequent first
+    let mut order: Vec<usize> = (0..genres.len()).collect();
+    order.sort_by(|&a, &b| {
+        counts[b]
+            .cmp(&counts[a])
+            .then_with(|| genres[a].id.cmp(&genres[b].id))
+    });
+    let mut position = vec![0; genres.len()];
+    for (pos, &i) in order.iter().enumerate() {
+        position[i] = pos;
+    }
+
+    let mut co_occurrence = vec![vec![0u32; genres.len()]; genres.len()];
+    for ids in &works_genres {
+        for &a in ids {
+            for &b in ids {
+                co_occurrence[position[a]][position[b]] += 1;
+            }
+        }
+    }
+
+    GenreMatrix {
+        works: works_genres.len(),
+        counts: order.iter().map(|&i| counts[i]).collect(),
+        genres: order.into_iter().map(|i| genres[i].clone()).collect(),
+        co_occurrence,
+    }
+}
+
+impl GenreMatrix {
+    /// Row/column of a genre ID.
+    pub fn index_of(&self, genre_id: &str) -> Option<usize> {
+        self.genres.iter().position(|g| g.id == genre_id)
+    }
+
+    /// Number of works having both genres.
+    pub fn count(&self, a: &str, b: &str) -> Option<u32> {
+        Some(self.co_occurrence[self.index_of(a)?][self.index_of(b)?])
+    }
+
+    /// Phi coefficient of two genres, between -1 (never together) and 1 (always together).
+    ///
+    /// `0.0` if one of them is on every work or on none, `None` if a genre is unknown.
+    pub fn correlation(&self, a: &str, b: &str) -> Option<f64> {
+        Some(self.correlation_at(self.index_of(a)?, self.index_of(b)?))
+    }
+
+    /// Phi coefficients of all genre pairs, in the order of [`GenreMatrix::genres`].
+    pub fn correlations(&self) -> Vec<Vec<f64>> {
+        (0..self.genres.len())
+            .map(|a| {
+                (0..self.genres.len())
+                    .map(|b| self.correlation_at(a, b))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Pairs of different genres found together, most frequent first.
+    pub fn pairs(&self) -> Vec<(&Genre, &Genre, u32)> {
+        let mut pairs = vec![];
+        for a in 0..self.genres.len() {
+            for b in (a + 1)..self.genres.len() {
+                let count = self.co_occurrence[a][b];
+                if count > 0 {
+                    pairs.push((&self.genres[a], &self.genres[b], count));
+                }
+            }
+        }
+        pairs.sort_by_key(|pair| Reverse(pair.2));
+        pairs
+    }
+
+    fn correlation_at(&self, a: usize, b: usize) -> f64 {
+        let n = self.works as f64;
+        let (na, nb) = (self.counts[a] as f64, self.counts[b] as f64);
+        let both = self.co_occurrence[a][b] as f64;
+        let denominator = (na * (n - na) * nb * (n - nb)).sqrt();
+        if denominator == 0.0 {
+            0.0
+        } else {
+            (n * both - na * nb) / denominator
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{genre_matrix, Genres};
+    use crate::interface::genre::Genre;
+
+    struct Work(Vec<Genre>);
+
+    impl Genres for Work {
+        fn genres(&self) -> &[Genre] {
+            &self.0
+        }
+    }
+
+    fn work(ids: &[&str]) -> Work {
+        Work(
+            ids.iter()
+                .map(|id| Genre {
+                    name: format!("genre {id}"),
+                    id: id.to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn matrix() {
+        let works = [
+            work(&["497", "496"]),
+            work(&["497", "496", "497"]),
+            work(&["497"]),
+            work(&["060"]),
+        ];
+        let matrix = genre_matrix(&works);
+
+        assert_eq!(matrix.works, 4);
+        let ids: Vec<&str> = matrix.genres.iter().map(|g| g.id.as_str()).collect();
+        assert_eq!(ids, vec!["497", "496", "060"]);
+        assert_eq!(matrix.counts, vec![3, 2, 1]);
+        assert_eq!(matrix.count("497", "496"), Some(2));
+        assert_eq!(matrix.count("497", "060"), Some(0));
+        assert_eq!(matrix.count("497", "999"), None);
+
+        // 496 always comes with 497, 060 never does
+        assert!(matrix.correlation("497", "496").unwrap() > 0.5);
+        assert_eq!(matrix.correlation("497", "060"), Some(-1.0));
+        assert_eq!(matrix.correlations()[0][0], 1.0);
+
+        let pairs: Vec<(&str, &str, u32)> = matrix
+            .pairs()
+            .into_iter()
+            .map(|(a, b, n)| (a.id.as_str(), b.id.as_str(), n))
+            .collect();
+        assert_eq!(pairs, vec![("497", "496", 2)]);
+    }
+}
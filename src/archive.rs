@@ -0,0 +1,234 @@
+//! Long-term archive of raw responses, to re-parse historical data with future versions of
+//! the parsers.
+//!
+//! When enabled with [`crate::DlsiteClientBuilder::archive`], every HTML/JSON body fetched by
+//! the client is written to the archive directory as a gzip-compressed
+//! [WARC](https://iipc.github.io/warc-specifications/) `resource` record, one file per
+//! response:
+//!
+//! ```text
+//! archive/
+//! └── 2024-12-01/
+//!     ├── 093012.345-www.dlsite.com_maniax_work___product_id_RJ403038.warc.gz
+//!     └── 093013.012-www.dlsite.com_maniax_product_info_ajax_product_id_RJ403038.warc.gz
+//! ```
+//!
+//! Responses served from the cache are not archived again. Records are written in the
+//! background, off the async runtime. Bulk api responses, read as they arrive, are kept in
+//! memory until fully received to be archived.
+//!
+//! # Example
+//! ```no_run
+//! use dlsite_gamebox::archive::Archive;
+//!
+//! let archive = Archive::new("./dlsite-archive");
+//! for path in archive.records().unwrap() {
+//!     let record = Archive::read(&path).unwrap();
+//!     println!("{} {} ({} bytes)", record.fetched_at, record.url, record.body.len());
+//! }
+//! ```
+
+use std::{
+    fs,
+    io::{self, BufRead as _, BufReader, Read as _, Write as _},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+/// Directory of archived responses.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+}
+
+/// A response read back from the archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedResponse {
+    pub url: String,
+    pub fetched_at: DateTime<Utc>,
+    /// `text/html` or `application/json`
+    pub content_type: String,
+    pub body: String,
+}
+
+impl Archive {
+    /// Use `dir` as archive directory. It is created when the first response is written.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The archive directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write a response fetched at `fetched_at` to the archive. Returns the path of the record.
+    pub(crate) fn write_at(&self, url: &str, body: &str, fetched_at: DateTime<Utc>) -> io::Result<PathBuf> {
+        let dir = self.dir.join(fetched_at.format("%Y-%m-%d").to_string());
+        fs::create_dir_all(&dir)?;
+        let slug: String = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(120)
+            .collect();
+        let path = dir.join(format!(
+            "{}-{}.warc.gz",
+            fetched_at.format("%H%M%S%.3f"),
+            slug
+        ));
+
+        let content_type = if body.trim_start().starts_with(['{', '[']) {
+            "application/json"
+        } else {
+            "text/html"
+        };
+        let mut encoder = GzEncoder::new(fs::File::create(&path)?, Compression::default());
+        write!(
+            encoder,
+            "WARC/1.1\r\n\
+             WARC-Type: resource\r\n\
+             WARC-Target-URI: {}\r\n\
+             WARC-Date: {}\r\n\
+             Content-Type: {}\r\n\
+             Content-Length: {}\r\n\
+             \r\n",
+            url,
+            fetched_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            content_type,
+            body.len()
+        )?;
+        encoder.write_all(body.as_bytes())?;
+        encoder.write_all(b"\r\n\r\n")?;
+        encoder.finish()?;
+
+        Ok(path)
+    }
+
+    /// Paths of all records in the archive, oldest first.
+    pub fn records(&self) -> io::Result<Vec<PathBuf>> {
+        let mut records = vec![];
+        if !self.dir.is_dir() {
+            return Ok(records);
+        }
+        for day in fs::read_dir(&self.dir)? {
+            let day = day?.path();
+            if !day.is_dir() {
+                continue;
+            }
+            for record in fs::read_dir(&day)? {
+                let record = record?.path();
+                if record.to_string_lossy().ends_with(".warc.gz") {
+                    records.push(record);
+                }
+            }
+        }
+        records.sort();
+        Ok(records)
+    }
+
+    /// Read a record written by the archive.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<ArchivedResponse> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut reader = BufReader::new(GzDecoder::new(fs::File::open(path)?));
+
+        let mut url = None;
+        let mut fetched_at = None;
+        let mut content_type = String::new();
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid("Unexpected end of WARC header"));
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(": ") else {
+                continue;
+            };
+            match name {
+                "WARC-Target-URI" => url = Some(value.to_string()),
+                "WARC-Date" => fetched_at = DateTime::parse_from_rfc3339(value).ok(),
+                "Content-Type" => content_type = value.to_string(),
+                "Content-Length" => length = value.parse::<u64>().ok(),
+                _ => {}
+            }
+        }
+
+        let length = length.ok_or_else(|| invalid("Missing Content-Length"))?;
+        let mut body = String::new();
+        reader.take(length).read_to_string(&mut body)?;
+
+        Ok(ArchivedResponse {
+            url: url.ok_or_else(|| invalid("Missing WARC-Target-URI"))?,
+            fetched_at: fetched_at
+                .ok_or_else(|| invalid("Missing WARC-Date"))?
+                .with_timezone(&Utc),
+            content_type,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::Archive;
+
+    #[test]
+    fn write_and_read() {
+        let dir = std::env::temp_dir().join(format!("dlsite-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = Archive::new(&dir);
+        assert!(archive.records().unwrap().is_empty());
+
+        let at = Utc.with_ymd_and_hms(2024, 12, 1, 9, 30, 12).unwrap();
+        let html = "<html lang=\"ja\"><body>ユウカASMR</body></html>";
+        let path = archive
+            .write_at(
+                "https://www.dlsite.com/maniax/work/=/product_id/RJ403038",
+                html,
+                at,
+            )
+            .unwrap();
+        archive
+            .write_at(
+                "https://www.dlsite.com/maniax/product/info/ajax?product_id=RJ403038",
+                "{\"RJ403038\":{}}",
+                at + chrono::Duration::seconds(1),
+            )
+            .unwrap();
+
+        let records = archive.records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], path);
+        assert!(path.starts_with(dir.join("2024-12-01")));
+
+        let record = Archive::read(&records[0]).unwrap();
+        assert_eq!(
+            record.url,
+            "https://www.dlsite.com/maniax/work/=/product_id/RJ403038"
+        );
+        assert_eq!(record.fetched_at, at);
+        assert_eq!(record.content_type, "text/html");
+        assert_eq!(record.body, html);
+
+        let record = Archive::read(&records[1]).unwrap();
+        assert_eq!(record.content_type, "application/json");
+        assert_eq!(record.body, "{\"RJ403038\":{}}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+//! Command line interface for DLsite, built on the `dlsite-gamebox` library.
+
+use std::{io::Write, path::PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use dlsite_gamebox::{
+    client::{
+        circle::CircleQuery,
+        ranking::{RankingEntry, RankingTerm},
+        search::{SearchProductItem, SearchProductQuery},
+    },
+    interface::genre::GenreCode,
+    library::{self, EnrichedItem, LibraryItem, WorkMetadata},
+    DlsiteClient,
+};
+
+#[derive(Parser)]
+#[command(name = "dlsite", version, about = "Command line interface for DLsite")]
+struct Cli {
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Format::Json, global = true)]
+    format: Format,
+
+    /// Base URL of the storefront
+    #[arg(long, default_value = "https://www.dlsite.com/maniax", global = true)]
+    base_url: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search products
+    Search {
+        /// Search keyword
+        keyword: Option<String>,
+        /// Genre IDs to filter by
+        #[arg(long)]
+        genre: Vec<GenreCode>,
+        #[arg(long)]
+        page: Option<u32>,
+        /// 30, 50 or 100
+        #[arg(long)]
+        per_page: Option<u32>,
+    },
+    /// Get product metadata
+    Product {
+        /// Product ID. Example: RJ403038
+        id: String,
+    },
+    /// List products of a circle
+    Circle {
+        /// Circle ID. Example: RG24350
+        id: String,
+        #[arg(long)]
+        page: Option<u32>,
+    },
+    /// Get a ranking
+    Ranking {
+        /// day, week, month, year or total
+        #[arg(long, default_value = "day")]
+        term: RankingTerm,
+        /// Genre ID for a per-genre ranking
+        #[arg(long)]
+        genre: Option<u32>,
+    },
+    /// Find works in a local library
+    Scan {
+        /// Library root directory
+        dir: PathBuf,
+    },
+    /// Find works in a local library and fetch their metadata
+    Enrich {
+        /// Library root directory
+        dir: PathBuf,
+        /// Maximum number of in-flight requests
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+}
+
+/// Types which can be written as CSV rows.
+trait CsvRow {
+    fn headers() -> &'static [&'static str];
+    fn row(&self) -> Vec<String>;
+}
+
+fn opt<T: ToString>(v: &Option<T>) -> String {
+    v.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl CsvRow for SearchProductItem {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "id", "title", "circle_id", "circle_name", "work_type", "price", "price_sale", "dl_count",
+            "rating",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.title.clone(),
+            self.circle_id.clone(),
+            self.circle_name.clone(),
+            self.work_type.to_string(),
+            self.price_original.to_string(),
+            opt(&self.price_sale),
+            opt(&self.dl_count),
+            opt(&self.rating),
+        ]
+    }
+}
+
+impl CsvRow for RankingEntry {
+    fn headers() -> &'static [&'static str] {
+        &["rank", "id", "title", "circle_id", "circle_name", "work_type", "price", "dl_count"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.rank.to_string(),
+            self.id.clone(),
+            self.title.clone(),
+            self.circle_id.clone(),
+            self.circle_name.clone(),
+            self.work_type.to_string(),
+            opt(&self.price),
+            opt(&self.dl_count),
+        ]
+    }
+}
+
+impl CsvRow for LibraryItem {
+    fn headers() -> &'static [&'static str] {
+        &["id", "path"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.id.clone(), self.path.display().to_string()]
+    }
+}
+
+impl CsvRow for WorkMetadata {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "id", "title", "circle_id", "circle_name", "work_type", "genres", "creators", "price",
+            "rating", "released_at",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.title.clone(),
+            opt(&self.circle_id),
+            self.circle_name.clone(),
+            self.work_type.to_string(),
+            self.genres
+                .iter()
+                .map(|g| g.name.as_str())
+                .collect::<Vec<_>>()
+                .join("|"),
+            self.creators.join("|"),
+            self.price.to_string(),
+            opt(&self.rating),
+            opt(&self.released_at),
+        ]
+    }
+}
+
+impl CsvRow for EnrichedItem {
+    fn headers() -> &'static [&'static str] {
+        &["id", "path", "title", "circle_name", "work_type", "error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let m = self.metadata.as_ref();
+        vec![
+            self.item.id.clone(),
+            self.item.path.display().to_string(),
+            m.map(|m| m.title.clone()).unwrap_or_default(),
+            m.map(|m| m.circle_name.clone()).unwrap_or_default(),
+            m.map(|m| m.work_type.to_string()).unwrap_or_default(),
+            opt(&self.error),
+        ]
+    }
+}
+
+fn output<T: serde::Serialize + CsvRow>(format: Format, items: &[T]) -> anyhow::Result<()> {
+    let stdout = std::io::stdout();
+    match format {
+        Format::Json => {
+            let mut out = stdout.lock();
+            serde_json::to_writer_pretty(&mut out, items)?;
+            writeln!(out)?;
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout.lock());
+            writer.write_record(T::headers())?;
+            for item in items {
+                writer.write_record(item.row())?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let client = DlsiteClient::new(&cli.base_url);
+
+    match cli.command {
+        Command::Search {
+            keyword,
+            genre,
+            page,
+            per_page,
+        } => {
+            let result = client
+                .search()
+                .search_product(&SearchProductQuery {
+                    keyword,
+                    genre: (!genre.is_empty()).then_some(genre),
+                    page,
+                    per_page,
+                    ..Default::default()
+                })
+                .await?;
+            eprintln!("{} products found", result.count);
+            output(cli.format, &result.products)?;
+        }
+        Command::Product { id } => {
+            let product = client.product_api().get(&id).await?;
+            output(cli.format, &[WorkMetadata::from(&product)])?;
+        }
+        Command::Circle { id, page } => {
+            let result = client
+                .circle()
+                .get_circle(
+                    &id,
+                    &CircleQuery {
+                        page,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            eprintln!("{} products found", result.count);
+            output(cli.format, &result.products)?;
+        }
+        Command::Ranking { term, genre } => {
+            let entries = match genre {
+                Some(genre) => client.ranking().by_genre(genre, term).await?,
+                None => client.ranking().get(term).await?,
+            };
+            output(cli.format, &entries)?;
+        }
+        Command::Scan { dir } => {
+            output(cli.format, &library::scan(dir)?)?;
+        }
+        Command::Enrich { dir, concurrency } => {
+            let items = library::scan(dir)?;
+            eprintln!("{} works found", items.len());
+            output(cli.format, &library::enrich(&client, items, concurrency).await)?;
+        }
+    }
+
+    Ok(())
+}
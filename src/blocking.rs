@@ -0,0 +1,221 @@
+//! Synchronous facade over the async client.
+//!
+//! [`DlsiteClient`] owns a single-threaded tokio runtime and blocks on it for each call, so
+//! CLI tools and GUI apps can use the client without running a runtime themselves. Only the
+//! most common calls are mirrored (search, product, circle); [`DlsiteClient::block_on`] runs
+//! anything else of the async API.
+//!
+//! Calls must not be made from inside an async runtime: blocking on a runtime from another
+//! one panics.
+//!
+//! # Example
+//! ```no_run
+//! use dlsite_gamebox::{blocking::DlsiteClient, client::search::SearchProductQuery};
+//!
+//! let client = DlsiteClient::default();
+//! let product = client.product().get_all("RJ01014447").unwrap();
+//! println!("{}", product.title);
+//!
+//! let result = client
+//!     .search()
+//!     .search_product(&SearchProductQuery {
+//!         keyword: Some("ASMR".to_string()),
+//!         ..Default::default()
+//!     })
+//!     .unwrap();
+//! println!("{} works", result.count);
+//! ```
+
+use std::{future::Future, sync::Arc};
+
+use tokio::runtime::Runtime;
+
+use crate::{
+    client::{
+        circle::{CircleProfile, CircleQuery, CircleStats},
+        product::{ajax::ProductAjax, html::ProductHtml, Product},
+        product_api::interface::ProductApiContent,
+        search::{SearchProductQuery, SearchResult},
+    },
+    error::Result,
+    interface::product_id::ProductId,
+    DlsiteClientBuilder,
+};
+
+/// Blocking version of [`crate::DlsiteClient`].
+///
+/// Clones share the same runtime and the same async client (cache, rate limiter, cookies).
+#[derive(Clone, Debug)]
+pub struct DlsiteClient {
+    inner: crate::DlsiteClient,
+    runtime: Arc<Runtime>,
+}
+
+impl Default for DlsiteClient {
+    fn default() -> Self {
+        Self::from_async(crate::DlsiteClient::default())
+    }
+}
+
+impl DlsiteClient {
+    /// Create a client with the default configuration for `base_url`, see
+    /// [`crate::DlsiteClient::new`].
+    pub fn new(base_url: &str) -> Self {
+        Self::from_async(crate::DlsiteClient::new(base_url))
+    }
+
+    /// Build a client from a configured builder, see [`crate::DlsiteClient::builder`].
+    pub fn from_builder(builder: DlsiteClientBuilder) -> Self {
+        Self::from_async(builder.build())
+    }
+
+    /// Wrap an async client.
+    ///
+    /// # Panics
+    /// Panics if the runtime can't be created.
+    pub fn from_async(client: crate::DlsiteClient) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create the tokio runtime");
+        Self {
+            inner: client,
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    /// The wrapped async client.
+    pub fn as_async(&self) -> &crate::DlsiteClient {
+        &self.inner
+    }
+
+    /// Run a call of the async API to completion.
+    ///
+    /// # Example
+    /// ```no_run
+    /// let client = dlsite_gamebox::blocking::DlsiteClient::default();
+    /// let campaigns = client
+    ///     .block_on(|c| async move { c.campaign().list().await })
+    ///     .unwrap();
+    /// ```
+    pub fn block_on<'c, F, Fut>(&'c self, f: F) -> Fut::Output
+    where
+        F: FnOnce(&'c crate::DlsiteClient) -> Fut,
+        Fut: Future,
+    {
+        self.runtime.block_on(f(&self.inner))
+    }
+
+    /// Blocking version of [`crate::DlsiteClient::product`].
+    pub fn product(&self) -> ProductClient<'_> {
+        ProductClient { c: self }
+    }
+
+    /// Blocking version of [`crate::DlsiteClient::product_api`].
+    pub fn product_api(&self) -> ProductApiClient<'_> {
+        ProductApiClient { c: self }
+    }
+
+    /// Blocking version of [`crate::DlsiteClient::search`].
+    pub fn search(&self) -> SearchClient<'_> {
+        SearchClient { c: self }
+    }
+
+    /// Blocking version of [`crate::DlsiteClient::circle`].
+    pub fn circle(&self) -> CircleClient<'_> {
+        CircleClient { c: self }
+    }
+}
+
+/// Blocking version of [`crate::client::product::ProductClient`].
+#[derive(Clone, Debug)]
+pub struct ProductClient<'a> {
+    c: &'a DlsiteClient,
+}
+
+impl ProductClient<'_> {
+    /// See [`crate::client::product::ProductClient::get_all`].
+    pub fn get_all(&self, product_id: impl Into<ProductId>) -> Result<Product> {
+        self.c
+            .block_on(|c| async move { c.product().get_all(product_id).await })
+    }
+
+    /// See [`crate::client::product::ProductClient::get_html`].
+    pub fn get_html(&self, product_id: impl Into<ProductId>) -> Result<ProductHtml> {
+        self.c
+            .block_on(|c| async move { c.product().get_html(product_id).await })
+    }
+
+    /// See [`crate::client::product::ProductClient::get_ajax`].
+    pub fn get_ajax(&self, product_id: impl Into<ProductId>) -> Result<ProductAjax> {
+        self.c
+            .block_on(|c| async move { c.product().get_ajax(product_id).await })
+    }
+}
+
+/// Blocking version of [`crate::client::product_api::ProductApiClient`].
+#[derive(Clone, Debug)]
+pub struct ProductApiClient<'a> {
+    c: &'a DlsiteClient,
+}
+
+impl ProductApiClient<'_> {
+    /// See [`crate::client::product_api::ProductApiClient::get`].
+    pub fn get(&self, id: impl Into<ProductId>) -> Result<ProductApiContent> {
+        self.c
+            .block_on(|c| async move { c.product_api().get(id).await })
+    }
+}
+
+/// Blocking version of [`crate::client::search::SearchClient`].
+#[derive(Clone, Debug)]
+pub struct SearchClient<'a> {
+    c: &'a DlsiteClient,
+}
+
+impl SearchClient<'_> {
+    /// See [`crate::client::search::SearchClient::search_product`].
+    pub fn search_product(&self, options: &SearchProductQuery) -> Result<SearchResult> {
+        self.c
+            .block_on(|c| async move { c.search().search_product(options).await })
+    }
+}
+
+/// Blocking version of [`crate::client::circle::CircleClient`].
+#[derive(Clone, Debug)]
+pub struct CircleClient<'a> {
+    c: &'a DlsiteClient,
+}
+
+impl CircleClient<'_> {
+    /// See [`crate::client::circle::CircleClient::get_circle`].
+    pub fn get_circle(&self, circle_id: &str, options: &CircleQuery) -> Result<SearchResult> {
+        self.c
+            .block_on(|c| async move { c.circle().get_circle(circle_id, options).await })
+    }
+
+    /// See [`crate::client::circle::CircleClient::get_profile`].
+    pub fn get_profile(&self, circle_id: &str) -> Result<CircleProfile> {
+        self.c
+            .block_on(|c| async move { c.circle().get_profile(circle_id).await })
+    }
+
+    /// See [`crate::client::circle::CircleClient::stats`].
+    pub fn stats(&self, circle_id: &str) -> Result<CircleStats> {
+        self.c
+            .block_on(|c| async move { c.circle().stats(circle_id).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DlsiteClient;
+
+    #[test]
+    fn get_product() {
+        let client = DlsiteClient::default();
+        let product = client.product().get_all("RJ01014447").unwrap();
+        assert_eq!(product.id, "RJ01014447");
+        assert!(client.circle().get_profile(&product.circle_id).is_ok());
+    }
+}
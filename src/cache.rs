@@ -1,9 +1,16 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use lru::LruCache;
 use std::sync::Mutex;
 use std::num::NonZeroUsize;
 
+use crate::persist::{self, Persisted};
+use crate::runtime::{self, Instant, SystemTime, UNIX_EPOCH};
+
 /// Generic cache entry with expiration time
 #[derive(Clone, Debug)]
 struct CacheEntry<T: Clone> {
@@ -18,67 +25,382 @@ impl<T: Clone> CacheEntry<T> {
     }
 }
 
-/// Thread-safe LRU cache for HTTP responses
+/// Counters of a cache since its creation, to tune its capacity and TTL.
+///
+/// Every lookup is either a hit or a miss; a lookup finding an expired entry is a miss and
+/// an expiration. Evictions are entries dropped to make room for new ones.
+///
+/// Each event is also logged as a `tracing` event at the trace level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub expirations: u64,
+}
+
+impl CacheStats {
+    /// Share of lookups which were hits, 0 if there was none.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Atomic counters behind [`CacheStats`], shared by the clones of a cache.
+#[derive(Debug, Default)]
+struct StatsCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+impl StatsCounters {
+    fn hit(&self, key: &str) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        tracing::trace!(key, "cache hit");
+    }
+
+    fn miss(&self, key: &str) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        tracing::trace!(key, "cache miss");
+    }
+
+    fn eviction(&self, key: &str) {
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+        tracing::trace!(key, "cache eviction");
+    }
+
+    fn expiration(&self, key: &str) {
+        self.expirations.fetch_add(1, Ordering::Relaxed);
+        tracing::trace!(key, "cache expiration");
+    }
+
+    fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            expirations: self.expirations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Storage of cached HTTP responses, keyed by URL.
+///
+/// Implementations decide where responses live and when they expire. Errors are not
+/// reported: a failing backend behaves as a cache miss.
+pub trait CacheBackend: Send + Sync + fmt::Debug {
+    /// Get a response which hasn't expired yet
+    fn get(&self, key: &str) -> Option<String>;
+    /// Store a response
+    fn insert(&self, key: String, value: String);
+    /// Remove all responses
+    fn clear(&self);
+    /// Number of stored responses
+    fn len(&self) -> usize;
+    /// Whether no response is stored
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Evictions and expirations of the backend, if it counts them. Hits and misses are
+    /// counted by [`ResponseCache`].
+    fn stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+}
+
+/// In-memory LRU cache backend (default)
+#[derive(Debug)]
+pub struct MemoryCache {
+    cache: GenericCache<String>,
+}
+
+impl MemoryCache {
+    /// Create a new memory cache with the specified capacity and TTL
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            cache: GenericCache::new(capacity, ttl),
+        }
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, key: &str) -> Option<String> {
+        self.cache.get(key)
+    }
+
+    fn insert(&self, key: String, value: String) {
+        self.cache.insert(key, value)
+    }
+
+    fn clear(&self) {
+        self.cache.clear()
+    }
+
+    fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+}
+
+/// A response stored by [`DiskCache`]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DiskEntry {
+    key: String,
+    value: String,
+    /// Expiration time in seconds since the Unix epoch
+    expires_at: u64,
+}
+
+impl Persisted for DiskEntry {
+    const KIND: &'static str = "cache_entry";
+    const VERSION: u32 = 1;
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Number of stored responses between two sweeps of a [`DiskCache`]
+const SWEEP_EVERY: usize = 100;
+
+/// Cache backend storing responses as JSON files under a directory, so they survive
+/// restarts.
+///
+/// Each response is a file named after a hash of its URL. Expired files are removed when read
+/// and by [`DiskCache::sweep`], which runs every 100 stored responses and also enforces
+/// [`DiskCache::max_entries`].
+///
+/// Inside a tokio runtime, responses are written on its blocking thread pool so requests don't
+/// wait for the disk.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_entries: Option<usize>,
+    /// Responses stored since the creation of the cache, to schedule sweeps
+    inserts: Arc<AtomicUsize>,
+    stats: Arc<StatsCounters>,
+}
+
+impl DiskCache {
+    /// Create a disk cache in `dir` (created when the first response is stored).
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+            max_entries: None,
+            inserts: Arc::default(),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Keep at most `max_entries` responses, removing the oldest ones when sweeping. Between
+    /// two sweeps, the directory may hold up to 100 more.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Remove the expired responses, then the oldest ones beyond [`DiskCache::max_entries`].
+    pub fn sweep(&self) {
+        let mut kept = vec![];
+        for path in self.entries() {
+            // Responses are written once, so a file expires `ttl` after its modification
+            let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if modified.elapsed().is_ok_and(|age| age >= self.ttl) {
+                if fs::remove_file(&path).is_ok() {
+                    self.stats.expiration(&path.to_string_lossy());
+                }
+            } else {
+                kept.push((modified, path));
+            }
+        }
+
+        let Some(max_entries) = self.max_entries else {
+            return;
+        };
+        if kept.len() > max_entries {
+            kept.sort();
+            for (_, path) in &kept[..kept.len() - max_entries] {
+                if fs::remove_file(path).is_ok() {
+                    self.stats.eviction(&path.to_string_lossy());
+                }
+            }
+        }
+    }
+
+    /// Directory the responses are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_of(&self, key: &str) -> PathBuf {
+        // FNV-1a, stable across Rust versions unlike `DefaultHasher`
+        let hash = key.bytes().fold(0xcbf29ce484222325_u64, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+        });
+        self.dir.join(format!("{:016x}.json", hash))
+    }
+
+    fn entries(&self) -> Vec<PathBuf> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return vec![];
+        };
+        dir.filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect()
+    }
+}
+
+impl CacheBackend for DiskCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let path = self.path_of(key);
+        let entry = persist::load::<DiskEntry>(&path)
+            .inspect_err(|e| tracing::warn!("Failed to read cache entry: {e}"))
+            .ok()??;
+        if entry.key != key {
+            // Hash collision
+            return None;
+        }
+        if entry.expires_at <= unix_now() {
+            self.stats.expiration(key);
+            runtime::spawn_blocking(move || {
+                let _ = fs::remove_file(path);
+            });
+            return None;
+        }
+        Some(entry.value)
+    }
+
+    fn insert(&self, key: String, value: String) {
+        let path = self.path_of(&key);
+        let entry = DiskEntry {
+            key,
+            value,
+            expires_at: unix_now() + self.ttl.as_secs(),
+        };
+        let inserts = self.inserts.fetch_add(1, Ordering::Relaxed) + 1;
+        let sweep = inserts.is_multiple_of(SWEEP_EVERY);
+        let cache = self.clone();
+        runtime::spawn_blocking(move || {
+            if let Err(e) = persist::save(&path, &entry) {
+                tracing::warn!("Failed to write cache entry: {e}");
+            }
+            if sweep {
+                cache.sweep();
+            }
+        });
+    }
+
+    fn clear(&self) {
+        for path in self.entries() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.stats.snapshot()
+    }
+}
+
+/// Thread-safe cache for HTTP responses
+///
+/// Responses are kept in memory by default; use [`ResponseCache::with_backend`] (or
+/// [`crate::DlsiteClientBuilder::cache_backend`]) to store them elsewhere, e.g. in a
+/// [`DiskCache`].
 #[derive(Clone, Debug)]
 pub struct ResponseCache {
-    cache: Arc<Mutex<LruCache<String, CacheEntry<String>>>>,
-    ttl: Duration,
+    backend: Arc<dyn CacheBackend>,
+    stats: Arc<StatsCounters>,
 }
 
 impl ResponseCache {
-    /// Create a new response cache with the specified capacity and TTL
+    /// Create a new in-memory response cache with the specified capacity and TTL
     ///
     /// # Arguments
     /// * `capacity` - Maximum number of entries in the cache
     /// * `ttl` - Time to live for each cache entry
     pub fn new(capacity: usize, ttl: Duration) -> Self {
-        let cache = LruCache::new(NonZeroUsize::new(capacity).unwrap());
+        Self::with_backend(MemoryCache::new(capacity, ttl))
+    }
+
+    /// Create a response cache storing responses in the given backend
+    pub fn with_backend(backend: impl CacheBackend + 'static) -> Self {
         Self {
-            cache: Arc::new(Mutex::new(cache)),
-            ttl,
+            backend: Arc::new(backend),
+            stats: Arc::default(),
         }
     }
 
     /// Get a value from the cache
     pub fn get(&self, key: &str) -> Option<String> {
-        let mut cache = self.cache.lock().unwrap();
-        if let Some(entry) = cache.get_mut(key) {
-            if !entry.is_expired() {
-                return Some(entry.data.clone());
-            } else {
-                // Remove expired entry
-                cache.pop(key);
-            }
+        let value = self.backend.get(key);
+        if value.is_some() {
+            self.stats.hit(key);
+        } else {
+            self.stats.miss(key);
         }
-        None
+        value
+    }
+
+    /// Whether a value which hasn't expired is cached, without counting a lookup in
+    /// [`ResponseCache::stats`]
+    pub fn contains(&self, key: &str) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// Get a value without counting a lookup in [`ResponseCache::stats`], for a request
+    /// already counted
+    pub(crate) fn peek(&self, key: &str) -> Option<String> {
+        self.backend.get(key)
     }
 
     /// Insert a value into the cache
     pub fn insert(&self, key: String, value: String) {
-        let mut cache = self.cache.lock().unwrap();
-        let entry = CacheEntry {
-            data: value,
-            expires_at: Instant::now() + self.ttl,
-        };
-        cache.put(key, entry);
+        self.backend.insert(key, value)
     }
 
     /// Clear all entries from the cache
     pub fn clear(&self) {
-        let mut cache = self.cache.lock().unwrap();
-        cache.clear();
+        self.backend.clear()
     }
 
     /// Get the number of entries in the cache
     pub fn len(&self) -> usize {
-        let cache = self.cache.lock().unwrap();
-        cache.len()
+        self.backend.len()
     }
 
     /// Check if the cache is empty
     pub fn is_empty(&self) -> bool {
-        let cache = self.cache.lock().unwrap();
-        cache.is_empty()
+        self.backend.is_empty()
+    }
+
+    /// Hits and misses of the lookups, with the evictions and expirations of the backend
+    pub fn stats(&self) -> CacheStats {
+        let stats = self.stats.snapshot();
+        CacheStats {
+            hits: stats.hits,
+            misses: stats.misses,
+            ..self.backend.stats()
+        }
     }
 }
 
@@ -87,6 +409,7 @@ impl ResponseCache {
 pub struct GenericCache<T: Clone> {
     cache: Arc<Mutex<LruCache<String, CacheEntry<T>>>>,
     ttl: Duration,
+    stats: Arc<StatsCounters>,
 }
 
 impl<T: Clone> GenericCache<T> {
@@ -96,6 +419,7 @@ impl<T: Clone> GenericCache<T> {
         Self {
             cache: Arc::new(Mutex::new(cache)),
             ttl,
+            stats: Arc::default(),
         }
     }
 
@@ -104,11 +428,14 @@ impl<T: Clone> GenericCache<T> {
         let mut cache = self.cache.lock().unwrap();
         if let Some(entry) = cache.get_mut(key) {
             if !entry.is_expired() {
+                self.stats.hit(key);
                 return Some(entry.data.clone());
             } else {
                 cache.pop(key);
+                self.stats.expiration(key);
             }
         }
+        self.stats.miss(key);
         None
     }
 
@@ -119,6 +446,11 @@ impl<T: Clone> GenericCache<T> {
             data: value,
             expires_at: Instant::now() + self.ttl,
         };
+        if !cache.contains(&key) && cache.len() == cache.cap().get() {
+            if let Some((evicted, _)) = cache.pop_lru() {
+                self.stats.eviction(&evicted);
+            }
+        }
         cache.put(key, entry);
     }
 
@@ -139,8 +471,16 @@ impl<T: Clone> GenericCache<T> {
         let cache = self.cache.lock().unwrap();
         cache.is_empty()
     }
+
+    /// Hits, misses, evictions and expirations since the cache was created
+    pub fn stats(&self) -> CacheStats {
+        self.stats.snapshot()
+    }
 }
 
+/// Cache for binary media (thumbnails, sample images...), keyed by URL
+pub type MediaCache = GenericCache<Arc<[u8]>>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +515,34 @@ mod tests {
         assert_eq!(cache.get("key3"), Some("value3".to_string()));
     }
 
+    #[test]
+    fn test_cache_stats() {
+        let cache = ResponseCache::new(2, Duration::from_secs(60));
+        assert_eq!(cache.get("key1"), None);
+        cache.insert("key1".to_string(), "value1".to_string());
+        cache.insert("key2".to_string(), "value2".to_string());
+        assert!(cache.get("key1").is_some());
+        cache.insert("key3".to_string(), "value3".to_string());
+        assert!(cache.contains("key1"));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 1,
+                expirations: 0,
+            }
+        );
+        assert_eq!(cache.stats().hit_rate(), 0.5);
+
+        let media = MediaCache::new(10, Duration::ZERO);
+        media.insert("image".to_string(), Arc::from(&b"png"[..]));
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(media.get("image"), None);
+        assert_eq!(media.stats().expirations, 1);
+        assert_eq!(media.stats().misses, 1);
+    }
+
     #[test]
     fn test_cache_clear() {
         let cache = ResponseCache::new(10, Duration::from_secs(60));
@@ -186,5 +554,50 @@ mod tests {
         assert_eq!(cache.len(), 0);
         assert!(cache.is_empty());
     }
-}
 
+    #[test]
+    fn test_disk_cache_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("dlsite-disk-cache-{}", std::process::id()));
+        let cache = ResponseCache::with_backend(DiskCache::new(&dir, Duration::from_secs(60)));
+        cache.insert("https://www.dlsite.com/maniax/a".to_string(), "value1".to_string());
+        cache.insert("https://www.dlsite.com/maniax/b".to_string(), "value2".to_string());
+
+        // A new cache on the same directory sees the entries
+        let cache = ResponseCache::with_backend(DiskCache::new(&dir, Duration::from_secs(60)));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("https://www.dlsite.com/maniax/a"), Some("value1".to_string()));
+        assert_eq!(cache.get("https://www.dlsite.com/maniax/c"), None);
+
+        cache.clear();
+        assert!(cache.is_empty());
+
+        // Expired entries are dropped
+        let cache = ResponseCache::with_backend(DiskCache::new(&dir, Duration::ZERO));
+        cache.insert("https://www.dlsite.com/maniax/a".to_string(), "value1".to_string());
+        assert_eq!(cache.get("https://www.dlsite.com/maniax/a"), None);
+        assert_eq!(cache.stats().expirations, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disk_cache_sweep() {
+        let dir = std::env::temp_dir().join(format!("dlsite-disk-sweep-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let disk = DiskCache::new(&dir, Duration::from_secs(60)).max_entries(2);
+        for i in 0..3 {
+            disk.insert(format!("https://www.dlsite.com/maniax/{i}"), i.to_string());
+        }
+        disk.sweep();
+        assert_eq!(disk.len(), 2);
+        assert_eq!(disk.stats().evictions, 1);
+
+        // Everything is expired with a TTL of 0
+        let disk = DiskCache::new(&dir, Duration::ZERO);
+        disk.sweep();
+        assert!(disk.is_empty());
+        assert_eq!(disk.stats().expirations, 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,578 @@
+//! Download of purchased works, with resumable ranged requests.
+//!
+//! Large works are split by DLsite into several archives (`.part1.exe`, `.part2.rar`...),
+//! listed on the split download page of the work. Each part is written to a `.partial` file
+//! in the destination, chunk by chunk, so an interrupted download restarts where it stopped.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read as _, Write as _},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use futures::StreamExt as _;
+use md5::{Digest as _, Md5};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use super::AccountClient;
+use crate::{
+    client::{follow::is_login_page, search::percent_decode},
+    error::Result,
+    interface::product_id::ProductId,
+    persist::io_error,
+    runtime,
+    utils::parse_file_size,
+    DlsiteError,
+};
+
+/// Size of the ranged requests, in bytes.
+const CHUNK_SIZE: u64 = 8 << 20;
+
+/// Size of the writes to the partial files, in bytes.
+const WRITE_SIZE: usize = 1 << 20;
+
+/// A file to download for a work, see [`AccountClient::download_parts`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DownloadPart {
+    /// 1-based
+    pub number: u32,
+    /// Absolute URL, redirecting to the file
+    pub url: String,
+    /// File name as listed by DLsite. Unknown for works in a single file until the download
+    /// starts.
+    pub file_name: Option<String>,
+    /// Size in bytes, as displayed by DLsite (rounded)
+    pub size: Option<u64>,
+    /// MD5 checksum in lowercase hex, when DLsite gives one
+    pub md5: Option<String>,
+}
+
+/// Progress of a download, given to the callback of
+/// [`AccountClient::download_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Part being downloaded, 1-based
+    pub part: u32,
+    /// Number of parts of the work
+    pub parts: u32,
+    /// Bytes of the part written to disk
+    pub downloaded: u64,
+    /// Size of the part in bytes, once known
+    pub total: Option<u64>,
+}
+
+impl AccountClient<'_> {
+    /// Get the files to download for an owned work: one per part for works split into several
+    /// archives, a single one otherwise.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in.
+    pub async fn download_parts(
+        &self,
+        product_id: impl Into<ProductId>,
+    ) -> Result<Vec<DownloadPart>> {
+        let product_id = product_id.into().checked()?;
+        let (c, id) = (self.c, product_id.as_str());
+        let base_url = &c.site_base_url(c.site());
+        c.authenticated(move || async move {
+            let path = format!("/download/split/=/product_id/{id}.html");
+            match c.get_fresh(&path).await {
+                Ok(html) => parse_download_parts(&html, base_url, id),
+                // Works in a single file have no split page
+                Err(e) if e.is_not_found() => parse_download_parts("", base_url, id),
+                Err(e) => Err(e),
+            }
+        })
+        .await
+    }
+
+    /// Download an owned work into the directory `destination`, returning the paths of the
+    /// downloaded files (one per part, see [`AccountClient::download_parts`]).
+    ///
+    /// Interrupted downloads resume where they stopped, and files already downloaded are
+    /// skipped. Parts are validated against their checksum when DLsite gives one. The
+    /// archives are not extracted.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in,
+    /// [`DlsiteError::ChecksumMismatch`] if a part is corrupted (its partial file is removed,
+    /// so the next call downloads it again), and [`DlsiteError::Persist`] if the files can't
+    /// be written.
+    pub async fn download(
+        &self,
+        product_id: impl Into<ProductId>,
+        destination: impl AsRef<Path>,
+    ) -> Result<Vec<PathBuf>> {
+        self.download_with_progress(product_id, destination, |_| {})
+            .await
+    }
+
+    /// Same as [`AccountClient::download`], calling `on_progress` after each chunk written.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(client: dlsite_gamebox::DlsiteClient) -> Result<(), dlsite_gamebox::DlsiteError> {
+    /// let files = client
+    ///     .account()
+    ///     .download_with_progress("RJ01014447", "downloads", |progress| {
+    ///         if let Some(total) = progress.total {
+    ///             println!(
+    ///                 "part {}/{}: {}%",
+    ///                 progress.part,
+    ///                 progress.parts,
+    ///                 progress.downloaded * 100 / total.max(1)
+    ///             );
+    ///         }
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_with_progress(
+        &self,
+        product_id: impl Into<ProductId>,
+        destination: impl AsRef<Path>,
+        mut on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<Vec<PathBuf>> {
+        let product_id = product_id.into().checked()?;
+        let destination = destination.as_ref();
+        let parts = self.download_parts(product_id.clone()).await?;
+        fs::create_dir_all(destination).map_err(|e| io_error(destination, e))?;
+
+        let mut files = Vec::with_capacity(parts.len());
+        for part in &parts {
+            let progress = DownloadProgress {
+                part: part.number,
+                parts: parts.len() as u32,
+                downloaded: 0,
+                total: None,
+            };
+            let file = self
+                .download_part(
+                    product_id.as_str(),
+                    part,
+                    destination,
+                    progress,
+                    &mut on_progress,
+                )
+                .await?;
+            files.push(file);
+        }
+        Ok(files)
+    }
+
+    async fn download_part(
+        &self,
+        id: &str,
+        part: &DownloadPart,
+        destination: &Path,
+        mut progress: DownloadProgress,
+        on_progress: &mut impl FnMut(DownloadProgress),
+    ) -> Result<PathBuf> {
+        if let Some(name) = &part.file_name {
+            let path = destination.join(safe_file_name(name));
+            if let Ok(metadata) = fs::metadata(&path) {
+                if let Some(md5) = &part.md5 {
+                    check_md5(&path, md5).await?;
+                }
+                progress.downloaded = metadata.len();
+                progress.total = Some(metadata.len());
+                on_progress(progress);
+                return Ok(path);
+            }
+        }
+
+        let partial = destination.join(format!("{id}-{}.partial", part.number));
+        progress.downloaded = fs::metadata(&partial).map_or(0, |m| m.len());
+        let mut file_name = part.file_name.clone();
+        loop {
+            let response = self
+                .c
+                .get_range(&part.url, progress.downloaded, CHUNK_SIZE)
+                .await?;
+            // Past the end: the whole file was already downloaded
+            if response.status == 416 {
+                break;
+            }
+            if response
+                .header("content-type")
+                .is_some_and(|t| t.starts_with("text/html"))
+            {
+                let page = response.buffer().await?.text();
+                return Err(if is_login_page(&Html::parse_document(&page)) {
+                    DlsiteError::Unauthenticated
+                } else {
+                    DlsiteError::Server(format!("{id} can't be downloaded, is it owned?"))
+                });
+            }
+            if file_name.is_none() {
+                file_name = response
+                    .header("content-disposition")
+                    .and_then(disposition_file_name);
+            }
+
+            // The server ignored the range and sends the whole file
+            let whole = response.status != 206;
+            if whole {
+                progress.downloaded = 0;
+                progress.total = response
+                    .header("content-length")
+                    .and_then(|len| len.parse().ok());
+            } else {
+                progress.total = response
+                    .header("content-range")
+                    .and_then(range_total)
+                    .or(progress.total);
+            }
+            let mut file = open_partial(&partial, whole).await?;
+
+            // The body is written as it arrives, by blocks of `WRITE_SIZE`
+            let mut body = response.body;
+            let mut buf = Vec::with_capacity(WRITE_SIZE);
+            let mut received = 0;
+            let mut ended = false;
+            while !ended {
+                match body.next().await.transpose()? {
+                    Some(chunk) => buf.extend_from_slice(&chunk),
+                    None => ended = true,
+                }
+                if buf.len() >= WRITE_SIZE || (ended && !buf.is_empty()) {
+                    let len = buf.len() as u64;
+                    (file, buf) = write_partial(file, buf, &partial).await?;
+                    received += len;
+                    progress.downloaded += len;
+                    on_progress(progress);
+                }
+            }
+
+            let done = match progress.total {
+                Some(total) => progress.downloaded >= total,
+                None => received < CHUNK_SIZE,
+            };
+            if whole || done || received == 0 {
+                break;
+            }
+        }
+
+        if let Some(md5) = &part.md5 {
+            if let Err(e) = check_md5(&partial, md5).await {
+                let _ = fs::remove_file(&partial);
+                return Err(e);
+            }
+        }
+        let name = file_name.unwrap_or_else(|| format!("{id}-{}", part.number));
+        let path = destination.join(safe_file_name(&name));
+        fs::rename(&partial, &path).map_err(|e| io_error(&path, e))?;
+        Ok(path)
+    }
+}
+
+/// Parse the split download page of a work. An empty page (work in a single file) gives its
+/// only download URL.
+pub(crate) fn parse_download_parts(
+    html: &str,
+    base_url: &str,
+    id: &str,
+) -> Result<Vec<DownloadPart>> {
+    static NUMBER_RE: OnceLock<Regex> = OnceLock::new();
+    static FILE_RE: OnceLock<Regex> = OnceLock::new();
+    static MD5_RE: OnceLock<Regex> = OnceLock::new();
+    let number_re = NUMBER_RE.get_or_init(|| Regex::new(r"/number/(\d+)/").unwrap());
+    let file_re = FILE_RE.get_or_init(|| {
+        Regex::new(r"(?i)[\w\-.]+\.(?:zip|rar|exe|bin|7z|lzh|pdf|apk|mp4)\b").unwrap()
+    });
+    let md5_re = MD5_RE.get_or_init(|| Regex::new(r"\b[0-9a-fA-F]{32}\b").unwrap());
+
+    let html = Html::parse_document(html);
+    if is_login_page(&html) {
+        return Err(DlsiteError::Unauthenticated);
+    }
+    let base = url::Url::parse(base_url).ok();
+    let link = Selector::parse(r#"a[href*="/download/=/number/"]"#).unwrap();
+
+    let mut parts: Vec<DownloadPart> = vec![];
+    for a in html.select(&link) {
+        let Some(href) = a.value().attr("href") else {
+            continue;
+        };
+        let Some(number) = number_re.captures(href).and_then(|cap| cap[1].parse().ok()) else {
+            continue;
+        };
+        if parts.iter().any(|part| part.number == number) {
+            continue;
+        }
+        // Name, size and checksum are in the row of the link
+        let row = a
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .find(|e| matches!(e.value().name(), "tr" | "li"))
+            .unwrap_or(a);
+        let text = row.text().collect::<Vec<_>>().join(" ");
+        let file_name = file_re.find(&text).map(|m| m.as_str().to_string());
+        let rest = match &file_name {
+            Some(name) => text.replace(name.as_str(), ""),
+            None => text.clone(),
+        };
+        parts.push(DownloadPart {
+            number,
+            url: base
+                .as_ref()
+                .and_then(|base| base.join(href).ok())
+                .map_or_else(|| href.to_string(), |url| url.to_string()),
+            file_name,
+            size: parse_file_size(&rest),
+            md5: md5_re.find(&rest).map(|m| m.as_str().to_ascii_lowercase()),
+        });
+    }
+
+    if parts.is_empty() {
+        parts.push(DownloadPart {
+            number: 1,
+            url: format!("{base_url}/download/=/product_id/{id}.html"),
+            file_name: None,
+            size: None,
+            md5: None,
+        });
+    }
+    parts.sort_by_key(|part| part.number);
+    Ok(parts)
+}
+
+/// Total size from a `Content-Range: bytes 0-99/1234` header.
+fn range_total(content_range: &str) -> Option<u64> {
+    content_range.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// File name from a `Content-Disposition` header, `filename*` preferred.
+fn disposition_file_name(disposition: &str) -> Option<String> {
+    let params = disposition
+        .split(';')
+        .filter_map(|p| p.trim().split_once('='));
+    let mut plain = None;
+    for (key, value) in params {
+        match key.trim().to_ascii_lowercase().as_str() {
+            "filename*" => {
+                let encoded = value.rsplit_once("''").map_or(value, |(_, v)| v);
+                return Some(percent_decode(encoded.trim_matches('"')));
+            }
+            "filename" => plain = Some(value.trim_matches('"').to_string()),
+            _ => {}
+        }
+    }
+    plain
+}
+
+/// Keep only the last component of a file name given by the server.
+fn safe_file_name(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    match name {
+        "" | "." | ".." => "download".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Open the partial file of a part on the blocking pool, emptied if `truncate` is set.
+async fn open_partial(path: &Path, truncate: bool) -> Result<File> {
+    let path = path.to_path_buf();
+    runtime::blocking(move || {
+        if truncate {
+            File::create(&path)
+        } else {
+            OpenOptions::new().create(true).append(true).open(&path)
+        }
+        .map_err(|e| io_error(&path, e))
+    })
+    .await
+}
+
+/// Append `buf` to the partial file on the blocking pool. Both are given back, `buf` emptied,
+/// for the next write.
+async fn write_partial(mut file: File, mut buf: Vec<u8>, path: &Path) -> Result<(File, Vec<u8>)> {
+    let path = path.to_path_buf();
+    runtime::blocking(move || {
+        file.write_all(&buf).map_err(|e| io_error(&path, e))?;
+        buf.clear();
+        Ok((file, buf))
+    })
+    .await
+}
+
+/// Check the MD5 checksum of a file, read on the blocking pool.
+async fn check_md5(path: &Path, expected: &str) -> Result<()> {
+    let (path, expected) = (path.to_path_buf(), expected.to_string());
+    runtime::blocking(move || {
+        let mut file = File::open(&path).map_err(|e| io_error(&path, e))?;
+        let mut hasher = Md5::new();
+        let mut buf = vec![0; 1 << 20];
+        loop {
+            let n = file.read(&mut buf).map_err(|e| io_error(&path, e))?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        let actual = format!("{:x}", hasher.finalize());
+        if actual.eq_ignore_ascii_case(&expected) {
+            Ok(())
+        } else {
+            Err(DlsiteError::ChecksumMismatch {
+                file: path.display().to_string(),
+                expected,
+                actual,
+            })
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{disposition_file_name, parse_download_parts, range_total, safe_file_name};
+    use crate::{
+        testing::FixtureTransport,
+        transport::{HttpRequest, HttpResponse},
+        DlsiteClient, DlsiteError,
+    };
+
+    const BASE_URL: &str = "https://www.dlsite.com/maniax";
+
+    #[test]
+    fn parse_split_page() {
+        let parts = parse_download_parts(
+            r#"<table class="work_download_list">
+                <tr><td>RJ01000001.part2.rar</td><td>1.2GB</td>
+                    <td>MD5: 0123456789ABCDEF0123456789abcdef</td>
+                    <td><a href="/maniax/download/=/number/2/product_id/RJ01000001.html">DL</a></td></tr>
+                <tr><td>RJ01000001.part1.exe</td><td>2GB</td>
+                    <td><a href="/maniax/download/=/number/1/product_id/RJ01000001.html">DL</a></td></tr>
+            </table>"#,
+            BASE_URL,
+            "RJ01000001",
+        )
+        .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].number, 1);
+        assert_eq!(
+            parts[0].url,
+            "https://www.dlsite.com/maniax/download/=/number/1/product_id/RJ01000001.html"
+        );
+        assert_eq!(parts[0].file_name.as_deref(), Some("RJ01000001.part1.exe"));
+        assert_eq!(parts[0].size, Some(2 << 30));
+        assert_eq!(parts[0].md5, None);
+        assert_eq!(
+            parts[1].md5.as_deref(),
+            Some("0123456789abcdef0123456789abcdef")
+        );
+    }
+
+    #[test]
+    fn parse_single_file() {
+        let parts = parse_download_parts("", BASE_URL, "RJ01000001").unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(
+            parts[0].url,
+            "https://www.dlsite.com/maniax/download/=/product_id/RJ01000001.html"
+        );
+        assert!(matches!(
+            parse_download_parts(r#"<form id="login_form"></form>"#, BASE_URL, "RJ01000001"),
+            Err(DlsiteError::Unauthenticated)
+        ));
+    }
+
+    #[test]
+    fn response_headers() {
+        assert_eq!(range_total("bytes 0-8388607/20000000"), Some(20_000_000));
+        assert_eq!(range_total("bytes 0-99/*"), None);
+        assert_eq!(
+            disposition_file_name(r#"attachment; filename="RJ01000001.zip""#).as_deref(),
+            Some("RJ01000001.zip")
+        );
+        assert_eq!(
+            disposition_file_name("attachment; filename=a.zip; filename*=UTF-8''%E4%BD%9C.zip")
+                .as_deref(),
+            Some("作.zip")
+        );
+        assert_eq!(safe_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(safe_file_name(".."), "download");
+    }
+
+    #[tokio::test]
+    async fn download_resumes() {
+        let split = "https://www.dlsite.com/maniax/download/split/=/product_id/RJ01000001.html";
+        let part = "https://www.dlsite.com/maniax/download/=/number/1/product_id/RJ01000001.html";
+        let transport = FixtureTransport::new()
+            .with_body(
+                split,
+                format!(
+                    r#"<table><tr><td>RJ01000001.zip</td><td>5B</td>
+                    <td>5d41402abc4b2a76b9719d911017c592</td><td><a href="{part}">DL</a></td>
+                    </tr></table>"#
+                ),
+            )
+            .with_response(
+                HttpRequest::get(part).with_header("Range", "bytes=3-8388610"),
+                HttpResponse::new(206, "lo").with_header("Content-Range", "bytes 3-4/5"),
+            );
+        let client = DlsiteClient::builder(BASE_URL).transport(transport).build();
+        let dir = std::env::temp_dir().join(format!("dlsite-download-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("RJ01000001-1.partial"), "hel").unwrap();
+
+        let mut progress = vec![];
+        let files = client
+            .account()
+            .download_with_progress("RJ01000001", &dir, |p| progress.push(p))
+            .await
+            .unwrap();
+        assert_eq!(files, vec![dir.join("RJ01000001.zip")]);
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), "hello");
+        assert!(!dir.join("RJ01000001-1.partial").exists());
+        let last = progress.last().unwrap();
+        assert_eq!((last.part, last.parts), (1, 1));
+        assert_eq!((last.downloaded, last.total), (5, Some(5)));
+
+        // Already downloaded: only checked
+        client.account().download("RJ01000001", &dir).await.unwrap();
+        fs::write(&files[0], "hellO").unwrap();
+        assert!(matches!(
+            client.account().download("RJ01000001", &dir).await,
+            Err(DlsiteError::ChecksumMismatch { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn download_range_ignored() {
+        let split = "https://www.dlsite.com/maniax/download/split/=/product_id/RJ01000002.html";
+        let part = "https://www.dlsite.com/maniax/download/=/number/1/product_id/RJ01000002.html";
+        let transport = FixtureTransport::new()
+            .with_body(
+                split,
+                format!(r#"<ul><li>RJ01000002.zip 5B <a href="{part}">DL</a></li></ul>"#),
+            )
+            .with_response(
+                HttpRequest::get(part).with_header("Range", "bytes=3-8388610"),
+                HttpResponse::new(200, "hello").with_header("Content-Length", "5"),
+            );
+        let client = DlsiteClient::builder(BASE_URL).transport(transport).build();
+        let dir = std::env::temp_dir().join(format!("dlsite-download-200-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("RJ01000002-1.partial"), "hel").unwrap();
+
+        // The whole file replaces the partial one
+        let mut progress = vec![];
+        let files = client
+            .account()
+            .download_with_progress("RJ01000002", &dir, |p| progress.push(p))
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), "hello");
+        let last = progress.last().unwrap();
+        assert_eq!((last.downloaded, last.total), (5, Some(5)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
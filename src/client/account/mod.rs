@@ -0,0 +1,199 @@
+//! Interfaces related to the logged-in account. For more information, see [`AccountClient`].
+
+#[cfg(feature = "download")]
+mod download;
+mod wishlist;
+
+use chrono::NaiveDate;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{
+    client::{follow::is_login_page, Page, Paginated},
+    error::Result,
+    runtime::MaybeBoxed as _,
+    utils::ToParseError as _,
+    DlsiteClient, DlsiteError,
+};
+
+#[cfg(feature = "download")]
+pub use self::download::{DownloadPart, DownloadProgress};
+pub(crate) use self::wishlist::parse_json;
+pub use self::wishlist::{CartItem, FavoriteWork};
+pub(crate) use self::wishlist::{parse_cart_response, parse_favorites_response};
+
+/// Client to fetch the purchase history of the logged-in user, manage their favorites and
+/// cart, and download their purchased works (with the `download` feature).
+///
+/// This needs a logged-in session, see [`DlsiteClient::login`] or
+/// [`crate::DlsiteClientBuilder::cookie`].
+#[derive(Clone, Debug)]
+pub struct AccountClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// Whether a purchased work can be downloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadStatus {
+    /// Downloadable, never downloaded
+    Available,
+    /// Downloadable, already downloaded at least once
+    Downloaded,
+    /// No longer downloadable (withdrawn work, expired rental...)
+    Unavailable,
+}
+
+/// A work in the purchase history ("購入履歴").
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PurchasedWork {
+    pub id: String,
+    pub title: String,
+    pub circle_name: String,
+    pub purchased_at: Option<NaiveDate>,
+    /// Price paid in yen, discounts included
+    pub price: Option<i32>,
+    pub download_status: DownloadStatus,
+}
+
+impl<'a> AccountClient<'a> {
+    /// Get one page (1-based) of the purchase history, most recent purchases first.
+    ///
+    /// Returns an empty list after the last page.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in.
+    pub async fn get_purchased_works(&self, page: u32) -> Result<Vec<PurchasedWork>> {
+        let c = self.c;
+        let path = &format!(
+            "/mypage/userbuy/=/type/all/start/all/sort/1/page/{}",
+            page.max(1)
+        );
+        c.authenticated(move || async move { parse_purchase_html(&c.get_fresh(path).await?) })
+            .await
+    }
+
+    /// Iterate over the whole purchase history, page by page.
+    pub fn purchased_works(&self) -> Paginated<'a, PurchasedWork> {
+        let account = self.clone();
+        Paginated::new(1, None, move |page| {
+            let account = account.clone();
+            async move {
+                let items = account.get_purchased_works(page).await?;
+                Result::Ok(Page { items, total: None })
+            }
+            .maybe_boxed()
+        })
+    }
+}
+
+pub(crate) fn parse_purchase_html(html: &str) -> Result<Vec<PurchasedWork>> {
+    let html = Html::parse_document(html);
+    if is_login_page(&html) {
+        return Err(DlsiteError::Unauthenticated);
+    }
+
+    html.select(&Selector::parse(".work_list_main tr[data-product_id]").unwrap())
+        .map(parse_purchase_item)
+        .collect()
+}
+
+fn parse_purchase_item(item: ElementRef) -> Result<PurchasedWork> {
+    let text_of = |selector: &str| {
+        item.select(&Selector::parse(selector).unwrap())
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .filter(|t| !t.is_empty())
+    };
+
+    let id = item
+        .value()
+        .attr("data-product_id")
+        .to_parse_error("Failed to get product id")?
+        .to_string();
+    let title = text_of(".work_name").to_parse_error("Failed to get title")?;
+    let purchased_at = text_of(".buy_date").and_then(|t| {
+        let date = t.split_whitespace().next()?;
+        NaiveDate::parse_from_str(date, "%Y/%m/%d").ok()
+    });
+    let price = text_of(".work_price").and_then(|t| {
+        t.trim_end_matches('円')
+            .replace(',', "")
+            .trim()
+            .parse()
+            .ok()
+    });
+
+    let download = item
+        .select(&Selector::parse(".work_dl a[href]").unwrap())
+        .next();
+    let download_status = match download {
+        None => DownloadStatus::Unavailable,
+        Some(link) if link.value().classes().any(|c| c == "dl_done") => DownloadStatus::Downloaded,
+        Some(_) => DownloadStatus::Available,
+    };
+
+    Ok(PurchasedWork {
+        id,
+        title,
+        circle_name: text_of(".maker_name").unwrap_or_default(),
+        purchased_at,
+        price,
+        download_status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{parse_purchase_html, DownloadStatus};
+    use crate::DlsiteError;
+
+    const PURCHASE_HTML: &str = r#"
+<table class="work_list_main">
+  <tr data-product_id="RJ01000001">
+    <td class="buy_date">2024/12/01 21:05</td>
+    <td class="work_content">
+      <dt class="work_name"><a href="/maniax/work/=/product_id/RJ01000001.html">新作ボイス</a></dt>
+      <dd class="maker_name"><a href="/maniax/circle/profile/=/maker_id/RG00001.html">Circle A</a></dd>
+    </td>
+    <td class="work_price">1,100円</td>
+    <td class="work_dl"><a class="btn_dl dl_done" href="/maniax/download/=/product_id/RJ01000001.html">ダウンロード</a></td>
+  </tr>
+  <tr data-product_id="RJ01000002">
+    <td class="buy_date">2024/11/15 10:00</td>
+    <td class="work_content">
+      <dt class="work_name"><a>販売終了作品</a></dt>
+      <dd class="maker_name"><a>Circle B</a></dd>
+    </td>
+    <td class="work_price">0円</td>
+    <td class="work_dl">販売終了</td>
+  </tr>
+</table>
+"#;
+
+    #[test]
+    fn parse_purchases() {
+        let works = parse_purchase_html(PURCHASE_HTML).unwrap();
+        assert_eq!(works.len(), 2);
+
+        assert_eq!(works[0].id, "RJ01000001");
+        assert_eq!(works[0].title, "新作ボイス");
+        assert_eq!(works[0].circle_name, "Circle A");
+        assert_eq!(works[0].purchased_at, NaiveDate::from_ymd_opt(2024, 12, 1));
+        assert_eq!(works[0].price, Some(1100));
+        assert_eq!(works[0].download_status, DownloadStatus::Downloaded);
+
+        assert_eq!(works[1].price, Some(0));
+        assert_eq!(works[1].download_status, DownloadStatus::Unavailable);
+    }
+
+    #[test]
+    fn login_required() {
+        let html = r#"<form id="login_form" action="https://login.dlsite.com/login"></form>"#;
+        assert!(matches!(
+            parse_purchase_html(html),
+            Err(DlsiteError::Unauthenticated)
+        ));
+    }
+}
@@ -0,0 +1,252 @@
+//! Favorites (wishlist, "お気に入り") and cart of the logged-in account, through the JSON
+//! endpoints used by the DLsite frontend.
+
+use chrono::NaiveDate;
+use scraper::Html;
+
+use super::AccountClient;
+use crate::{
+    client::{follow::is_login_page, Page, Paginated},
+    error::Result,
+    interface::product_id::ProductId,
+    runtime::MaybeBoxed as _,
+    DlsiteError,
+};
+
+/// A work in the favorites ("お気に入り") of the account.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FavoriteWork {
+    pub id: String,
+    pub title: String,
+    pub circle_name: String,
+    /// Current price in yen, discounts included
+    pub price: Option<i32>,
+    pub added_at: Option<NaiveDate>,
+}
+
+/// A work in the cart of the account.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CartItem {
+    pub id: String,
+    pub title: String,
+    pub circle_name: String,
+    /// Price in yen, discounts included
+    pub price: Option<i32>,
+}
+
+/// Work as listed by the favorites and cart endpoints.
+#[derive(Debug, serde::Deserialize)]
+struct ListedWork {
+    workno: String,
+    work_name: String,
+    #[serde(default)]
+    maker_name: String,
+    price: Option<i32>,
+    /// `YYYY-MM-DD hh:mm:ss`, favorites only
+    insert_date: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct FavoritesResponse {
+    #[serde(default)]
+    works: Vec<ListedWork>,
+    page_info: Option<PageInfo>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PageInfo {
+    count: usize,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CartResponse {
+    #[serde(default)]
+    items: Vec<ListedWork>,
+}
+
+impl<'a> AccountClient<'a> {
+    /// Get one page (1-based) of the favorites, most recently added first.
+    ///
+    /// Returns an empty list after the last page.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in.
+    pub async fn list_favorites(&self, page: u32) -> Result<Vec<FavoriteWork>> {
+        Ok(self.favorites_page(page).await?.items)
+    }
+
+    /// Iterate over all the favorites, page by page.
+    pub fn favorites(&self) -> Paginated<'a, FavoriteWork> {
+        let account = self.clone();
+        Paginated::new(1, None, move |page| {
+            let account = account.clone();
+            async move { account.favorites_page(page).await }.maybe_boxed()
+        })
+    }
+
+    async fn favorites_page(&self, page: u32) -> Result<Page<FavoriteWork>> {
+        let c = self.c;
+        let path = &format!("/mypage/wishlist/ajax/=/page/{}", page.max(1));
+        c.authenticated(move || async move { parse_favorites_response(&c.get_fresh(path).await?) })
+            .await
+    }
+
+    /// Add a work to the favorites.
+    pub async fn add_favorite(&self, product_id: impl Into<ProductId>) -> Result<()> {
+        self.update("/mypage/wishlist/ajax/=/type/add", product_id.into())
+            .await
+    }
+
+    /// Remove a work from the favorites.
+    pub async fn remove_favorite(&self, product_id: impl Into<ProductId>) -> Result<()> {
+        self.update("/mypage/wishlist/ajax/=/type/delete", product_id.into())
+            .await
+    }
+
+    /// Get the works in the cart.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in.
+    pub async fn list_cart(&self) -> Result<Vec<CartItem>> {
+        let c = self.c;
+        c.authenticated(move || async move {
+            parse_cart_response(&c.get_fresh("/cart/ajax/=/mode/list").await?)
+        })
+        .await
+    }
+
+    /// Add a work to the cart.
+    pub async fn add_to_cart(&self, product_id: impl Into<ProductId>) -> Result<()> {
+        self.update("/cart/ajax/=/mode/add", product_id.into())
+            .await
+    }
+
+    /// Remove a work from the cart.
+    pub async fn remove_from_cart(&self, product_id: impl Into<ProductId>) -> Result<()> {
+        self.update("/cart/ajax/=/mode/delete", product_id.into())
+            .await
+    }
+
+    /// Post a change of the favorites or cart.
+    async fn update(&self, path: &str, product_id: ProductId) -> Result<()> {
+        let product_id = product_id.checked()?;
+        let (c, id) = (self.c, product_id.as_str());
+        c.authenticated(move || async move {
+            let body = c.post_form(path, &[("product_id", id)]).await?;
+            parse_update_response(&body)
+        })
+        .await
+    }
+}
+
+/// Parse a JSON response, or detect the login page DLsite serves instead when the session
+/// is missing.
+pub(crate) fn parse_json<T: serde::de::DeserializeOwned>(body: &str) -> Result<T> {
+    serde_json::from_str(body).map_err(|e| {
+        if is_login_page(&Html::parse_document(body)) {
+            DlsiteError::Unauthenticated
+        } else {
+            e.into()
+        }
+    })
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok()
+}
+
+pub(crate) fn parse_favorites_response(body: &str) -> Result<Page<FavoriteWork>> {
+    let response: FavoritesResponse = parse_json(body)?;
+    Ok(Page {
+        items: response
+            .works
+            .into_iter()
+            .map(|work| FavoriteWork {
+                added_at: work.insert_date.as_deref().and_then(parse_date),
+                id: work.workno,
+                title: work.work_name,
+                circle_name: work.maker_name,
+                price: work.price,
+            })
+            .collect(),
+        total: response.page_info.map(|info| info.count),
+    })
+}
+
+pub(crate) fn parse_cart_response(body: &str) -> Result<Vec<CartItem>> {
+    let response: CartResponse = parse_json(body)?;
+    Ok(response
+        .items
+        .into_iter()
+        .map(|work| CartItem {
+            id: work.workno,
+            title: work.work_name,
+            circle_name: work.maker_name,
+            price: work.price,
+        })
+        .collect())
+}
+
+pub(crate) fn parse_update_response(body: &str) -> Result<()> {
+    let json: serde_json::Value = parse_json(body)?;
+    if json["result"].as_bool().unwrap_or(false) {
+        return Ok(());
+    }
+    let message = json["error_msg"]
+        .as_str()
+        .or(json["message"].as_str())
+        .unwrap_or("Failed to get error message");
+    Err(DlsiteError::Server(message.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{parse_cart_response, parse_favorites_response, parse_update_response};
+    use crate::DlsiteError;
+
+    #[test]
+    fn parse_favorites() {
+        let page = parse_favorites_response(
+            r#"{"works": [
+                {"workno": "RJ01000001", "work_name": "新作ボイス", "maker_name": "Circle A",
+                 "price": 880, "insert_date": "2024-12-05 10:00:00"},
+                {"workno": "RJ01000002", "work_name": "予告作品", "price": null}
+            ], "page_info": {"count": 42}}"#,
+        )
+        .unwrap();
+        assert_eq!(page.total, Some(42));
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[0].id, "RJ01000001");
+        assert_eq!(page.items[0].price, Some(880));
+        assert_eq!(page.items[0].added_at, NaiveDate::from_ymd_opt(2024, 12, 5));
+        assert_eq!(page.items[1].circle_name, "");
+        assert_eq!(page.items[1].added_at, None);
+    }
+
+    #[test]
+    fn parse_cart() {
+        let items = parse_cart_response(
+            r#"{"items": [{"workno": "RJ01000001", "work_name": "新作ボイス",
+                "maker_name": "Circle A", "price": 1100}], "total_price": 1100}"#,
+        )
+        .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "新作ボイス");
+        assert!(parse_cart_response(r#"{"items": []}"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_updates() {
+        assert!(parse_update_response(r#"{"result": true}"#).is_ok());
+        assert!(matches!(
+            parse_update_response(r#"{"result": false, "error_msg": "上限に達しました"}"#),
+            Err(DlsiteError::Server(message)) if message == "上限に達しました"
+        ));
+        assert!(matches!(
+            parse_update_response(r#"<form id="login_form"></form>"#),
+            Err(DlsiteError::Unauthenticated)
+        ));
+    }
+}
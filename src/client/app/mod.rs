@@ -0,0 +1,241 @@
+//! Interfaces related to the DLsite app storefront (`appx`: Android games and apps). For more
+//! information, see [`AppClient`].
+
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use scraper::{Html, Selector};
+
+use super::{
+    product::html::slider_images,
+    storefront::{maker_id, maker_link, OutlineTable},
+    DlsiteClient,
+};
+use crate::{
+    error::Result,
+    interface::{
+        genre::Genre,
+        product::{AgeCategory, Platforms},
+        product_id::ProductId,
+        site::Site,
+    },
+    utils::ToParseError as _,
+};
+
+/// Client to get apps from the DLsite app storefront.
+///
+/// App works have `AJ` IDs (routed to [`Site::AppX`] by [`Site::from_product_id`]) or the
+/// usual `RJ`/`VJ` ones, but their pages live on [`Site::AppX`] and describe an APK: its version, the Android version it needs and its in-app purchases.
+/// Requests always go to [`Site::AppX`], whatever the storefront of the client.
+#[derive(Clone, Debug)]
+pub struct AppClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// App data got from the html page.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AppHtml {
+    pub released_at: NaiveDate,
+    pub age_rating: Option<AgeCategory>,
+    pub circle_id: String,
+    pub circle_name: String,
+    /// Version of the APK, e.g. `1.2.0`
+    pub apk_version: Option<String>,
+    /// Oldest Android version supported, e.g. `8.0`
+    pub min_android_version: Option<String>,
+    /// Notes about in-app purchases, as written on the page. Empty if the page doesn't
+    /// mention any.
+    pub in_app_purchases: Vec<String>,
+    pub genre: Vec<Genre>,
+    pub file_size: Option<String>,
+    pub platforms: Platforms,
+    /// 動作環境
+    pub sys_req: Option<String>,
+    pub images: Vec<String>,
+    pub description_html: Option<String>,
+}
+
+/// An app on the DLsite app storefront.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct App {
+    pub id: String,
+    pub title: String,
+    pub price: i32,
+    pub sale_count: Option<i32>,
+    pub rating: Option<f32>,
+    pub rate_count: Option<i32>,
+    #[serde(flatten)]
+    pub html: AppHtml,
+}
+
+impl<'a> AppClient<'a> {
+    /// Get an app: its page and the ajax api are fetched concurrently.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let app = client.app().get("RJ01056473").await.unwrap();
+    ///     println!("{} needs Android {:?}", app.title, app.html.min_android_version);
+    /// }
+    /// ```
+    pub async fn get(&self, product_id: impl Into<ProductId>) -> Result<App> {
+        let product_id = product_id.into().checked()?;
+        let (html, ajax) = self
+            .c
+            .fetch_storefront_work(Site::AppX, &product_id, parse_app_html)
+            .await?;
+        Ok(App {
+            id: product_id.into(),
+            title: ajax.title,
+            price: ajax.price,
+            sale_count: ajax.sale_count,
+            rating: ajax.rating,
+            rate_count: ajax.rate_count,
+            html,
+        })
+    }
+
+    /// Scrape the html page of an app and parse it.
+    pub async fn get_html(&self, product_id: impl Into<ProductId>) -> Result<AppHtml> {
+        let product_id = product_id.into().checked()?;
+        self.c
+            .fetch_storefront_page(Site::AppX, &product_id, parse_app_html)
+            .await
+    }
+}
+
+pub(crate) fn parse_app_html(html: &Html) -> Result<AppHtml> {
+    let table = OutlineTable::new(html);
+
+    let circle = maker_link(html).to_parse_error("No circle found")?;
+    let circle_name = circle.text().collect::<String>().trim().to_string();
+    let circle_id = maker_id(&circle)
+        .to_parse_error("Failed to parse circle id")?
+        .to_string();
+
+    let description = html
+        .select(&Selector::parse("[itemprop='description']").unwrap())
+        .next();
+    let description_text = description
+        .map(|e| e.text().collect::<Vec<_>>().join("\n"))
+        .unwrap_or_default();
+
+    // APK details are spread between the outline table and the description
+    let mut lines: Vec<String> = vec![];
+    for label in ["バージョン", "更新情報", "対応OS", "動作環境", "その他"] {
+        if let Some(e) = table.get(label) {
+            lines.extend(
+                e.text()
+                    .flat_map(|t| t.lines())
+                    .map(|l| l.trim().to_string()),
+            );
+        }
+    }
+    lines.extend(description_text.lines().map(|l| l.trim().to_string()));
+    lines.retain(|l| !l.is_empty());
+
+    let apk_version = table
+        .text("バージョン")
+        .and_then(|v| find_version(&v).or(Some(v)))
+        .or_else(|| lines.iter().find_map(|l| find_version(l)));
+    let min_android_version = lines.iter().find_map(|l| find_min_android(l));
+    let mut in_app_purchases: Vec<String> = vec![];
+    for line in &lines {
+        let lower = line.to_lowercase();
+        if (line.contains("課金") || lower.contains("in-app purchase"))
+            && !in_app_purchases.contains(line)
+        {
+            in_app_purchases.push(line.clone());
+        }
+    }
+
+    let supported_os = table.text("対応OS");
+    let mut platforms = Platforms::from_labels(supported_os.iter().map(|s| s.as_str()));
+    // Everything sold on appx runs on Android, even when 対応OS is missing
+    platforms.android = true;
+
+    Ok(AppHtml {
+        released_at: table.released_at()?,
+        age_rating: table.age_rating()?,
+        circle_id,
+        circle_name,
+        apk_version,
+        min_android_version,
+        in_app_purchases,
+        genre: table.genres(),
+        file_size: table.file_size(),
+        platforms,
+        sys_req: table.text("動作環境"),
+        images: slider_images(html),
+        description_html: description.map(|e| e.inner_html()),
+    })
+}
+
+/// Version number of a line like `Ver.1.2.0` or `バージョン 1.2`.
+fn find_version(line: &str) -> Option<String> {
+    static VERSION: OnceLock<Regex> = OnceLock::new();
+    let version = VERSION.get_or_init(|| {
+        Regex::new(r"(?i)(?:\bver(?:sion)?\.?|バージョン)\s*:?\s*v?(\d+(?:\.\d+)+|\d+)").unwrap()
+    });
+    Some(version.captures(line)?[1].to_string())
+}
+
+/// Minimum Android version of a line like `Android 8.0以上` or `Android 9 or later`.
+fn find_min_android(line: &str) -> Option<String> {
+    static ANDROID: OnceLock<Regex> = OnceLock::new();
+    let android = ANDROID.get_or_init(|| {
+        Regex::new(
+            r"(?i)android\s*(?:os\s*)?(\d+(?:\.\d+)*)\s*(?:以上|以降|or (?:later|higher)|\+)",
+        )
+        .unwrap()
+    });
+    Some(android.captures(line)?[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Html;
+
+    use super::parse_app_html;
+    use crate::DlsiteClient;
+
+    #[test]
+    fn parse_app() {
+        let html = Html::parse_document(
+            r#"<table id="work_maker"><tr><td><span class="maker_name">
+                <a href="https://www.dlsite.com/appx/circle/profile/=/maker_id/RG12345.html">サークルA</a>
+            </span></td></tr></table>
+            <table id="work_outline">
+                <tr><th>販売日</th><td><a href="/appx/new/=/date/2024-01-20/">2024年01月20日</a></td></tr>
+                <tr><th>更新情報</th><td>2024年03月01日 Ver.1.2.0 に更新</td></tr>
+                <tr><th>対応OS</th><td>Android 8.0以上</td></tr>
+                <tr><th>年齢指定</th><td><div class="work_genre"><span>全年齢</span></div></td></tr>
+            </table>
+            <div itemprop="description">
+                放置系RPG。<br>
+                ※本作品はアプリ内課金はありません。<br>
+            </div>"#,
+        );
+        let app = parse_app_html(&html).unwrap();
+        assert_eq!(app.circle_id, "RG12345");
+        assert_eq!(app.apk_version.as_deref(), Some("1.2.0"));
+        assert_eq!(app.min_android_version.as_deref(), Some("8.0"));
+        assert_eq!(
+            app.in_app_purchases,
+            vec!["※本作品はアプリ内課金はありません。"]
+        );
+        assert!(app.platforms.android);
+    }
+
+    #[tokio::test]
+    async fn get_app() {
+        let client = DlsiteClient::default();
+        let app = client.app().get("RJ01056473").await.unwrap();
+        assert!(!app.title.is_empty());
+        assert!(app.html.platforms.android);
+    }
+}
@@ -0,0 +1,401 @@
+//! Logged-in sessions. See [`DlsiteClient::login`].
+//!
+//! The session cookies are stored in the cookie jar of the client, so every request made
+//! afterwards (including by clones of the client) is authenticated. A session can be exported
+//! with [`DlsiteClient::export_session`] and saved to disk, then imported by a later process
+//! instead of logging in again.
+//!
+//! Sessions expire. With [`crate::DlsiteClientBuilder::auto_relogin`], operations needing a
+//! login (purchases, follows, coupons...) log in again and retry once when DLsite answers with
+//! the login page, and [`DlsiteClient::keep_session_alive`] checks the session periodically,
+//! so long-running jobs survive an expiry.
+
+use std::{
+    fmt,
+    future::Future,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use scraper::{Html, Selector};
+
+use super::follow::is_login_page;
+use crate::{
+    error::Result,
+    persist::{self, Persisted},
+    runtime,
+    shutdown::Shutdown,
+    DlsiteClient, DlsiteError,
+};
+
+/// Login form of DLsite's account server
+const LOGIN_URL: &str = "https://login.dlsite.com/login";
+
+/// Cookies of a logged-in session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    /// `(url, cookie)` pairs, the cookie in the `Set-Cookie` format with its domain, path and
+    /// expiration. Sessions exported by older versions only have `name=value`.
+    cookies: Vec<(String, String)>,
+}
+
+impl Persisted for Session {
+    const KIND: &'static str = "session";
+    const VERSION: u32 = 1;
+}
+
+impl Session {
+    /// Load a session saved with [`Session::save`]. Returns `None` if the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        persist::load(path)
+    }
+
+    /// Save the session. The file gives access to the account, so on unix it is only readable
+    /// by its owner.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        persist::save_private(path, self)
+    }
+
+    /// Whether the session holds no cookie.
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+}
+
+/// Credentials kept by a client to log in again, see
+/// [`crate::DlsiteClientBuilder::auto_relogin`].
+pub(crate) struct Relogin {
+    login_id: String,
+    password: String,
+    /// Number of logins done with these credentials
+    logins: AtomicU64,
+    /// Held while logging in, so that concurrent failures log in once
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl fmt::Debug for Relogin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Relogin")
+            .field("login_id", &self.login_id)
+            .field("logins", &self.logins)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Relogin {
+    pub(crate) fn new(login_id: String, password: String) -> Self {
+        Self {
+            login_id,
+            password,
+            logins: AtomicU64::new(0),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+}
+
+impl DlsiteClient {
+    /// Log in to DLsite with a login ID (or email) and password.
+    ///
+    /// On success, the session cookies are kept in the cookie jar of the client and sent with
+    /// every following request, which unlocks pages needing a login (purchases, follows...).
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::LoginFailed`] if DLsite rejects the credentials. Accounts with
+    /// two-factor authentication or a captcha challenge can't log in this way; import the
+    /// cookies of a browser session with [`crate::DlsiteClientBuilder::cookie`] instead.
+    pub async fn login(&self, login_id: &str, password: &str) -> Result<()> {
+        let form = self.fetch(LOGIN_URL.to_string(), false).await?;
+        let token = parse_login_token(&form)?;
+
+        let body = self
+            .post_form_url(
+                LOGIN_URL,
+                &[
+                    ("_token", &token),
+                    ("login_id", login_id),
+                    ("password", password),
+                ],
+            )
+            .await?;
+        if is_login_page(&Html::parse_document(&body)) {
+            return Err(DlsiteError::LoginFailed(
+                "Invalid login ID or password".to_string(),
+            ));
+        }
+
+        // Propagate the session from the account server to the storefronts
+        self.get_fresh("/login/=/skip_register/1").await?;
+        if !self.is_logged_in().await? {
+            return Err(DlsiteError::LoginFailed(
+                "Session was not established".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Run an operation needing a login. If it fails with [`DlsiteError::Unauthenticated`]
+    /// and [`crate::DlsiteClientBuilder::auto_relogin`] is set, log in again and run it once
+    /// more.
+    pub(crate) async fn authenticated<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let Some(relogin) = &self.relogin else {
+            return op().await;
+        };
+        let logins = relogin.logins.load(Ordering::SeqCst);
+        match op().await {
+            Err(DlsiteError::Unauthenticated) => {
+                self.relogin(relogin, logins).await?;
+                op().await
+            }
+            result => result,
+        }
+    }
+
+    /// Log in again, unless another request did since `logins` was read.
+    async fn relogin(&self, relogin: &Relogin, logins: u64) -> Result<()> {
+        let _guard = relogin.lock.lock().await;
+        if relogin.logins.load(Ordering::SeqCst) != logins {
+            return Ok(());
+        }
+        tracing::info!("Session expired, logging in again as {}", relogin.login_id);
+        self.login(&relogin.login_id, &relogin.password).await?;
+        relogin.logins.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Make sure the client has a valid session, logging in again if it expired and
+    /// [`crate::DlsiteClientBuilder::auto_relogin`] is set.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the session expired without auto re-login,
+    /// or the error of the login.
+    pub async fn ensure_logged_in(&self) -> Result<()> {
+        let logins = self
+            .relogin
+            .as_ref()
+            .map(|relogin| relogin.logins.load(Ordering::SeqCst));
+        if self.is_logged_in().await? {
+            return Ok(());
+        }
+        match (&self.relogin, logins) {
+            (Some(relogin), Some(logins)) => self.relogin(relogin, logins).await,
+            _ => Err(DlsiteError::Unauthenticated),
+        }
+    }
+
+    /// Check the session every `interval` with [`DlsiteClient::ensure_logged_in`], so that it
+    /// is renewed before a long-running job needs it.
+    ///
+    /// Runs until `shutdown` is triggered, or the session can't be renewed. Network errors
+    /// are logged and the check is tried again at the next tick.
+    pub async fn keep_session_alive(&self, interval: Duration, shutdown: &Shutdown) -> Result<()> {
+        let mut signal = shutdown.signal();
+        let mut ticker = runtime::interval(interval);
+        loop {
+            tokio::select! {
+                biased;
+                _ = signal.triggered() => return Ok(()),
+                _ = ticker.tick() => {}
+            }
+            match self.ensure_logged_in().await {
+                Ok(()) => {}
+                Err(e @ (DlsiteError::Unauthenticated | DlsiteError::LoginFailed(_))) => {
+                    return Err(e)
+                }
+                Err(e) => tracing::warn!("Failed to check the session: {e}"),
+            }
+        }
+    }
+
+    /// Check whether the client has a valid session, by requesting the account page.
+    pub async fn is_logged_in(&self) -> Result<bool> {
+        let html = self.get_fresh("/mypage").await?;
+        Ok(!is_login_page(&Html::parse_document(&html)))
+    }
+
+    /// Export the DLsite cookies of the client, with their domain, path and expiration.
+    ///
+    /// Not available on `wasm32`, where the browser keeps the cookies.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_session(&self) -> Session {
+        let cookies = self
+            .cookie_jar()
+            .set_cookie_strs()
+            .into_iter()
+            .filter(|(url, _)| {
+                url.host_str()
+                    .is_some_and(|host| crate::utils::is_in_domain(host, "dlsite.com"))
+            })
+            .map(|(url, cookie)| (url.to_string(), cookie))
+            .collect();
+        Session { cookies }
+    }
+
+    /// Add the cookies of an exported session to the cookie jar of the client.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn import_session(&self, session: &Session) {
+        for (url, cookie) in &session.cookies {
+            let Ok(url) = url.parse::<url::Url>() else {
+                continue;
+            };
+            self.cookie_jar().add_cookie_str(cookie, &url);
+        }
+    }
+}
+
+/// CSRF token of the login form.
+fn parse_login_token(html: &str) -> Result<String> {
+    let html = Html::parse_document(html);
+    html.select(&Selector::parse("input[name=\"_token\"]").unwrap())
+        .next()
+        .and_then(|e| e.value().attr("value"))
+        .map(str::to_string)
+        .ok_or_else(|| DlsiteError::Parse("Login token not found".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::{parse_login_token, Session, LOGIN_URL};
+    use crate::{
+        runtime::MaybeBoxFuture,
+        transport::{HttpRequest, HttpResponse, Method, Transport},
+        DlsiteClient, DlsiteError,
+    };
+
+    const LOGIN_FORM: &str = r#"<form id="login_form"><input name="_token" value="t"></form>"#;
+
+    /// DLsite with a session which expired before the first request.
+    #[derive(Debug, Default)]
+    struct Server {
+        logged_in: AtomicBool,
+        logins: AtomicUsize,
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct ServerTransport(Arc<Server>);
+
+    impl Transport for ServerTransport {
+        fn send(
+            &self,
+            request: HttpRequest,
+        ) -> MaybeBoxFuture<'_, crate::error::Result<HttpResponse>> {
+            let server = &self.0;
+            let body = match (request.method, request.url.as_str()) {
+                (Method::Get, LOGIN_URL) => LOGIN_FORM,
+                (Method::Post, LOGIN_URL) => {
+                    server.logged_in.store(true, Ordering::SeqCst);
+                    server.logins.fetch_add(1, Ordering::SeqCst);
+                    "<p>Welcome</p>"
+                }
+                _ if !server.logged_in.load(Ordering::SeqCst) => LOGIN_FORM,
+                (_, url) if url.ends_with("/mypage/follow/works") => {
+                    r#"<ul class="follow_work_list"></ul>"#
+                }
+                _ => "<p>My page</p>",
+            };
+            Box::pin(async move { Ok(HttpResponse::new(200, body)) })
+        }
+    }
+
+    fn client(transport: &ServerTransport) -> crate::DlsiteClientBuilder {
+        DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::ZERO, Duration::ZERO)
+            .transport(transport.clone())
+    }
+
+    #[test]
+    fn login_token() {
+        let html = r#"
+<form id="login_form" action="https://login.dlsite.com/login" method="post">
+  <input type="hidden" name="_token" value="abc123">
+  <input type="text" name="login_id">
+</form>"#;
+        assert_eq!(parse_login_token(html).unwrap(), "abc123");
+        assert!(parse_login_token("<html></html>").is_err());
+    }
+
+    #[test]
+    fn session_roundtrip() {
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .cookie("__DLsite_SID", "session-id")
+            .build();
+        let session = client.export_session();
+        assert!(!session.is_empty());
+
+        let other = DlsiteClient::default();
+        assert!(other.export_session().is_empty());
+        other.import_session(&session);
+        assert_eq!(other.export_session(), session);
+    }
+
+    #[test]
+    fn session_keeps_cookie_attributes() {
+        use reqwest::cookie::CookieStore as _;
+
+        let client = DlsiteClient::default();
+        let url = "https://www.dlsite.com/maniax/".parse().unwrap();
+        client
+            .cookie_jar()
+            .add_cookie_str("sid=1; Domain=.dlsite.com; Path=/maniax; Secure", &url);
+        let other = DlsiteClient::default();
+        other.import_session(&client.export_session());
+
+        let cookies = |url: &str| {
+            other
+                .cookie_jar()
+                .cookies(&url.parse().unwrap())
+                .map(|header| header.to_str().unwrap().to_string())
+        };
+        assert_eq!(
+            cookies("https://login.dlsite.com/maniax/").as_deref(),
+            Some("sid=1")
+        );
+        assert_eq!(cookies("https://www.dlsite.com/books/"), None);
+        assert_eq!(cookies("http://www.dlsite.com/maniax/"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn session_file_is_private() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let path = std::env::temp_dir().join(format!("dlsite-session-{}.json", std::process::id()));
+        Session::default().save(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn relogin_on_expiry() {
+        let transport = ServerTransport::default();
+        let anonymous = client(&transport).build();
+        assert!(matches!(
+            anonymous.follow().new_releases().await,
+            Err(DlsiteError::Unauthenticated)
+        ));
+        assert_eq!(transport.0.logins.load(Ordering::SeqCst), 0);
+
+        let transport = ServerTransport::default();
+        let client = client(&transport).auto_relogin("user", "pass").build();
+        assert!(client.follow().new_releases().await.unwrap().is_empty());
+        assert_eq!(transport.0.logins.load(Ordering::SeqCst), 1);
+
+        // The session is valid now
+        client.ensure_logged_in().await.unwrap();
+        assert!(client.follow().new_releases().await.is_ok());
+        assert_eq!(transport.0.logins.load(Ordering::SeqCst), 1);
+    }
+}
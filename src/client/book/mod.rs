@@ -0,0 +1,222 @@
+//! Interfaces related to the DLsite Books storefront (`BJ` products). For more information,
+//! see [`BookClient`].
+
+use chrono::NaiveDate;
+use scraper::Html;
+
+use super::{
+    product::html::slider_images,
+    storefront::{description_html, maker_id, maker_link, OutlineTable},
+    DlsiteClient,
+};
+use crate::{
+    error::Result,
+    interface::{genre::Genre, product::AgeCategory, product_id::ProductId, site::Site},
+};
+
+/// Client to get books (comics, novels...) from DLsite Books.
+///
+/// Book pages credit authors, illustrators and the original work instead of a circle, and
+/// carry a publisher, an imprint and a magazine of serialization, so they are parsed
+/// separately from [`super::product::ProductClient`]. Requests always go to
+/// [`Site::Books`], whatever the storefront of the client.
+#[derive(Clone, Debug)]
+pub struct BookClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// Publisher of a book (出版社).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Publisher {
+    /// Maker ID, e.g. `BG01234`
+    pub id: Option<String>,
+    pub name: String,
+}
+
+/// Book data got from the html page.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BookHtml {
+    pub released_at: NaiveDate,
+    pub age_rating: Option<AgeCategory>,
+    pub publisher: Option<Publisher>,
+    /// 著者
+    pub authors: Vec<String>,
+    /// 作画 / イラスト
+    pub illustrators: Vec<String>,
+    /// 原作
+    pub original_authors: Vec<String>,
+    /// レーベル
+    pub imprint: Option<String>,
+    /// 掲載誌: magazine the book was serialized in
+    pub serialization: Option<String>,
+    pub series: Option<String>,
+    pub page_count: Option<u32>,
+    pub genre: Vec<Genre>,
+    pub product_format: Vec<String>,
+    pub file_format: Vec<String>,
+    pub file_size: Option<String>,
+    pub images: Vec<String>,
+    pub description_html: Option<String>,
+}
+
+/// A book on DLsite Books.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Book {
+    pub id: String,
+    pub title: String,
+    pub price: i32,
+    pub sale_count: Option<i32>,
+    pub rating: Option<f32>,
+    pub rate_count: Option<i32>,
+    #[serde(flatten)]
+    pub html: BookHtml,
+}
+
+impl<'a> BookClient<'a> {
+    /// Get a book: its page and the ajax api are fetched concurrently.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let book = client.book().get("BJ370220").await.unwrap();
+    ///     println!("{} by {:?}", book.title, book.html.authors);
+    /// }
+    /// ```
+    pub async fn get(&self, product_id: impl Into<ProductId>) -> Result<Book> {
+        let product_id = product_id.into().checked()?;
+        let (html, ajax) = self
+            .c
+            .fetch_storefront_work(Site::Books, &product_id, parse_book_html)
+            .await?;
+        Ok(Book {
+            id: product_id.into(),
+            title: ajax.title,
+            price: ajax.price,
+            sale_count: ajax.sale_count,
+            rating: ajax.rating,
+            rate_count: ajax.rate_count,
+            html,
+        })
+    }
+
+    /// Scrape the html page of a book and parse it.
+    pub async fn get_html(&self, product_id: impl Into<ProductId>) -> Result<BookHtml> {
+        let product_id = product_id.into().checked()?;
+        self.c
+            .fetch_storefront_page(Site::Books, &product_id, parse_book_html)
+            .await
+    }
+}
+
+pub(crate) fn parse_book_html(html: &Html) -> Result<BookHtml> {
+    let table = OutlineTable::new(html);
+
+    // The publisher is shown like the circle of other storefronts
+    let publisher = maker_link(html)
+        .map(|a| Publisher {
+            id: maker_id(&a).map(|id| id.to_string()),
+            name: a.text().collect::<String>().trim().to_string(),
+        })
+        .or_else(|| {
+            table
+                .text("出版社名")
+                .map(|name| Publisher { id: None, name })
+        });
+
+    let page_count = table.text("ページ数").and_then(|pages| {
+        pages
+            .chars()
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse()
+            .ok()
+    });
+
+    Ok(BookHtml {
+        released_at: table.released_at()?,
+        age_rating: table.age_rating()?,
+        publisher,
+        authors: table.links(&["著者", "作者"]),
+        illustrators: table.links(&["作画", "イラスト"]),
+        original_authors: table.links(&["原作"]),
+        imprint: table.text("レーベル"),
+        serialization: table.text("掲載誌"),
+        series: table.text("シリーズ名"),
+        page_count,
+        genre: table.genres(),
+        product_format: table.work_genre("作品形式"),
+        file_format: table.work_genre("ファイル形式"),
+        file_size: table.file_size(),
+        images: slider_images(html),
+        description_html: description_html(html),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use scraper::Html;
+
+    use super::{parse_book_html, Publisher};
+    use crate::{interface::product::AgeCategory, DlsiteClient};
+
+    #[test]
+    fn parse_book() {
+        let html = Html::parse_document(
+            r#"<table id="work_maker"><tr><th>出版社名</th><td><span class="maker_name">
+                <a href="https://www.dlsite.com/books/circle/profile/=/maker_id/BG01234.html">ワニマガジン社</a>
+            </span></td></tr></table>
+            <table id="work_outline">
+                <tr><th>販売日</th><td><a href="/books/new/=/date/2023-04-14/">2023年04月14日</a></td></tr>
+                <tr><th>シリーズ名</th><td><a href="/books/fsr/=/title_id/SRI0001">猫の手も借りたい</a></td></tr>
+                <tr><th>著者</th><td><a href="/books/fsr/=/author/1">山田太郎</a> / <a href="/books/fsr/=/author/2">鈴木花子</a></td></tr>
+                <tr><th>原作</th><td><a href="/books/fsr/=/author/3">佐藤一</a></td></tr>
+                <tr><th>レーベル</th><td><a href="/books/fsr/=/label/1">WANIMAGAZINE COMICS</a></td></tr>
+                <tr><th>掲載誌</th><td><a href="/books/fsr/=/magazine/1">コミック快楽天</a></td></tr>
+                <tr><th>年齢指定</th><td><div class="work_genre"><span>18禁</span></div></td></tr>
+                <tr><th>作品形式</th><td><div class="work_genre"><a><span>マンガ</span></a></div></td></tr>
+                <tr><th>ページ数</th><td>212</td></tr>
+                <tr><th>ジャンル</th><td><div class="main_genre">
+                    <a href="https://www.dlsite.com/books/fsr/=/genre/060/from/work.genre">ラブラブ/あまあま</a>
+                </div></td></tr>
+                <tr><th>ファイル容量</th><td><div>総計 98.5MB</div></td></tr>
+            </table>"#,
+        );
+        let book = parse_book_html(&html).unwrap();
+        assert_eq!(
+            book.released_at,
+            NaiveDate::from_ymd_opt(2023, 4, 14).unwrap()
+        );
+        assert_eq!(book.age_rating, Some(AgeCategory::Adult));
+        assert_eq!(
+            book.publisher,
+            Some(Publisher {
+                id: Some("BG01234".to_string()),
+                name: "ワニマガジン社".to_string(),
+            })
+        );
+        assert_eq!(book.authors, vec!["山田太郎", "鈴木花子"]);
+        assert!(book.illustrators.is_empty());
+        assert_eq!(book.original_authors, vec!["佐藤一"]);
+        assert_eq!(book.imprint.as_deref(), Some("WANIMAGAZINE COMICS"));
+        assert_eq!(book.serialization.as_deref(), Some("コミック快楽天"));
+        assert_eq!(book.series.as_deref(), Some("猫の手も借りたい"));
+        assert_eq!(book.page_count, Some(212));
+        assert_eq!(book.product_format, vec!["マンガ"]);
+        assert_eq!(book.genre[0].id, "060");
+        assert_eq!(book.file_size.as_deref(), Some("98.5MB"));
+    }
+
+    #[tokio::test]
+    async fn get_book() {
+        let client = DlsiteClient::default();
+        let book = client.book().get("BJ370220").await.unwrap();
+        assert!(!book.title.is_empty());
+        assert!(!book.html.authors.is_empty());
+        assert!(book.html.publisher.is_some());
+    }
+}
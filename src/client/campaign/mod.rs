@@ -0,0 +1,382 @@
+//! Interfaces related to sale campaigns. For more information, see [`CampaignClient`].
+
+use std::{ops::Range, sync::OnceLock};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{
+    client::{
+        search::{parse_work_list_page, SearchProductItem},
+        Page, Paginated,
+    },
+    error::Result,
+    runtime::MaybeBoxed as _,
+    utils::ToParseError as _,
+    DlsiteClient,
+};
+
+/// Client to scrape sale campaigns (seasonal sales, circle fairs...) on DLsite.
+#[derive(Clone, Debug)]
+pub struct CampaignClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// Kind of a campaign, guessed from its title and URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CampaignKind {
+    /// Sitewide or seasonal sale
+    Sale,
+    /// Fair of a single circle or publisher
+    CircleFair,
+    Other,
+}
+
+/// A sale campaign.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Campaign {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub kind: CampaignKind,
+    /// `None` if the page only shows the end date
+    pub start: Option<NaiveDateTime>,
+    /// `None` if the campaign has no announced end
+    pub end: Option<NaiveDateTime>,
+    /// Highest discount of the campaign in percent (e.g. 50 for `最大50%OFF`)
+    #[serde(default)]
+    pub discount_rate: Option<u32>,
+    /// Number of works on sale in the campaign
+    #[serde(default)]
+    pub work_count: Option<u32>,
+}
+
+impl Campaign {
+    /// Whether the campaign is running at the given time.
+    pub fn is_ongoing(&self, at: NaiveDateTime) -> bool {
+        self.start.is_none_or(|start| start <= at) && self.end.is_none_or(|end| at < end)
+    }
+
+    /// Whether the campaign starts after the given time.
+    pub fn is_upcoming(&self, at: NaiveDateTime) -> bool {
+        self.start.is_some_and(|start| at < start)
+    }
+
+    fn overlaps(&self, range: &Range<NaiveDate>) -> bool {
+        self.start.is_none_or(|start| start.date() < range.end)
+            && self.end.is_none_or(|end| end.date() >= range.start)
+    }
+}
+
+/// Campaigns overlapping a date range, sorted by start date.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CampaignCalendar {
+    pub campaigns: Vec<Campaign>,
+}
+
+impl CampaignCalendar {
+    /// Campaigns running at the given time.
+    pub fn ongoing(&self, at: NaiveDateTime) -> impl Iterator<Item = &Campaign> {
+        self.campaigns.iter().filter(move |c| c.is_ongoing(at))
+    }
+
+    /// Campaigns starting after the given time.
+    pub fn upcoming(&self, at: NaiveDateTime) -> impl Iterator<Item = &Campaign> {
+        self.campaigns.iter().filter(move |c| c.is_upcoming(at))
+    }
+}
+
+impl<'a> CampaignClient<'a> {
+    /// Get all campaigns listed on the campaign page.
+    pub async fn list(&self) -> Result<Vec<Campaign>> {
+        let html = self.c.get("/campaign").await?;
+        self.c.dump_parse_error(
+            self.c.site(),
+            "/campaign",
+            &html,
+            parse_campaign_list_html(&html),
+        )
+    }
+
+    /// Get ongoing and upcoming campaigns overlapping the given date range.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    /// use chrono::{Days, Local};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let today = Local::now().date_naive();
+    ///     let calendar = client
+    ///         .campaign()
+    ///         .calendar(today..today + Days::new(30))
+    ///         .await
+    ///         .unwrap();
+    ///     for campaign in calendar.campaigns {
+    ///         println!("{:?} ~ {:?}: {}", campaign.start, campaign.end, campaign.title);
+    ///     }
+    /// }
+    /// ```
+    pub async fn calendar(&self, range: Range<NaiveDate>) -> Result<CampaignCalendar> {
+        let mut campaigns: Vec<Campaign> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|c| c.overlaps(&range))
+            .collect();
+        campaigns.sort_by_key(|c| c.start);
+
+        Ok(CampaignCalendar { campaigns })
+    }
+
+    /// List the works on sale in a campaign page by page, see [`Paginated`].
+    ///
+    /// # Arguments
+    /// * `campaign_id` - [`Campaign::id`] of a campaign (not a circle fair).
+    /// * `per_page` - 30, 50 or 100.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let campaign = client.campaign();
+    ///     for sale in campaign.list().await.unwrap() {
+    ///         let mut works = campaign.get_campaign_works(&sale.id, 30);
+    ///         if let Some(page) = works.next_page().await.unwrap() {
+    ///             println!("{}: {:?} works", sale.title, works.total());
+    ///             for work in page {
+    ///                 println!("  {} {:?}", work.title, work.price_sale);
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn get_campaign_works(
+        &self,
+        campaign_id: &str,
+        per_page: u32,
+    ) -> Paginated<'a, SearchProductItem> {
+        let c = self.c;
+        let campaign_id = campaign_id.to_string();
+        Paginated::new(1, Some(per_page), move |page| {
+            let path = format!(
+                "/campaign/=/cp_id/{}/per_page/{}/page/{}",
+                campaign_id, per_page, page
+            );
+            async move {
+                let html = c.get(&path).await?;
+                let (items, count, _) = c.dump_parse_error(
+                    c.site(),
+                    &path,
+                    &html,
+                    parse_work_list_page(&Html::parse_document(&html), c.site()),
+                )?;
+                Result::Ok(Page {
+                    items,
+                    total: Some(count.max(0) as usize),
+                })
+            }
+            .maybe_boxed()
+        })
+    }
+}
+
+pub(crate) fn parse_campaign_list_html(html: &str) -> Result<Vec<Campaign>> {
+    let html = Html::parse_document(html);
+    let mut result = vec![];
+
+    for item in html.select(&Selector::parse(".campaign_list li").unwrap()) {
+        if let Some(campaign) = parse_campaign_item(item)? {
+            result.push(campaign);
+        }
+    }
+
+    Ok(result)
+}
+
+fn parse_campaign_item(item: ElementRef) -> Result<Option<Campaign>> {
+    let Some(link) = item.select(&Selector::parse("a[href]").unwrap()).next() else {
+        return Ok(None);
+    };
+    let url = link
+        .value()
+        .attr("href")
+        .to_parse_error("Failed to get campaign link")?
+        .to_string();
+    let id = url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .to_parse_error("Invalid campaign url")?
+        .trim_end_matches(".html")
+        .to_string();
+    let title = item
+        .select(&Selector::parse(".campaign_title").unwrap())
+        .next()
+        .map(|e| e.text().collect::<String>())
+        .or_else(|| link.value().attr("title").map(|t| t.to_string()))
+        .unwrap_or_else(|| link.text().collect::<String>())
+        .trim()
+        .to_string();
+    let period = item
+        .select(&Selector::parse(".campaign_period").unwrap())
+        .next()
+        .map(|e| e.text().collect::<String>())
+        .unwrap_or_default();
+    let (start, end) = parse_period(&period);
+    let number_in = |selector: &str| {
+        item.select(&Selector::parse(selector).unwrap())
+            .next()
+            .and_then(|e| parse_number(&e.text().collect::<String>()))
+    };
+
+    Ok(Some(Campaign {
+        kind: campaign_kind(&title, &url),
+        id,
+        title,
+        url,
+        start,
+        end,
+        discount_rate: number_in(".campaign_discount"),
+        work_count: number_in(".campaign_work_count"),
+    }))
+}
+
+/// First number of a text such as `最大50%OFF` or `1,234作品`.
+fn parse_number(text: &str) -> Option<u32> {
+    let digits: String = text
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+fn campaign_kind(title: &str, url: &str) -> CampaignKind {
+    if url.contains("/fair/") || title.contains("フェア") {
+        CampaignKind::CircleFair
+    } else if title.contains("セール") || title.to_ascii_uppercase().contains("SALE") {
+        CampaignKind::Sale
+    } else {
+        CampaignKind::Other
+    }
+}
+
+/// Parse periods like `2024年12月1日 00:00 ～ 2024年12月20日 23:59` or `2024年12月20日 23:59まで`.
+fn parse_period(text: &str) -> (Option<NaiveDateTime>, Option<NaiveDateTime>) {
+    static DATE_RE: OnceLock<Regex> = OnceLock::new();
+    let re = DATE_RE.get_or_init(|| {
+        Regex::new(r"(\d{4})年(\d{1,2})月(\d{1,2})日(?:\s*[（(][^)）]*[)）])?\s*(?:(\d{1,2}):(\d{2}))?")
+            .unwrap()
+    });
+
+    let dates: Vec<NaiveDateTime> = re
+        .captures_iter(text)
+        .filter_map(|cap| {
+            let date = NaiveDate::from_ymd_opt(
+                cap[1].parse().ok()?,
+                cap[2].parse().ok()?,
+                cap[3].parse().ok()?,
+            )?;
+            let time = match (cap.get(4), cap.get(5)) {
+                (Some(h), Some(m)) => {
+                    NaiveTime::from_hms_opt(h.as_str().parse().ok()?, m.as_str().parse().ok()?, 0)?
+                }
+                _ => NaiveTime::MIN,
+            };
+            Some(date.and_time(time))
+        })
+        .collect();
+
+    match dates.as_slice() {
+        [start, end, ..] => (Some(*start), Some(*end)),
+        [end] if text.contains("まで") => (None, Some(*end)),
+        [start] => (Some(*start), None),
+        [] => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{parse_campaign_list_html, CampaignCalendar, CampaignKind};
+
+    const CAMPAIGN_HTML: &str = r#"
+<ul class="campaign_list">
+  <li>
+    <a href="https://www.dlsite.com/maniax/campaign/=/cp_id/winter2024">
+      <span class="campaign_title">冬のビッグセール</span>
+    </a>
+    <span class="campaign_period">2024年12月1日(日) 00:00 ～ 2024年12月20日(金) 23:59</span>
+    <span class="campaign_discount">最大70%OFF</span>
+    <span class="campaign_work_count">12,345作品</span>
+  </li>
+  <li>
+    <a href="/maniax/fair/=/maker_id/RG00001.html" title="サークルAフェア"></a>
+    <span class="campaign_period">2024年12月10日 23:59まで</span>
+  </li>
+  <li>
+    <a href="/maniax/campaign/=/cp_id/newyear"><span class="campaign_title">お年玉企画</span></a>
+    <span class="campaign_period">2025年1月1日 00:00 ～</span>
+  </li>
+</ul>
+"#;
+
+    #[test]
+    fn parse_campaigns() {
+        let campaigns = parse_campaign_list_html(CAMPAIGN_HTML).unwrap();
+        assert_eq!(campaigns.len(), 3);
+
+        assert_eq!(campaigns[0].id, "winter2024");
+        assert_eq!(campaigns[0].title, "冬のビッグセール");
+        assert_eq!(campaigns[0].kind, CampaignKind::Sale);
+        assert_eq!(
+            campaigns[0].start,
+            NaiveDate::from_ymd_opt(2024, 12, 1).unwrap().and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(
+            campaigns[0].end,
+            NaiveDate::from_ymd_opt(2024, 12, 20).unwrap().and_hms_opt(23, 59, 0)
+        );
+
+        assert_eq!(campaigns[0].discount_rate, Some(70));
+        assert_eq!(campaigns[0].work_count, Some(12345));
+
+        assert_eq!(campaigns[1].id, "RG00001");
+        assert_eq!(campaigns[1].title, "サークルAフェア");
+        assert_eq!(campaigns[1].kind, CampaignKind::CircleFair);
+        assert_eq!(campaigns[1].start, None);
+        assert!(campaigns[1].end.is_some());
+        assert_eq!(campaigns[1].discount_rate, None);
+
+        assert_eq!(campaigns[2].kind, CampaignKind::Other);
+        assert!(campaigns[2].start.is_some());
+        assert_eq!(campaigns[2].end, None);
+
+        let range = NaiveDate::from_ymd_opt(2024, 12, 15).unwrap()
+            ..NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        let mut in_range: Vec<_> = campaigns.into_iter().filter(|c| c.overlaps(&range)).collect();
+        in_range.sort_by_key(|c| c.start);
+        assert_eq!(
+            in_range.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+            vec!["winter2024", "newyear"]
+        );
+
+        let calendar = CampaignCalendar { campaigns: in_range };
+        let now = NaiveDate::from_ymd_opt(2024, 12, 18)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(calendar.ongoing(now).count(), 1);
+        assert_eq!(calendar.upcoming(now).next().unwrap().id, "newyear");
+    }
+}
@@ -2,13 +2,21 @@
 
 mod query;
 
-use scraper::{Html, Selector};
+use std::{fmt, sync::OnceLock};
+
+use scraper::Html;
 
 use super::{
-    search::{parse_search_html, SearchResult},
-    DlsiteClient,
+    search::{parse_work_list_page, SearchProductItem, SearchResult},
+    DlsiteClient, Page, Paginated,
+};
+use crate::{
+    error::Result,
+    interface::site::Site,
+    runtime::MaybeBoxed as _,
+    selector::{ParseReport, SelectorChain},
+    utils::ToParseError as _,
 };
-use crate::{error::Result, utils::ToParseError as _};
 
 pub use self::query::CircleQuery;
 
@@ -18,41 +26,437 @@ pub struct CircleClient<'a> {
     pub(crate) c: &'a DlsiteClient,
 }
 
+/// Number of works per request when walking all works of a circle.
+const STATS_PER_PAGE: u32 = 100;
+
+/// Follower and download counts of a circle.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CircleStats {
+    /// Number of followers, if displayed on the profile page
+    pub followers: Option<i32>,
+    /// Number of works
+    pub works: i32,
+    /// Sum of the download counts of the works (works with hidden counts are skipped)
+    pub downloads: i64,
+}
+
+/// Circle (maker) ID, e.g. `RG24350`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct CircleId(pub String);
+
+impl CircleId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Read the ID from a link to a circle page, e.g.
+    /// `/maniax/circle/profile/=/maker_id/RG24350.html`.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let (_, rest) = url.split_once("maker_id/")?;
+        let id: String = rest
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect();
+        (!id.is_empty()).then_some(CircleId(id))
+    }
+}
+
+impl fmt::Display for CircleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for CircleId {
+    fn from(id: &str) -> Self {
+        CircleId(id.to_string())
+    }
+}
+
+impl AsRef<str> for CircleId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Header of a circle profile page.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CircleProfile {
+    pub id: CircleId,
+    pub name: String,
+    pub followers: Option<i32>,
+    /// Links to the circle's pages on other sites
+    pub links: Vec<CircleLink>,
+    /// Self-introduction written by the circle
+    pub profile_text: Option<String>,
+    /// Total number of works on sale
+    pub work_count: Option<i32>,
+    /// Related circles and other labels of the same creator (関連サークル, 別名義)
+    pub related: Vec<CircleId>,
+}
+
+/// Link to a page of a circle outside DLsite.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CircleLink {
+    pub kind: CircleLinkKind,
+    pub url: String,
+}
+
+/// Site a [`CircleLink`] points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircleLinkKind {
+    /// Twitter / X
+    Twitter,
+    Cien,
+    Pixiv,
+    /// Any other site, usually the circle's own website
+    Website,
+}
+
+impl CircleLinkKind {
+    fn from_url(url: &str) -> Self {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let is = |domain: &str| host == domain || host.ends_with(&format!(".{domain}"));
+        if is("twitter.com") || is("x.com") {
+            CircleLinkKind::Twitter
+        } else if is("ci-en.jp") || is("ci-en.net") || is("ci-en.dlsite.com") {
+            CircleLinkKind::Cien
+        } else if is("pixiv.net") {
+            CircleLinkKind::Pixiv
+        } else {
+            CircleLinkKind::Website
+        }
+    }
+}
+
+/// A parsed page of the circle profile.
+struct CirclePage {
+    products: Vec<SearchProductItem>,
+    count: i32,
+    followers: Option<i32>,
+    report: ParseReport,
+}
+
 impl<'a> CircleClient<'a> {
     /// Search circle-related products.
     pub async fn get_circle(&self, circle_id: &str, options: &CircleQuery) -> Result<SearchResult> {
         let query_path = options.to_path(circle_id);
         let html = self.c.get(&query_path).await?;
-        let html = Html::parse_fragment(&html);
-        let products_html = html
-            .select(&Selector::parse("#search_result_list").unwrap())
-            .next()
-            .to_parse_error("Product list not found")?;
-
-        let count: i32 = html
-            .select(&Selector::parse(".page_total > strong").unwrap())
-            .next()
-            .to_parse_error("No total item count found")?
-            .text()
-            .next()
-            .to_parse_error("No total item count found 2")?
-            .parse()
-            .to_parse_error("Failed to parse total item count")?;
-
-        let products = parse_search_html(&products_html.html())?;
+        let page = parse_circle_page(&html, self.c.site())?;
 
         Ok(SearchResult {
-            products,
-            count,
+            products: page.products,
+            count: page.count,
             query_path,
+            report: page.report,
+            skipped: vec![],
+        })
+    }
+
+    /// Get the profile of a circle: name, follower count, links to other sites, profile text,
+    /// number of works and related circles.
+    pub async fn get_profile(&self, circle_id: &str) -> Result<CircleProfile> {
+        // Same page as `get_circle` with default options, so it may come from the cache
+        let html = self
+            .c
+            .get(&CircleQuery::default().to_path(circle_id))
+            .await?;
+        parse_circle_profile(&html, circle_id)
+    }
+
+    /// Get the follower count and the total download count of a circle.
+    ///
+    /// All works of the circle are listed to sum their download counts, which takes one
+    /// request per 100 works.
+    pub async fn stats(&self, circle_id: &str) -> Result<CircleStats> {
+        // Same page as the first one listed below, so it is only requested once
+        let first_page = CircleQuery {
+            per_page: Some(STATS_PER_PAGE),
+            page: Some(1),
+            ..Default::default()
+        };
+        let html = self.c.get(&first_page.to_path(circle_id)).await?;
+        let followers = parse_circle_page(&html, self.c.site())?.followers;
+
+        let mut pages = self.works(circle_id, STATS_PER_PAGE);
+        let mut downloads = 0;
+        while let Some(works) = pages.next_page().await? {
+            downloads += works
+                .iter()
+                .filter_map(|p| p.dl_count)
+                .map(i64::from)
+                .sum::<i64>();
+        }
+
+        Ok(CircleStats {
+            followers,
+            works: pages.total().unwrap_or_default() as i32,
+            downloads,
+        })
+    }
+
+    /// List all works of a circle page by page, see [`Paginated`].
+    ///
+    /// # Arguments
+    /// * `per_page` - 30, 50 or 100.
+    pub fn works(&self, circle_id: &str, per_page: u32) -> Paginated<'a, SearchProductItem> {
+        let c = self.c;
+        let circle_id = circle_id.to_string();
+        Paginated::new(1, Some(per_page), move |page| {
+            let path = CircleQuery {
+                per_page: Some(per_page),
+                page: Some(page),
+                ..Default::default()
+            }
+            .to_path(&circle_id);
+            async move {
+                let html = c.get(&path).await?;
+                let parsed = parse_circle_page(&html, c.site())?;
+                Result::Ok(Page {
+                    items: parsed.products,
+                    total: Some(parsed.count.max(0) as usize),
+                })
+            }
+            .maybe_boxed()
         })
     }
 }
 
+/// Selector of the follower count on the circle profile page.
+fn follower_count() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new(
+            "followers",
+            &[".prof_follow_count", ".follow_count", ".btn_follow .count"],
+        )
+    })
+}
+
+/// Selector of the circle name on the circle profile page.
+fn circle_name() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| SelectorChain::new("name", &[".prof_maker_name", ".circle_name", "h1"]))
+}
+
+/// Selector of the external links on the circle profile page.
+fn circle_links() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new(
+            "links",
+            &[
+                ".prof_link a[href]",
+                ".circle_link a[href]",
+                ".prof_sns a[href]",
+            ],
+        )
+    })
+}
+
+/// Selector of the self-introduction on the circle profile page.
+fn profile_text() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new(
+            "profile_text",
+            &[".prof_text", ".prof_comment", ".circle_profile_text"],
+        )
+    })
+}
+
+/// Selector of the total number of works on the circle profile page.
+fn work_count() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new("work_count", &[".page_total > strong", ".prof_work_count"])
+    })
+}
+
+/// Parse a number such as `1,234` or `1,234件`.
+fn parse_number(text: &str) -> Option<i32> {
+    let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// Selector of the links to related circles on the circle profile page.
+fn related_circles() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new(
+            "related",
+            &[
+                ".prof_related a[href*=\"maker_id\"]",
+                ".circle_related a[href*=\"maker_id\"]",
+                ".another_name a[href*=\"maker_id\"]",
+            ],
+        )
+    })
+}
+
+pub(crate) fn parse_circle_profile(html: &str, circle_id: &str) -> Result<CircleProfile> {
+    let html = Html::parse_document(html);
+    let mut report = ParseReport::default();
+
+    let name = circle_name()
+        .select(html.root_element(), &mut report)
+        .map(|e| e.text().collect::<String>().trim().to_string())
+        .filter(|name| !name.is_empty())
+        .to_parse_error("Circle name not found")?;
+
+    let followers = follower_count()
+        .select(html.root_element(), &mut report)
+        .and_then(|e| parse_number(&e.text().collect::<String>()));
+    let work_count = work_count()
+        .select(html.root_element(), &mut report)
+        .and_then(|e| parse_number(&e.text().collect::<String>()));
+    let profile_text = profile_text()
+        .select(html.root_element(), &mut report)
+        .map(|e| e.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty());
+
+    let mut links: Vec<CircleLink> = vec![];
+    for link in circle_links().select_all(&html, &mut report) {
+        let Some(url) = link.value().attr("href") else {
+            continue;
+        };
+        if !url.starts_with("http") || links.iter().any(|l| l.url == url) {
+            continue;
+        }
+        links.push(CircleLink {
+            kind: CircleLinkKind::from_url(url),
+            url: url.to_string(),
+        });
+    }
+
+    let id = CircleId::from(circle_id);
+    let mut related: Vec<CircleId> = vec![];
+    for link in related_circles().select_all(&html, &mut report) {
+        let Some(other) = link.value().attr("href").and_then(CircleId::from_url) else {
+            continue;
+        };
+        if other != id && !related.contains(&other) {
+            related.push(other);
+        }
+    }
+
+    Ok(CircleProfile {
+        id,
+        name,
+        followers,
+        links,
+        profile_text,
+        work_count,
+        related,
+    })
+}
+
+fn parse_circle_page(html: &str, site: Site) -> Result<CirclePage> {
+    let html = Html::parse_fragment(html);
+    let (products, count, mut report) = parse_work_list_page(&html, site)?;
+    let followers = follower_count()
+        .select(html.root_element(), &mut report)
+        .and_then(|e| {
+            e.text()
+                .collect::<String>()
+                .replace(',', "")
+                .trim()
+                .parse()
+                .ok()
+        });
+
+    Ok(CirclePage {
+        products,
+        count,
+        followers,
+        report,
+    })
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{parse_circle_profile, CircleId, CircleLink, CircleLinkKind};
     use crate::DlsiteClient;
 
+    #[test]
+    fn circle_profile_related() {
+        let html = r#"
+<div class="prof_maker">
+  <h1 class="prof_maker_name">Circle A</h1>
+  <ul class="prof_related">
+    <li><a href="https://www.dlsite.com/maniax/circle/profile/=/maker_id/RG00002.html">Circle B</a></li>
+    <li><a href="/home/circle/profile/=/maker_id/RG00003.html">Circle C</a></li>
+    <li><a href="/maniax/circle/profile/=/maker_id/RG00001.html">Circle A</a></li>
+    <li><a href="/maniax/circle/profile/=/maker_id/RG00002.html">Circle B</a></li>
+  </ul>
+</div>"#;
+        let profile = parse_circle_profile(html, "RG00001").unwrap();
+        assert_eq!(profile.id, CircleId::from("RG00001"));
+        assert_eq!(profile.name, "Circle A");
+        assert_eq!(
+            profile.related,
+            vec![CircleId::from("RG00002"), CircleId::from("RG00003")]
+        );
+        assert_eq!(profile.followers, None);
+        assert!(profile.links.is_empty());
+    }
+
+    #[test]
+    fn circle_profile_header() {
+        let html = r#"
+<div class="prof_maker">
+  <h1 class="prof_maker_name">Circle A</h1>
+  <span class="prof_follow_count">1,234</span>
+  <div class="prof_text">
+    Making voice works since 2015.
+  </div>
+  <ul class="prof_link">
+    <li><a href="https://x.com/circle_a">X</a></li>
+    <li><a href="https://ci-en.dlsite.com/creator/1234">Ci-en</a></li>
+    <li><a href="https://www.pixiv.net/users/5678">pixiv</a></li>
+    <li><a href="https://circle-a.example.com/">HP</a></li>
+    <li><a href="/maniax/circle/profile/=/maker_id/RG00001.html">DLsite</a></li>
+  </ul>
+</div>
+<div class="page_total"><strong>42</strong>件中</div>"#;
+        let profile = parse_circle_profile(html, "RG00001").unwrap();
+        assert_eq!(profile.followers, Some(1234));
+        assert_eq!(profile.work_count, Some(42));
+        assert_eq!(
+            profile.profile_text.as_deref(),
+            Some("Making voice works since 2015.")
+        );
+        assert_eq!(
+            profile.links,
+            vec![
+                CircleLink {
+                    kind: CircleLinkKind::Twitter,
+                    url: "https://x.com/circle_a".to_string(),
+                },
+                CircleLink {
+                    kind: CircleLinkKind::Cien,
+                    url: "https://ci-en.dlsite.com/creator/1234".to_string(),
+                },
+                CircleLink {
+                    kind: CircleLinkKind::Pixiv,
+                    url: "https://www.pixiv.net/users/5678".to_string(),
+                },
+                CircleLink {
+                    kind: CircleLinkKind::Website,
+                    url: "https://circle-a.example.com/".to_string(),
+                },
+            ]
+        );
+        assert!(profile.related.is_empty());
+    }
+
     #[tokio::test]
     async fn get_circle_1() {
         let client = DlsiteClient::default();
@@ -87,4 +491,23 @@ mod tests {
 
         assert!(!res.products.is_empty());
     }
+
+    #[tokio::test]
+    async fn circle_stats() {
+        let client = DlsiteClient::default();
+        let stats = client.circle().stats("RG24350").await.unwrap();
+
+        assert!(stats.works > 50);
+        assert!(stats.downloads > 0);
+    }
+
+    #[tokio::test]
+    async fn circle_profile() {
+        let client = DlsiteClient::default();
+        let profile = client.circle().get_profile("RG24350").await.unwrap();
+
+        assert_eq!(profile.id, CircleId::from("RG24350"));
+        assert!(!profile.name.is_empty());
+        assert!(profile.work_count.unwrap() > 50);
+    }
 }
@@ -0,0 +1,75 @@
+//! Cookie jar of a client. See [`CookieJar`].
+
+use std::sync::RwLock;
+
+use cookie_store::{CookieDomain, CookieExpiration, CookieStore, RawCookie};
+use reqwest::header::HeaderValue;
+
+/// Cookies sent with every request of a client (login session, age confirmation...).
+///
+/// Unlike [`reqwest::cookie::Jar`], the domain, path and expiration of the stored cookies can be
+/// read back, so that [`crate::DlsiteClient::export_session`] keeps them.
+#[derive(Debug, Default)]
+pub struct CookieJar(RwLock<CookieStore>);
+
+impl CookieJar {
+    /// Add a cookie in the `Set-Cookie` format, as if it was set by a response from `url`.
+    pub fn add_cookie_str(&self, cookie: &str, url: &url::Url) {
+        let Ok(cookie) = RawCookie::parse(cookie.to_string()) else {
+            return;
+        };
+        self.0
+            .write()
+            .unwrap()
+            .store_response_cookies(std::iter::once(cookie), url);
+    }
+
+    /// Unexpired cookies in the `Set-Cookie` format, with a URL of the host and path they were
+    /// set for, so that [`CookieJar::add_cookie_str`] restores them as they were.
+    pub(crate) fn set_cookie_strs(&self) -> Vec<(url::Url, String)> {
+        let store = self.0.read().unwrap();
+        store
+            .iter_unexpired()
+            .filter_map(|cookie| {
+                let host = cookie.domain.as_cow()?;
+                let url = format!("https://{}{}", host, &*cookie.path).parse().ok()?;
+
+                let mut raw = RawCookie::new(cookie.name().to_string(), cookie.value().to_string());
+                if let CookieDomain::Suffix(domain) = &cookie.domain {
+                    raw.set_domain(domain.clone());
+                }
+                raw.set_path(cookie.path.to_string());
+                raw.set_secure(cookie.secure());
+                raw.set_http_only(cookie.http_only());
+                if let CookieExpiration::AtUtc(expires) = cookie.expires {
+                    raw.set_expires(expires);
+                }
+                Some((url, raw.to_string()))
+            })
+            .collect()
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &url::Url) {
+        let cookies = cookie_headers
+            .filter_map(|header| header.to_str().ok())
+            .filter_map(|header| RawCookie::parse(header.to_string()).ok());
+        self.0.write().unwrap().store_response_cookies(cookies, url);
+    }
+
+    fn cookies(&self, url: &url::Url) -> Option<HeaderValue> {
+        let header = self
+            .0
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
@@ -0,0 +1,228 @@
+//! Interfaces related to coupons. For more information, see [`CouponClient`].
+
+use std::sync::OnceLock;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{
+    client::follow::is_login_page, error::Result, utils::ToParseError as _, DlsiteClient,
+    DlsiteError,
+};
+
+/// Client to find and claim coupons on DLsite.
+///
+/// Listing coupons works without a login, but claiming them needs a logged-in session, see
+/// [`crate::DlsiteClientBuilder::cookie`].
+#[derive(Clone, Debug)]
+pub struct CouponClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// Discount given by a coupon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CouponDiscount {
+    /// Discount rate in percent
+    Percent(u32),
+    /// Fixed discount in yen
+    Amount(i32),
+}
+
+/// A coupon listed on the coupon page.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Coupon {
+    pub id: String,
+    pub name: String,
+    pub discount: Option<CouponDiscount>,
+    /// Usage conditions (e.g. target genres or minimum price), as displayed
+    pub conditions: Option<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    /// Whether the coupon can be claimed now (`false` if already claimed)
+    pub claimable: bool,
+}
+
+impl<'a> CouponClient<'a> {
+    /// Get publicly claimable coupons (welcome-back, genre coupons...).
+    pub async fn available(&self) -> Result<Vec<Coupon>> {
+        let html = self.c.get_fresh("/coupon/list").await?;
+        parse_coupon_list_html(&html)
+    }
+
+    /// Claim a coupon. Needs a logged-in session.
+    ///
+    /// # Arguments
+    /// * `coupon_id` - ID of the coupon, see [`Coupon::id`].
+    pub async fn claim(&self, coupon_id: &str) -> Result<()> {
+        let c = self.c;
+        c.authenticated(move || async move {
+            let body = c
+                .post_form("/coupon/acquire", &[("coupon_id", coupon_id)])
+                .await?;
+            parse_claim_response(&body)
+        })
+        .await
+    }
+}
+
+pub(crate) fn parse_claim_response(body: &str) -> Result<()> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        // DLsite answers with the login page when the session is missing
+        if is_login_page(&Html::parse_document(body)) {
+            return Err(DlsiteError::Unauthenticated);
+        }
+        return Err(DlsiteError::Parse(
+            "Failed to parse coupon response".to_string(),
+        ));
+    };
+
+    if json["result"].as_bool().unwrap_or(false) {
+        Ok(())
+    } else {
+        let message = json["error_msg"]
+            .as_str()
+            .or(json["message"].as_str())
+            .unwrap_or("Failed to get error message");
+        Err(DlsiteError::Server(format!(
+            "Failed to claim coupon: {}",
+            message
+        )))
+    }
+}
+
+pub(crate) fn parse_coupon_list_html(html: &str) -> Result<Vec<Coupon>> {
+    let html = Html::parse_document(html);
+    html.select(&Selector::parse(".coupon_list [data-coupon_id]").unwrap())
+        .map(parse_coupon_item)
+        .collect()
+}
+
+fn parse_coupon_item(item: ElementRef) -> Result<Coupon> {
+    let text_of = |selector: &str| {
+        item.select(&Selector::parse(selector).unwrap())
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .filter(|t| !t.is_empty())
+    };
+
+    let id = item
+        .value()
+        .attr("data-coupon_id")
+        .to_parse_error("Failed to get coupon id")?
+        .to_string();
+    let name = text_of(".coupon_name").to_parse_error("Failed to get coupon name")?;
+    let discount = text_of(".coupon_discount").and_then(|t| parse_discount(&t));
+    let expires_at = text_of(".coupon_period").and_then(|t| parse_expiry(&t));
+    let claimable = item
+        .select(&Selector::parse(".btn_coupon_get:not(.disabled)").unwrap())
+        .next()
+        .is_some();
+
+    Ok(Coupon {
+        id,
+        name,
+        discount,
+        conditions: text_of(".coupon_condition"),
+        expires_at,
+        claimable,
+    })
+}
+
+/// Parse texts like `30%OFF` or `300円OFF`.
+fn parse_discount(text: &str) -> Option<CouponDiscount> {
+    let text = text.replace(',', "").replace('％', "%");
+    if let Some((rate, _)) = text.split_once('%') {
+        return rate.trim().parse().ok().map(CouponDiscount::Percent);
+    }
+    let (amount, _) = text.split_once('円')?;
+    amount.trim().parse().ok().map(CouponDiscount::Amount)
+}
+
+fn parse_expiry(text: &str) -> Option<NaiveDateTime> {
+    static DATE_RE: OnceLock<Regex> = OnceLock::new();
+    let re = DATE_RE.get_or_init(|| {
+        Regex::new(r"(\d{4})[年/](\d{1,2})[月/](\d{1,2})日?\s*(?:(\d{1,2}):(\d{2}))?").unwrap()
+    });
+    // The last date is the end of the period
+    let cap = re.captures_iter(text).last()?;
+    let date = NaiveDate::from_ymd_opt(
+        cap[1].parse().ok()?,
+        cap[2].parse().ok()?,
+        cap[3].parse().ok()?,
+    )?;
+    let time = match (cap.get(4), cap.get(5)) {
+        (Some(h), Some(m)) => {
+            NaiveTime::from_hms_opt(h.as_str().parse().ok()?, m.as_str().parse().ok()?, 0)?
+        }
+        _ => NaiveTime::from_hms_opt(23, 59, 59)?,
+    };
+    Some(date.and_time(time))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{parse_claim_response, parse_coupon_list_html, CouponDiscount};
+    use crate::DlsiteError;
+
+    const COUPON_HTML: &str = r#"
+<div class="coupon_list">
+  <div class="coupon_item" data-coupon_id="CP001">
+    <p class="coupon_name">おかえりなさいクーポン</p>
+    <p class="coupon_discount">30%OFF</p>
+    <p class="coupon_condition">全作品対象</p>
+    <p class="coupon_period">2024/12/31 23:59まで</p>
+    <a class="btn_coupon_get">獲得する</a>
+  </div>
+  <div class="coupon_item" data-coupon_id="CP002">
+    <p class="coupon_name">ASMRクーポン</p>
+    <p class="coupon_discount">300円OFF</p>
+    <p class="coupon_period">2025年1月15日まで</p>
+    <a class="btn_coupon_get disabled">獲得済み</a>
+  </div>
+</div>
+"#;
+
+    #[test]
+    fn parse_coupons() {
+        let coupons = parse_coupon_list_html(COUPON_HTML).unwrap();
+        assert_eq!(coupons.len(), 2);
+
+        assert_eq!(coupons[0].id, "CP001");
+        assert_eq!(coupons[0].name, "おかえりなさいクーポン");
+        assert_eq!(coupons[0].discount, Some(CouponDiscount::Percent(30)));
+        assert_eq!(coupons[0].conditions.as_deref(), Some("全作品対象"));
+        assert_eq!(
+            coupons[0].expires_at,
+            NaiveDate::from_ymd_opt(2024, 12, 31)
+                .unwrap()
+                .and_hms_opt(23, 59, 0)
+        );
+        assert!(coupons[0].claimable);
+
+        assert_eq!(coupons[1].discount, Some(CouponDiscount::Amount(300)));
+        assert_eq!(coupons[1].conditions, None);
+        assert_eq!(
+            coupons[1].expires_at,
+            NaiveDate::from_ymd_opt(2025, 1, 15)
+                .unwrap()
+                .and_hms_opt(23, 59, 59)
+        );
+        assert!(!coupons[1].claimable);
+    }
+
+    #[test]
+    fn claim_response() {
+        assert!(parse_claim_response(r#"{"result": true}"#).is_ok());
+        assert!(matches!(
+            parse_claim_response(r#"{"result": false, "error_msg": "expired"}"#),
+            Err(DlsiteError::Server(_))
+        ));
+        assert!(matches!(
+            parse_claim_response(r#"<html><form id="login_form"></form></html>"#),
+            Err(DlsiteError::Unauthenticated)
+        ));
+    }
+}
@@ -0,0 +1,313 @@
+//! Interfaces related to creators (voice actors, illustrators...). For more information, see
+//! [`CreatorClient`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::{
+    product_api::interface::{Creator, ProductApiContent},
+    search::{SearchProductItem, SearchProductQuery},
+    DlsiteClient,
+};
+use crate::{error::Result, interface::product_id::ProductId, DlsiteError};
+
+/// Client to get the works credited to a creator.
+#[derive(Clone, Debug)]
+pub struct CreatorClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// Role of a creator in a work.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CreatorRole {
+    /// 作者
+    Author,
+    /// 声優
+    VoiceActor,
+    /// イラスト
+    Illustrator,
+    /// シナリオ
+    Scenario,
+    /// Listed by DLsite's creator search, but not credited under a known role
+    Other,
+}
+
+/// Options for [`CreatorClient::get`].
+#[derive(Default)]
+pub struct CreatorQuery {
+    /// 30, 50 or 100
+    pub per_page: Option<u32>,
+    pub page: Option<u32>,
+}
+
+/// Works of a creator, grouped by role.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CreatorProfile {
+    pub name: String,
+    /// Creator ID, when found in the credits of one of the works
+    pub id: Option<String>,
+    /// Total number of works found (on all pages)
+    pub count: i32,
+    /// Works of the requested page by role. A work appears under each role the creator had.
+    pub works: BTreeMap<CreatorRole, Vec<SearchProductItem>>,
+}
+
+impl CreatorProfile {
+    /// Roles the creator had in a work of the requested page.
+    pub fn roles_of(&self, product_id: &str) -> Vec<CreatorRole> {
+        self.works
+            .iter()
+            .filter(|(_, works)| works.iter().any(|w| w.id == product_id))
+            .map(|(role, _)| *role)
+            .collect()
+    }
+}
+
+impl<'a> CreatorClient<'a> {
+    /// Get the works credited to a creator, grouped by role.
+    ///
+    /// Works are listed like on DLsite's creator page (a search by creator), then the roles are
+    /// read from the credits of the works using the product api, with one request per 100
+    /// works (see [`super::product_api::ProductApiClient::get_many`]).
+    ///
+    /// # Arguments
+    /// * `creator` - Creator name (e.g. `春花らん`). Credits are matched by name, and the ID
+    ///   of the creator is read from them.
+    ///
+    /// # Errors
+    /// DLsite only lists the works of a creator by name: creator IDs (digits only) fail with
+    /// [`DlsiteError::NotFound`] instead of being searched as a name.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::{client::creator::{CreatorQuery, CreatorRole}, DlsiteClient};
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let profile = client.creator().get("春花らん", &CreatorQuery::default()).await.unwrap();
+    ///     println!("{:?}", profile.works.get(&CreatorRole::VoiceActor).map(|w| w.len()));
+    /// }
+    /// ```
+    pub async fn get(&self, creator: &str, options: &CreatorQuery) -> Result<CreatorProfile> {
+        if !creator.is_empty() && creator.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(DlsiteError::NotFound(format!(
+                "creator {creator}: works can only be listed by creator name"
+            )));
+        }
+        let result = self
+            .c
+            .search()
+            .search_product(&SearchProductQuery {
+                keyword_creator: Some(creator.to_string()),
+                per_page: options.per_page,
+                page: options.page,
+                ..Default::default()
+            })
+            .await?;
+
+        let credits = self
+            .c
+            .product_api()
+            .get_many(result.products.iter().map(|item| item.id.as_str()))
+            .await;
+        Ok(group_by_role(
+            creator,
+            result.count,
+            result.products,
+            &credits,
+        ))
+    }
+}
+
+/// Group works by the roles of `creator` in their credits. Works whose credits don't name the
+/// creator, or couldn't be fetched, are listed under [`CreatorRole::Other`].
+fn group_by_role(
+    creator: &str,
+    count: i32,
+    products: Vec<SearchProductItem>,
+    credits: &HashMap<ProductId, Result<ProductApiContent>>,
+) -> CreatorProfile {
+    let mut profile = CreatorProfile {
+        name: creator.to_string(),
+        id: None,
+        count,
+        works: BTreeMap::new(),
+    };
+    for item in products {
+        let mut roles = match credits.get(&ProductId::from(&item.id)) {
+            Some(Ok(credits)) => find_roles(credits, creator, &mut profile),
+            Some(Err(e)) => {
+                tracing::warn!("Failed to get credits of {}: {e}", item.id);
+                vec![]
+            }
+            None => vec![],
+        };
+        if roles.is_empty() {
+            roles.push(CreatorRole::Other);
+        }
+        for role in roles {
+            profile.works.entry(role).or_default().push(item.clone());
+        }
+    }
+    profile
+}
+
+/// Roles of `creator` in the credits of a product. Records the creator's name and ID in
+/// `profile` when found.
+fn find_roles(
+    product: &ProductApiContent,
+    creator: &str,
+    profile: &mut CreatorProfile,
+) -> Vec<CreatorRole> {
+    let Some(creators) = &product.creators else {
+        return vec![];
+    };
+    let lists: [(CreatorRole, &Option<Vec<Creator>>); 4] = [
+        (CreatorRole::Author, &creators.created_by),
+        (CreatorRole::VoiceActor, &creators.voice_by),
+        (CreatorRole::Illustrator, &creators.illust_by),
+        (CreatorRole::Scenario, &creators.scenario_by),
+    ];
+
+    let mut roles = vec![];
+    for (role, list) in lists {
+        let found = list
+            .iter()
+            .flatten()
+            .find(|c| c.name == creator || c.id == creator);
+        if let Some(found) = found {
+            profile.name = found.name.clone();
+            profile.id.get_or_insert_with(|| found.id.clone());
+            roles.push(role);
+        }
+    }
+    roles
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{group_by_role, CreatorQuery, CreatorRole};
+    use crate::{
+        client::{product_api::interface::ProductApiContent, search::SearchProductItem},
+        interface::product::{AgeCategory, WorkType},
+        DlsiteClient, DlsiteError,
+    };
+
+    fn item(id: &str) -> SearchProductItem {
+        SearchProductItem {
+            id: id.to_string(),
+            title: String::new(),
+            creator: None,
+            creator_omitted: None,
+            circle_name: String::new(),
+            circle_id: String::new(),
+            dl_count: None,
+            rate_count: None,
+            review_count: None,
+            favorite_count: None,
+            price_original: 0,
+            price_sale: None,
+            age_category: AgeCategory::General,
+            work_type: WorkType::SOU,
+            thumbnail_url: String::new(),
+            rating: None,
+        }
+    }
+
+    fn credits(id: &str, creaters: serde_json::Value) -> ProductApiContent {
+        let mut content: serde_json::Value =
+            serde_json::from_str(include_str!("../product_api/fixture.json")).unwrap();
+        content["workno"] = id.into();
+        content["creaters"] = creaters;
+        serde_json::from_value(content).unwrap()
+    }
+
+    #[test]
+    fn group_works_by_role() {
+        let haruka = serde_json::json!({
+            "id": "27631", "name": "春花らん", "classification": "voice_by"
+        });
+        let other = serde_json::json!({
+            "id": "1", "name": "山田太郎", "classification": "illust_by"
+        });
+        let credits = HashMap::from([
+            (
+                "RJ01000001".into(),
+                Ok(credits(
+                    "RJ01000001",
+                    serde_json::json!({ "voice_by": [other, haruka], "scenario_by": [haruka] }),
+                )),
+            ),
+            (
+                "RJ01000002".into(),
+                Ok(credits(
+                    "RJ01000002",
+                    serde_json::json!({ "illust_by": [other] }),
+                )),
+            ),
+            (
+                "RJ01000003".into(),
+                Err(DlsiteError::NotFound("RJ01000003".to_string())),
+            ),
+        ]);
+
+        let profile = group_by_role(
+            "春花らん",
+            4,
+            ["RJ01000001", "RJ01000002", "RJ01000003", "RJ01000004"]
+                .map(item)
+                .into(),
+            &credits,
+        );
+        assert_eq!(profile.id.as_deref(), Some("27631"));
+        assert_eq!(profile.count, 4);
+        let ids = |role| -> Vec<&str> {
+            profile.works[&role]
+                .iter()
+                .map(|item| item.id.as_str())
+                .collect()
+        };
+        assert_eq!(ids(CreatorRole::VoiceActor), ["RJ01000001"]);
+        assert_eq!(ids(CreatorRole::Scenario), ["RJ01000001"]);
+        // Not credited, credits not fetched or not requested
+        assert_eq!(
+            ids(CreatorRole::Other),
+            ["RJ01000002", "RJ01000003", "RJ01000004"]
+        );
+        assert!(!profile.works.contains_key(&CreatorRole::Illustrator));
+        assert_eq!(
+            profile.roles_of("RJ01000001"),
+            [CreatorRole::VoiceActor, CreatorRole::Scenario]
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_creator_id() {
+        let client = DlsiteClient::default();
+        assert!(matches!(
+            client
+                .creator()
+                .get("27631", &CreatorQuery::default())
+                .await,
+            Err(DlsiteError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_creator() {
+        let client = DlsiteClient::default();
+        let profile = client
+            .creator()
+            .get("春花らん", &CreatorQuery::default())
+            .await
+            .unwrap();
+
+        assert!(profile.count > 0);
+        assert!(profile.id.is_some());
+        assert!(!profile.works[&CreatorRole::VoiceActor].is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::utils::is_in_domain;
+
+/// Kind of endpoint a request is sent to, see [`EndpointOverrides`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// AJAX search (`/fsr/ajax/...`)
+    Search,
+    /// JSON apis (`/api/...`, `/product/info/ajax`)
+    Api,
+    /// Images and other media served by the CDN (`img.dlsite.jp`)
+    Media,
+    /// HTML pages
+    Html,
+}
+
+/// Origins replacing the DLsite ones for some kinds of requests, e.g. to send api traffic
+/// through a caching mirror while pages are still fetched from DLsite.
+///
+/// Only the scheme, host and port are replaced; paths and query strings are kept. Requests
+/// to hosts other than the one of the base URL (and the CDN for media) are never rewritten.
+///
+/// # Example
+/// ```
+/// use dlsite_gamebox::{client::EndpointOverrides, DlsiteClient};
+///
+/// let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+///     .endpoint_overrides(
+///         EndpointOverrides::new()
+///             .api("http://localhost:8080")
+///             .media("https://img-mirror.example.com"),
+///     )
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EndpointOverrides {
+    search: Option<url::Url>,
+    api: Option<url::Url>,
+    media: Option<url::Url>,
+    html: Option<url::Url>,
+}
+
+impl EndpointOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Origin used for the AJAX search endpoint.
+    ///
+    /// # Panics
+    /// Panics if `origin` is not a valid URL, like the other builder methods taking URLs.
+    pub fn search(self, origin: &str) -> Self {
+        self.set(Endpoint::Search, origin)
+    }
+
+    /// Origin used for the JSON apis. See [`EndpointOverrides::search`].
+    pub fn api(self, origin: &str) -> Self {
+        self.set(Endpoint::Api, origin)
+    }
+
+    /// Origin used for images. See [`EndpointOverrides::search`].
+    pub fn media(self, origin: &str) -> Self {
+        self.set(Endpoint::Media, origin)
+    }
+
+    /// Origin used for HTML pages. See [`EndpointOverrides::search`].
+    pub fn html(self, origin: &str) -> Self {
+        self.set(Endpoint::Html, origin)
+    }
+
+    /// Set the origin used for the given kind of endpoint.
+    pub fn set(mut self, endpoint: Endpoint, origin: &str) -> Self {
+        let origin = Some(url::Url::parse(origin).expect("Invalid endpoint origin"));
+        match endpoint {
+            Endpoint::Search => self.search = origin,
+            Endpoint::Api => self.api = origin,
+            Endpoint::Media => self.media = origin,
+            Endpoint::Html => self.html = origin,
+        }
+        self
+    }
+
+    fn get(&self, endpoint: Endpoint) -> Option<&url::Url> {
+        match endpoint {
+            Endpoint::Search => self.search.as_ref(),
+            Endpoint::Api => self.api.as_ref(),
+            Endpoint::Media => self.media.as_ref(),
+            Endpoint::Html => self.html.as_ref(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.search.is_none() && self.api.is_none() && self.media.is_none() && self.html.is_none()
+    }
+
+    /// Rewrite `url` if its kind of endpoint is overridden. `site_host` is the host of the
+    /// base URL of the client.
+    pub(crate) fn apply(&self, url: String, site_host: Option<&str>) -> String {
+        if self.is_empty() {
+            return url;
+        }
+        let Ok(mut parsed) = url::Url::parse(&url) else {
+            return url;
+        };
+        let Some(endpoint) = classify(&parsed, site_host) else {
+            return url;
+        };
+        let Some(origin) = self.get(endpoint) else {
+            return url;
+        };
+
+        let rewritten = parsed.set_scheme(origin.scheme()).is_ok()
+            && parsed.set_host(origin.host_str()).is_ok()
+            && parsed.set_port(origin.port()).is_ok();
+        if rewritten {
+            parsed.to_string()
+        } else {
+            url
+        }
+    }
+}
+
+/// Kind of endpoint of a URL, `None` for hosts which are never rewritten.
+fn classify(url: &url::Url, site_host: Option<&str>) -> Option<Endpoint> {
+    let host = url.host_str()?;
+    if is_in_domain(host, "dlsite.jp") {
+        return Some(Endpoint::Media);
+    }
+    if Some(host) != site_host {
+        return None;
+    }
+    let path = url.path();
+    Some(if path.contains("/fsr/ajax/") {
+        Endpoint::Search
+    } else if path.contains("/api/") || path.contains("/product/info/ajax") {
+        Endpoint::Api
+    } else {
+        Endpoint::Html
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EndpointOverrides;
+
+    #[test]
+    fn rewrite_endpoints() {
+        let overrides = EndpointOverrides::new()
+            .api("http://localhost:8080")
+            .media("https://img-mirror.example.com");
+        let host = Some("www.dlsite.com");
+
+        assert_eq!(
+            overrides.apply(
+                "https://www.dlsite.com/maniax/api/=/product.json?workno=RJ1".to_string(),
+                host
+            ),
+            "http://localhost:8080/maniax/api/=/product.json?workno=RJ1"
+        );
+        assert_eq!(
+            overrides.apply(
+                "https://img.dlsite.jp/modpub/images2/work/RJ1_img_main.jpg".to_string(),
+                host
+            ),
+            "https://img-mirror.example.com/modpub/images2/work/RJ1_img_main.jpg"
+        );
+        // Not overridden
+        let page = "https://www.dlsite.com/maniax/work/=/product_id/RJ1.html";
+        assert_eq!(overrides.apply(page.to_string(), host), page);
+        let login = "https://login.dlsite.com/api/login";
+        assert_eq!(overrides.apply(login.to_string(), host), login);
+        let other = "https://evildlsite.jp/pixel.gif";
+        assert_eq!(overrides.apply(other.to_string(), host), other);
+    }
+}
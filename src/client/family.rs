@@ -0,0 +1,372 @@
+//! Translation families: an original work and all its language editions. See
+//! [`DlsiteClient::translation_family`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::product_api::interface::{Either, ProductApiContent};
+use crate::{error::Result, interface::product_id::ProductId, DlsiteClient, DlsiteError};
+
+/// Maximum number of works fetched for one family, in case of a loop of bad links.
+const MAX_FAMILY_SIZE: usize = 200;
+
+/// How two works of a family are linked.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FamilyLink {
+    /// `to` is a translation of `from` (`translation_info.parent_workno`/`child_worknos`)
+    Translation,
+    /// `to` is an official language edition listed by `from` (`language_editions`)
+    Edition,
+}
+
+/// Link between two works of a family.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct FamilyEdge {
+    pub from: ProductId,
+    pub to: ProductId,
+    pub link: FamilyLink,
+}
+
+/// A work of a family.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FamilyMember {
+    pub id: ProductId,
+    pub title: String,
+    /// Language of the work, e.g. `ENG` or `CHI_HANS`. `None` for most originals.
+    pub lang: Option<String>,
+    pub is_original: bool,
+}
+
+/// An original work and all its translations and language editions, as a graph.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TranslationFamily {
+    /// The original work: the canonical entry of the family
+    pub original: ProductId,
+    pub members: BTreeMap<ProductId, FamilyMember>,
+    /// Links between members, sorted
+    pub edges: Vec<FamilyEdge>,
+}
+
+/// Links of a work to the other works of its family.
+#[derive(Debug, Clone)]
+struct WorkLinks {
+    member: FamilyMember,
+    /// Work this one was translated from, or the original
+    parent: Option<ProductId>,
+    children: Vec<ProductId>,
+    editions: Vec<ProductId>,
+}
+
+impl From<&ProductApiContent> for WorkLinks {
+    fn from(content: &ProductApiContent) -> Self {
+        let info = &content.translation_info;
+        let editions: Vec<&str> = match &content.language_editions {
+            Either::Left(editions) => editions.values().map(|e| e.workno.as_str()).collect(),
+            Either::Right(editions) => editions.iter().map(|e| e.workno.as_str()).collect(),
+        };
+        WorkLinks {
+            member: FamilyMember {
+                id: ProductId::from(content.workno.as_str()),
+                title: content.work_name.clone(),
+                lang: info.lang.clone(),
+                is_original: info.is_original,
+            },
+            parent: info
+                .parent_workno
+                .as_deref()
+                .or(info.original_workno.as_deref())
+                .map(ProductId::from),
+            children: info.child_worknos.iter().map(ProductId::from).collect(),
+            editions: editions.into_iter().map(ProductId::from).collect(),
+        }
+    }
+}
+
+impl WorkLinks {
+    /// Works this one links to.
+    fn neighbors(&self) -> impl Iterator<Item = &ProductId> {
+        self.parent
+            .iter()
+            .chain(&self.children)
+            .chain(&self.editions)
+    }
+}
+
+impl TranslationFamily {
+    /// Build the graph of the works of a family. Links to works which are not in `works`
+    /// are dropped.
+    fn build(root: &ProductId, works: Vec<WorkLinks>) -> Self {
+        let ids: BTreeSet<ProductId> = works.iter().map(|w| w.member.id.clone()).collect();
+        let mut edges = BTreeSet::new();
+        for work in &works {
+            let id = &work.member.id;
+            let mut add = |from: &ProductId, to: &ProductId, link| {
+                if from != to && ids.contains(from) && ids.contains(to) {
+                    edges.insert(FamilyEdge {
+                        from: from.clone(),
+                        to: to.clone(),
+                        link,
+                    });
+                }
+            };
+            if let Some(parent) = &work.parent {
+                add(parent, id, FamilyLink::Translation);
+            }
+            for child in &work.children {
+                add(id, child, FamilyLink::Translation);
+            }
+            for edition in &work.editions {
+                // Editions list each other: keep one edge per pair
+                let (from, to) = if id < edition {
+                    (id, edition)
+                } else {
+                    (edition, id)
+                };
+                add(from, to, FamilyLink::Edition);
+            }
+        }
+
+        let original = works
+            .iter()
+            .find(|w| w.member.is_original)
+            .or_else(|| works.iter().find(|w| w.parent.is_none()))
+            .map(|w| w.member.id.clone())
+            .unwrap_or_else(|| root.clone());
+
+        // A pair linked both ways keeps its translation edge only
+        let translations: BTreeSet<(ProductId, ProductId)> = edges
+            .iter()
+            .filter(|e| e.link == FamilyLink::Translation)
+            .flat_map(|e| {
+                [
+                    (e.from.clone(), e.to.clone()),
+                    (e.to.clone(), e.from.clone()),
+                ]
+            })
+            .collect();
+        let edges = edges
+            .into_iter()
+            .filter(|e| {
+                e.link == FamilyLink::Translation
+                    || !translations.contains(&(e.from.clone(), e.to.clone()))
+            })
+            .collect();
+
+        TranslationFamily {
+            original,
+            members: works
+                .into_iter()
+                .map(|w| (w.member.id.clone(), w.member))
+                .collect(),
+            edges,
+        }
+    }
+
+    /// The original work.
+    pub fn canonical(&self) -> Option<&FamilyMember> {
+        self.members.get(&self.original)
+    }
+
+    /// Works directly translated from `id`.
+    pub fn translations_of<'s>(
+        &'s self,
+        id: &'s ProductId,
+    ) -> impl Iterator<Item = &'s FamilyMember> {
+        self.edges
+            .iter()
+            .filter(move |e| e.link == FamilyLink::Translation && &e.from == id)
+            .filter_map(move |e| self.members.get(&e.to))
+    }
+
+    /// Languages of the family, sorted. Works without a language are skipped.
+    pub fn languages(&self) -> Vec<&str> {
+        let languages: BTreeSet<&str> = self
+            .members
+            .values()
+            .filter_map(|m| m.lang.as_deref())
+            .collect();
+        languages.into_iter().collect()
+    }
+
+    /// Whether `id` belongs to the family.
+    pub fn contains(&self, id: &ProductId) -> bool {
+        self.members.contains_key(id)
+    }
+}
+
+impl DlsiteClient {
+    /// Get the translation family of a work: its original and every translation and language
+    /// edition, whichever of them `product_id` is.
+    ///
+    /// Original/translation links are followed in both directions with the product api, one
+    /// batched request per level of the graph (see
+    /// [`super::product_api::ProductApiClient::get_many`]). Linked works which can't be
+    /// fetched (delisted...) are left out.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let family = client.translation_family("RJ01014447").await.unwrap();
+    ///     println!("{} works in {:?}", family.members.len(), family.languages());
+    /// }
+    /// ```
+    pub async fn translation_family(
+        &self,
+        product_id: impl Into<ProductId>,
+    ) -> Result<TranslationFamily> {
+        let root = product_id.into().checked()?;
+        let mut works: BTreeMap<ProductId, WorkLinks> = BTreeMap::new();
+        let mut visited = BTreeSet::from([root.clone()]);
+        let mut frontier = vec![root.clone()];
+
+        while !frontier.is_empty() && works.len() < MAX_FAMILY_SIZE {
+            let mut results = self.product_api().get_many(&frontier).await;
+            let mut next = vec![];
+            for id in frontier {
+                let content = match results.remove(&id) {
+                    Some(Ok(content)) => content,
+                    Some(Err(e)) if id == root => return Err(e),
+                    Some(Err(e)) => {
+                        tracing::warn!("Failed to get {id} of the family of {root}: {e}");
+                        continue;
+                    }
+                    None if id == root => return Err(DlsiteError::NotFound(id.to_string())),
+                    None => continue,
+                };
+                let links = WorkLinks::from(&content);
+                for neighbor in links.neighbors() {
+                    if neighbor.is_valid() && visited.insert(neighbor.clone()) {
+                        next.push(neighbor.clone());
+                    }
+                }
+                works.insert(id, links);
+            }
+            frontier = next;
+        }
+
+        Ok(TranslationFamily::build(
+            &root,
+            works.into_values().collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::{FamilyLink, FamilyMember, TranslationFamily, WorkLinks};
+    use crate::{interface::product_id::ProductId, testing::FixtureTransport, DlsiteClient};
+
+    fn work(id: &str, lang: Option<&str>, parent: Option<&str>, children: &[&str]) -> WorkLinks {
+        WorkLinks {
+            member: FamilyMember {
+                id: id.into(),
+                title: format!("title of {id}"),
+                lang: lang.map(|l| l.to_string()),
+                is_original: parent.is_none(),
+            },
+            parent: parent.map(ProductId::from),
+            children: children.iter().map(|&c| ProductId::from(c)).collect(),
+            editions: vec![],
+        }
+    }
+
+    #[test]
+    fn build_family() {
+        let mut english = work("RJ01000002", Some("ENG"), Some("RJ01000001"), &[]);
+        english.editions = vec!["RJ01000001".into(), "RJ01000003".into()];
+        let family = TranslationFamily::build(
+            &"RJ01000003".into(),
+            vec![
+                work(
+                    "RJ01000001",
+                    None,
+                    None,
+                    &["RJ01000002", "RJ01000003", "RJ09999999"],
+                ),
+                english,
+                work("RJ01000003", Some("CHI_HANS"), Some("RJ01000001"), &[]),
+            ],
+        );
+
+        assert_eq!(family.original, ProductId::from("RJ01000001"));
+        assert_eq!(family.canonical().unwrap().title, "title of RJ01000001");
+        assert_eq!(family.languages(), vec!["CHI_HANS", "ENG"]);
+        let original = ProductId::from("RJ01000001");
+        let translations: Vec<&str> = family
+            .translations_of(&original)
+            .map(|m| m.id.as_str())
+            .collect();
+        assert_eq!(translations, vec!["RJ01000002", "RJ01000003"]);
+
+        // Unknown works are dropped, and translation links win over edition links
+        assert!(!family.contains(&"RJ09999999".into()));
+        let editions: Vec<(&str, &str)> = family
+            .edges
+            .iter()
+            .filter(|e| e.link == FamilyLink::Edition)
+            .map(|e| (e.from.as_str(), e.to.as_str()))
+            .collect();
+        assert_eq!(editions, vec![("RJ01000002", "RJ01000003")]);
+        assert_eq!(family.edges.len(), 3);
+    }
+
+    /// Product api response of a single work.
+    fn api_response(
+        id: &str,
+        lang: Option<&str>,
+        parent: Option<&str>,
+        children: &[&str],
+    ) -> String {
+        let mut item: serde_json::Value =
+            serde_json::from_str(include_str!("product_api/fixture.json")).unwrap();
+        item["workno"] = json!(id);
+        item["work_name"] = json!(format!("title of {id}"));
+        let info = &mut item["translation_info"];
+        info["is_original"] = json!(parent.is_none());
+        info["is_child"] = json!(parent.is_some());
+        info["lang"] = json!(lang);
+        info["parent_workno"] = json!(parent);
+        info["child_worknos"] = json!(children);
+        json!([item]).to_string()
+    }
+
+    #[tokio::test]
+    async fn translation_family() {
+        let url =
+            |id: &str| format!("https://www.dlsite.com/maniax/api/=/product.json?workno={id}");
+        let transport = FixtureTransport::new()
+            .with_body(
+                &url("RJ01000001"),
+                api_response("RJ01000001", None, None, &["RJ01000002", "RJ01000003"]),
+            )
+            .with_body(
+                &url("RJ01000002"),
+                api_response("RJ01000002", Some("ENG"), Some("RJ01000001"), &[]),
+            )
+            .with_body(
+                &url("RJ01000003"),
+                api_response("RJ01000003", Some("CHI_HANS"), Some("RJ01000001"), &[]),
+            );
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::ZERO, Duration::ZERO)
+            .transport(transport)
+            .build();
+
+        // Starting from a translation finds the original, then the other translation
+        let family = client.translation_family("RJ01000002").await.unwrap();
+        assert_eq!(family.original, ProductId::from("RJ01000001"));
+        assert_eq!(family.members.len(), 3);
+        assert_eq!(family.languages(), vec!["CHI_HANS", "ENG"]);
+        assert_eq!(family.edges.len(), 2);
+    }
+}
@@ -0,0 +1,186 @@
+//! Interfaces related to followed circles. For more information, see [`FollowClient`].
+
+use chrono::NaiveDate;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{
+    error::Result, interface::product::WorkType, utils::ToParseError as _, DlsiteClient,
+    DlsiteError,
+};
+
+/// Client to fetch the new releases of circles followed by the logged-in user.
+///
+/// This needs a logged-in session, see [`crate::DlsiteClientBuilder::cookie`].
+#[derive(Clone, Debug)]
+pub struct FollowClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// A product in the followed circles feed.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FollowEntry {
+    pub id: String,
+    pub title: String,
+    pub circle_id: String,
+    pub circle_name: String,
+    pub work_type: WorkType,
+    pub released_at: Option<NaiveDate>,
+    pub price: Option<i32>,
+    pub thumbnail_url: Option<String>,
+}
+
+impl<'a> FollowClient<'a> {
+    /// Get new releases of followed circles ("フォロー中サークルの新着").
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in.
+    pub async fn new_releases(&self) -> Result<Vec<FollowEntry>> {
+        let c = self.c;
+        c.authenticated(move || async move {
+            parse_follow_html(&c.get_fresh("/mypage/follow/works").await?)
+        })
+        .await
+    }
+}
+
+/// Whether the page is the login form DLsite redirects to when the session is missing.
+pub(crate) fn is_login_page(html: &Html) -> bool {
+    html.select(&Selector::parse("form#login_form, form[action*=\"login\"]").unwrap())
+        .next()
+        .is_some()
+}
+
+pub(crate) fn parse_follow_html(html: &str) -> Result<Vec<FollowEntry>> {
+    let html = Html::parse_document(html);
+    if is_login_page(&html) {
+        return Err(DlsiteError::Unauthenticated);
+    }
+
+    html.select(&Selector::parse(".follow_work_list [data-product_id]").unwrap())
+        .map(parse_follow_item)
+        .collect()
+}
+
+fn parse_follow_item(item: ElementRef) -> Result<FollowEntry> {
+    let id = item
+        .value()
+        .attr("data-product_id")
+        .to_parse_error("Failed to get product id")?
+        .to_string();
+    let title = item
+        .select(&Selector::parse(".work_name a").unwrap())
+        .next()
+        .to_parse_error("Failed to get title")?;
+    let title = title
+        .value()
+        .attr("title")
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| title.text().collect::<String>().trim().to_string());
+    let maker_e = item
+        .select(&Selector::parse(".maker_name a").unwrap())
+        .next()
+        .to_parse_error("Failed to find maker element")?;
+    let circle_id = maker_e
+        .value()
+        .attr("href")
+        .to_parse_error("Failed to get maker link")?
+        .split('/')
+        .next_back()
+        .to_parse_error("Invalid url")?
+        .split('.')
+        .next()
+        .to_parse_error("Failed to find maker id")?
+        .to_string();
+    let work_type = item
+        .select(&Selector::parse(".work_category").unwrap())
+        .next()
+        .and_then(|e| e.value().attr("class"))
+        .and_then(|class| {
+            class.split(' ').find_map(|c| {
+                let wt = c.strip_prefix("type_")?.parse::<WorkType>().ok()?;
+                (!matches!(wt, WorkType::Unknown(_))).then_some(wt)
+            })
+        })
+        .unwrap_or(WorkType::Unknown("".to_string()));
+    let released_at = item
+        .select(&Selector::parse(".sales_date").unwrap())
+        .next()
+        .and_then(|e| {
+            let text = e.text().collect::<String>();
+            let date = text.trim().trim_start_matches("販売日:").trim();
+            NaiveDate::parse_from_str(date, "%Y年%m月%d日").ok()
+        });
+    let price = item
+        .select(&Selector::parse(".work_price .work_price_base").unwrap())
+        .next()
+        .and_then(|e| e.text().next()?.replace(',', "").parse().ok());
+    let thumbnail_url = item
+        .select(&Selector::parse("img").unwrap())
+        .next()
+        .and_then(|e| e.value().attr("data-src").or(e.value().attr("src")))
+        .map(|src| match src.strip_prefix("//") {
+            Some(rest) => format!("https://{}", rest),
+            None => src.to_string(),
+        });
+
+    Ok(FollowEntry {
+        id,
+        title,
+        circle_name: maker_e.text().next().unwrap_or("").trim().to_string(),
+        circle_id,
+        work_type,
+        released_at,
+        price,
+        thumbnail_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::parse_follow_html;
+    use crate::{interface::product::WorkType, DlsiteError};
+
+    const FOLLOW_HTML: &str = r#"
+<ul class="follow_work_list">
+  <li data-product_id="RJ01000001">
+    <img src="//img.dlsite.jp/modpub/images2/work/doujin/RJ01001000/RJ01000001_img_main_240x240.jpg">
+    <dt class="work_name"><a href="/maniax/work/=/product_id/RJ01000001.html" title="新作ボイス">新作ボイス</a></dt>
+    <dd class="maker_name"><a href="/maniax/circle/profile/=/maker_id/RG00001.html">Circle A</a></dd>
+    <div class="work_category type_SOU"><a>ボイス・ASMR</a></div>
+    <span class="sales_date">販売日: 2024年12月01日</span>
+    <span class="work_price"><span class="work_price_base">1,100</span></span>
+  </li>
+</ul>
+"#;
+
+    #[test]
+    fn parse_follow_feed() {
+        let entries = parse_follow_html(FOLLOW_HTML).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "RJ01000001");
+        assert_eq!(entries[0].title, "新作ボイス");
+        assert_eq!(entries[0].circle_id, "RG00001");
+        assert_eq!(entries[0].circle_name, "Circle A");
+        assert_eq!(entries[0].work_type, WorkType::SOU);
+        assert_eq!(
+            entries[0].released_at,
+            NaiveDate::from_ymd_opt(2024, 12, 1)
+        );
+        assert_eq!(entries[0].price, Some(1100));
+        assert_eq!(
+            entries[0].thumbnail_url.as_deref(),
+            Some("https://img.dlsite.jp/modpub/images2/work/doujin/RJ01001000/RJ01000001_img_main_240x240.jpg")
+        );
+    }
+
+    #[test]
+    fn login_required() {
+        let html = r#"<form id="login_form" action="https://login.dlsite.com/login"></form>"#;
+        assert!(matches!(
+            parse_follow_html(html),
+            Err(DlsiteError::Unauthenticated)
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// URLs currently being fetched, so concurrent requests for the same URL are coalesced.
+///
+/// The first request for a URL holds its lock until the response is in the cache. Others
+/// wait for the lock, then find the response in the cache instead of hitting DLsite again.
+/// If the first request failed, the next one in line makes its own request.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InFlight {
+    urls: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+}
+
+impl InFlight {
+    /// Wait until no other request for `url` is in flight.
+    pub(crate) async fn acquire(&self, url: &str) -> InFlightGuard {
+        let lock = self
+            .urls
+            .lock()
+            .unwrap()
+            .entry(url.to_string())
+            .or_default()
+            .clone();
+        let guard = lock.lock_owned().await;
+        InFlightGuard {
+            urls: self.urls.clone(),
+            url: url.to_string(),
+            guard: Some(guard),
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.urls.lock().unwrap().len()
+    }
+}
+
+/// Marks a URL as in flight until dropped.
+pub(crate) struct InFlightGuard {
+    urls: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+    url: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        // Release the lock first, so the count below only includes waiting requests
+        drop(self.guard.take());
+        let mut urls = self.urls.lock().unwrap();
+        if let Some(lock) = urls.get(&self.url) {
+            // Only the map holds the lock: nobody is waiting for this URL
+            if Arc::strong_count(lock) == 1 {
+                urls.remove(&self.url);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::InFlight;
+
+    #[tokio::test]
+    async fn same_url_waits() {
+        let in_flight = InFlight::default();
+        let first = in_flight.acquire("https://example.com/a").await;
+
+        // Other URLs are not blocked
+        drop(in_flight.acquire("https://example.com/b").await);
+
+        let second = {
+            let in_flight = in_flight.clone();
+            tokio::spawn(async move {
+                drop(in_flight.acquire("https://example.com/a").await);
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!second.is_finished());
+
+        drop(first);
+        second.await.unwrap();
+        assert_eq!(in_flight.len(), 0);
+    }
+}
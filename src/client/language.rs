@@ -0,0 +1,72 @@
+/// What to do when DLsite serves a page in another language than the requested one.
+///
+/// DLsite falls back to Japanese when the locale can't be applied (e.g. a missing `locale`
+/// cookie after a redirect), and parsers would then mix Japanese and translated fields.
+/// The requested language is read from the `locale` query parameter of the request, the
+/// served one from the `lang` attribute of the `<html>` tag. Responses without either are
+/// never checked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LanguageCheck {
+    /// Don't check
+    Off,
+    /// Log a warning and use the response anyway (default)
+    #[default]
+    Warn,
+    /// Fail with [`crate::DlsiteError::LanguageMismatch`]. The response is not cached.
+    Error,
+}
+
+/// Language requested by the `locale` query parameter of `url` (`en_US` → `en`).
+pub(crate) fn requested_language(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let (_, locale) = url.query_pairs().find(|(key, _)| key == "locale")?;
+    primary_subtag(&locale)
+}
+
+/// Language of an HTML document, from the `lang` attribute of its `<html>` tag.
+pub(crate) fn served_language(body: &str) -> Option<String> {
+    if !body.trim_start().starts_with('<') {
+        return None;
+    }
+    let start = body.find("<html")?;
+    let tag = &body[start..];
+    let tag = &tag[..tag.find('>')?];
+    let (_, lang) = tag.split_once("lang=")?;
+    primary_subtag(lang.trim_start_matches(['"', '\'']))
+}
+
+/// `ja-JP` or `ja_JP` → `ja`
+fn primary_subtag(tag: &str) -> Option<String> {
+    let code: String = tag
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    (!code.is_empty()).then_some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{requested_language, served_language};
+
+    #[test]
+    fn detect_languages() {
+        assert_eq!(
+            requested_language(
+                "https://www.dlsite.com/maniax/work/=/product_id/RJ1.html?locale=en_US"
+            ),
+            Some("en".to_string())
+        );
+        assert_eq!(
+            requested_language("https://www.dlsite.com/maniax/work/=/product_id/RJ1.html"),
+            None
+        );
+
+        assert_eq!(
+            served_language("<!DOCTYPE html>\n<html lang=\"ja-JP\" class=\"maniax\"><head>"),
+            Some("ja".to_string())
+        );
+        assert_eq!(served_language("<html><body></body></html>"), None);
+        assert_eq!(served_language(r#"{"html": "<html lang=\"ja\">"}"#), None);
+    }
+}
@@ -1,27 +1,216 @@
-use crate::cache::ResponseCache;
+use crate::cache::{CacheBackend, CacheStats, MediaCache, ResponseCache};
 use crate::error::{DlsiteError, Result};
+use crate::eta::RequestStats;
+use crate::events::{EventListener, EventListeners, RequestEvent};
+use crate::interface::{locale::Locale, site::Site};
+use crate::normalize::Normalizer;
+use crate::ratelimit::{IntervalLimiter, RateLimiter, TokenBucketLimiter};
 use crate::retry::RetryConfig;
-use std::sync::Arc;
-use std::sync::atomic::AtomicU64;
+use crate::runtime::{self, Instant};
+use crate::transport::{
+    BodyStream, HttpRequest, HttpResponse, ReqwestTransport, StreamedResponse, Transport,
+};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use futures::StreamExt as _;
+
+/// Cookie set by DLsite once the age verification is confirmed
+#[cfg(not(target_arch = "wasm32"))]
+const ADULT_CHECK_COOKIE: &str = "adultchecked=1";
+
+pub mod account;
+pub mod app;
+pub mod auth;
+pub mod book;
+pub mod campaign;
 pub mod circle;
+#[cfg(not(target_arch = "wasm32"))]
+mod cookies;
+pub mod coupon;
+pub mod creator;
+mod endpoints;
+pub mod family;
+pub mod follow;
+mod inflight;
+mod language;
+mod options;
+mod paginate;
+pub mod play;
+pub mod pool;
+pub mod pro;
 pub mod product;
 pub mod product_api;
+pub mod ranking;
 pub mod search;
+mod storefront;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use cookies::CookieJar;
+pub use endpoints::{Endpoint, EndpointOverrides};
+pub use language::LanguageCheck;
+pub use options::{FetchOptions, ProductFields};
+pub use paginate::{Page, Paginated};
+
+use inflight::InFlight;
+
+/// Priority of requests made by a client.
+///
+/// Foreground requests get the rate limiter slots before background ones, waiting for at most
+/// the one slot already reserved by a background request, so requests triggered by a user stay
+/// responsive while a crawler is using the same client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// Interactive requests (default)
+    #[default]
+    Foreground,
+    /// Bulk requests which can be delayed
+    Background,
+}
+
+/// Orders rate limiter slots between foreground and background requests, shared by clones.
+#[derive(Debug, Default)]
+struct PriorityGate {
+    /// Number of foreground requests waiting for the rate limiter
+    foreground_pending: AtomicUsize,
+    /// Notified when no foreground request is pending anymore
+    foreground_done: tokio::sync::Notify,
+    /// Held by the background request waiting for the rate limiter, so that background
+    /// requests reserve their slots one at a time
+    background_turn: tokio::sync::Mutex<()>,
+}
+
+/// Decrements the number of pending foreground requests when dropped.
+struct ForegroundGuard(Arc<PriorityGate>);
+
+impl ForegroundGuard {
+    fn new(gate: &Arc<PriorityGate>) -> Self {
+        gate.foreground_pending.fetch_add(1, Ordering::SeqCst);
+        Self(gate.clone())
+    }
+}
+
+impl Drop for ForegroundGuard {
+    fn drop(&mut self) {
+        if self.0.foreground_pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.foreground_done.notify_waiters();
+        }
+    }
+}
+
+/// Requests allowed by [`DlsiteClientBuilder::request_budget`], shared by clones.
+#[derive(Debug)]
+struct RequestBudget {
+    limit: u64,
+    used: AtomicU64,
+}
+
+impl RequestBudget {
+    /// Count a request about to be sent, or fail if the budget is spent.
+    fn spend(&self) -> Result<()> {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                (used < self.limit).then_some(used + 1)
+            })
+            .map(|_| ())
+            .map_err(|_| DlsiteError::BudgetExhausted { limit: self.limit })
+    }
+}
+
+/// Stops the requests of a client and its clones after DLsite served a challenge page, see
+/// [`DlsiteClientBuilder::challenge_cooldown`].
+#[derive(Debug)]
+struct CircuitBreaker {
+    cooldown: Duration,
+    /// Challenge URL and when it was served, while open
+    open: Mutex<Option<(String, Instant)>>,
+}
+
+impl CircuitBreaker {
+    fn trip(&self, url: &str) {
+        tracing::warn!(
+            "DLsite served a bot challenge ({url}), pausing requests for {:?}",
+            self.cooldown
+        );
+        *self.open.lock().unwrap() = Some((url.to_string(), Instant::now()));
+    }
+
+    /// Fail with the challenge which opened the circuit, until the cooldown is over.
+    fn check(&self) -> Result<()> {
+        let mut open = self.open.lock().unwrap();
+        match &*open {
+            Some((url, at)) if at.elapsed() < self.cooldown => {
+                Err(DlsiteError::Challenge { url: url.clone() })
+            }
+            Some(_) => {
+                *open = None;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
 
 /// API client for DLsite.
 #[derive(Clone, Debug)]
 pub struct DlsiteClient {
-    client: reqwest::Client,
+    /// Sends the requests, reqwest unless set with [`DlsiteClientBuilder::transport`]
+    transport: Arc<dyn Transport>,
     base_url: String,
+    /// Storefront the base URL points to
+    site: Site,
+    /// Language requested from DLsite
+    locale: Locale,
     /// Rate limiter to prevent IP bans (2 requests per second by default)
-    /// Stores the timestamp of the last request in milliseconds
-    last_request_time: Arc<AtomicU64>,
+    rate_limiter: Arc<dyn RateLimiter>,
+    /// Priority of requests waiting for the rate limiter
+    priority_gate: Arc<PriorityGate>,
+    /// Priority of requests made by this client
+    priority: Priority,
+    /// Query parameters appended to every request
+    default_query: Arc<Vec<(String, String)>>,
+    /// Cookies sent with every request (login session, age confirmation...)
+    #[cfg(not(target_arch = "wasm32"))]
+    cookie_jar: Arc<CookieJar>,
+    /// Credentials to log in again when the session expires
+    relogin: Option<Arc<auth::Relogin>>,
     /// Response cache for caching HTTP responses
     cache: ResponseCache,
+    /// Cached requests currently in flight, shared by clones of this client
+    in_flight: InFlight,
+    /// Listeners notified of cache, retry and rate limiter events
+    events: EventListeners,
+    /// Requests and retries sent by this client and its clones, for [`crate::eta::Eta`]
+    request_stats: Arc<RequestStats>,
+    /// Maximum number of requests, shared by clones of this client
+    budget: Option<Arc<RequestBudget>>,
+    /// Opened when DLsite serves a challenge, shared by clones of this client
+    circuit: Arc<CircuitBreaker>,
+    /// Cache for images and other binary media
+    media_cache: MediaCache,
     /// Retry configuration for automatic retries
     retry_config: RetryConfig,
+    /// Directory where responses which failed to parse are written
+    dump_dir: Option<Arc<PathBuf>>,
+    /// What to do when a page is served in another language than requested
+    language_check: LanguageCheck,
+    /// Whether search items which fail to parse are skipped instead of failing the page
+    lenient_parsing: bool,
+    /// Origins replacing the DLsite ones for some kinds of requests
+    endpoints: Arc<EndpointOverrides>,
+    /// Normalization applied to parsed titles
+    title_normalizer: Option<Normalizer>,
+    /// Full-text index updated with fetched products
+    #[cfg(feature = "tantivy")]
+    local_index: Option<crate::index::LocalIndex>,
+    /// Whether user tags are added to the local index
+    #[cfg(feature = "tantivy")]
+    index_user_tags: bool,
+    /// Archive every fetched response is written to
+    #[cfg(feature = "archive")]
+    archive: Option<Arc<crate::archive::Archive>>,
 }
 
 impl Default for DlsiteClient {
@@ -33,11 +222,42 @@ impl Default for DlsiteClient {
 /// Builder for DlsiteClient with customizable configuration
 pub struct DlsiteClientBuilder {
     base_url: String,
+    locale: Locale,
+    // Connections, timeouts and cookies are handled by the browser on wasm32
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     pool_max_idle_per_host: usize,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     timeout: Duration,
     cache_capacity: usize,
     cache_ttl: Duration,
+    media_cache_capacity: usize,
     retry_config: RetryConfig,
+    request_interval: (Duration, Duration),
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
+    request_budget: Option<u64>,
+    challenge_cooldown: Duration,
+    transport: Option<Arc<dyn Transport>>,
+    event_listeners: Vec<Arc<dyn EventListener>>,
+    default_query: Vec<(String, String)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<reqwest::Proxy>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    cookies: Vec<String>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    confirm_adult: bool,
+    relogin: Option<(String, String)>,
+    shared_cache: Option<ResponseCache>,
+    dump_dir: Option<PathBuf>,
+    language_check: LanguageCheck,
+    lenient_parsing: bool,
+    endpoints: EndpointOverrides,
+    title_normalizer: Option<Normalizer>,
+    #[cfg(feature = "tantivy")]
+    local_index: Option<crate::index::LocalIndex>,
+    #[cfg(feature = "tantivy")]
+    index_user_tags: bool,
+    #[cfg(feature = "archive")]
+    archive: Option<crate::archive::Archive>,
 }
 
 impl DlsiteClientBuilder {
@@ -45,11 +265,37 @@ impl DlsiteClientBuilder {
     pub fn new(base_url: &str) -> Self {
         Self {
             base_url: base_url.to_string(),
+            locale: Locale::default(),
             pool_max_idle_per_host: 10,
             timeout: Duration::from_secs(30),
             cache_capacity: 100,
             cache_ttl: Duration::from_secs(3600),
+            media_cache_capacity: 200,
             retry_config: RetryConfig::default(),
+            request_interval: (Duration::from_millis(500), Duration::from_millis(500)),
+            rate_limiter: None,
+            request_budget: None,
+            challenge_cooldown: Duration::from_secs(30 * 60),
+            transport: None,
+            event_listeners: Vec::new(),
+            default_query: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            proxy: None,
+            cookies: Vec::new(),
+            confirm_adult: false,
+            relogin: None,
+            shared_cache: None,
+            dump_dir: None,
+            language_check: LanguageCheck::default(),
+            lenient_parsing: false,
+            endpoints: EndpointOverrides::default(),
+            title_normalizer: None,
+            #[cfg(feature = "tantivy")]
+            local_index: None,
+            #[cfg(feature = "tantivy")]
+            index_user_tags: false,
+            #[cfg(feature = "archive")]
+            archive: None,
         }
     }
 
@@ -72,27 +318,345 @@ impl DlsiteClientBuilder {
         self
     }
 
+    /// Set the number of images kept in the media cache. Default: 200.
+    ///
+    /// Media entries use the TTL set by [`DlsiteClientBuilder::cache`].
+    pub fn media_cache_capacity(mut self, capacity: usize) -> Self {
+        self.media_cache_capacity = capacity;
+        self
+    }
+
     /// Set the retry configuration
     pub fn retry_config(mut self, config: RetryConfig) -> Self {
         self.retry_config = config;
         self
     }
 
+    /// Set the gap between consecutive requests.
+    ///
+    /// Each gap is picked at random from `min..=max`, which makes crawl traffic less regular
+    /// and avoids synchronized bursts from multiple workers. Pass the same value twice for a
+    /// fixed gap. Default: 500ms (2 requests per second).
+    pub fn request_interval(mut self, min: Duration, max: Duration) -> Self {
+        self.request_interval = (min, max);
+        self
+    }
+
+    /// Allow bursts of up to `requests` requests, refilled at `requests` per `per`, instead
+    /// of a fixed gap between requests. See [`crate::ratelimit::TokenBucketLimiter`].
+    ///
+    /// Like the default limiter, it is shared by all clones and sub-clients of the client.
+    /// Replaces [`DlsiteClientBuilder::request_interval`].
+    ///
+    /// # Panics
+    /// Panics if `requests` is 0.
+    pub fn rate_limit(mut self, requests: u32, per: Duration) -> Self {
+        self.rate_limiter = Some(Arc::new(TokenBucketLimiter::new(requests, per)));
+        self
+    }
+
+    /// Use a custom rate limiter instead of the in-memory one, e.g. a
+    /// [`crate::ratelimit::RedisRateLimiter`] shared by multiple processes.
+    ///
+    /// [`DlsiteClientBuilder::request_interval`] is ignored in this case.
+    pub fn rate_limiter(mut self, limiter: impl RateLimiter + 'static) -> Self {
+        self.rate_limiter = Some(Arc::new(limiter));
+        self
+    }
+
+    /// Send at most `requests` requests over the life of the client and its clones (retries
+    /// and media downloads included). Requests beyond fail with
+    /// [`DlsiteError::BudgetExhausted`] without reaching DLsite, so a scheduled job can't
+    /// exceed its quota. Cached responses don't count. Default: unlimited.
+    pub fn request_budget(mut self, requests: u64) -> Self {
+        self.request_budget = Some(requests);
+        self
+    }
+
+    /// How long to stop sending requests after DLsite served a CAPTCHA or bot challenge.
+    /// Meanwhile, requests of the client and its clones fail with [`DlsiteError::Challenge`]
+    /// without reaching DLsite, so a flagged crawler doesn't make things worse. Default: 30
+    /// minutes.
+    pub fn challenge_cooldown(mut self, cooldown: Duration) -> Self {
+        self.challenge_cooldown = cooldown;
+        self
+    }
+
+    /// Send requests with a custom transport instead of reqwest, e.g. a
+    /// [`crate::testing::FixtureTransport`] serving recorded responses.
+    ///
+    /// The cookies, proxy, timeout and connection pool settings only apply to the default
+    /// transport.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Register a listener notified of cache hits/misses, retries, rate limiter waits and
+    /// completed requests. See [`crate::events`].
+    pub fn event_listener(mut self, listener: impl EventListener + 'static) -> Self {
+        self.event_listeners.push(Arc::new(listener));
+        self
+    }
+
+    /// Add a query parameter appended to every request (e.g. an affiliate `ana` code or
+    /// `locale`).
+    ///
+    /// Parameters already present in a request URL are not overridden.
+    pub fn default_query_param(mut self, key: &str, value: &str) -> Self {
+        self.default_query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send all requests through the given proxy
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Add a cookie sent with every request to DLsite.
+    ///
+    /// To use features which need a login, pass the session cookies of a logged-in browser
+    /// here (e.g. `cookie("__DLsite_SID", "...")`).
+    pub fn cookie(mut self, name: &str, value: &str) -> Self {
+        self.cookies.push(format!("{}={}", name, value));
+        self
+    }
+
+    /// Answer the age verification ("18歳以上ですか？") in advance, so that pages of adult works
+    /// are returned instead of the confirmation page. Default: false.
+    ///
+    /// This sets the `adultchecked` cookie a browser gets after confirming.
+    pub fn confirm_adult(mut self, confirm: bool) -> Self {
+        self.confirm_adult = confirm;
+        self
+    }
+
+    /// Log in again with these credentials when the session expires, then retry the failed
+    /// request once. See [`crate::client::auth`].
+    ///
+    /// The credentials are kept in memory by the client and its clones. The client is not
+    /// logged in by this call: use [`DlsiteClient::login`] (or
+    /// [`DlsiteClient::ensure_logged_in`]) to log in the first time.
+    pub fn auto_relogin(mut self, login_id: &str, password: &str) -> Self {
+        self.relogin = Some((login_id.to_string(), password.to_string()));
+        self
+    }
+
+    /// Use an existing response cache instead of creating a new one.
+    ///
+    /// Clients sharing a cache see each other's responses. The capacity and TTL set by
+    /// [`DlsiteClientBuilder::cache`] are ignored in this case.
+    pub fn shared_cache(mut self, cache: ResponseCache) -> Self {
+        self.shared_cache = Some(cache);
+        self
+    }
+
+    /// Write responses which fail to parse to `dir`, one file per failure.
+    ///
+    /// Each file contains the URL, the status and the error, followed by the response body,
+    /// so bug reports about DOM changes can include the exact page that broke the parser.
+    /// The directory is created if needed. Disabled by default.
+    pub fn dump_failed_responses(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dump_dir = Some(dir.into());
+        self
+    }
+
+    /// Request pages in the given language: titles, genres and descriptions are translated
+    /// when DLsite has a translation, and parsers understand the localized labels.
+    /// Default: [`Locale::JaJp`].
+    ///
+    /// Sets the `locale` query parameter of every request and the `locale` cookie, replacing
+    /// a `locale` set with [`DlsiteClientBuilder::default_query_param`] or
+    /// [`DlsiteClientBuilder::cookie`].
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self.default_query.retain(|(key, _)| key != "locale");
+        self.default_query
+            .push(("locale".to_string(), locale.to_string()));
+        self.cookies.retain(|cookie| !cookie.starts_with("locale="));
+        self.cookies
+            .push(format!("locale={}", locale.cookie_value()));
+        self
+    }
+
+    /// Use another storefront by replacing the last path segment of the base URL, e.g.
+    /// `https://www.dlsite.com/books` for [`Site::Books`]. A base URL without storefront gets
+    /// one appended.
+    ///
+    /// Searches, circle and campaign pages are requested on that storefront, and parsed
+    /// according to it: works without age icon are all-ages on [`Site::Home`], [`Site::Soft`]
+    /// and [`Site::Comic`], and adult elsewhere.
+    pub fn site(mut self, site: Site) -> Self {
+        let base = self.base_url.trim_end_matches('/');
+        self.base_url = match base.rsplit_once('/') {
+            Some((origin, last)) if last.parse::<Site>().is_ok() => format!("{origin}/{site}"),
+            _ => format!("{base}/{site}"),
+        };
+        self
+    }
+
+    /// Set what happens when DLsite serves a page in another language than the one requested
+    /// with the `locale` query parameter. Default: [`LanguageCheck::Warn`].
+    pub fn language_check(mut self, check: LanguageCheck) -> Self {
+        self.language_check = check;
+        self
+    }
+
+    /// Skip the search results which fail to parse instead of failing the whole page. They
+    /// are returned in [`crate::client::search::SearchResult::skipped`]. Disabled by default.
+    pub fn lenient_parsing(mut self, lenient: bool) -> Self {
+        self.lenient_parsing = lenient;
+        self
+    }
+
+    /// Normalize the titles of search results and products (see [`crate::normalize`]).
+    /// Disabled by default: titles are returned as written on DLsite.
+    pub fn normalize_titles(mut self, normalizer: Normalizer) -> Self {
+        self.title_normalizer = Some(normalizer);
+        self
+    }
+
+    /// Send some kinds of requests (search, apis, images, pages) to other origins, e.g. mirrors
+    /// or caching proxies. See [`EndpointOverrides`].
+    pub fn endpoint_overrides(mut self, overrides: EndpointOverrides) -> Self {
+        self.endpoints = overrides;
+        self
+    }
+
+    /// Store responses in the given cache backend instead of the in-memory LRU, e.g. a
+    /// [`crate::DiskCache`] to keep responses across restarts.
+    ///
+    /// The capacity and TTL set by [`DlsiteClientBuilder::cache`] are ignored in this case.
+    /// Replaces a cache set by [`DlsiteClientBuilder::shared_cache`].
+    pub fn cache_backend(mut self, backend: impl CacheBackend + 'static) -> Self {
+        self.shared_cache = Some(ResponseCache::with_backend(backend));
+        self
+    }
+
+    /// Add every product fetched by the client to the given full-text index.
+    #[cfg(feature = "tantivy")]
+    pub fn local_index(mut self, index: crate::index::LocalIndex) -> Self {
+        self.local_index = Some(index);
+        self
+    }
+
+    /// Also add the user tags (みんなのタグ) of scraped products to the local index, so that
+    /// [`crate::index::LocalIndex::search`] matches them. Off by default, as anyone can add
+    /// tags.
+    #[cfg(feature = "tantivy")]
+    pub fn index_user_tags(mut self, enabled: bool) -> Self {
+        self.index_user_tags = enabled;
+        self
+    }
+
+    /// Write every response fetched from DLsite to the given archive, see
+    /// [`crate::archive`].
+    #[cfg(feature = "archive")]
+    pub fn archive(mut self, archive: crate::archive::Archive) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Make a builder from the TOML profile at `path`, see [`crate::config`].
+    ///
+    /// Settings can still be overridden on the returned builder.
+    #[cfg(feature = "config")]
+    pub fn from_config(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        crate::config::ClientConfig::load(path)?.builder()
+    }
+
     /// Build the DlsiteClient
     pub fn build(self) -> DlsiteClient {
-        let client = reqwest::Client::builder()
-            .pool_max_idle_per_host(self.pool_max_idle_per_host)
-            .timeout(self.timeout)
-            .user_agent("dlsite-rs/0.2.0")
-            .build()
-            .expect("Failed to build HTTP client");
+        #[cfg(not(target_arch = "wasm32"))]
+        let (client, cookie_jar) = {
+            let cookie_jar = Arc::new(CookieJar::default());
+            if let Ok(url) = url::Url::parse(&self.base_url) {
+                // Share cookies between storefronts and the login server
+                let domain = match url.host_str() {
+                    Some(host) if crate::utils::is_in_domain(host, "dlsite.com") => {
+                        "; Domain=.dlsite.com"
+                    }
+                    _ => "",
+                };
+                let adult = self.confirm_adult.then(|| ADULT_CHECK_COOKIE.to_string());
+                for cookie in self.cookies.iter().chain(adult.iter()) {
+                    cookie_jar.add_cookie_str(&format!("{}{}; Path=/", cookie, domain), &url);
+                }
+            }
+
+            let mut client = reqwest::Client::builder()
+                .cookie_provider(cookie_jar.clone())
+                .pool_max_idle_per_host(self.pool_max_idle_per_host)
+                .timeout(self.timeout)
+                .user_agent("dlsite-rs/0.2.0");
+            if let Some(proxy) = self.proxy {
+                client = client.proxy(proxy);
+            }
+            let client = client.build().expect("Failed to build HTTP client");
+            (client, cookie_jar)
+        };
+        // The browser backend of reqwest sends the cookies of the browser
+        #[cfg(target_arch = "wasm32")]
+        let client = reqwest::Client::new();
+
+        let site = self
+            .base_url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default();
 
         DlsiteClient {
-            client,
+            transport: self
+                .transport
+                .unwrap_or_else(|| Arc::new(ReqwestTransport::new(client))),
             base_url: self.base_url,
-            last_request_time: Arc::new(AtomicU64::new(0)),
-            cache: ResponseCache::new(self.cache_capacity, self.cache_ttl),
+            site,
+            locale: self.locale,
+            rate_limiter: self.rate_limiter.unwrap_or_else(|| {
+                let (min, max) = self.request_interval;
+                Arc::new(IntervalLimiter::new(min, max))
+            }),
+            priority_gate: Arc::default(),
+            priority: Priority::default(),
+            default_query: Arc::new(self.default_query),
+            #[cfg(not(target_arch = "wasm32"))]
+            cookie_jar,
+            relogin: self
+                .relogin
+                .map(|(login_id, password)| Arc::new(auth::Relogin::new(login_id, password))),
+            cache: self
+                .shared_cache
+                .unwrap_or_else(|| ResponseCache::new(self.cache_capacity, self.cache_ttl)),
+            in_flight: InFlight::default(),
+            events: EventListeners::new(self.event_listeners),
+            request_stats: Arc::default(),
+            budget: self.request_budget.map(|limit| {
+                Arc::new(RequestBudget {
+                    limit,
+                    used: AtomicU64::new(0),
+                })
+            }),
+            circuit: Arc::new(CircuitBreaker {
+                cooldown: self.challenge_cooldown,
+                open: Mutex::default(),
+            }),
+            media_cache: MediaCache::new(self.media_cache_capacity, self.cache_ttl),
             retry_config: self.retry_config,
+            dump_dir: self.dump_dir.map(Arc::new),
+            language_check: self.language_check,
+            lenient_parsing: self.lenient_parsing,
+            endpoints: Arc::new(self.endpoints),
+            title_normalizer: self.title_normalizer,
+            #[cfg(feature = "tantivy")]
+            local_index: self.local_index,
+            #[cfg(feature = "tantivy")]
+            index_user_tags: self.index_user_tags,
+            #[cfg(feature = "archive")]
+            archive: self.archive.map(Arc::new),
         }
     }
 }
@@ -125,88 +689,633 @@ impl DlsiteClient {
     /// Rate limit: 2 requests per second (500ms between requests)
     /// Cache: 100 entries with 1 hour TTL
     /// Retry: 3 attempts with exponential backoff for retryable errors
+    ///
+    /// Concurrent calls for the same URL share a single request.
     pub async fn get(&self, path: &str) -> Result<String> {
-        let url = format!("{}{}", self.base_url, path);
+        self.fetch(format!("{}{}", self.base_url, path), true).await
+    }
+
+    /// Similar to `get`, but the response cache is bypassed. Used for pages which depend on
+    /// the session (e.g. personal feeds).
+    pub(crate) async fn get_fresh(&self, path: &str) -> Result<String> {
+        self.fetch(format!("{}{}", self.base_url, path), false).await
+    }
+
+    /// Same as `get_fresh`, with an absolute URL. Used for the other DLsite hosts (e.g. DLsite
+    /// Play).
+    pub(crate) async fn get_fresh_url(&self, url: &str) -> Result<String> {
+        self.fetch(url.to_string(), false).await
+    }
+
+    /// Similar to `get`, but the request is sent to the given storefront instead of the one
+    /// the base URL points to.
+    pub async fn get_on(&self, site: Site, path: &str) -> Result<String> {
+        if site == self.site {
+            return self.get(path).await;
+        }
+        self.fetch(format!("{}{}", self.site_base_url(site), path), true)
+            .await
+    }
+
+    /// Fetch an absolute URL with rate limiting, caching and retries.
+    async fn fetch(&self, url: String, use_cache: bool) -> Result<String> {
+        let url = self.rewrite_endpoint(self.apply_default_query(url));
 
         // Check cache first
-        if let Some(cached) = self.cache.get(&url) {
-            return Ok(cached);
+        let _in_flight = if use_cache {
+            if let Some(cached) = self.cache.get(&url) {
+                self.events.emit(|l| l.on_cache_hit(&url));
+                return Ok(cached);
+            }
+            // Wait for a concurrent request of the same URL, which may have cached it. The
+            // lookup was already counted as a miss.
+            let guard = self.in_flight.acquire(&url).await;
+            if let Some(cached) = self.cache.peek(&url) {
+                self.events.emit(|l| l.on_cache_hit(&url));
+                return Ok(cached);
+            }
+            self.events.emit(|l| l.on_cache_miss(&url));
+            Some(guard)
+        } else {
+            None
+        };
+
+        let started = Instant::now();
+        let (response, attempts) = self.send_get(&url, started, true).await?;
+        let status = Some(response.status);
+        let body = match response.buffer().await {
+            Ok(response) => response.text(),
+            Err(err) => return self.finish(&url, started, attempts, status, Err(err)),
+        };
+        #[cfg(feature = "archive")]
+        self.archive_response(&url, &body);
+        if let Err(err) = self.check_language(&url, &body) {
+            return self.finish(&url, started, attempts, status, Err(err));
+        }
+        // Cache the response
+        if use_cache {
+            self.cache.insert(url.clone(), body.clone());
         }
+        self.finish(&url, started, attempts, status, Ok(body))
+    }
+
+    /// Same as `get_on`, but the body is returned as chunks as they arrive and is not cached,
+    /// so that large api responses are never held in memory at once. The request is reported
+    /// to the event listeners once its headers are received.
+    pub(crate) async fn get_streamed_on(&self, site: Site, path: &str) -> Result<BodyStream> {
+        let url = if site == self.site {
+            format!("{}{}", self.base_url, path)
+        } else {
+            format!("{}{}", self.site_base_url(site), path)
+        };
+        let url = self.rewrite_endpoint(self.apply_default_query(url));
+        let started = Instant::now();
+        let (response, attempts) = self.send_get(&url, started, false).await?;
+        let status = Some(response.status);
+        let body = self.check_streamed_language(&url, response.body).await;
+        #[cfg(feature = "archive")]
+        let body = body.map(|body| self.archive_streamed_response(&url, body));
+        self.finish(&url, started, attempts, status, body)
+    }
 
-        // Retry loop
+    /// [`DlsiteClient::check_language`] for streamed bodies. An HTML page is read whole to be
+    /// checked; other bodies are passed through as they arrive.
+    async fn check_streamed_language(&self, url: &str, mut body: BodyStream) -> Result<BodyStream> {
+        if self.language_check == LanguageCheck::Off {
+            return Ok(body);
+        }
+        let Some(mut page) = body.next().await.transpose()? else {
+            return Ok(body);
+        };
+        if !page.trim_ascii_start().starts_with(b"<") {
+            return Ok(Box::pin(futures::stream::iter([Ok(page)]).chain(body)));
+        }
+        while let Some(chunk) = body.next().await {
+            page.extend_from_slice(&chunk?);
+        }
+        self.check_language(url, &String::from_utf8_lossy(&page))?;
+        Ok(Box::pin(futures::stream::iter([Ok(page)])))
+    }
+
+    /// Send a GET request of `url` with the circuit breaker, request budget, rate limiter and
+    /// retries, until it succeeds. Returns the response and the number of attempts; failures
+    /// are reported to the event listeners.
+    ///
+    /// Bodies of successful responses are read before returning only if `buffer` is set, so
+    /// that challenge pages served as successes are detected. Error pages are always read.
+    async fn send_get(
+        &self,
+        url: &str,
+        started: Instant,
+        buffer: bool,
+    ) -> Result<(StreamedResponse, u32)> {
         let mut last_error = None;
+        let mut last_status = None;
         for attempt in 0..=self.retry_config.max_retries {
-            // Rate limiting: ensure at least 500ms between requests (2 req/sec)
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64;
-
-            let last_time = self.last_request_time.load(std::sync::atomic::Ordering::Relaxed);
-            let elapsed = now.saturating_sub(last_time);
-
-            if elapsed < 500 {
-                let sleep_time = Duration::from_millis(500 - elapsed);
-                tokio::time::sleep(sleep_time).await;
+            if let Err(err) = self.circuit.check().and_then(|()| self.spend_budget()) {
+                // Nothing was sent for this attempt
+                return match attempt {
+                    0 => Err(err),
+                    _ => self.finish(url, started, attempt, last_status, Err(err)),
+                };
             }
+            self.wait_for_slot().await;
 
-            self.last_request_time.store(now, std::sync::atomic::Ordering::Relaxed);
-
-            match self.client.get(&url).send().await {
-                Ok(response) => {
+            let response = match self.transport.send_streamed(HttpRequest::get(url)).await {
+                Ok(response) if buffer || !response.is_success() => {
+                    response.buffer().await.map(|response| (response, None))
+                }
+                // Only the headers are checked
+                Ok(StreamedResponse {
+                    status,
+                    headers,
+                    body,
+                }) => {
+                    let head = HttpResponse {
+                        status,
+                        headers,
+                        body: vec![],
+                    };
+                    Ok((head, Some(body)))
+                }
+                Err(e) => Err(e),
+            };
+            let err = match response {
+                Ok((response, body)) => {
                     // Check HTTP status code
-                    let status = response.status();
-                    if status == 429 {
-                        let err = DlsiteError::RateLimit(
-                            "Too many requests, please retry later".to_string()
-                        );
-                        if attempt < self.retry_config.max_retries && self.retry_config.is_retryable(&err) {
-                            last_error = Some(err);
-                            let delay = self.retry_config.calculate_delay(attempt);
-                            tokio::time::sleep(delay).await;
-                            continue;
-                        }
-                        return Err(err);
+                    let status = response.status;
+                    last_status = Some(status);
+                    if let Some(challenge) = challenge_url(&response, url) {
+                        self.circuit.trip(&challenge);
+                        let err = DlsiteError::Challenge { url: challenge };
+                        return self.finish(url, started, attempt + 1, last_status, Err(err));
                     }
-                    if !status.is_success() {
-                        let err = DlsiteError::HttpStatus(status.as_u16());
-                        if attempt < self.retry_config.max_retries && self.retry_config.is_retryable(&err) {
-                            last_error = Some(err);
-                            let delay = self.retry_config.calculate_delay(attempt);
-                            tokio::time::sleep(delay).await;
-                            continue;
-                        }
-                        return Err(err);
+                    if status == 429 {
+                        rate_limit_error(&response)
+                    } else if !response.is_success() {
+                        DlsiteError::HttpStatus(status)
+                    } else {
+                        let response = match body {
+                            Some(body) => StreamedResponse {
+                                status,
+                                headers: response.headers,
+                                body,
+                            },
+                            None => response.into(),
+                        };
+                        return Ok((response, attempt + 1));
                     }
+                }
+                Err(e) => e,
+            };
+
+            if attempt < self.retry_config.max_retries && self.retry_config.is_retryable(&err) {
+                // Wait at least as long as the server asked to
+                let delay = self
+                    .retry_config
+                    .retry_delay(attempt)
+                    .max(err.retry_after().unwrap_or_default());
+                self.events.emit(|l| l.on_retry(url, attempt + 1, &err, delay));
+                self.retry_config.notify_retry(attempt + 1, &err, delay);
+                last_error = Some(err);
+                runtime::sleep(delay).await;
+                continue;
+            }
+            return self.finish(url, started, attempt + 1, last_status, Err(err));
+        }
+
+        // If we exhausted all retries, return the last error
+        let err = last_error.unwrap_or_else(|| DlsiteError::Parse("Unknown error".to_string()));
+        self.finish(
+            url,
+            started,
+            self.retry_config.max_retries + 1,
+            last_status,
+            Err(err),
+        )
+    }
+
+    /// Report a finished request to the event listeners.
+    fn finish<T>(
+        &self,
+        url: &str,
+        started: Instant,
+        attempts: u32,
+        status: Option<u16>,
+        result: Result<T>,
+    ) -> Result<T> {
+        self.request_stats.record(attempts);
+        self.events.emit(|l| {
+            l.on_request_complete(&RequestEvent {
+                url,
+                status,
+                attempts,
+                elapsed: started.elapsed(),
+                error: result.as_ref().err(),
+            })
+        });
+        result
+    }
+
+    /// Apply the normalization set by [`DlsiteClientBuilder::normalize_titles`], if any.
+    pub(crate) fn normalize_title(&self, title: &mut String) {
+        if let Some(normalizer) = &self.title_normalizer {
+            *title = normalizer.apply(title);
+        }
+    }
+
+    /// Average gap between requests allowed by the rate limiter, if it reports one.
+    pub(crate) fn request_interval(&self) -> Option<Duration> {
+        self.rate_limiter.interval()
+    }
+
+    /// Requests and retries sent by this client and its clones.
+    pub(crate) fn request_stats(&self) -> Arc<RequestStats> {
+        self.request_stats.clone()
+    }
+
+    /// Requests left in the budget set by [`DlsiteClientBuilder::request_budget`], `None`
+    /// without budget.
+    pub fn remaining_budget(&self) -> Option<u64> {
+        self.budget.as_ref().map(|budget| {
+            budget
+                .limit
+                .saturating_sub(budget.used.load(Ordering::SeqCst))
+        })
+    }
+
+    /// Whether requests are paused because DLsite served a bot challenge, see
+    /// [`DlsiteClientBuilder::challenge_cooldown`].
+    pub fn is_circuit_open(&self) -> bool {
+        self.circuit.check().is_err()
+    }
+
+    /// Resume requests before the end of the cooldown, e.g. once the challenge was solved in
+    /// a browser sharing the cookies of the client.
+    pub fn close_circuit(&self) {
+        *self.circuit.open.lock().unwrap() = None;
+    }
 
-                    let body = response.text().await?;
+    /// Count a request against the budget, if any.
+    fn spend_budget(&self) -> Result<()> {
+        match &self.budget {
+            Some(budget) => budget.spend(),
+            None => Ok(()),
+        }
+    }
 
-                    // Cache the response
-                    self.cache.insert(url, body.clone());
+    /// Write a response to the archive set by [`DlsiteClientBuilder::archive`], if any. The
+    /// record is written on the blocking pool.
+    #[cfg(feature = "archive")]
+    fn archive_response(&self, url: &str, body: &str) {
+        if let Some(archive) = &self.archive {
+            archive_body(archive.clone(), url.to_string(), body.to_string());
+        }
+    }
 
-                    return Ok(body);
+    /// [`DlsiteClient::archive_response`] for streamed bodies. Chunks are passed through as
+    /// they arrive and kept until the end of the body, which is then archived. Bodies failing
+    /// midway are not archived.
+    #[cfg(feature = "archive")]
+    fn archive_streamed_response(&self, url: &str, body: BodyStream) -> BodyStream {
+        let Some(archive) = &self.archive else {
+            return body;
+        };
+        let state = (body, vec![], archive.clone(), url.to_string());
+        Box::pin(futures::stream::unfold(Some(state), |state| async move {
+            let (mut body, mut received, archive, url) = state?;
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    received.extend_from_slice(&chunk);
+                    Some((Ok(chunk), Some((body, received, archive, url))))
+                }
+                Some(Err(e)) => Some((Err(e), None)),
+                None => {
+                    let body = String::from_utf8_lossy(&received).into_owned();
+                    archive_body(archive, url, body);
+                    None
                 }
-                Err(e) => {
-                    let err = DlsiteError::from(e);
-                    if attempt < self.retry_config.max_retries && self.retry_config.is_retryable(&err) {
-                        last_error = Some(err);
-                        let delay = self.retry_config.calculate_delay(attempt);
-                        tokio::time::sleep(delay).await;
-                        continue;
+            }
+        }))
+    }
+
+    /// Compare the language of a response with the requested one, see [`LanguageCheck`].
+    fn check_language(&self, url: &str, body: &str) -> Result<()> {
+        if self.language_check == LanguageCheck::Off {
+            return Ok(());
+        }
+        let (Some(requested), Some(served)) = (
+            language::requested_language(url),
+            language::served_language(body),
+        ) else {
+            return Ok(());
+        };
+        if requested == served {
+            return Ok(());
+        }
+        match self.language_check {
+            LanguageCheck::Error => Err(DlsiteError::LanguageMismatch { requested, served }),
+            _ => {
+                tracing::warn!("Requested {url} in {requested}, but DLsite served {served}");
+                Ok(())
+            }
+        }
+    }
+
+    /// Dump the response to the directory set by
+    /// [`DlsiteClientBuilder::dump_failed_responses`] if `result` is a parse error.
+    ///
+    /// Only successful responses are parsed, so the recorded status is always 200.
+    pub(crate) fn dump_parse_error<T>(
+        &self,
+        site: Site,
+        path: &str,
+        body: &str,
+        result: Result<T>,
+    ) -> Result<T> {
+        if let (Some(dir), Err(err @ (DlsiteError::Parse(_) | DlsiteError::SerdeJson(_)))) =
+            (&self.dump_dir, &result)
+        {
+            let url = format!("{}{}", self.site_base_url(site), path);
+            match write_dump(dir, &url, body, err) {
+                Ok(file) => {
+                    tracing::warn!("Failed to parse {url}, response dumped to {}", file.display())
+                }
+                Err(e) => tracing::warn!("Failed to dump response of {url}: {e}"),
+            }
+        }
+        result
+    }
+
+    /// Wait until the rate limiter allows the next request.
+    ///
+    /// Background requests reserve a slot one at a time, once no foreground request is
+    /// pending, so that a foreground request waits for at most one background slot.
+    async fn wait_for_slot(&self) {
+        let started = Instant::now();
+        let gate = &self.priority_gate;
+        match self.priority {
+            Priority::Foreground => {
+                let _guard = ForegroundGuard::new(gate);
+                self.rate_limiter.acquire().await;
+            }
+            Priority::Background => {
+                let _turn = gate.background_turn.lock().await;
+                loop {
+                    // Registered before checking, so that the last foreground request
+                    // finishing in between is not missed
+                    let done = gate.foreground_done.notified();
+                    let mut done = std::pin::pin!(done);
+                    done.as_mut().enable();
+                    if gate.foreground_pending.load(Ordering::SeqCst) == 0 {
+                        break;
                     }
-                    return Err(err);
+                    done.await;
                 }
+                self.rate_limiter.acquire().await;
             }
         }
 
-        // If we exhausted all retries, return the last error
-        Err(last_error.unwrap_or_else(|| DlsiteError::Parse("Unknown error".to_string())))
+        let waited = started.elapsed();
+        if waited >= Duration::from_millis(1) {
+            self.events.emit(|l| l.on_rate_limit_wait(waited));
+        }
+    }
+
+    /// Append the client-wide default query parameters to a URL.
+    fn apply_default_query(&self, url: String) -> String {
+        if self.default_query.is_empty() {
+            return url;
+        }
+        let Ok(mut parsed) = url::Url::parse(&url) else {
+            return url;
+        };
+        let existing: Vec<String> = parsed.query_pairs().map(|(k, _)| k.into_owned()).collect();
+        {
+            let mut pairs = parsed.query_pairs_mut();
+            for (key, value) in self.default_query.iter() {
+                if !existing.contains(key) {
+                    pairs.append_pair(key, value);
+                }
+            }
+        }
+        parsed.to_string()
+    }
+
+    /// Send the request to the origin set by [`DlsiteClientBuilder::endpoint_overrides`], if
+    /// any.
+    fn rewrite_endpoint(&self, url: String) -> String {
+        let base = url::Url::parse(&self.base_url).ok();
+        self.endpoints.apply(url, base.as_ref().and_then(|b| b.host_str()))
+    }
+
+    /// Make a form POST request to a path under the base URL.
+    ///
+    /// The request respects the rate limiter, but is neither cached nor retried since it may
+    /// change state on the server.
+    pub(crate) async fn post_form(&self, path: &str, form: &[(&str, &str)]) -> Result<String> {
+        let url = self.apply_default_query(format!("{}{}", self.base_url, path));
+        self.post_form_url(&self.rewrite_endpoint(url), form).await
+    }
+
+    /// Same as `post_form`, with an absolute URL.
+    async fn post_form_url(&self, url: &str, form: &[(&str, &str)]) -> Result<String> {
+        self.circuit.check()?;
+        self.spend_budget()?;
+        self.wait_for_slot().await;
+
+        let response = self
+            .transport
+            .send(HttpRequest::post_form(url, form))
+            .await?;
+        if let Some(challenge) = challenge_url(&response, url) {
+            self.circuit.trip(&challenge);
+            return Err(DlsiteError::Challenge { url: challenge });
+        }
+        let status = response.status;
+        if status == 429 {
+            return Err(rate_limit_error(&response));
+        }
+        if status == 401 || status == 403 {
+            return Err(DlsiteError::Unauthenticated);
+        }
+        if !response.is_success() {
+            return Err(DlsiteError::HttpStatus(status));
+        }
+        Ok(response.text())
+    }
+
+    /// Download an image (or other binary file) by absolute URL, using the media cache.
+    ///
+    /// Media is served by DLsite's CDN, so these requests don't go through the rate limiter.
+    pub async fn get_media(&self, url: &str) -> Result<Arc<[u8]>> {
+        if let Some(cached) = self.media_cache.get(url) {
+            return Ok(cached);
+        }
+
+        self.spend_budget()?;
+        let response = self
+            .transport
+            .send(HttpRequest::get(self.rewrite_endpoint(url.to_string())))
+            .await?;
+        if !response.is_success() {
+            return Err(DlsiteError::HttpStatus(response.status));
+        }
+        let bytes: Arc<[u8]> = response.body.into();
+        self.media_cache.insert(url.to_string(), bytes.clone());
+
+        Ok(bytes)
+    }
+
+    /// Get `len` bytes of an absolute URL from `start`, with a `Range` request. Not cached,
+    /// and like media, not rate limited.
+    ///
+    /// Returns the response whatever its status: `206` for the range, `200` if the server
+    /// ignored it, `416` past the end. The body is returned as chunks as they arrive, so that
+    /// a server sending the whole file doesn't load it in memory.
+    #[cfg(feature = "download")]
+    pub(crate) async fn get_range(
+        &self,
+        url: &str,
+        start: u64,
+        len: u64,
+    ) -> Result<StreamedResponse> {
+        self.circuit.check()?;
+        self.spend_budget()?;
+        let range = format!("bytes={}-{}", start, start + len - 1);
+        let request =
+            HttpRequest::get(self.rewrite_endpoint(url.to_string())).with_header("Range", &range);
+        let response = self.transport.send_streamed(request).await?;
+        if response.status == 429 {
+            return Err(rate_limit_error(&response.buffer().await?));
+        }
+        if !response.is_success() && response.status != 416 {
+            return Err(DlsiteError::HttpStatus(response.status));
+        }
+        Ok(response)
     }
 
     /// Similar to `get`, but this method does not prepend the base URL.
     pub async fn get_raw(&self, url: &str) -> Result<String> {
-        let body = self.client.get(url).send().await?.text().await?;
-        Ok(body)
+        let url = self.rewrite_endpoint(url.to_string());
+        self.circuit.check()?;
+        self.spend_budget()?;
+        let response = self.transport.send(HttpRequest::get(url.as_str())).await?;
+        if let Some(challenge) = challenge_url(&response, &url) {
+            self.circuit.trip(&challenge);
+            return Err(DlsiteError::Challenge { url: challenge });
+        }
+        Ok(response.text())
+    }
+
+    /// Get a client sharing the rate limiter, cache and connection pool of this client whose
+    /// requests are made with the given priority.
+    ///
+    /// # Example
+    /// ```
+    /// use dlsite_gamebox::{client::Priority, DlsiteClient};
+    ///
+    /// let client = DlsiteClient::default();
+    /// let crawler = client.with_priority(Priority::Background);
+    /// assert_eq!(crawler.priority(), Priority::Background);
+    /// ```
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Priority of requests made by this client.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Language requested from DLsite, see [`DlsiteClientBuilder::locale`].
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// Storefront the base URL of this client points to.
+    pub fn site(&self) -> Site {
+        self.site
+    }
+
+    /// Whether search results which fail to parse are skipped, see
+    /// [`DlsiteClientBuilder::lenient_parsing`].
+    pub fn lenient_parsing(&self) -> bool {
+        self.lenient_parsing
+    }
+
+    /// Whether the response of a request to `path` on the given storefront is in the cache.
+    pub(crate) fn is_cached_on(&self, site: Site, path: &str) -> bool {
+        let base = if site == self.site {
+            self.base_url.clone()
+        } else {
+            self.site_base_url(site)
+        };
+        let url = self.rewrite_endpoint(self.apply_default_query(format!("{}{}", base, path)));
+        self.cache.contains(&url)
+    }
+
+    /// Base URL of the given storefront, derived from the base URL of this client.
+    pub fn site_base_url(&self, site: Site) -> String {
+        match self.base_url.trim_end_matches('/').rsplit_once('/') {
+            Some((origin, _)) => format!("{}/{}", origin, site),
+            None => format!("{}/{}", self.base_url, site),
+        }
+    }
+
+    /// Cookie jar shared by all requests of this client
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn cookie_jar(&self) -> &Arc<CookieJar> {
+        &self.cookie_jar
+    }
+
+    /// Response cache used by this client
+    pub fn cache(&self) -> &ResponseCache {
+        &self.cache
+    }
+
+    /// Full-text index updated with products fetched by this client
+    #[cfg(feature = "tantivy")]
+    pub fn local_index(&self) -> Option<&crate::index::LocalIndex> {
+        self.local_index.as_ref()
+    }
+
+    /// Add a fetched product to the local index, if any. Failures are only logged.
+    #[cfg(feature = "tantivy")]
+    pub(crate) fn index_work(&self, mut work: crate::index::IndexedWork) {
+        if !self.index_user_tags {
+            work.tags.clear();
+        }
+        if let Some(index) = &self.local_index {
+            if let Err(e) = index.add(&work) {
+                tracing::warn!("Failed to index {}: {}", work.id, e);
+            }
+        }
+    }
+
+    /// Record the cover hash of a work in the local index, if any. Decoding the image is CPU
+    /// bound, so it runs on the blocking thread pool.
+    #[cfg(feature = "image-hash")]
+    pub(crate) async fn index_cover(&self, id: &str, image: Arc<[u8]>) {
+        let Some(index) = self.local_index.clone() else {
+            return;
+        };
+        let owned_id = id.to_string();
+        let result = tokio::task::spawn_blocking(move || index.add_cover(&owned_id, &image))
+            .await
+            .map_err(|e| DlsiteError::Index(e.to_string()))
+            .and_then(|hashed| hashed);
+        if let Err(e) = result {
+            tracing::warn!("Failed to hash cover of {}: {}", id, e);
+        }
+    }
+
+    /// Media cache used by [`DlsiteClient::get_media`]
+    pub fn media_cache(&self) -> &MediaCache {
+        &self.media_cache
     }
 
     /// Clear the response cache
@@ -219,6 +1328,12 @@ impl DlsiteClient {
         self.cache.len()
     }
 
+    /// Hits, misses, evictions and expirations of the response cache, see
+    /// [`DlsiteClient::media_cache`] for those of the media cache.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
     /// Set the retry configuration
     pub fn set_retry_config(&mut self, config: RetryConfig) {
         self.retry_config = config;
@@ -228,29 +1343,514 @@ impl DlsiteClient {
     pub fn retry_config(&self) -> &RetryConfig {
         &self.retry_config
     }
+
+    /// Get a product merged from its page and the product api. Shorthand for
+    /// [`product::ProductClient::get_unified`].
+    pub async fn unified_product(
+        &self,
+        product_id: impl Into<crate::interface::product_id::ProductId>,
+    ) -> Result<product::unified::UnifiedProduct> {
+        self.product().get_unified(product_id).await
+    }
+
+    /// Get the works released since `since`. Shorthand for
+    /// [`search::SearchClient::new_arrivals`].
+    pub async fn new_arrivals(
+        &self,
+        since: chrono::NaiveDate,
+    ) -> Result<Vec<search::SearchProductItem>> {
+        self.search().new_arrivals(since).await
+    }
 }
 
 /// These methods return a “sub-client”.
 /// The sub-client has a DlsiteClient reference inside and has implementations of fetch and parse focused on certain purposes.
 impl DlsiteClient {
+    /// Get a client to fetch the purchase history, favorites and cart of the logged-in user.
+    /// For more information, see [`account::AccountClient`].
+    pub fn account(&self) -> account::AccountClient<'_> {
+        account::AccountClient { c: self }
+    }
+
+    /// Get a client to browse the works of the logged-in user on DLsite Play. For more
+    /// information, see [`play::PlayClient`].
+    pub fn play(&self) -> play::PlayClient<'_> {
+        play::PlayClient { c: self }
+    }
+
+    /// Get a client to fetch commercial works from DLsite Pro. For more information, see
+    /// [`pro::ProClient`].
+    pub fn pro(&self) -> pro::ProClient<'_> {
+        pro::ProClient { c: self }
+    }
+
     /// Get a client to fetch product info using 'scraping' method. For more information, see [`product::ProductClient`].
-    pub fn product(&self) -> product::ProductClient {
+    pub fn product(&self) -> product::ProductClient<'_> {
         product::ProductClient { c: self }
     }
 
     /// Get a client to fetch product info using 'api' method. For more information, see
     /// [`product_api::ProductApiClient`].
-    pub fn product_api(&self) -> product_api::ProductApiClient {
+    pub fn product_api(&self) -> product_api::ProductApiClient<'_> {
         product_api::ProductApiClient { c: self }
     }
 
+    /// Get a client to fetch Android apps from the app storefront. For more information, see
+    /// [`app::AppClient`].
+    pub fn app(&self) -> app::AppClient<'_> {
+        app::AppClient { c: self }
+    }
+
+    /// Get a client to fetch books from DLsite Books. For more information, see
+    /// [`book::BookClient`].
+    pub fn book(&self) -> book::BookClient<'_> {
+        book::BookClient { c: self }
+    }
+
+    /// Get a client to fetch sale campaigns. For more information, see
+    /// [`campaign::CampaignClient`].
+    pub fn campaign(&self) -> campaign::CampaignClient<'_> {
+        campaign::CampaignClient { c: self }
+    }
+
     /// Get a client to fetch circle info. For more information, see [`circle::CircleClient`].
-    pub fn circle(&self) -> circle::CircleClient {
+    pub fn circle(&self) -> circle::CircleClient<'_> {
         circle::CircleClient { c: self }
     }
 
+    /// Get a client to fetch the works of a creator. For more information, see
+    /// [`creator::CreatorClient`].
+    pub fn creator(&self) -> creator::CreatorClient<'_> {
+        creator::CreatorClient { c: self }
+    }
+
+    /// Get a client to find and claim coupons. For more information, see
+    /// [`coupon::CouponClient`].
+    pub fn coupon(&self) -> coupon::CouponClient<'_> {
+        coupon::CouponClient { c: self }
+    }
+
+    /// Get a client to fetch the feed of followed circles. For more information, see
+    /// [`follow::FollowClient`].
+    pub fn follow(&self) -> follow::FollowClient<'_> {
+        follow::FollowClient { c: self }
+    }
+
+    /// Get a client to fetch ranking pages. For more information, see [`ranking::RankingClient`].
+    pub fn ranking(&self) -> ranking::RankingClient<'_> {
+        ranking::RankingClient { c: self }
+    }
+
     /// Get a client to search things. For more information, see [`search::SearchClient`].
-    pub fn search(&self) -> search::SearchClient {
+    pub fn search(&self) -> search::SearchClient<'_> {
         search::SearchClient::new(self)
     }
 }
+
+/// Error for a 429 response, with the delay of its `Retry-After` header.
+/// Write a response to `archive` on the blocking pool, timestamped now.
+#[cfg(feature = "archive")]
+fn archive_body(archive: Arc<crate::archive::Archive>, url: String, body: String) {
+    let fetched_at = chrono::Utc::now();
+    runtime::spawn_blocking(move || {
+        if let Err(e) = archive.write_at(&url, &body, fetched_at) {
+            tracing::warn!("Failed to archive {url}: {e}");
+        }
+    });
+}
+
+fn rate_limit_error(response: &HttpResponse) -> DlsiteError {
+    let retry_after = response
+        .header("retry-after")
+        .and_then(|v| crate::retry::parse_retry_after(v, chrono::Utc::now()));
+    DlsiteError::RateLimit {
+        message: "Too many requests, please retry later".to_string(),
+        retry_after,
+    }
+}
+
+/// URL of the CAPTCHA or bot challenge served instead of `url`, if `response` is one: the
+/// CAPTCHA page when the response embeds one, `url` otherwise.
+fn challenge_url(response: &HttpResponse, url: &str) -> Option<String> {
+    // Markers only found on challenge pages
+    const CHALLENGE_MARKERS: [&str; 3] = [
+        "/cdn-cgi/challenge-platform/",
+        "captcha-delivery.com",
+        "<title>Just a moment...</title>",
+    ];
+    // Widgets also found on regular pages (e.g. the login form), only a challenge on errors
+    const CAPTCHA_MARKERS: [&str; 3] = ["g-recaptcha", "h-captcha", "cf-turnstile"];
+    static CAPTCHA_URL_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+
+    let body = String::from_utf8_lossy(&response.body);
+    let challenged = response
+        .header("cf-mitigated")
+        .is_some_and(|v| v.eq_ignore_ascii_case("challenge"))
+        || CHALLENGE_MARKERS.iter().any(|m| body.contains(m))
+        || (!response.is_success() && CAPTCHA_MARKERS.iter().any(|m| body.contains(m)));
+    if !challenged {
+        return None;
+    }
+    let re = CAPTCHA_URL_RE.get_or_init(|| {
+        regex::Regex::new(r#"https?://[\w.-]*captcha-delivery\.com/[^"'\s<>]*"#).unwrap()
+    });
+    Some(
+        re.find(&body)
+            .map_or_else(|| url.to_string(), |m| m.as_str().replace("&amp;", "&")),
+    )
+}
+
+/// Write a failed response to a new file in `dir` and return its path.
+fn write_dump(dir: &Path, url: &str, body: &str, err: &DlsiteError) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let slug: String = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(100)
+        .collect();
+    let file = dir.join(format!(
+        "{}-{}.txt",
+        chrono::Local::now().format("%Y%m%d-%H%M%S%.3f"),
+        slug
+    ));
+    std::fs::write(
+        &file,
+        format!("URL: {url}\nStatus: 200\nError: {err}\n\n{body}"),
+    )?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::{challenge_url, DlsiteClient, Priority};
+    use crate::{
+        events::EventListener,
+        interface::{locale::Locale, site::Site},
+        testing::FixtureTransport,
+        transport::{HttpRequest, HttpResponse},
+        DlsiteError,
+    };
+
+    #[derive(Default)]
+    struct CacheCounter {
+        hits: AtomicUsize,
+        misses: AtomicUsize,
+    }
+
+    impl EventListener for Arc<CacheCounter> {
+        fn on_cache_hit(&self, _url: &str) {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_cache_miss(&self, _url: &str) {
+            self.misses.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_hit_event() {
+        let counter = Arc::new(CacheCounter::default());
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .event_listener(counter.clone())
+            .build();
+        client.cache().insert(
+            "https://www.dlsite.com/maniax/cached".to_string(),
+            "body".to_string(),
+        );
+
+        assert_eq!(client.get("/cached").await.unwrap(), "body");
+        assert_eq!(counter.hits.load(Ordering::SeqCst), 1);
+        assert_eq!(counter.misses.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn cache_stats() {
+        let transport =
+            FixtureTransport::new().with_body("https://www.dlsite.com/maniax/page", "body");
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::ZERO, Duration::ZERO)
+            .transport(transport)
+            .build();
+
+        // Each request is one lookup: a miss, then a hit
+        assert_eq!(client.get("/page").await.unwrap(), "body");
+        assert_eq!(client.get("/page").await.unwrap(), "body");
+        let stats = client.cache_stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[tokio::test]
+    async fn foreground_priority() {
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::from_millis(100), Duration::from_millis(100))
+            .build();
+        let background = client.with_priority(Priority::Background);
+        let order = std::sync::Mutex::new(vec![]);
+        let wait = |client: &DlsiteClient, name: &'static str| {
+            let (client, order) = (client.clone(), &order);
+            async move {
+                client.wait_for_slot().await;
+                order.lock().unwrap().push(name);
+            }
+        };
+
+        // The second background request has reserved its slot when the foreground one
+        // arrives, but the third one waits for the foreground one
+        futures::join!(
+            wait(&background, "background 1"),
+            wait(&background, "background 2"),
+            wait(&background, "background 3"),
+            async {
+                crate::runtime::sleep(Duration::from_millis(20)).await;
+                wait(&client, "foreground").await;
+            },
+        );
+        assert_eq!(
+            *order.lock().unwrap(),
+            ["background 1", "background 2", "foreground", "background 3"]
+        );
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn archive_responses() {
+        use futures::TryStreamExt as _;
+
+        use crate::archive::Archive;
+
+        let dir =
+            std::env::temp_dir().join(format!("dlsite-client-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let transport = FixtureTransport::new()
+            .with_body("https://www.dlsite.com/maniax/page", "<html></html>")
+            .with_body("https://www.dlsite.com/maniax/api", "[{}]");
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::ZERO, Duration::ZERO)
+            .transport(transport)
+            .archive(Archive::new(&dir))
+            .build();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            client.get("/page").await.unwrap();
+            let body = client.get_streamed_on(Site::Maniax, "/api").await.unwrap();
+            let chunks: Vec<_> = body.try_collect().await.unwrap();
+            assert_eq!(chunks.concat(), b"[{}]");
+        });
+        // Dropping the runtime waits for the records being written
+        drop(runtime);
+
+        let archive = Archive::new(&dir);
+        let mut bodies: Vec<_> = archive
+            .records()
+            .unwrap()
+            .iter()
+            .map(|path| Archive::read(path).unwrap())
+            .map(|record| (record.url, record.body))
+            .collect();
+        bodies.sort();
+        assert_eq!(
+            bodies,
+            [
+                (
+                    "https://www.dlsite.com/maniax/api".to_string(),
+                    "[{}]".to_string()
+                ),
+                (
+                    "https://www.dlsite.com/maniax/page".to_string(),
+                    "<html></html>".to_string()
+                ),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn default_query_params() {
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .default_query_param("locale", "en_US")
+            .default_query_param("ana", "abc")
+            .build();
+        assert_eq!(
+            client.apply_default_query(
+                "https://www.dlsite.com/maniax/product/info/ajax?product_id=RJ1&locale=ja_JP"
+                    .to_string()
+            ),
+            "https://www.dlsite.com/maniax/product/info/ajax?product_id=RJ1&locale=ja_JP&ana=abc"
+        );
+        assert_eq!(
+            client.apply_default_query("https://www.dlsite.com/maniax/fsr/ajax/=/language/jp".to_string()),
+            "https://www.dlsite.com/maniax/fsr/ajax/=/language/jp?locale=en_US&ana=abc"
+        );
+    }
+
+    #[test]
+    fn site() {
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax/")
+            .site(Site::GirlsPro)
+            .build();
+        assert_eq!(client.site(), Site::GirlsPro);
+        assert_eq!(client.base_url, "https://www.dlsite.com/girls-pro");
+        assert_eq!(
+            client.site_base_url(Site::AppX),
+            "https://www.dlsite.com/appx"
+        );
+
+        let client = DlsiteClient::builder("http://localhost:8080")
+            .site(Site::Books)
+            .build();
+        assert_eq!(client.base_url, "http://localhost:8080/books");
+        assert_eq!(client.site(), Site::Books);
+    }
+
+    #[test]
+    fn locale() {
+        use reqwest::cookie::CookieStore as _;
+
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .default_query_param("locale", "ja_JP")
+            .locale(Locale::EnUs)
+            .build();
+        assert_eq!(client.locale(), Locale::EnUs);
+        assert_eq!(
+            client.apply_default_query("https://www.dlsite.com/maniax/fsr/ajax".to_string()),
+            "https://www.dlsite.com/maniax/fsr/ajax?locale=en_US"
+        );
+        let cookies = client
+            .cookie_jar()
+            .cookies(&"https://www.dlsite.com/home/".parse().unwrap())
+            .unwrap();
+        assert!(cookies.to_str().unwrap().contains("locale=en-us"));
+    }
+
+    #[test]
+    fn confirm_adult_cookie() {
+        use reqwest::cookie::CookieStore as _;
+
+        let url: url::Url = "https://www.dlsite.com/home/work/=/product_id/RJ403038.html"
+            .parse()
+            .unwrap();
+        let cookies = |client: &DlsiteClient| {
+            client
+                .cookie_jar()
+                .cookies(&url)
+                .map(|v| v.to_str().unwrap().to_string())
+                .unwrap_or_default()
+        };
+
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .confirm_adult(true)
+            .build();
+        assert!(cookies(&client).contains("adultchecked=1"));
+
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax").build();
+        assert!(!cookies(&client).contains("adultchecked"));
+    }
+
+    #[tokio::test]
+    async fn dump_failed_responses() {
+        let dir = std::env::temp_dir().join(format!("dlsite-dump-{}", std::process::id()));
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .dump_failed_responses(&dir)
+            .build();
+        client.cache().insert(
+            "https://www.dlsite.com/maniax/work/=/product_id/RJ01014447".to_string(),
+            "<html><body>メンテナンス中</body></html>".to_string(),
+        );
+
+        assert!(client.product().get_html("RJ01014447").await.is_err());
+        let dumps: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(dumps.len(), 1);
+        let dump = std::fs::read_to_string(dumps[0].as_ref().unwrap().path()).unwrap();
+        assert!(dump.starts_with(
+            "URL: https://www.dlsite.com/maniax/work/=/product_id/RJ01014447\nStatus: 200\n"
+        ));
+        assert!(dump.ends_with("メンテナンス中</body></html>"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn request_budget() {
+        let transport = FixtureTransport::new()
+            .with_body("https://www.dlsite.com/maniax/a", "a")
+            .with_body("https://www.dlsite.com/maniax/b", "b");
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .transport(transport)
+            .request_budget(2)
+            .build();
+        assert_eq!(client.remaining_budget(), Some(2));
+
+        client.get("/a").await.unwrap();
+        // Cached responses are free
+        client.get("/a").await.unwrap();
+        client.clone().get("/b").await.unwrap();
+        assert_eq!(client.remaining_budget(), Some(0));
+        assert!(matches!(
+            client.get_fresh("/a").await,
+            Err(DlsiteError::BudgetExhausted { limit: 2 })
+        ));
+        assert_eq!(DlsiteClient::default().remaining_budget(), None);
+    }
+
+    #[test]
+    fn detect_challenge() {
+        let url = "https://www.dlsite.com/maniax/a";
+        let captcha =
+            r#"<iframe src="https://geo.captcha-delivery.com/captcha/?cid=1&amp;t=fe"></iframe>"#;
+        assert_eq!(
+            challenge_url(&HttpResponse::new(403, captcha), url).as_deref(),
+            Some("https://geo.captcha-delivery.com/captcha/?cid=1&t=fe")
+        );
+        let cloudflare = HttpResponse::new(403, "").with_header("cf-mitigated", "challenge");
+        assert_eq!(challenge_url(&cloudflare, url).as_deref(), Some(url));
+        // The login form has a CAPTCHA too
+        let login = r#"<form id="login_form"><div class="g-recaptcha"></div></form>"#;
+        assert_eq!(challenge_url(&HttpResponse::new(200, login), url), None);
+        assert_eq!(
+            challenge_url(&HttpResponse::new(503, login), url).as_deref(),
+            Some(url)
+        );
+    }
+
+    #[tokio::test]
+    async fn challenge_opens_circuit() {
+        let transport = FixtureTransport::new()
+            .with_response(
+                HttpRequest::get("https://www.dlsite.com/maniax/a"),
+                HttpResponse::new(503, "<title>Just a moment...</title>"),
+            )
+            .with_body("https://www.dlsite.com/maniax/b", "b");
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .transport(transport)
+            .build();
+
+        let challenge = DlsiteError::Challenge {
+            url: "https://www.dlsite.com/maniax/a".to_string(),
+        };
+        let err = client.get("/a").await.unwrap_err();
+        assert_eq!(err.to_string(), challenge.to_string());
+        assert!(client.is_circuit_open());
+        // Clones stop too, without sending requests
+        let err = client.clone().get("/b").await.unwrap_err();
+        assert_eq!(err.to_string(), challenge.to_string());
+
+        client.close_circuit();
+        assert!(!client.is_circuit_open());
+        assert_eq!(client.get("/b").await.unwrap(), "b");
+    }
+}
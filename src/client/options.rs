@@ -0,0 +1,225 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use crate::{client::product::unified::Source, interface::site::Site};
+
+/// Per-call options for fetching a product.
+#[derive(Clone, Debug, Default)]
+pub struct FetchOptions {
+    pub(crate) cross_site_fallback: bool,
+    pub(crate) source_fallback: bool,
+    pub(crate) fields: Option<ProductFields>,
+}
+
+impl FetchOptions {
+    /// Create options with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When the product is not found on the storefront it was routed to, retry on the other
+    /// storefronts (maniax → home → pro → books) before returning [`crate::DlsiteError::NotFound`].
+    pub fn cross_site_fallback(mut self, enabled: bool) -> Self {
+        self.cross_site_fallback = enabled;
+        self
+    }
+
+    /// When the product page can't be scraped or parsed (e.g. after a DLsite-side markup change or
+    /// during a partial outage), get the product from the product api instead, and vice versa.
+    ///
+    /// Which one was used is recorded in [`crate::client::product::Product::source`].
+    pub fn source_fallback(mut self, enabled: bool) -> Self {
+        self.source_fallback = enabled;
+        self
+    }
+
+    /// Only the given fields of [`crate::client::product::Product`] are needed.
+    ///
+    /// The product is then got from the cheapest source providing all of them: the ajax api
+    /// (one small request), the product api (one request), or the product page (three
+    /// requests). A source whose response is already cached is preferred over a cheaper one.
+    /// Other fields may be left empty or at their default value.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::{client::ProductFields, DlsiteClient, FetchOptions};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let options = FetchOptions::new().fields(ProductFields::PRICE | ProductFields::RATING);
+    ///     let product = client.product().get_all_with("RJ403038", &options).await.unwrap();
+    ///     println!("{} yen, rated {:?}", product.price, product.rating);
+    /// }
+    /// ```
+    pub fn fields(mut self, fields: ProductFields) -> Self {
+        self.fields = Some(fields);
+        self
+    }
+
+    /// Storefronts to try, in order, for a product first routed to `site`.
+    pub(crate) fn sites(&self, site: Site) -> Vec<Site> {
+        let mut sites = vec![site];
+        if self.cross_site_fallback {
+            sites.extend(
+                [Site::Maniax, Site::Home, Site::Pro, Site::Books]
+                    .into_iter()
+                    .filter(|s| *s != site),
+            );
+        }
+        sites
+    }
+}
+
+/// Set of fields of [`crate::client::product::Product`], see [`FetchOptions::fields`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProductFields(u32);
+
+impl ProductFields {
+    /// `title`
+    pub const TITLE: Self = Self(1);
+    /// `work_type`
+    pub const WORK_TYPE: Self = Self(1 << 1);
+    /// `circle_id` and `circle_name`
+    pub const CIRCLE: Self = Self(1 << 2);
+    /// `released_at`
+    pub const RELEASE_DATE: Self = Self(1 << 3);
+    /// `age_rating`
+    pub const AGE_RATING: Self = Self(1 << 4);
+    /// `genre`
+    pub const GENRE: Self = Self(1 << 5);
+    /// `series`
+    pub const SERIES: Self = Self(1 << 6);
+    /// `price`
+    pub const PRICE: Self = Self(1 << 7);
+    /// `rating`, `rate_count` and `rating_distribution`
+    pub const RATING: Self = Self(1 << 8);
+    /// `sale_count`
+    pub const SALES: Self = Self(1 << 9);
+    /// `review_count`
+    pub const REVIEWS: Self = Self(1 << 10);
+    /// `favorite_count`
+    pub const FAVORITES: Self = Self(1 << 11);
+    /// `translation_permission`
+    pub const TRANSLATION: Self = Self(1 << 12);
+    /// `images`
+    pub const IMAGES: Self = Self(1 << 13);
+    /// `people`
+    pub const PEOPLE: Self = Self(1 << 14);
+    /// `file_format`, `file_size` and `platforms`
+    pub const FILES: Self = Self(1 << 15);
+    /// `product_format`
+    pub const PRODUCT_FORMAT: Self = Self(1 << 16);
+    /// `reviewer_genre`
+    pub const REVIEWER_GENRE: Self = Self(1 << 17);
+    /// `user_tags`
+    pub const USER_TAGS: Self = Self(1 << 18);
+    /// `drm`
+    pub const DRM: Self = Self(1 << 19);
+    /// All fields
+    pub const ALL: Self = Self((1 << 20) - 1);
+
+    /// Fields provided by the ajax api.
+    const AJAX: Self = Self(
+        Self::TITLE.0
+            | Self::WORK_TYPE.0
+            | Self::PRICE.0
+            | Self::RATING.0
+            | Self::SALES.0
+            | Self::REVIEWS.0
+            | Self::FAVORITES.0
+            | Self::TRANSLATION.0,
+    );
+    /// Fields provided by the product api.
+    const API: Self = Self(
+        Self::TITLE.0
+            | Self::WORK_TYPE.0
+            | Self::CIRCLE.0
+            | Self::RELEASE_DATE.0
+            | Self::AGE_RATING.0
+            | Self::GENRE.0
+            | Self::SERIES.0
+            | Self::PRICE.0
+            | Self::RATING.0
+            | Self::TRANSLATION.0
+            | Self::IMAGES.0
+            | Self::PEOPLE.0
+            | Self::FILES.0,
+    );
+
+    /// Whether all fields of `other` are in this set.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Fields provided by a source.
+    fn provided_by(source: Source) -> Self {
+        match source {
+            Source::Ajax => Self::AJAX,
+            Source::Api => Self::API,
+            Source::Scraping => Self::ALL,
+        }
+    }
+
+    /// The cheapest source providing all these fields. `cached` tells whether the responses
+    /// of a source are already cached, which makes it free.
+    pub(crate) fn cheapest_source(self, cached: impl Fn(Source) -> bool) -> Source {
+        let capable: Vec<Source> = [Source::Ajax, Source::Api, Source::Scraping]
+            .into_iter()
+            .filter(|source| Self::provided_by(*source).contains(self))
+            .collect();
+        capable
+            .iter()
+            .copied()
+            .find(|source| cached(*source))
+            .unwrap_or(capable[0])
+    }
+}
+
+impl BitOr for ProductFields {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ProductFields {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FetchOptions, ProductFields};
+    use crate::{client::product::unified::Source, interface::site::Site};
+
+    #[test]
+    fn fallback_sites() {
+        assert_eq!(FetchOptions::new().sites(Site::Pro), vec![Site::Pro]);
+        assert_eq!(
+            FetchOptions::new().cross_site_fallback(true).sites(Site::Pro),
+            vec![Site::Pro, Site::Maniax, Site::Home, Site::Books]
+        );
+    }
+
+    #[test]
+    fn cheapest_source() {
+        let price = ProductFields::PRICE | ProductFields::RATING;
+        assert_eq!(price.cheapest_source(|_| false), Source::Ajax);
+        assert_eq!(
+            price.cheapest_source(|s| s == Source::Scraping),
+            Source::Scraping
+        );
+
+        let mut details = price;
+        details |= ProductFields::GENRE;
+        assert_eq!(details.cheapest_source(|_| false), Source::Api);
+        assert_eq!(
+            (details | ProductFields::SALES).cheapest_source(|_| false),
+            Source::Scraping
+        );
+        assert!(ProductFields::ALL.contains(details));
+        assert!(!details.contains(ProductFields::ALL));
+    }
+}
@@ -0,0 +1,195 @@
+use futures::{Stream, StreamExt as _, TryStreamExt as _};
+
+use crate::{
+    error::Result,
+    eta::Eta,
+    runtime::{MaybeBoxFuture, MaybeSend},
+    DlsiteClient,
+};
+
+/// One page of a [`Paginated`] listing.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Total number of items on all pages, if the endpoint returns it
+    pub total: Option<usize>,
+}
+
+type FetchPage<'a, T> = Box<dyn FnMut(u32) -> MaybeBoxFuture<'a, Result<Page<T>>> + Send + 'a>;
+
+/// A listing spread over multiple pages (search results, circle works, reviews...).
+///
+/// Pages are fetched one at a time, either with [`Paginated::next_page`] or by polling the
+/// stream returned by [`Paginated::into_stream`]. Iteration stops after a page with fewer
+/// items than the page size, or once the total reported by the endpoint is reached. Every
+/// page is a regular request, so the rate limiter of the client applies.
+///
+/// # Example
+/// ```no_run
+/// use dlsite_gamebox::{DlsiteClient, client::search::SearchProductQuery};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = DlsiteClient::default();
+///     let search = client.search();
+///     let query = SearchProductQuery {
+///         keyword: Some("ねこぐらし".to_string()),
+///         ..Default::default()
+///     };
+///     let mut pages = search.paginate(&query);
+///     while let Some(items) = pages.next_page().await.unwrap() {
+///         println!("{} / {:?}", items.len(), pages.total());
+///     }
+/// }
+/// ```
+pub struct Paginated<'a, T> {
+    fetch: FetchPage<'a, T>,
+    next: Option<u32>,
+    per_page: Option<u32>,
+    total: Option<usize>,
+    /// Number of items up to the last fetched page, pages before the first one included
+    seen: usize,
+    /// Progress handle and the value of `seen` when it was created
+    eta: Option<(Eta, usize)>,
+}
+
+impl<T> std::fmt::Debug for Paginated<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Paginated")
+            .field("next", &self.next)
+            .field("per_page", &self.per_page)
+            .field("total", &self.total)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T: Send + 'a> Paginated<'a, T> {
+    /// Create a listing starting at `first_page` (1-based).
+    ///
+    /// # Arguments
+    /// * `per_page` - Number of items per page, if known. A shorter page ends the listing.
+    /// * `fetch` - Fetches the given page.
+    pub(crate) fn new<F>(first_page: u32, per_page: Option<u32>, fetch: F) -> Self
+    where
+        F: FnMut(u32) -> MaybeBoxFuture<'a, Result<Page<T>>> + Send + 'a,
+    {
+        let first_page = first_page.max(1);
+        Self {
+            fetch: Box::new(fetch),
+            next: Some(first_page),
+            per_page,
+            total: None,
+            seen: per_page.map_or(0, |n| (first_page as usize - 1) * n as usize),
+            eta: None,
+        }
+    }
+
+    /// Fetch the next page. Returns `None` once all pages were fetched.
+    ///
+    /// On error, the same page is fetched again by the next call.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<T>>> {
+        let Some(page) = self.next else {
+            return Ok(None);
+        };
+        let result = (self.fetch)(page).await?;
+
+        if result.total.is_some() {
+            self.total = result.total;
+        }
+        self.seen += result.items.len();
+        if let Some((eta, offset)) = &self.eta {
+            if let Some(total) = self.total {
+                eta.set_total(total.saturating_sub(*offset));
+            }
+            eta.complete(result.items.len());
+        }
+        let done = result.items.is_empty()
+            || self
+                .per_page
+                .is_some_and(|n| result.items.len() < n as usize)
+            || self.total.is_some_and(|total| self.seen >= total);
+        self.next = (!done).then_some(page + 1);
+
+        Ok((!result.items.is_empty()).then_some(result.items))
+    }
+
+    /// Total number of items, once a page reporting it was fetched.
+    pub fn total(&self) -> Option<usize> {
+        self.total
+    }
+
+    /// Number of the page fetched by the next call to [`Paginated::next_page`].
+    pub fn next_page_number(&self) -> Option<u32> {
+        self.next
+    }
+
+    /// Track the progress of the remaining pages, in items.
+    ///
+    /// The total is unknown until the first page reporting it is fetched. `client` must be the
+    /// client fetching the pages, its rate limit is used for the first estimates.
+    pub fn eta(&mut self, client: &DlsiteClient) -> Eta {
+        let requests_per_item = self.per_page.map_or(1.0, |n| 1.0 / f64::from(n.max(1)));
+        let remaining = self
+            .total
+            .map_or(0, |total| total.saturating_sub(self.seen));
+        let eta = Eta::new(client, remaining, requests_per_item);
+        self.eta = Some((eta.clone(), self.seen));
+        eta
+    }
+
+    /// Fetch all remaining pages.
+    pub async fn collect_all(mut self) -> Result<Vec<T>> {
+        let mut items = vec![];
+        while let Some(page) = self.next_page().await? {
+            items.extend(page);
+        }
+        Ok(items)
+    }
+
+    /// Stream the items of all remaining pages. Dropping the stream stops fetching.
+    pub fn into_stream(self) -> impl Stream<Item = Result<T>> + MaybeSend + 'a {
+        futures::stream::try_unfold(self, |mut pages| async move {
+            let items = pages.next_page().await?;
+            Result::Ok(items.map(|items| (items, pages)))
+        })
+        .map_ok(|items| futures::stream::iter(items).map(Ok))
+        .try_flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{FutureExt as _, TryStreamExt as _};
+
+    use super::{Page, Paginated};
+
+    /// 7 items, 3 per page
+    fn numbers(first_page: u32, total: Option<usize>) -> Paginated<'static, u32> {
+        Paginated::new(first_page, Some(3), move |page| {
+            async move {
+                let start = (page - 1) * 3;
+                Ok(Page {
+                    items: (start..(start + 3).min(7)).collect(),
+                    total,
+                })
+            }
+            .boxed()
+        })
+    }
+
+    #[tokio::test]
+    async fn paginate() {
+        let mut pages = numbers(1, Some(7));
+        let eta = pages.eta(&crate::DlsiteClient::default());
+        assert_eq!(pages.next_page().await.unwrap(), Some(vec![0, 1, 2]));
+        assert_eq!(pages.total(), Some(7));
+        assert_eq!(pages.next_page_number(), Some(2));
+        assert_eq!((eta.completed(), eta.total()), (3, 7));
+
+        let rest: Vec<_> = pages.into_stream().try_collect().await.unwrap();
+        assert_eq!(rest, vec![3, 4, 5, 6]);
+
+        // Without total, the short last page ends the listing
+        assert_eq!(numbers(2, None).collect_all().await.unwrap(), vec![3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,424 @@
+//! Interfaces related to DLsite Play (`play.dlsite.com`), the online viewer of purchased
+//! works. For more information, see [`PlayClient`].
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use super::{account::parse_json, Page, Paginated};
+use crate::{
+    error::Result,
+    interface::{locale::Locale, product_id::ProductId},
+    runtime::MaybeBoxed as _,
+    DlsiteClient, DlsiteError,
+};
+
+/// Origin of DLsite Play.
+const PLAY_URL: &str = "https://play.dlsite.com";
+
+/// Client to browse the works of the logged-in user on DLsite Play, and the files inside
+/// them without downloading the archives.
+///
+/// This needs a logged-in session, see [`DlsiteClient::login`] or
+/// [`crate::DlsiteClientBuilder::cookie`].
+///
+/// # Example
+/// ```no_run
+/// use dlsite_gamebox::{client::play::PlayEntry, DlsiteClient};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = DlsiteClient::default();
+///     client.login("login_id", "password").await.unwrap();
+///     let tree = client.play().file_tree("RJ01014447").await.unwrap();
+///     for file in tree.files() {
+///         println!("{} ({:?})", file.path, file.kind);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PlayClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// A work in the DLsite Play library of the account.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlayWork {
+    pub id: String,
+    /// Title in the language of the client when DLsite has one
+    pub title: String,
+    pub circle_name: String,
+    /// Work type code, e.g. `SOU` for voice works
+    pub work_type: Option<String>,
+    pub age_category: Option<String>,
+    pub purchased_at: Option<DateTime<Utc>>,
+}
+
+/// Files of a work as browsed by DLsite Play, see [`PlayClient::file_tree`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlayFileTree {
+    pub product_id: String,
+    /// Version of the content, changed by DLsite when the work is updated
+    pub hash: String,
+    /// Top-level files and folders
+    pub entries: Vec<PlayEntry>,
+}
+
+/// A file or folder of a [`PlayFileTree`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlayEntry {
+    Folder {
+        name: String,
+        /// Path from the root of the work, `/`-separated
+        path: String,
+        children: Vec<PlayEntry>,
+    },
+    File(PlayFile),
+}
+
+/// A file of a [`PlayFileTree`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlayFile {
+    pub name: String,
+    /// Path from the root of the work, `/`-separated
+    pub path: String,
+    /// Name of the file on the DLsite Play servers
+    pub hashname: String,
+    /// Kind of file as served by DLsite Play: `image`, `audio`, `video`, `pdf`, `text`...
+    pub kind: Option<String>,
+    /// Size in bytes of the streamed version, if given
+    pub size: Option<u64>,
+    /// URL of the version streamed by DLsite Play, readable with the cookies of the client
+    /// until the token of [`PlayClient::file_tree`] expires (a few hours)
+    pub url: Option<String>,
+}
+
+impl PlayFileTree {
+    /// All the files of the work, depth first.
+    pub fn files(&self) -> Vec<&PlayFile> {
+        fn walk<'t>(entries: &'t [PlayEntry], files: &mut Vec<&'t PlayFile>) {
+            for entry in entries {
+                match entry {
+                    PlayEntry::Folder { children, .. } => walk(children, files),
+                    PlayEntry::File(file) => files.push(file),
+                }
+            }
+        }
+        let mut files = vec![];
+        walk(&self.entries, &mut files);
+        files
+    }
+}
+
+/// Text localized by DLsite Play, by locale (`ja_JP`...).
+type Localized = HashMap<String, String>;
+
+#[derive(Debug, serde::Deserialize)]
+struct PurchasesResponse {
+    #[serde(default)]
+    works: Vec<PurchasedItem>,
+    total: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PurchasedItem {
+    workno: String,
+    #[serde(default)]
+    name: Localized,
+    maker: Option<Maker>,
+    work_type: Option<String>,
+    age_category: Option<String>,
+    sales_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Maker {
+    #[serde(default)]
+    name: Localized,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct DownloadToken {
+    /// Base URL of the content, ending with `/`
+    url: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ZipTree {
+    #[serde(default)]
+    hash: String,
+    #[serde(default)]
+    tree: Vec<TreeNode>,
+    /// Streamed versions of the files, by hashname
+    #[serde(default)]
+    playfile: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum TreeNode {
+    Folder {
+        path: String,
+        #[serde(default)]
+        children: Vec<TreeNode>,
+    },
+    File {
+        name: String,
+        hashname: String,
+    },
+}
+
+impl<'a> PlayClient<'a> {
+    /// Get one page (1-based) of the works available in DLsite Play, most recent purchases
+    /// first.
+    ///
+    /// Returns an empty list after the last page.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in.
+    pub async fn list_works(&self, page: u32) -> Result<Vec<PlayWork>> {
+        Ok(self.works_page(page).await?.items)
+    }
+
+    /// Iterate over all the works available in DLsite Play, page by page.
+    pub fn works(&self) -> Paginated<'a, PlayWork> {
+        let play = self.clone();
+        Paginated::new(1, None, move |page| {
+            let play = play.clone();
+            async move { play.works_page(page).await }.maybe_boxed()
+        })
+    }
+
+    async fn works_page(&self, page: u32) -> Result<Page<PlayWork>> {
+        let (c, locale) = (self.c, self.c.locale());
+        let url = &format!("{PLAY_URL}/api/purchases?page={}", page.max(1));
+        c.authenticated(
+            move || async move { parse_purchases_response(&get(c, url).await?, locale) },
+        )
+        .await
+    }
+
+    /// Get the files of an owned work as browsed by DLsite Play, with the URLs of their
+    /// streamed versions (resized images, re-encoded audio...). Archives are listed unpacked.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Unauthenticated`] if the client is not logged in.
+    pub async fn file_tree(&self, product_id: impl Into<ProductId>) -> Result<PlayFileTree> {
+        let product_id = product_id.into().checked()?;
+        let (c, id) = (self.c, product_id.as_str());
+        c.authenticated(move || async move {
+            let url = format!("{PLAY_URL}/api/download_token?workno={id}");
+            let token: DownloadToken = parse_json(&get(c, &url).await?)?;
+            let base_url = format!("{}/", token.url.trim_end_matches('/'));
+            let tree = get(c, &format!("{base_url}ziptree.json")).await?;
+            parse_ziptree(&tree, id, &base_url)
+        })
+        .await
+    }
+}
+
+/// Fetch a DLsite Play URL. The api answers 401 without session.
+async fn get(c: &DlsiteClient, url: &str) -> Result<String> {
+    c.get_fresh_url(url).await.map_err(|e| match e {
+        DlsiteError::HttpStatus(401 | 403) => DlsiteError::Unauthenticated,
+        e => e,
+    })
+}
+
+/// Text in `locale`, or in Japanese, or in any language.
+fn localized(text: &Localized, locale: Locale) -> String {
+    text.get(&locale.to_string())
+        .or_else(|| text.get("ja_JP"))
+        .or_else(|| text.values().min())
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub(crate) fn parse_purchases_response(body: &str, locale: Locale) -> Result<Page<PlayWork>> {
+    let response: PurchasesResponse = parse_json(body)?;
+    Ok(Page {
+        items: response
+            .works
+            .into_iter()
+            .map(|work| PlayWork {
+                title: localized(&work.name, locale),
+                circle_name: work
+                    .maker
+                    .map(|maker| localized(&maker.name, locale))
+                    .unwrap_or_default(),
+                id: work.workno,
+                work_type: work.work_type,
+                age_category: work.age_category,
+                purchased_at: work.sales_date,
+            })
+            .collect(),
+        total: response.total,
+    })
+}
+
+pub(crate) fn parse_ziptree(body: &str, product_id: &str, base_url: &str) -> Result<PlayFileTree> {
+    let tree: ZipTree = parse_json(body)?;
+    Ok(PlayFileTree {
+        product_id: product_id.to_string(),
+        entries: convert_nodes(tree.tree, "", &tree.playfile, base_url),
+        hash: tree.hash,
+    })
+}
+
+fn convert_nodes(
+    nodes: Vec<TreeNode>,
+    parent: &str,
+    playfile: &HashMap<String, serde_json::Value>,
+    base_url: &str,
+) -> Vec<PlayEntry> {
+    let join = |name: &str| match parent {
+        "" => name.to_string(),
+        parent => format!("{parent}/{name}"),
+    };
+    nodes
+        .into_iter()
+        .map(|node| match node {
+            TreeNode::Folder { path, children } => {
+                // Folder paths are given from the root
+                let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+                let path = if path.contains('/') {
+                    path
+                } else {
+                    join(&path)
+                };
+                PlayEntry::Folder {
+                    children: convert_nodes(children, &path, playfile, base_url),
+                    name,
+                    path,
+                }
+            }
+            TreeNode::File { name, hashname } => {
+                let info = playfile.get(&hashname);
+                let kind = info.and_then(|i| i["type"].as_str()).map(str::to_string);
+                let optimized = kind
+                    .as_deref()
+                    .and_then(|kind| info.map(|i| &i[kind]["optimized"]));
+                PlayEntry::File(PlayFile {
+                    path: join(&name),
+                    name,
+                    hashname,
+                    size: optimized
+                        .and_then(|o| o["length"].as_u64())
+                        .or_else(|| info.and_then(|i| i["length"].as_u64())),
+                    url: optimized
+                        .and_then(|o| o["name"].as_str())
+                        .map(|file| format!("{base_url}optimized/{file}")),
+                    kind,
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_purchases_response, parse_ziptree, PlayEntry};
+    use crate::{
+        interface::locale::Locale,
+        testing::FixtureTransport,
+        transport::{HttpRequest, HttpResponse},
+        DlsiteClient, DlsiteError,
+    };
+
+    const ZIPTREE: &str = r#"{
+        "hash": "abc123",
+        "tree": [
+            {"type": "file", "name": "readme.txt", "hashname": "0a.txt"},
+            {"type": "folder", "path": "mp3", "children": [
+                {"type": "file", "name": "01.mp3", "hashname": "1b.mp3"},
+                {"type": "folder", "path": "mp3/bonus", "children": [
+                    {"type": "file", "name": "cover.jpg", "hashname": "2c.jpg"}
+                ]}
+            ]}
+        ],
+        "playfile": {
+            "0a.txt": {"type": "text", "length": 120},
+            "1b.mp3": {"type": "audio", "audio": {"optimized": {"name": "1b.mp3", "length": 4096}}},
+            "2c.jpg": {"type": "image", "image": {"optimized": {"name": "2c.webp", "width": 1280}}}
+        }
+    }"#;
+
+    #[test]
+    fn parse_purchases() {
+        let page = parse_purchases_response(
+            r#"{"limit": 50, "offset": 0, "total": 2, "works": [
+                {"workno": "RJ01000001", "name": {"ja_JP": "ボイス", "en_US": "Voice"},
+                 "maker": {"name": {"ja_JP": "サークル"}}, "work_type": "SOU",
+                 "age_category": "adult", "sales_date": "2024-01-02T03:04:05.000000Z"},
+                {"workno": "RJ01000002", "name": {"zh_CN": "漫画"}}
+            ]}"#,
+            Locale::EnUs,
+        )
+        .unwrap();
+        assert_eq!(page.total, Some(2));
+        assert_eq!(page.items[0].title, "Voice");
+        assert_eq!(page.items[0].circle_name, "サークル");
+        assert_eq!(page.items[0].work_type.as_deref(), Some("SOU"));
+        assert_eq!(
+            page.items[0].purchased_at,
+            Some("2024-01-02T03:04:05Z".parse().unwrap())
+        );
+        assert_eq!(page.items[1].title, "漫画");
+        assert_eq!(page.items[1].circle_name, "");
+        assert!(matches!(
+            parse_purchases_response(r#"<form id="login_form"></form>"#, Locale::JaJp),
+            Err(DlsiteError::Unauthenticated)
+        ));
+    }
+
+    #[test]
+    fn parse_tree() {
+        let base_url = "https://play.dl.dlsite.com/content/work/doujin/RJ01000001/";
+        let tree = parse_ziptree(ZIPTREE, "RJ01000001", base_url).unwrap();
+        assert_eq!(tree.hash, "abc123");
+        let paths: Vec<_> = tree.files().iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["readme.txt", "mp3/01.mp3", "mp3/bonus/cover.jpg"]);
+        let PlayEntry::Folder { name, children, .. } = &tree.entries[1] else {
+            panic!("not a folder: {:?}", tree.entries[1]);
+        };
+        assert_eq!((name.as_str(), children.len()), ("mp3", 2));
+
+        let files = tree.files();
+        assert_eq!(files[0].kind.as_deref(), Some("text"));
+        assert_eq!((files[0].size, files[0].url.as_deref()), (Some(120), None));
+        assert_eq!(files[1].size, Some(4096));
+        assert_eq!(
+            files[2].url.as_deref(),
+            Some("https://play.dl.dlsite.com/content/work/doujin/RJ01000001/optimized/2c.webp")
+        );
+    }
+
+    #[tokio::test]
+    async fn file_tree() {
+        let transport = FixtureTransport::new()
+            .with_body(
+                "https://play.dlsite.com/api/download_token?workno=RJ01000001",
+                r#"{"url": "https://play.dl.dlsite.com/content/work/doujin/RJ01000001",
+                    "expires_at": "2024-01-02T03:04:05Z"}"#,
+            )
+            .with_body(
+                "https://play.dl.dlsite.com/content/work/doujin/RJ01000001/ziptree.json",
+                ZIPTREE,
+            )
+            .with_response(
+                HttpRequest::get("https://play.dlsite.com/api/purchases?page=1"),
+                HttpResponse::new(401, "{}"),
+            );
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .transport(transport)
+            .build();
+
+        let tree = client.play().file_tree("RJ01000001").await.unwrap();
+        assert_eq!(tree.files().len(), 3);
+        assert!(matches!(
+            client.play().list_works(1).await,
+            Err(DlsiteError::Unauthenticated)
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+//! Pool of clients for high-volume usage. For more information, see [`ClientPool`].
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{
+    account, app, book, campaign, circle, coupon, creator, follow, play, pro, product, product_api,
+    ranking, search, DlsiteClient, DlsiteClientBuilder,
+};
+use crate::error::Result;
+
+/// Pool of clients rotating across multiple sessions or proxies.
+///
+/// Each client keeps its own connection pool and rate limiter, so a pool of `n` clients can
+/// make `n` times as many requests as a single client. All clients share one response cache.
+///
+/// The pool exposes the same sub-client API as [`DlsiteClient`]; each call picks the next
+/// client in round-robin order.
+#[derive(Clone, Debug)]
+pub struct ClientPool {
+    clients: Vec<DlsiteClient>,
+    next: std::sync::Arc<AtomicUsize>,
+}
+
+impl ClientPool {
+    /// Build a pool from client builders, typically configured with different proxies.
+    ///
+    /// The response cache of the first builder is shared by all clients.
+    ///
+    /// # Panics
+    /// Panics if `builders` is empty.
+    pub fn new(builders: impl IntoIterator<Item = DlsiteClientBuilder>) -> Self {
+        let mut builders = builders.into_iter();
+        let first = builders
+            .next()
+            .expect("ClientPool needs at least one client")
+            .build();
+        let cache = first.cache().clone();
+
+        let mut clients = vec![first];
+        clients.extend(builders.map(|b| b.shared_cache(cache.clone()).build()));
+
+        Self {
+            clients,
+            next: Default::default(),
+        }
+    }
+
+    /// Number of clients in the pool
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Whether the pool is empty (never true for a pool built with [`ClientPool::new`])
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Clients in the pool
+    pub fn clients(&self) -> &[DlsiteClient] {
+        &self.clients
+    }
+
+    /// Get the next client in round-robin order.
+    pub fn next_client(&self) -> &DlsiteClient {
+        let i = self.next.fetch_add(1, Ordering::Relaxed);
+        &self.clients[i % self.clients.len()]
+    }
+
+    /// See [`DlsiteClient::account`].
+    pub fn account(&self) -> account::AccountClient<'_> {
+        self.next_client().account()
+    }
+
+    /// See [`DlsiteClient::play`].
+    pub fn play(&self) -> play::PlayClient<'_> {
+        self.next_client().play()
+    }
+
+    /// See [`DlsiteClient::pro`].
+    pub fn pro(&self) -> pro::ProClient<'_> {
+        self.next_client().pro()
+    }
+
+    /// See [`DlsiteClient::coupon`].
+    pub fn coupon(&self) -> coupon::CouponClient<'_> {
+        self.next_client().coupon()
+    }
+
+    /// See [`DlsiteClient::creator`].
+    pub fn creator(&self) -> creator::CreatorClient<'_> {
+        self.next_client().creator()
+    }
+
+    /// See [`DlsiteClient::follow`].
+    pub fn follow(&self) -> follow::FollowClient<'_> {
+        self.next_client().follow()
+    }
+
+    /// See [`DlsiteClient::product`].
+    pub fn product(&self) -> product::ProductClient<'_> {
+        self.next_client().product()
+    }
+
+    /// See [`DlsiteClient::product_api`].
+    pub fn product_api(&self) -> product_api::ProductApiClient<'_> {
+        self.next_client().product_api()
+    }
+
+    /// See [`DlsiteClient::app`].
+    pub fn app(&self) -> app::AppClient<'_> {
+        self.next_client().app()
+    }
+
+    /// See [`DlsiteClient::book`].
+    pub fn book(&self) -> book::BookClient<'_> {
+        self.next_client().book()
+    }
+
+    /// See [`DlsiteClient::campaign`].
+    pub fn campaign(&self) -> campaign::CampaignClient<'_> {
+        self.next_client().campaign()
+    }
+
+    /// See [`DlsiteClient::circle`].
+    pub fn circle(&self) -> circle::CircleClient<'_> {
+        self.next_client().circle()
+    }
+
+    /// See [`DlsiteClient::ranking`].
+    pub fn ranking(&self) -> ranking::RankingClient<'_> {
+        self.next_client().ranking()
+    }
+
+    /// See [`DlsiteClient::search`].
+    pub fn search(&self) -> search::SearchClient<'_> {
+        self.next_client().search()
+    }
+
+    /// See [`DlsiteClient::unified_product`].
+    pub async fn unified_product(
+        &self,
+        product_id: &str,
+    ) -> Result<product::unified::UnifiedProduct> {
+        self.next_client().unified_product(product_id).await
+    }
+
+    /// See [`DlsiteClient::new_arrivals`].
+    pub async fn new_arrivals(
+        &self,
+        since: chrono::NaiveDate,
+    ) -> Result<Vec<search::SearchProductItem>> {
+        self.next_client().new_arrivals(since).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClientPool;
+    use crate::DlsiteClient;
+
+    #[test]
+    fn pool_round_robin_and_shared_cache() {
+        let pool = ClientPool::new([
+            DlsiteClient::builder("https://www.dlsite.com/maniax"),
+            DlsiteClient::builder("https://www.dlsite.com/maniax"),
+        ]);
+        assert_eq!(pool.len(), 2);
+
+        let a = pool.next_client() as *const DlsiteClient;
+        let b = pool.next_client() as *const DlsiteClient;
+        let c = pool.next_client() as *const DlsiteClient;
+        assert_ne!(a, b);
+        assert_eq!(a, c);
+
+        pool.clients()[0]
+            .cache()
+            .insert("key".to_string(), "value".to_string());
+        assert_eq!(pool.clients()[1].cache().get("key"), Some("value".to_string()));
+    }
+}
@@ -0,0 +1,362 @@
+//! Interfaces related to the DLsite Pro storefront (`VJ` products: 美少女ゲーム and other
+//! commercial PC software). For more information, see [`ProClient`].
+
+use chrono::NaiveDate;
+use scraper::{ElementRef, Html, Selector};
+
+use super::{
+    product::html::slider_images,
+    storefront::{description_html, maker_id, maker_link, OutlineTable},
+    DlsiteClient,
+};
+use crate::{
+    error::Result,
+    interface::{
+        genre::Genre,
+        product::{AgeCategory, Platforms},
+        product_id::ProductId,
+        site::Site,
+    },
+    utils::ToParseError as _,
+};
+
+/// Words marking a note about copy protection or activation.
+const DRM_KEYWORDS: [&str; 5] = [
+    "DRM",
+    "認証",
+    "アクティベーション",
+    "シリアル",
+    "プロテクト",
+];
+
+/// Client to get commercial works from DLsite Pro.
+///
+/// Commercial works are published by a brand rather than a circle, often come in several
+/// editions (通常版, 豪華版...) and may require an online activation, so their pages are
+/// parsed separately from [`super::product::ProductClient`]. Requests always go to
+/// [`Site::Pro`], whatever the storefront of the client.
+#[derive(Clone, Debug)]
+pub struct ProClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// Brand (publisher) of a commercial work.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Brand {
+    /// Maker ID, e.g. `VG01234`
+    pub id: String,
+    pub name: String,
+}
+
+/// An edition of a commercial work, as listed on its page.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Edition {
+    /// `None` for the edition of the page itself when it isn't linked
+    pub product_id: Option<String>,
+    /// e.g. `通常版`, `豪華版`
+    pub label: String,
+    /// Whether this is the edition of the page
+    pub current: bool,
+}
+
+/// An edition with its price, see [`ProClient::compare_editions`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EditionPrice {
+    pub product_id: String,
+    pub label: String,
+    pub title: String,
+    pub price: i32,
+    /// Price before the current sale, if the edition is discounted
+    pub official_price: Option<i32>,
+}
+
+/// Commercial work data got from the html page.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProHtml {
+    pub released_at: NaiveDate,
+    pub age_rating: Option<AgeCategory>,
+    pub brand: Brand,
+    pub series: Option<String>,
+    /// シナリオ
+    pub scenario: Vec<String>,
+    /// 原画 / イラスト
+    pub illustration: Vec<String>,
+    /// 声優
+    pub voice_actors: Vec<String>,
+    /// 音楽
+    pub music: Vec<String>,
+    pub genre: Vec<Genre>,
+    pub product_format: Vec<String>,
+    pub file_format: Vec<String>,
+    pub file_size: Option<String>,
+    pub platforms: Platforms,
+    /// 動作環境
+    pub sys_req: Option<String>,
+    /// Notes about copy protection or activation (e.g. `DRM: 認証あり`), as written on the
+    /// page. Empty if the page doesn't mention any.
+    pub drm: Vec<String>,
+    pub editions: Vec<Edition>,
+    pub images: Vec<String>,
+    pub description_html: Option<String>,
+}
+
+/// A commercial work on DLsite Pro.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProWork {
+    pub id: String,
+    pub title: String,
+    pub price: i32,
+    pub sale_count: Option<i32>,
+    pub rating: Option<f32>,
+    pub rate_count: Option<i32>,
+    #[serde(flatten)]
+    pub html: ProHtml,
+}
+
+impl<'a> ProClient<'a> {
+    /// Get a commercial work: its page and the ajax api are fetched concurrently.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let work = client.pro().get("VJ01000513").await.unwrap();
+    ///     println!("{} by {}", work.title, work.html.brand.name);
+    /// }
+    /// ```
+    pub async fn get(&self, product_id: impl Into<ProductId>) -> Result<ProWork> {
+        let product_id = product_id.into().checked()?;
+        let (html, ajax) = self
+            .c
+            .fetch_storefront_work(Site::Pro, &product_id, parse_pro_html)
+            .await?;
+        Ok(ProWork {
+            id: product_id.into(),
+            title: ajax.title,
+            price: ajax.price,
+            sale_count: ajax.sale_count,
+            rating: ajax.rating,
+            rate_count: ajax.rate_count,
+            html,
+        })
+    }
+
+    /// Scrape the html page of a commercial work and parse it.
+    pub async fn get_html(&self, product_id: impl Into<ProductId>) -> Result<ProHtml> {
+        let product_id = product_id.into().checked()?;
+        self.c
+            .fetch_storefront_page(Site::Pro, &product_id, parse_pro_html)
+            .await
+    }
+
+    /// Compare the prices of the editions of a work.
+    ///
+    /// The page of the work is fetched to list its editions, then the ajax api of each
+    /// edition. Works with a single edition return it alone.
+    pub async fn compare_editions(
+        &self,
+        product_id: impl Into<ProductId>,
+    ) -> Result<Vec<EditionPrice>> {
+        let product_id = product_id.into().checked()?;
+        let html = self
+            .c
+            .fetch_storefront_page(Site::Pro, &product_id, parse_pro_html)
+            .await?;
+        let mut editions = html.editions;
+        if editions.is_empty() {
+            editions.push(Edition {
+                product_id: None,
+                label: String::new(),
+                current: true,
+            });
+        }
+
+        let mut prices = Vec::with_capacity(editions.len());
+        for edition in editions {
+            let id = match edition.product_id {
+                Some(id) => id,
+                None => product_id.to_string(),
+            };
+            let ajax = self.c.product().get_ajax_on(&id, Site::Pro).await?;
+            prices.push(EditionPrice {
+                official_price: (ajax.official_price != ajax.price).then_some(ajax.official_price),
+                product_id: id,
+                label: edition.label,
+                title: ajax.work_name,
+                price: ajax.price,
+            });
+        }
+        Ok(prices)
+    }
+}
+
+pub(crate) fn parse_pro_html(html: &Html) -> Result<ProHtml> {
+    let table = OutlineTable::new(html);
+
+    let brand = maker_link(html).to_parse_error("No brand found")?;
+    let brand = Brand {
+        id: maker_id(&brand)
+            .to_parse_error("Failed to parse brand id")?
+            .to_string(),
+        name: brand.text().collect::<String>().trim().to_string(),
+    };
+
+    let product_format = table.work_genre("作品形式");
+    let misc = table.work_genre("その他");
+    let supported_os = table.text("対応OS");
+    let platforms = Platforms::from_labels(
+        supported_os
+            .iter()
+            .chain(product_format.iter())
+            .chain(misc.iter())
+            .map(|s| s.as_str()),
+    );
+
+    Ok(ProHtml {
+        released_at: table.released_at()?,
+        age_rating: table.age_rating()?,
+        brand,
+        series: table.text("シリーズ名"),
+        scenario: table.links(&["シナリオ"]),
+        illustration: table.links(&["原画", "イラスト"]),
+        voice_actors: table.links(&["声優"]),
+        music: table.links(&["音楽"]),
+        genre: table.genres(),
+        product_format,
+        file_format: table.work_genre("ファイル形式"),
+        file_size: table.file_size(),
+        platforms,
+        sys_req: table.text("動作環境"),
+        drm: parse_drm_notes(html, &misc),
+        editions: parse_editions(html),
+        images: slider_images(html),
+        description_html: description_html(html),
+    })
+}
+
+/// Notes about copy protection from the `その他` cell and the spec/notes sections.
+fn parse_drm_notes(html: &Html, misc: &[String]) -> Vec<String> {
+    let sections =
+        Selector::parse("#work_outline td, .work_spec, .work_parts_container, .work_notice")
+            .unwrap();
+    let mut notes: Vec<String> = misc.to_vec();
+    for element in html.select(&sections) {
+        notes.extend(
+            element
+                .text()
+                .flat_map(|t| t.lines())
+                .map(|l| l.trim().to_string()),
+        );
+    }
+
+    let mut drm: Vec<String> = vec![];
+    for note in notes {
+        if DRM_KEYWORDS.iter().any(|k| note.contains(k)) && !drm.contains(&note) {
+            drm.push(note);
+        }
+    }
+    drm
+}
+
+/// Editions linked from the edition switcher of the page.
+fn parse_editions(html: &Html) -> Vec<Edition> {
+    let items = Selector::parse(".work_edition .type_edition > *").unwrap();
+    html.select(&items)
+        .filter_map(|item: ElementRef| {
+            let label = item.text().collect::<String>().trim().to_string();
+            if label.is_empty() {
+                return None;
+            }
+            let product_id = item
+                .value()
+                .attr("href")
+                .and_then(|href| href.split("/product_id/").nth(1))
+                .and_then(|rest| rest.split('.').next())
+                .map(|id| id.to_string());
+            let current = product_id.is_none()
+                || item
+                    .value()
+                    .classes()
+                    .any(|c| c == "is-active" || c == "current");
+            Some(Edition {
+                product_id,
+                label,
+                current,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use scraper::Html;
+
+    use super::{parse_pro_html, Brand, Edition};
+    use crate::{interface::product::AgeCategory, DlsiteClient};
+
+    #[test]
+    fn parse_pro() {
+        let html = Html::parse_document(
+            r#"<table id="work_maker"><tr><th>ブランド名</th><td><span class="maker_name">
+                <a href="https://www.dlsite.com/pro/circle/profile/=/maker_id/VG01234.html">ゆずソフト</a>
+            </span></td></tr></table>
+            <div class="work_edition"><div class="work_edition_linklist type_edition">
+                <a href="https://www.dlsite.com/pro/work/=/product_id/VJ01000513.html" class="is-active">通常版</a>
+                <a href="https://www.dlsite.com/pro/work/=/product_id/VJ01000514.html">豪華版</a>
+            </div></div>
+            <table id="work_outline">
+                <tr><th>販売日</th><td><a href="/pro/new/=/date/2023-06-30/">2023年06月30日</a></td></tr>
+                <tr><th>シナリオ</th><td><a href="/pro/fsr/=/keyword_creater/x">山田太郎</a></td></tr>
+                <tr><th>原画</th><td><a href="/pro/fsr/=/keyword_creater/x">むりりん</a> / <a href="/pro/fsr/=/keyword_creater/x">こぶいち</a></td></tr>
+                <tr><th>年齢指定</th><td><div class="work_genre"><span>18禁</span></div></td></tr>
+                <tr><th>対応OS</th><td>Windows 10 / 11</td></tr>
+                <tr><th>その他</th><td><div class="work_genre"><span>DRM: 認証あり</span><span>体験版あり</span></div></td></tr>
+                <tr><th>ファイル容量</th><td><div>総計 5.2GB</div></td></tr>
+            </table>"#,
+        );
+        let work = parse_pro_html(&html).unwrap();
+        assert_eq!(
+            work.brand,
+            Brand {
+                id: "VG01234".to_string(),
+                name: "ゆずソフト".to_string(),
+            }
+        );
+        assert_eq!(
+            work.released_at,
+            NaiveDate::from_ymd_opt(2023, 6, 30).unwrap()
+        );
+        assert_eq!(work.age_rating, Some(AgeCategory::Adult));
+        assert_eq!(work.scenario, vec!["山田太郎"]);
+        assert_eq!(work.illustration, vec!["むりりん", "こぶいち"]);
+        assert!(work.platforms.windows);
+        assert_eq!(work.drm, vec!["DRM: 認証あり"]);
+        assert_eq!(work.file_size.as_deref(), Some("5.2GB"));
+        assert_eq!(
+            work.editions,
+            vec![
+                Edition {
+                    product_id: Some("VJ01000513".to_string()),
+                    label: "通常版".to_string(),
+                    current: true,
+                },
+                Edition {
+                    product_id: Some("VJ01000514".to_string()),
+                    label: "豪華版".to_string(),
+                    current: false,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_pro() {
+        let client = DlsiteClient::default();
+        let work = client.pro().get("VJ01000513").await.unwrap();
+        assert!(!work.title.is_empty());
+        assert!(work.html.brand.id.starts_with("VG"));
+    }
+}
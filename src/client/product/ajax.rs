@@ -3,7 +3,7 @@ use std::{collections::HashMap, str::FromStr as _};
 use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 
-use crate::interface::product::WorkType;
+use crate::interface::product::{RatingDistribution, TranslationPermission, WorkType};
 
 #[derive(Debug, Clone, Deserialize)]
 #[cfg_attr(feature = "unknown-field-error", serde(deny_unknown_fields))]
@@ -217,6 +217,36 @@ pub struct ProductAjax {
     pub work_rentals: Vec<Value>,
 }
 
+impl ProductAjax {
+    /// Star rating breakdown built from `rate_count_detail`.
+    pub fn rating_distribution(&self) -> RatingDistribution {
+        RatingDistribution::from_pairs(
+            self.rate_count_detail
+                .iter()
+                .map(|d| (d.review_point.max(0) as u32, d.count.max(0) as u32)),
+        )
+    }
+
+    /// Community translation status built from `translation_info`.
+    pub fn translation_permission(&self) -> TranslationPermission {
+        let info = &self.translation_info;
+        let mut languages: Vec<String> = match &info.translation_bonus_langs {
+            TranslationBonusLangsTypes::Map(langs) => langs.keys().cloned().collect(),
+            TranslationBonusLangsTypes::Arr(_) => vec![],
+        };
+        languages.sort();
+
+        TranslationPermission {
+            allowed: info.is_translation_agree,
+            is_translation: info.is_child,
+            languages,
+            royalty_rate: u32::try_from(info.production_trade_price_rate)
+                .ok()
+                .filter(|rate| *rate > 0),
+        }
+    }
+}
+
 fn deserialize_work_type<'de, D>(deserializer: D) -> std::result::Result<WorkType, D::Error>
 where
     D: Deserializer<'de>,
@@ -0,0 +1,55 @@
+//! Product metadata merged from the Japanese and English versions of DLsite.
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::product_api::interface::ProductApiContent;
+
+/// Product texts in both Japanese and English.
+///
+/// English fields are `None` when the English version could not be fetched. Note that DLsite
+/// returns the Japanese text for products which have no translation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BilingualProduct {
+    pub id: String,
+    pub title_ja: String,
+    pub title_en: Option<String>,
+    pub description_ja: Option<String>,
+    pub description_en: Option<String>,
+    pub genres: Vec<BilingualGenre>,
+}
+
+/// A genre with its Japanese and English names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BilingualGenre {
+    pub id: String,
+    pub name_ja: String,
+    pub name_en: Option<String>,
+}
+
+impl BilingualProduct {
+    pub(crate) fn merge(ja: &ProductApiContent, en: Option<&ProductApiContent>) -> Self {
+        let genres = ja
+            .genres
+            .iter()
+            .map(|genre| BilingualGenre {
+                id: genre.id.to_string(),
+                name_ja: genre.name.clone(),
+                name_en: en.and_then(|en| {
+                    en.genres
+                        .iter()
+                        .find(|g| g.id == genre.id)
+                        .map(|g| g.name.clone())
+                }),
+            })
+            .collect();
+
+        Self {
+            id: ja.workno.clone(),
+            title_ja: ja.work_name.clone(),
+            title_en: en.map(|en| en.work_name.clone()),
+            description_ja: ja.intro_s.clone(),
+            description_en: en.and_then(|en| en.intro_s.clone()),
+            genres,
+        }
+    }
+}
@@ -1,20 +1,25 @@
-use std::collections::HashMap;
+use std::{cmp::Reverse, collections::HashMap, sync::OnceLock};
 
 use chrono::NaiveDate;
+use regex::Regex;
 use scraper::{ElementRef, Html, Selector};
 use url::Url;
 
 use crate::{
     error::Result,
-    interface::{genre::Genre, product::AgeCategory},
-    utils::ToParseError,
+    interface::{
+        genre::Genre,
+        product::{AgeCategory, DrmInfo, FileFormat, FileInfo, Platforms},
+    },
+    selector::{ParseReport, SelectorChain},
+    utils::{parse_file_size, ToParseError},
     DlsiteError,
 };
 
 use super::ProductPeople;
 
 /// Product data got from html
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct ProductHtml {
     pub released_at: NaiveDate,
     pub age_rating: Option<AgeCategory>,
@@ -26,6 +31,8 @@ pub struct ProductHtml {
     pub series: Option<String>,
     pub file_format: Vec<String>,
     pub file_size: Option<String>,
+    /// Typed `file_format` and `file_size`, with the trial version
+    pub file_info: FileInfo,
     pub product_format: Vec<String>,
     pub description_html: Option<String>,
     pub event: Vec<String>,
@@ -39,12 +46,20 @@ pub struct ProductHtml {
     pub sys_req: Option<String>,
     pub coupling: Vec<String>,
     pub lang_refs: Vec<(String, String)>,
+    pub platforms: Platforms,
+    /// Tags added by users (みんなのタグ) with their vote counts, most voted first
+    pub user_tags: Vec<(String, u32)>,
+    /// Activation and launchers noted in the description and system requirements
+    pub drm: DrmInfo,
+    /// Selectors used to parse the page
+    #[serde(skip)]
+    pub report: ParseReport,
 }
 
-pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
-    let circle = html
-        .select(&Selector::parse("#work_maker .maker_name a").unwrap())
-        .next()
+pub(crate) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
+    let mut report = ParseReport::default();
+    let circle = circle_link()
+        .select(html.root_element(), &mut report)
         .to_parse_error("No circle found")?;
     let circle_name = circle
         .text()
@@ -63,33 +78,17 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
         .to_parse_error("Failed to parse circle id")?
         .to_string();
 
-    let images: Vec<String> = html
-        .select(&Selector::parse(".product-slider-data > div").unwrap())
-        .flat_map(|element| {
-            let url = element.value().attr("data-src")?;
-            let url: Url = format!("https:{}", url).parse().ok()?;
-            Some(url.to_string())
-        })
-        .collect();
+    let images = slider_images(html);
 
     // work_outline_table
-    let mut work_outline_table = get_work_outline_table(html);
+    let mut work_outline_table = work_outline_table(html, &mut report);
     work_outline_table.remove("作者");
     work_outline_table.remove("声優");
-    let file_size = work_outline_table
-        .remove("ファイル容量")
-        .and_then(|v| {
-            v.select(&Selector::parse("div").unwrap()).next().map(|v| {
-                Some(
-                    v.text()
-                        .collect::<String>()
-                        .trim()
-                        .strip_prefix("総計\u{a0}")?
-                        .to_owned(),
-                )
-            })
-        })
-        .flatten();
+    let file_size = work_outline_table.remove("ファイル容量").and_then(|v| {
+        v.select(&Selector::parse("div").unwrap())
+            .next()
+            .and_then(|v| total_size(&v.text().collect::<String>()))
+    });
     let work_genre_extractor = |work_outline_table: &mut HashMap<String, ElementRef>, key: &str| {
         work_outline_table
             .remove(key)
@@ -115,10 +114,11 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
             .unwrap_or_default()
     };
     let product_format = work_genre_extractor(&mut work_outline_table, "作品形式");
-    let description_html: Option<String> = html
-        .select(&Selector::parse("[itemprop='description']").unwrap())
-        .next()
-        .map(|v| v.inner_html());
+    let description = description().select(html.root_element(), &mut report);
+    let description_text = description.map(|v| v.text().collect::<Vec<_>>().join("\n"));
+    let description_html: Option<String> = description.map(|v| v.inner_html());
+    #[cfg(feature = "sanitize")]
+    let description_html = description_html.map(|html| crate::utils::sanitize_html(&html));
     let event = a_extractor(&mut work_outline_table, "イベント");
     let pages = work_outline_table
         .remove("ページ数")
@@ -130,8 +130,9 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
     let illustration = a_extractor(&mut work_outline_table, "イラスト");
     let misc = work_genre_extractor(&mut work_outline_table, "その他");
     let langs = work_genre_extractor(&mut work_outline_table, "対応言語");
-    let lang_refs = html
-        .select(&Selector::parse(".work_edition .type_trans > a").unwrap())
+    let lang_refs = translation_links()
+        .select_all(html, &mut report)
+        .into_iter()
         .filter_map(|v| {
             Some((
                 v.text().collect::<String>().trim().to_owned(),
@@ -143,6 +144,13 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
     let sys_req = work_outline_table
         .remove("動作環境")
         .map(|v| v.text().collect::<String>().trim().to_owned());
+    let drm = DrmInfo::from_notes(
+        description_text
+            .iter()
+            .chain(sys_req.iter())
+            .chain(misc.iter())
+            .map(|s| s.as_str()),
+    );
     let coupling = a_extractor(&mut work_outline_table, "カップリング");
     let file_format = work_outline_table
         .remove("ファイル形式")
@@ -160,30 +168,19 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
                 })
         })
         .unwrap_or_default();
+    let file_info = parse_file_info(html, file_size.as_deref(), &file_format, &mut report);
     let age_rating = work_outline_table
         .remove("年齢指定")
         .map(|v| {
-            Ok::<_, DlsiteError>(
-                v.select(&Selector::parse("span").unwrap())
-                    .next()
-                    .to_parse_error("No age rating found")?
-                    .inner_html(),
-            )
+            let badge = v
+                .select(&Selector::parse("span").unwrap())
+                .next()
+                .to_parse_error("No age rating found")?;
+            parse_age_badge(badge).ok_or_else(|| {
+                DlsiteError::Parse(format!("failed to convert {} to enum", badge.inner_html()))
+            })
         })
         .transpose()?;
-    let age_rating = match age_rating {
-        Some(age_rating) => Some(match age_rating.as_str() {
-            "全年齢" => AgeCategory::General,
-            "R18" => AgeCategory::Adult,
-            "R-15" => AgeCategory::R15,
-            _ => {
-                return Err(DlsiteError::Parse(format!(
-                    "failed to convert {age_rating} to enum"
-                )))
-            }
-        }),
-        None => None,
-    };
 
     let series = work_outline_table.remove("シリーズ名");
     let series = series.map(|series| series.text().collect::<String>().trim().to_owned());
@@ -194,13 +191,8 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
         .text()
         .next()
         .to_parse_error("No released_at found")?;
-    let released_at = regex::Regex::new(r"\d*年\d*月\d*日")
-        .unwrap()
-        .find(released_at)
-        .to_parse_error("Failed to parse released_at")?
-        .as_str();
-    let released_at = NaiveDate::parse_from_str(released_at, "%Y年%m月%d日")
-        .map_err(|_| DlsiteError::Parse("Failed to parse released_at".to_string()))?;
+    let released_at =
+        parse_release_date(released_at).to_parse_error("Failed to parse released_at")?;
     let genre = work_outline_table
         .remove("ジャンル")
         .map(|element| {
@@ -224,6 +216,16 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
                 .collect::<Vec<_>>()
         })
         .unwrap_or_default();
+    let supported_os = work_outline_table
+        .remove("対応OS")
+        .map(|v| v.text().collect::<String>().trim().to_owned());
+    let platforms = Platforms::from_labels(
+        supported_os
+            .iter()
+            .chain(product_format.iter())
+            .chain(misc.iter())
+            .map(|s| s.as_str()),
+    );
     if !work_outline_table.is_empty() {
         return Err(DlsiteError::Parse(format!(
             "failed to parse tags {:?}",
@@ -241,6 +243,7 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
         series,
         file_format,
         file_size,
+        file_info,
         product_format,
         description_html,
         event,
@@ -254,9 +257,132 @@ pub(super) fn parse_product_html(html: &Html) -> Result<ProductHtml> {
         sys_req,
         coupling,
         lang_refs,
+        platforms,
+        user_tags: parse_user_tags(html),
+        drm,
+        report,
     })
 }
 
+/// User tags (みんなのタグ) of a product page, with their vote counts, most voted first.
+///
+/// Each tag is a link of the tag box, with the count in a child element:
+/// `<div class="work_tags"><a href="...">ASMR<span class="tag_count">(12)</span></a></div>`.
+/// Tags without a readable count are kept with 0 votes.
+pub(crate) fn parse_user_tags(html: &Html) -> Vec<(String, u32)> {
+    let count_selector = Selector::parse(".tag_count").unwrap();
+    let mut tags: Vec<(String, u32)> = vec![];
+    for tag in html.select(&Selector::parse(".work_tags a").unwrap()) {
+        let count_text = tag
+            .select(&count_selector)
+            .next()
+            .map(|count| count.text().collect::<String>())
+            .unwrap_or_default();
+        let name = tag.text().collect::<String>();
+        let name = name.replace(&count_text, "").trim().to_owned();
+        if name.is_empty() || tags.iter().any(|(n, _)| *n == name) {
+            continue;
+        }
+        let count = count_text
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>()
+            .parse()
+            .unwrap_or(0);
+        tags.push((name, count));
+    }
+    tags.sort_by_key(|t| Reverse(t.1));
+    tags
+}
+
+/// Sample media shown on a product page, see
+/// [`super::ProductClient::get_sample_images`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct SampleImages {
+    /// Full-size images of the slider, in display order
+    pub images: Vec<String>,
+    /// Embed URL of the chobit sample player, if the page has one
+    pub chobit: Option<String>,
+}
+
+pub(crate) fn parse_sample_images(html: &Html) -> SampleImages {
+    let chobit = html
+        .select(&Selector::parse("iframe[src*='chobit.cc'], a[href*='chobit.cc']").unwrap())
+        .find_map(|element| {
+            let url = element
+                .value()
+                .attr("src")
+                .or_else(|| element.value().attr("href"))?;
+            absolute_url(url)
+        });
+    SampleImages {
+        images: slider_images(html),
+        chobit,
+    }
+}
+
+/// Age category of an age badge like `<span class="icon_ADL" title="18禁">18禁</span>`.
+///
+/// The label is read from the title, then the text; girls and BL pages sometimes only have
+/// the code of the class (`icon_GEN`, `icon_R15`, `icon_ADL`).
+pub(crate) fn parse_age_badge(badge: ElementRef) -> Option<AgeCategory> {
+    let value = badge.value();
+    value
+        .attr("title")
+        .and_then(AgeCategory::from_label)
+        .or_else(|| AgeCategory::from_label(&badge.text().collect::<String>()))
+        .or_else(|| {
+            value
+                .classes()
+                .find_map(|class| AgeCategory::from_code(class.strip_prefix("icon_")?))
+        })
+}
+
+/// Full-size image URLs of the product slider. Thumbnails (`data-thumb`) are skipped.
+pub(crate) fn slider_images(html: &Html) -> Vec<String> {
+    let mut images: Vec<String> = vec![];
+    for element in html.select(&Selector::parse(".product-slider-data > div").unwrap()) {
+        if let Some(url) = element.value().attr("data-src").and_then(absolute_url) {
+            if !images.contains(&url) {
+                images.push(url);
+            }
+        }
+    }
+    images
+}
+
+/// Resolve a protocol-relative URL (`//img.dlsite.jp/...`) to https.
+fn absolute_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let url: Url = if url.starts_with("//") {
+        format!("https:{}", url).parse().ok()?
+    } else {
+        url.parse().ok()?
+    };
+    Some(url.to_string())
+}
+
+/// Build the file info from the parsed `ファイル容量` and `ファイル形式` cells and the trial
+/// download button.
+fn parse_file_info(
+    html: &Html,
+    file_size: Option<&str>,
+    file_format: &[String],
+    report: &mut ParseReport,
+) -> FileInfo {
+    let trial = trial_button().select(html.root_element(), report);
+    FileInfo {
+        total_size_bytes: file_size.and_then(parse_file_size),
+        formats: file_format
+            .iter()
+            .filter(|f| !f.is_empty())
+            .map(|f| FileFormat::from_label(f))
+            .collect(),
+        has_trial: trial.is_some(),
+        trial_size_bytes: trial.and_then(|e| parse_file_size(&e.text().collect::<String>())),
+    }
+}
+
 pub(super) fn parse_product_people(html: &Html) -> Result<ProductPeople> {
     let work_outline_table = get_work_outline_table(html);
 
@@ -282,18 +408,331 @@ pub(super) fn parse_product_people(html: &Html) -> Result<ProductPeople> {
     })
 }
 
-fn get_work_outline_table(html: &Html) -> HashMap<String, ElementRef> {
+/// Parse the date of the `販売日` cell: `2022年12月06日` (also used in Chinese),
+/// `2022년 12월 06일` or, in English, `12/06/2022`.
+pub(crate) fn parse_release_date(text: &str) -> Option<NaiveDate> {
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    let date = DATE.get_or_init(|| {
+        Regex::new(r"(\d{4})\s*[年년]\s*(\d{1,2})\s*[月월]\s*(\d{1,2})\s*[日일]").unwrap()
+    });
+    if let Some(captures) = date.captures(text) {
+        return NaiveDate::from_ymd_opt(
+            captures[1].parse().ok()?,
+            captures[2].parse().ok()?,
+            captures[3].parse().ok()?,
+        );
+    }
+    let text = text.split_whitespace().next()?;
+    ["%m/%d/%Y", "%b/%d/%Y", "%Y/%m/%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+}
+
+/// Total size from the `ファイル容量` cell (`総計 1.2GB`, `Total 1.2GB`...). `None` if the
+/// cell lists sizes without total.
+pub(crate) fn total_size(text: &str) -> Option<String> {
+    let text = text.trim();
+    let size = ["総計", "总计", "總計", "Total", "총계"]
+        .iter()
+        .find_map(|label| text.strip_prefix(label))?;
+    Some(
+        size.trim_start_matches([' ', '\u{a0}', ':', '：'])
+            .to_owned(),
+    )
+}
+
+/// Japanese label of a row of the `#work_outline` table, for pages displayed in another
+/// language. Unknown labels are returned as is.
+fn canonical_label(label: &str) -> &str {
+    match label.to_lowercase().as_str() {
+        "release date" | "贩卖日" | "販賣日" | "发售日" | "發售日" | "판매일" => {
+            "販売日"
+        }
+        "update information" | "更新信息" | "更新資訊" | "업데이트 정보" => {
+            "更新情報"
+        }
+        "series name" | "series" | "系列名" | "시리즈명" => "シリーズ名",
+        "author" | "작가" => "作者",
+        "scenario" | "剧情" | "劇情" | "시나리오" => "シナリオ",
+        "illustration" | "插画" | "插畫" | "일러스트" => "イラスト",
+        "voice actor" | "声优" | "聲優" | "성우" => "声優",
+        "music" | "音乐" | "音樂" | "음악" => "音楽",
+        "age" | "年龄指定" | "年齡指定" | "연령 지정" => "年齢指定",
+        "product format" | "作品类型" | "作品類型" | "작품 형식" => "作品形式",
+        "file format" | "文件形式" | "檔案形式" | "파일 형식" => "ファイル形式",
+        "supported languages" | "对应语言" | "對應語言" | "대응 언어" => "対応言語",
+        "genre" | "分类" | "分類" | "장르" => "ジャンル",
+        "file size" | "文件容量" | "檔案容量" | "파일 용량" => "ファイル容量",
+        "event" | "活动" | "活動" | "이벤트" => "イベント",
+        "page count" | "pages" | "页数" | "頁數" | "페이지 수" => "ページ数",
+        "other" | "其他" | "기타" => "その他",
+        "supported os" | "对应os" | "對應os" | "대응 os" => "対応OS",
+        "required specs" | "system requirements" | "运行环境" | "動作環境" | "동작 환경" => {
+            "動作環境"
+        }
+        "coupling" | "配对" | "配對" | "커플링" => "カップリング",
+        _ => label,
+    }
+}
+
+/// Link to the circle of the work in the header of a product page.
+fn circle_link() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new(
+            "circle",
+            &[
+                "#work_maker .maker_name a",
+                ".maker_name a",
+                "a[href*='/circle/profile/=/maker_id/']",
+            ],
+        )
+    })
+}
+
+/// Rows of the `#work_outline` table.
+fn outline_rows() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR
+        .get_or_init(|| SelectorChain::new("outline", &["#work_outline tr", ".work_outline tr"]))
+}
+
+/// Description of the work.
+fn description() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new(
+            "description",
+            &["[itemprop='description']", ".work_parts_container"],
+        )
+    })
+}
+
+/// Links to the translated editions of the work.
+fn translation_links() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new(
+            "translations",
+            &[".work_edition .type_trans > a", ".type_trans a"],
+        )
+    })
+}
+
+/// Download button of the trial version.
+fn trial_button() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new("trial", &[".trial_download", ".work_trial", "a.btn_trial"])
+    })
+}
+
+pub(crate) fn get_work_outline_table(html: &Html) -> HashMap<String, ElementRef<'_>> {
+    work_outline_table(html, &mut ParseReport::default())
+}
+
+/// Cells of the `#work_outline` table by Japanese label, see [`canonical_label`].
+fn work_outline_table<'a>(
+    html: &'a Html,
+    report: &mut ParseReport,
+) -> HashMap<String, ElementRef<'a>> {
     let mut map = HashMap::new();
-    for element in html.select(&Selector::parse("#work_outline tr").unwrap()) {
+    for element in outline_rows().select_all(html, report) {
         let th = element.select(&Selector::parse("th").unwrap()).next();
         let td = element.select(&Selector::parse("td").unwrap()).next();
         if let (Some(th), Some(td)) = (th, td) {
             let th = th.text().next();
             if let Some(th) = th {
-                let th = th.trim().to_string();
-                map.insert(th, td);
+                map.insert(canonical_label(th.trim()).to_string(), td);
             }
         }
     }
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use scraper::Html;
+
+    use chrono::NaiveDate;
+
+    use super::{parse_product_html, parse_sample_images, parse_user_tags};
+    use crate::interface::product::AgeCategory;
+
+    #[test]
+    fn sample_images() {
+        let html = Html::parse_document(
+            r#"<div class="product-slider-data">
+                <div data-src="//img.dlsite.jp/modpub/images2/work/doujin/RJ404000/RJ403038_img_main.jpg"
+                     data-thumb="//img.dlsite.jp/resize/images2/work/doujin/RJ404000/RJ403038_img_main_100x100.jpg"></div>
+                <div data-src="//img.dlsite.jp/modpub/images2/work/doujin/RJ404000/RJ403038_img_smp1.jpg"
+                     data-thumb="//img.dlsite.jp/resize/images2/work/doujin/RJ404000/RJ403038_img_smp1_100x100.jpg"></div>
+                <div data-src="//img.dlsite.jp/modpub/images2/work/doujin/RJ404000/RJ403038_img_smp1.jpg"></div>
+            </div>
+            <div class="work_parts"><iframe src="//chobit.cc/embed/abc12/def34/" class="chobit"></iframe></div>"#,
+        );
+        let samples = parse_sample_images(&html);
+        assert_eq!(
+            samples.images,
+            vec![
+                "https://img.dlsite.jp/modpub/images2/work/doujin/RJ404000/RJ403038_img_main.jpg",
+                "https://img.dlsite.jp/modpub/images2/work/doujin/RJ404000/RJ403038_img_smp1.jpg",
+            ]
+        );
+        assert_eq!(
+            samples.chobit.as_deref(),
+            Some("https://chobit.cc/embed/abc12/def34/")
+        );
+
+        let samples = parse_sample_images(&Html::parse_document("<div></div>"));
+        assert!(samples.images.is_empty());
+        assert_eq!(samples.chobit, None);
+    }
+
+    #[test]
+    fn localized_labels() {
+        let html = Html::parse_document(
+            r#"<table id="work_maker"><tr><td><span class="maker_name">
+                <a href="https://www.dlsite.com/maniax/circle/profile/=/maker_id/RG51654.html">CANDY VOICE</a>
+            </span></td></tr></table>
+            <table id="work_outline">
+                <tr><th>Release date</th><td><a href="/maniax/new/=/date/2022-12-06/">12/06/2022</a></td></tr>
+                <tr><th>Age</th><td><div class="work_genre"><span>All ages</span></div></td></tr>
+                <tr><th>Voice Actor</th><td><a href="/maniax/fsr/=/keyword_creater/x">竹達彩奈</a></td></tr>
+                <tr><th>File size</th><td><div>Total&nbsp;1.2GB</div></td></tr>
+                <tr><th>分类</th><td><div class="main_genre">
+                    <a href="https://www.dlsite.com/maniax/fsr/=/genre/497/from/work.genre">ASMR</a>
+                </div></td></tr>
+            </table>"#,
+        );
+        let product = parse_product_html(&html).unwrap();
+        assert_eq!(
+            product.released_at,
+            NaiveDate::from_ymd_opt(2022, 12, 6).unwrap()
+        );
+        assert_eq!(product.age_rating, Some(AgeCategory::General));
+        assert_eq!(
+            product.people.voice_actor,
+            Some(vec!["竹達彩奈".to_string()])
+        );
+        assert_eq!(product.file_size.as_deref(), Some("1.2GB"));
+        assert_eq!(product.genre[0].id, "497");
+    }
+
+    #[test]
+    fn fallback_selectors() {
+        // Older markup: circle outside `#work_maker`, outline table and description by class
+        let html = Html::parse_document(
+            r#"<div class="work_right_info"><span class="maker_name">
+                <a href="https://www.dlsite.com/maniax/circle/profile/=/maker_id/RG51654.html">CANDY VOICE</a>
+            </span></div>
+            <table class="work_outline">
+                <tr><th>販売日</th><td><a href="/maniax/new/=/date/2022-12-06/">2022年12月06日</a></td></tr>
+                <tr><th>シリーズ名</th><td><a href="/maniax/fsr/=/title_id/SRI1">シリーズ</a></td></tr>
+            </table>
+            <div class="work_parts_container"><p>説明文</p></div>"#,
+        );
+        let product = parse_product_html(&html).unwrap();
+        assert_eq!(product.circle_id, "RG51654");
+        assert_eq!(product.circle_name, "CANDY VOICE");
+        assert_eq!(
+            product.released_at,
+            NaiveDate::from_ymd_opt(2022, 12, 6).unwrap()
+        );
+        assert_eq!(product.series.as_deref(), Some("シリーズ"));
+        assert_eq!(product.description_html.as_deref(), Some("<p>説明文</p>"));
+        assert_eq!(
+            product.report.fallbacks().collect::<Vec<_>>(),
+            vec![
+                ("circle", ".maker_name a"),
+                ("description", ".work_parts_container"),
+                ("outline", ".work_outline tr"),
+            ]
+        );
+        assert_eq!(
+            product.report.missing().collect::<Vec<_>>(),
+            vec!["translations", "trial"]
+        );
+
+        // Current markup uses the primary selectors
+        let html = Html::parse_document(
+            r#"<table id="work_maker"><tr><td><span class="maker_name">
+                <a href="https://www.dlsite.com/maniax/circle/profile/=/maker_id/RG51654.html">CANDY VOICE</a>
+            </span></td></tr></table>
+            <table id="work_outline">
+                <tr><th>販売日</th><td><a href="/maniax/new/=/date/2022-12-06/">2022年12月06日</a></td></tr>
+            </table>"#,
+        );
+        assert!(!parse_product_html(&html).unwrap().report.used_fallback());
+    }
+
+    #[test]
+    fn drm_notes() {
+        let html = Html::parse_document(
+            r#"<table id="work_maker"><tr><td><span class="maker_name">
+                <a href="https://www.dlsite.com/pro/circle/profile/=/maker_id/VG01000.html">Brand</a>
+            </span></td></tr></table>
+            <table id="work_outline">
+                <tr><th>販売日</th><td><a href="/pro/new/=/date/2023-02-14/">2023年02月14日</a></td></tr>
+                <tr><th>動作環境</th><td>Windows 10 / 11<br>
+                    起動時にオンライン認証が必要です</td></tr>
+            </table>
+            <div itemprop="description"><p>本作品はシリアルコードの入力が必要です。</p></div>"#,
+        );
+        let product = parse_product_html(&html).unwrap();
+        assert!(product.drm.online_activation);
+        assert!(product.drm.serial_code);
+        assert_eq!(
+            product.drm.notes,
+            vec![
+                "本作品はシリアルコードの入力が必要です",
+                "起動時にオンライン認証が必要です"
+            ]
+        );
+    }
+
+    #[test]
+    fn girls_and_bl_age_badges() {
+        let page = |badge: &str| {
+            Html::parse_document(&format!(
+                r#"<table id="work_maker"><tr><td><span class="maker_name">
+                    <a href="https://www.dlsite.com/girls/circle/profile/=/maker_id/RG60001.html">乙女サークル</a>
+                </span></td></tr></table>
+                <table id="work_outline">
+                    <tr><th>販売日</th><td><a href="/girls/new/=/date/2023-02-14/">2023年02月14日</a></td></tr>
+                    <tr><th>年齢指定</th><td><div class="work_genre">{badge}</div></td></tr>
+                </table>"#
+            ))
+        };
+
+        // Girls: icon only
+        let product = parse_product_html(&page(r#"<span class="icon_ADL"></span>"#)).unwrap();
+        assert_eq!(product.age_rating, Some(AgeCategory::Adult));
+        // BL: label in the title
+        let product =
+            parse_product_html(&page(r#"<span class="icon_R15" title="R-15">R15</span>"#)).unwrap();
+        assert_eq!(product.age_rating, Some(AgeCategory::R15));
+        assert!(parse_product_html(&page(r#"<span class="icon_NEW">新作</span>"#)).is_err());
+    }
+
+    #[test]
+    fn user_tags() {
+        let html = Html::parse_document(
+            r#"<div class="work_tags">
+                <a href="/maniax/fsr/=/keyword/耳かき">耳かき<span class="tag_count">(12)</span></a>
+                <a href="/maniax/fsr/=/keyword/癒し">癒し<span class="tag_count">(1,024)</span></a>
+                <a href="/maniax/fsr/=/keyword/耳かき">耳かき<span class="tag_count">(3)</span></a>
+                <a href="/maniax/fsr/=/keyword/囁き">囁き</a>
+            </div>"#,
+        );
+        assert_eq!(
+            parse_user_tags(&html),
+            vec![
+                ("癒し".to_string(), 1024),
+                ("耳かき".to_string(), 12),
+                ("囁き".to_string(), 0),
+            ]
+        );
+        assert!(parse_user_tags(&Html::parse_document("<div></div>")).is_empty());
+    }
+}
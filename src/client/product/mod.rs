@@ -2,21 +2,40 @@
 
 use std::collections::HashMap;
 
+
 use crate::{
+    client::{
+        product_api::{
+            api_path,
+            interface::{Creator, Creators, ProductApiContent},
+        },
+        Page, Paginated, ProductFields,
+    },
     error::Result,
     interface::{
         genre::Genre,
-        product::{AgeCategory, WorkType},
+        product::{
+            AgeCategory, DrmInfo, FileInfo, Platforms, RatingDistribution, TranslationPermission,
+            WorkType,
+        },
+        product_id::ProductId,
+        site::Site,
     },
+    runtime::MaybeBoxed as _,
     utils::ToParseError as _,
-    DlsiteClient, DlsiteError,
+    DlsiteClient, DlsiteError, FetchOptions,
 };
 use ajax::ProductAjax;
+use bilingual::BilingualProduct;
+use unified::{Source, UnifiedProduct};
 use chrono::NaiveDate;
 
 pub mod ajax;
+pub mod bilingual;
 pub mod html;
 pub mod review;
+pub mod tracklist;
+pub mod unified;
 #[cfg(test)]
 mod test;
 
@@ -51,6 +70,8 @@ pub struct ProductClient<'a> {
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Product {
     pub id: String,
+    /// Storefront the product was fetched from
+    pub site: Site,
     pub title: String,
     pub work_type: WorkType,
     pub released_at: NaiveDate,
@@ -62,14 +83,141 @@ pub struct Product {
     pub series: Option<String>,
     pub sale_count: Option<i32>,
     pub review_count: Option<i32>,
+    /// Number of users who added the work to their favorites (お気に入り数)
+    #[serde(default)]
+    pub favorite_count: Option<i32>,
     pub rating: Option<f32>,
     pub rate_count: Option<i32>,
+    pub rating_distribution: RatingDistribution,
+    pub translation_permission: TranslationPermission,
     pub images: Vec<String>,
     pub people: ProductPeople,
     pub reviewer_genre: Vec<(Genre, i32)>,
+    /// Tags added by users (みんなのタグ) with their vote counts, most voted first. Unlike
+    /// genres, anyone can add them, so they catch details the official genres miss.
+    #[serde(default)]
+    pub user_tags: Vec<(String, u32)>,
     pub file_format: Vec<String>,
     pub file_size: Option<String>,
+    /// Typed `file_format` and `file_size`, with the trial version
+    #[serde(default)]
+    pub file_info: FileInfo,
     pub product_format: Vec<String>,
+    #[serde(default)]
+    pub platforms: Platforms,
+    /// Online activation, serial code or launcher the work needs, as noted on its page
+    #[serde(default)]
+    pub drm: DrmInfo,
+    /// Where the data comes from. Only differs from [`Source::Scraping`] when
+    /// [`FetchOptions::source_fallback`] is enabled.
+    #[serde(default)]
+    pub source: Source,
+}
+
+impl Product {
+    /// Build a product from product api data, e.g. when the product page can't be scraped.
+    ///
+    /// The api doesn't provide sale count, review count, favorite count, reviewer genres,
+    /// product format nor DRM notes, so these are left empty.
+    pub fn from_api(api: ProductApiContent) -> Result<Self> {
+        let released_at = api
+            .regist_date
+            .as_deref()
+            .and_then(|date| date.get(..10))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .to_parse_error("Failed to parse release date")?;
+        let rating_distribution = api.rating_distribution();
+        let translation_permission = api.translation_permission();
+        let images = std::iter::once(&api.image_main)
+            .chain(api.image_samples.iter().flatten())
+            .map(|file| match file.url.strip_prefix("//") {
+                Some(url) => format!("https://{}", url),
+                None => file.url.clone(),
+            })
+            .collect();
+
+        Ok(Product {
+            id: api.workno.clone(),
+            site: api.site,
+            title: api.work_name.clone(),
+            work_type: api.work_type.clone(),
+            released_at,
+            age_rating: Some(api.age_category.clone()),
+            genre: api
+                .genres
+                .iter()
+                .map(|g| Genre {
+                    name: g.name.clone(),
+                    id: g.id.to_string(),
+                })
+                .collect(),
+            circle_id: api.maker_id.clone(),
+            circle_name: api.maker_name.clone(),
+            price: api.price as i32,
+            series: api.series_name.clone(),
+            sale_count: None,
+            review_count: None,
+            favorite_count: None,
+            rating: (rating_distribution.total() > 0).then(|| api.rate_average_star as f32 / 10.0),
+            rate_count: Some(rating_distribution.total() as i32),
+            rating_distribution,
+            translation_permission,
+            images,
+            people: ProductPeople::from_api(api.creators.as_ref()),
+            reviewer_genre: vec![],
+            user_tags: vec![],
+            file_format: api.file_type_string.clone().into_iter().collect(),
+            file_size: api.file_size.clone(),
+            file_info: api.file_info(),
+            product_format: vec![],
+            platforms: api.platforms(),
+            drm: DrmInfo::default(),
+            source: Source::Api,
+        })
+    }
+
+    /// Build a product from ajax api data, when only the fields it provides are needed (see
+    /// [`FetchOptions::fields`]).
+    ///
+    /// The ajax api doesn't provide the release date, circle name, genres, images, people,
+    /// file information nor DRM notes, so these are left empty (the release date is
+    /// `1970-01-01`).
+    pub fn from_ajax(id: &str, site: Site, ajax: ProductAjax) -> Self {
+        let rating_distribution = ajax.rating_distribution();
+        let translation_permission = ajax.translation_permission();
+
+        Product {
+            id: id.to_string(),
+            site,
+            title: ajax.work_name,
+            work_type: ajax.work_type,
+            released_at: NaiveDate::default(),
+            age_rating: None,
+            genre: vec![],
+            circle_id: ajax.maker_id,
+            circle_name: String::new(),
+            price: ajax.price,
+            series: None,
+            sale_count: ajax.dl_count,
+            review_count: ajax.review_count,
+            favorite_count: Some(ajax.wishlist_count),
+            rating: ajax.rate_average_2dp,
+            rate_count: ajax.rate_count,
+            rating_distribution,
+            translation_permission,
+            images: vec![],
+            people: ProductPeople::from_api(None),
+            reviewer_genre: vec![],
+            user_tags: vec![],
+            file_format: vec![],
+            file_size: None,
+            file_info: FileInfo::default(),
+            product_format: vec![],
+            platforms: Platforms::default(),
+            drm: DrmInfo::default(),
+            source: Source::Ajax,
+        }
+    }
 }
 
 /// People who contributed to a product on DLsite.
@@ -81,6 +229,30 @@ pub struct ProductPeople {
     pub voice_actor: Option<Vec<String>>,
 }
 
+impl ProductPeople {
+    pub(crate) fn from_api(creators: Option<&Creators>) -> Self {
+        fn names(creators: Option<&Vec<Creator>>) -> Option<Vec<String>> {
+            creators
+                .filter(|c| !c.is_empty())
+                .map(|c| c.iter().map(|c| c.name.clone()).collect())
+        }
+
+        ProductPeople {
+            author: names(creators.and_then(|c| c.created_by.as_ref())),
+            scenario: names(creators.and_then(|c| c.scenario_by.as_ref())),
+            illustrator: names(creators.and_then(|c| c.illust_by.as_ref())),
+            voice_actor: names(creators.and_then(|c| c.voice_by.as_ref())),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.author.is_none()
+            && self.scenario.is_none()
+            && self.illustrator.is_none()
+            && self.voice_actor.is_none()
+    }
+}
+
 impl<'a> ProductClient<'a> {
     /// Get full information about a product. For more detail, see documentation of [`ProductClient`].
     ///
@@ -88,8 +260,8 @@ impl<'a> ProductClient<'a> {
     /// * `product_id` - The product ID to get information about. Example: `RJ123456`. NOTE: This must be capitalized.
     ///
     /// # Example
-    /// ```
-    /// use dlsite::DlsiteClient;
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
     /// #[tokio::main]
     /// async fn main() {
     ///     let client = DlsiteClient::default();
@@ -97,15 +269,95 @@ impl<'a> ProductClient<'a> {
     ///     println!("{:#?}", product);
     /// }
     /// ```
-    pub async fn get_all(&self, product_id: &str) -> Result<Product> {
+    pub async fn get_all(&self, product_id: impl Into<ProductId>) -> Result<Product> {
+        self.get_all_with(product_id, &FetchOptions::default())
+            .await
+    }
+
+    /// Same as [`ProductClient::get_all`], with per-call options.
+    ///
+    /// With [`FetchOptions::cross_site_fallback`] enabled, the other storefronts are tried when
+    /// the product doesn't exist on the one it was routed to.
+    ///
+    /// With [`FetchOptions::source_fallback`] enabled, the product api is used when the product
+    /// page can't be scraped.
+    ///
+    /// With [`FetchOptions::fields`] set, the product is got from the cheapest source
+    /// providing these fields.
+    pub async fn get_all_with(
+        &self,
+        product_id: impl Into<ProductId>,
+        options: &FetchOptions,
+    ) -> Result<Product> {
+        let product_id = product_id.into().checked()?;
+        let mut product = self.get_all_on_sites(product_id.as_str(), options).await?;
+        self.c.normalize_title(&mut product.title);
+        Ok(product)
+    }
+
+    /// Try the storefronts allowed by `options` until the product is found.
+    async fn get_all_on_sites(&self, product_id: &str, options: &FetchOptions) -> Result<Product> {
+        for site in options.sites(self.site_for(product_id)) {
+            let result = match options.fields {
+                Some(fields) => self.get_fields_on(product_id, site, fields).await,
+                None => self.get_all_on(product_id, site).await,
+            };
+            match result {
+                Err(e) if e.is_not_found() => {
+                    tracing::debug!("{product_id} not found on {site}");
+                    continue;
+                }
+                Err(e) if options.source_fallback && e.is_source_failure() => {
+                    tracing::warn!("Failed to scrape {product_id}, falling back to api: {e}");
+                    let api = self.c.product_api().get_on(product_id, site, None).await?;
+                    return Product::from_api(api);
+                }
+                result => return result,
+            }
+        }
+        Err(DlsiteError::NotFound(product_id.to_string()))
+    }
+
+    /// Get a product from the cheapest source providing `fields`.
+    async fn get_fields_on(
+        &self,
+        product_id: &str,
+        site: Site,
+        fields: ProductFields,
+    ) -> Result<Product> {
+        let source = fields.cheapest_source(|source| {
+            let path = match source {
+                Source::Ajax => ajax_path(product_id),
+                Source::Api => api_path(product_id),
+                Source::Scraping => html_path(product_id),
+            };
+            self.c.is_cached_on(site, &path)
+        });
+        match source {
+            Source::Ajax => Ok(Product::from_ajax(
+                product_id,
+                site,
+                self.get_ajax_on(product_id, site).await?,
+            )),
+            Source::Api => {
+                Product::from_api(self.c.product_api().get_on(product_id, site, None).await?)
+            }
+            Source::Scraping => self.get_all_on(product_id, site).await,
+        }
+    }
+
+    pub(crate) async fn get_all_on(&self, product_id: &str, site: Site) -> Result<Product> {
         let (html_data, ajax_data, review_data) = tokio::try_join!(
-            self.get_html(product_id),
-            self.get_ajax(product_id),
-            self.get_review(product_id, 6, 1, true, review::ReviewSortOrder::New)
+            self.get_html_on(product_id, site),
+            self.get_ajax_on(product_id, site),
+            self.get_review_on(site, product_id, 6, 1, true, review::ReviewSortOrder::New)
         )?;
+        let rating_distribution = ajax_data.rating_distribution();
+        let translation_permission = ajax_data.translation_permission();
 
-        Ok(Product {
+        let product = Product {
             id: product_id.to_string(),
+            site,
             title: ajax_data.work_name,
             work_type: ajax_data.work_type,
             released_at: html_data.released_at,
@@ -117,36 +369,168 @@ impl<'a> ProductClient<'a> {
             price: ajax_data.price,
             rating: ajax_data.rate_average_2dp,
             rate_count: ajax_data.rate_count,
+            rating_distribution,
+            translation_permission,
             sale_count: ajax_data.dl_count,
             review_count: ajax_data.review_count,
+            favorite_count: Some(ajax_data.wishlist_count),
             images: html_data.images,
             people: html_data.people,
             reviewer_genre: review_data.reviewer_genre_list.unwrap_or_default(),
+            user_tags: html_data.user_tags,
             file_format: html_data.file_format,
             file_size: html_data.file_size,
+            file_info: html_data.file_info,
             product_format: html_data.product_format,
-        })
+            platforms: html_data.platforms,
+            drm: html_data.drm,
+            source: Source::Scraping,
+        };
+
+        #[cfg(feature = "tantivy")]
+        self.c.index_work((&product).into());
+
+        Ok(product)
+    }
+
+    /// Get the title, description and genres of a product in both Japanese and English.
+    ///
+    /// Both locales are fetched concurrently from the product api. If only the English
+    /// request fails, English fields are left empty.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let product = client.product().get_bilingual("RJ403038").await.unwrap();
+    ///     println!("{} / {:?}", product.title_ja, product.title_en);
+    /// }
+    /// ```
+    pub async fn get_bilingual(
+        &self,
+        product_id: impl Into<ProductId>,
+    ) -> Result<BilingualProduct> {
+        let product_id = product_id.into().checked()?;
+        let api = self.c.product_api();
+        let (ja, en) = tokio::join!(
+            api.get_localized(&product_id, "ja_JP"),
+            api.get_localized(&product_id, "en_US")
+        );
+        let ja = ja?;
+        let en = en
+            .inspect_err(|e| tracing::warn!("Failed to get english version of {product_id}: {e}"))
+            .ok();
+
+        Ok(BilingualProduct::merge(&ja, en.as_ref()))
+    }
+
+    /// Get a product from both the product page and the product api, merged into one
+    /// [`UnifiedProduct`] recording where each field comes from.
+    ///
+    /// Both sources are fetched concurrently, and both must succeed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let product = client.product().get_unified("RJ403038").await.unwrap();
+    ///     println!("{} ({:?})", product.price.value, product.price.source);
+    /// }
+    /// ```
+    pub async fn get_unified(&self, product_id: impl Into<ProductId>) -> Result<UnifiedProduct> {
+        let product_id = product_id.into().checked()?;
+        let product_api = self.c.product_api();
+        let (html, api) =
+            tokio::try_join!(self.get_html(&product_id), product_api.get(&product_id))?;
+
+        Ok(UnifiedProduct::merge(product_id.as_str(), html, api))
+    }
+
+    /// Storefront to fetch the product from.
+    ///
+    /// Products whose ID prefix implies another storefront (e.g. `VJ` → pro, `BJ` → books) are
+    /// routed there, otherwise the client's site is used.
+    pub fn site_for(&self, product_id: impl Into<ProductId>) -> Site {
+        product_id.into().site().unwrap_or_else(|| self.c.site())
     }
 
     /// Scrapes the HTML page of a product and parses it.
-    #[tracing::instrument(err)]
-    pub async fn get_html(&self, product_id: &str) -> Result<html::ProductHtml> {
-        let path = format!("/work/=/product_id/{}", product_id);
-        let html = self.c.get(&path).await?;
-        let html = scraper::Html::parse_document(&html);
+    #[tracing::instrument(err, skip_all, fields(product_id))]
+    pub async fn get_html(&self, product_id: impl Into<ProductId>) -> Result<html::ProductHtml> {
+        let product_id = product_id.into().checked()?;
+        tracing::Span::current().record("product_id", product_id.as_str());
+        self.get_html_on(product_id.as_str(), self.site_for(&product_id))
+            .await
+    }
+
+    async fn get_html_on(&self, product_id: &str, site: Site) -> Result<html::ProductHtml> {
+        let path = html_path(product_id);
+        let body = self.c.get_on(site, &path).await?;
+        let html = scraper::Html::parse_document(&body);
+
+        self.c
+            .dump_parse_error(site, &path, &body, html::parse_product_html(&html))
+    }
 
-        html::parse_product_html(&html)
+    /// Full-size sample images of a product, from the slider of its page, with the chobit
+    /// sample player if the page embeds one.
+    ///
+    /// Only the HTML page is fetched, so it is cheaper than [`ProductClient::get_all`] when
+    /// only previews are needed (e.g. to prefetch a gallery).
+    pub async fn get_sample_images(
+        &self,
+        product_id: impl Into<ProductId>,
+    ) -> Result<html::SampleImages> {
+        let product_id = product_id.into().checked()?;
+        let path = html_path(product_id.as_str());
+        let body = self.c.get_on(self.site_for(&product_id), &path).await?;
+        let html = scraper::Html::parse_document(&body);
+        Ok(html::parse_sample_images(&html))
+    }
+
+    /// Track list of a voice/ASMR work, parsed from the description of its page.
+    ///
+    /// Empty if the circle didn't write one, or wrote it in a way which couldn't be
+    /// recognized (e.g. as an image).
+    pub async fn get_tracklist(
+        &self,
+        product_id: impl Into<ProductId>,
+    ) -> Result<Vec<tracklist::Track>> {
+        let product_id = product_id.into().checked()?;
+        let path = html_path(product_id.as_str());
+        let body = self.c.get_on(self.site_for(&product_id), &path).await?;
+        let html = scraper::Html::parse_document(&body);
+        Ok(tracklist::parse_tracklist(&html))
     }
 
     /// Fetch detailed product information using 'ajax api'.
-    pub async fn get_ajax(&self, product_id: &str) -> Result<ProductAjax> {
-        let path = format!("/product/info/ajax?product_id={}", product_id);
-        let ajax_json_str = self.c.get(&path).await?;
+    pub async fn get_ajax(&self, product_id: impl Into<ProductId>) -> Result<ProductAjax> {
+        let product_id = product_id.into().checked()?;
+        self.get_ajax_on(product_id.as_str(), self.site_for(&product_id))
+            .await
+    }
+
+    pub(crate) async fn get_ajax_on(&self, product_id: &str, site: Site) -> Result<ProductAjax> {
+        let path = ajax_path(product_id);
+        let ajax_json_str = self.c.get_on(site, &path).await?;
+        // Unknown products are returned as an empty array
+        if ajax_json_str.trim() == "[]" {
+            return Err(DlsiteError::NotFound(product_id.to_string()));
+        }
 
-        let mut json: HashMap<String, ProductAjax> = serde_json::from_str(&ajax_json_str)?;
+        let mut json: HashMap<String, ProductAjax> = self.c.dump_parse_error(
+            site,
+            &path,
+            &ajax_json_str,
+            serde_json::from_str(&ajax_json_str).map_err(Into::into),
+        )?;
         let product = json
             .remove(product_id)
-            .ok_or_else(|| DlsiteError::Parse("Failed to parse ajax json".to_string()))?;
+            .ok_or_else(|| DlsiteError::NotFound(product_id.to_string()))?;
 
         Ok(product)
     }
@@ -154,11 +538,15 @@ impl<'a> ProductClient<'a> {
     /// Fetch detailed multiple products information using 'ajax api'.
     ///
     /// It is more efficient to use this method than calling `get_ajax` multiple times.
-    #[tracing::instrument(err)]
+    #[tracing::instrument(err, skip_all)]
     pub async fn get_ajax_multiple(
         &self,
-        product_ids: Vec<&str>,
+        product_ids: impl IntoIterator<Item = impl Into<ProductId>>,
     ) -> Result<HashMap<String, ProductAjax>> {
+        let product_ids = product_ids
+            .into_iter()
+            .map(|id| id.into().checked().map(String::from))
+            .collect::<Result<Vec<_>>>()?;
         let path = format!("/product/info/ajax?product_id={}", product_ids.join(","));
         let ajax_json_str = self.c.get(&path).await?;
 
@@ -181,6 +569,27 @@ impl<'a> ProductClient<'a> {
     #[tracing::instrument(err, skip_all)]
     pub async fn get_review(
         &self,
+        product_id: impl Into<ProductId>,
+        limit: u32,
+        page: u32,
+        mix_pickup: bool,
+        order: review::ReviewSortOrder,
+    ) -> Result<review::ProductReview> {
+        let product_id = product_id.into().checked()?;
+        self.get_review_on(
+            self.site_for(&product_id),
+            product_id.as_str(),
+            limit,
+            page,
+            mix_pickup,
+            order,
+        )
+        .await
+    }
+
+    async fn get_review_on(
+        &self,
+        site: Site,
         product_id: &str,
         limit: u32,
         page: u32,
@@ -196,7 +605,7 @@ impl<'a> ProductClient<'a> {
             "/api/review?product_id={}&limit={}&mix_pickup={}&page={}&order={}&locale=ja_JP",
             product_id, limit, mix_pickup, page, order_str
         );
-        let json_str = self.c.get(&path).await?;
+        let json_str = self.c.get_on(site, &path).await?;
         let json: serde_json::Value = serde_json::from_str(&json_str)?;
 
         if !json["is_success"]
@@ -215,4 +624,44 @@ impl<'a> ProductClient<'a> {
         let json: review::ProductReview = serde_json::from_value(json)?;
         Ok(json)
     }
+
+    /// List all reviews of a product page by page, see [`Paginated`].
+    ///
+    /// # Arguments
+    /// * `product_id` - Product ID.
+    /// * `per_page` - Number of reviews per request.
+    /// * `order` - Sort order of reviews.
+    pub fn reviews(
+        &self,
+        product_id: impl Into<ProductId>,
+        per_page: u32,
+        order: review::ReviewSortOrder,
+    ) -> Paginated<'a, review::Review> {
+        let client = self.clone();
+        let product_id = product_id.into();
+        Paginated::new(1, Some(per_page), move |page| {
+            let client = client.clone();
+            let product_id = product_id.clone();
+            async move {
+                let reviews = client
+                    .get_review(&product_id, per_page, page, false, order)
+                    .await?;
+                Result::Ok(Page {
+                    items: reviews.review_list,
+                    total: None,
+                })
+            }
+            .maybe_boxed()
+        })
+    }
+}
+
+/// Path of the product page.
+pub(crate) fn html_path(product_id: &str) -> String {
+    format!("/work/=/product_id/{}", product_id)
+}
+
+/// Path of the ajax api for a single product.
+fn ajax_path(product_id: &str) -> String {
+    format!("/product/info/ajax?product_id={}", product_id)
 }
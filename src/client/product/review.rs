@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use serde::{Deserialize, Deserializer};
+use chrono::{Datelike as _, NaiveDate};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::interface::genre::Genre;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReviewSortOrder {
     New,
     Top,
@@ -47,6 +49,144 @@ pub struct Review {
     pub genre: Vec<Genre>,
 }
 
+impl Review {
+    /// Rating given by the reviewer (1 to 5), if any.
+    pub fn rating(&self) -> Option<u8> {
+        self.rate
+            .as_deref()
+            .and_then(|r| r.trim().parse().ok())
+            .filter(|r| (1..=5).contains(r))
+    }
+
+    /// Date the review was posted.
+    pub fn date(&self) -> Option<NaiveDate> {
+        let date = self.regist_date.get(..10)?;
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+    }
+}
+
+/// Windows (in days) for which [`ReviewStats::recent`] is computed.
+const RECENCY_WINDOWS: [u32; 3] = [30, 90, 365];
+
+/// Average rating of the reviews posted in the last `days` days.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowAverage {
+    pub days: u32,
+    /// Number of rated reviews in the window
+    pub count: usize,
+    pub average: Option<f32>,
+}
+
+/// Average rating of the reviews posted in a month.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthlyRating {
+    pub year: i32,
+    pub month: u32,
+    pub count: usize,
+    pub average: f32,
+}
+
+/// Statistics over a set of reviews.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewStats {
+    /// Number of reviews
+    pub count: usize,
+    /// Number of reviews with a rating
+    pub rated_count: usize,
+    /// Average rating of all rated reviews
+    pub average: Option<f32>,
+    /// Average rating over the last 30, 90 and 365 days
+    pub recent: Vec<WindowAverage>,
+    /// Average rating per month, oldest first
+    pub trend: Vec<MonthlyRating>,
+    /// Genres tagged by reviewers, most used first
+    pub top_tags: Vec<(Genre, usize)>,
+}
+
+impl ReviewStats {
+    /// Compute statistics over reviews, e.g. [`ProductReview::review_list`]. Recency windows
+    /// end today.
+    pub fn aggregate<'a>(reviews: impl IntoIterator<Item = &'a Review>) -> Self {
+        Self::aggregate_at(reviews, chrono::Local::now().date_naive())
+    }
+
+    /// Same as [`ReviewStats::aggregate`], with recency windows ending at `today`.
+    pub fn aggregate_at<'a>(
+        reviews: impl IntoIterator<Item = &'a Review>,
+        today: NaiveDate,
+    ) -> Self {
+        let mut count = 0;
+        let mut ratings = vec![];
+        let mut months: BTreeMap<(i32, u32), (usize, u32)> = BTreeMap::new();
+        let mut tags: HashMap<String, (Genre, usize)> = HashMap::new();
+
+        for review in reviews {
+            count += 1;
+            for genre in &review.genre {
+                tags.entry(genre.id.clone())
+                    .or_insert_with(|| (genre.clone(), 0))
+                    .1 += 1;
+            }
+            let Some(rating) = review.rating() else {
+                continue;
+            };
+            let date = review.date();
+            ratings.push((rating, date));
+            if let Some(date) = date {
+                let month = months.entry((date.year(), date.month())).or_default();
+                month.0 += 1;
+                month.1 += u32::from(rating);
+            }
+        }
+
+        let recent = RECENCY_WINDOWS
+            .iter()
+            .map(|&days| {
+                let window: Vec<u8> = ratings
+                    .iter()
+                    .filter(|(_, date)| {
+                        date.is_some_and(|d| d <= today && (today - d).num_days() < i64::from(days))
+                    })
+                    .map(|(rating, _)| *rating)
+                    .collect();
+                WindowAverage {
+                    days,
+                    count: window.len(),
+                    average: average(&window),
+                }
+            })
+            .collect();
+
+        let trend = months
+            .into_iter()
+            .map(|((year, month), (count, sum))| MonthlyRating {
+                year,
+                month,
+                count,
+                average: sum as f32 / count as f32,
+            })
+            .collect();
+
+        let mut top_tags: Vec<_> = tags.into_values().collect();
+        top_tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+
+        let ratings: Vec<u8> = ratings.into_iter().map(|(rating, _)| rating).collect();
+        Self {
+            count,
+            rated_count: ratings.len(),
+            average: average(&ratings),
+            recent,
+            trend,
+            top_tags,
+        }
+    }
+}
+
+fn average(ratings: &[u8]) -> Option<f32> {
+    (!ratings.is_empty())
+        .then(|| ratings.iter().map(|&r| f32::from(r)).sum::<f32>() / ratings.len() as f32)
+}
+
 fn deserialize_genre<'de, D>(deserializer: D) -> std::result::Result<Vec<Genre>, D::Error>
 where
     D: Deserializer<'de>,
@@ -97,3 +237,57 @@ where
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Review, ReviewStats};
+    use crate::interface::genre::Genre;
+
+    fn review(date: &str, rate: Option<&str>, tags: &[&str]) -> Review {
+        Review {
+            regist_date: format!("{date} 12:00:00"),
+            rate: rate.map(|r| r.to_string()),
+            genre: tags
+                .iter()
+                .map(|t| Genre {
+                    name: t.to_string(),
+                    id: t.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aggregate_reviews() {
+        let reviews = [
+            review("2024-01-10", Some("2"), &["癒し"]),
+            review("2024-01-20", Some("4"), &["癒し", "ASMR"]),
+            review("2024-05-25", Some("5"), &["ASMR"]),
+            review("2024-06-01", None, &["ASMR"]),
+        ];
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let stats = ReviewStats::aggregate_at(&reviews, today);
+
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.rated_count, 3);
+        assert_eq!(stats.average, Some(11.0 / 3.0));
+
+        assert_eq!((stats.recent[0].days, stats.recent[0].count), (30, 1));
+        assert_eq!(stats.recent[0].average, Some(5.0));
+        assert_eq!(stats.recent[2].average, Some(11.0 / 3.0));
+
+        let trend: Vec<_> = stats
+            .trend
+            .iter()
+            .map(|m| (m.year, m.month, m.count, m.average))
+            .collect();
+        assert_eq!(trend, vec![(2024, 1, 2, 3.0), (2024, 5, 1, 5.0)]);
+
+        assert_eq!(stats.top_tags[0].0.name, "ASMR");
+        assert_eq!(stats.top_tags[0].1, 3);
+        assert_eq!(stats.top_tags[1].1, 2);
+    }
+}
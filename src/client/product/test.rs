@@ -80,3 +80,107 @@ async fn get_product_success(id: &str) {
     let client = DlsiteClient::default();
     client.product().get_all(id).await.unwrap();
 }
+
+#[tokio::test]
+async fn get_bilingual() {
+    let client = DlsiteClient::default();
+    let res = client.product().get_bilingual("RJ403038").await.unwrap();
+
+    assert_eq!(res.id, "RJ403038");
+    assert_eq!(
+        res.title_ja,
+        "【ブルーアーカイブ】ユウカASMR～頑張るあなたのすぐそばに～"
+    );
+    assert!(res.title_en.is_some());
+    let asmr = res.genres.iter().find(|g| g.id == "497").unwrap();
+    assert_eq!(asmr.name_ja, "ASMR");
+    assert!(asmr.name_en.is_some());
+}
+
+#[tokio::test]
+async fn get_unified() {
+    use super::unified::Source;
+
+    let client = DlsiteClient::default();
+    let res = client.unified_product("RJ403038").await.unwrap();
+
+    assert_eq!(res.circle_id.value, "RG62982");
+    assert_eq!(res.price.source, Source::Api);
+    assert_eq!(res.description_html.source, Source::Scraping);
+    assert_eq!(
+        res.people.value.voice_actor,
+        Some(vec!["春花らん".to_string()])
+    );
+    assert!(res.conflicts.is_empty());
+}
+
+#[tokio::test]
+async fn get_translation_permission() {
+    let client = DlsiteClient::default();
+    let res = client.product().get_all("RJ01017217").await.unwrap();
+    let api = client.product_api().get("RJ01017217").await.unwrap();
+
+    let permission = res.translation_permission;
+    assert!(!permission.is_translation);
+    assert_eq!(permission.allowed, api.translation_permission().allowed);
+    assert_eq!(permission.languages, api.translation_permission().languages);
+}
+
+#[tokio::test]
+async fn get_selected_fields() {
+    use crate::{client::product::unified::Source, client::ProductFields, FetchOptions};
+
+    let client = DlsiteClient::default();
+    let options = FetchOptions::new().fields(ProductFields::PRICE | ProductFields::RATING);
+    let res = client
+        .product()
+        .get_all_with("RJ403038", &options)
+        .await
+        .unwrap();
+    assert_eq!(res.source, Source::Ajax);
+    assert!(res.price > 0);
+    assert!(res.rating.is_some());
+
+    let options = options.fields(ProductFields::PRICE | ProductFields::GENRE);
+    let res = client
+        .product()
+        .get_all_with("RJ403038", &options)
+        .await
+        .unwrap();
+    assert_eq!(res.source, Source::Api);
+    assert!(!res.genre.is_empty());
+}
+
+#[tokio::test]
+async fn get_file_info() {
+    use crate::interface::product::FileFormat;
+
+    let client = DlsiteClient::default();
+    let res = client.product().get_all("RJ403038").await.unwrap();
+
+    assert!(res.file_info.total_size_bytes.unwrap() > 100 * 1024 * 1024);
+    assert!(res.file_info.formats.contains(&FileFormat::Wav));
+}
+
+#[tokio::test]
+async fn get_sample_images() {
+    let client = DlsiteClient::default();
+    let res = client
+        .product()
+        .get_sample_images("RJ403038")
+        .await
+        .unwrap();
+
+    assert!(!res.images.is_empty());
+    assert!(res
+        .images
+        .iter()
+        .all(|url| url.starts_with("https://img.dlsite.jp/") && !url.contains("100x100")));
+}
+
+#[tokio::test]
+async fn invalid_product_id() {
+    let client = DlsiteClient::default();
+    let err = client.product().get_all("RJ4030381").await.unwrap_err();
+    assert!(matches!(err, crate::DlsiteError::InvalidProductId(_)));
+}
@@ -0,0 +1,188 @@
+//! Track lists of voice works, see [`super::ProductClient::get_tracklist`].
+
+use std::{sync::OnceLock, time::Duration};
+
+use regex::Regex;
+use scraper::{Html, Selector};
+
+/// A track of a voice/ASMR work.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Track {
+    /// Track number as written by the circle (usually 1-based)
+    pub index: u32,
+    pub title: String,
+    pub duration: Option<Duration>,
+}
+
+/// Parse the track list from the description of a product page.
+///
+/// Circles write track lists freely, either as a table or as lines like
+/// `01. おかえりなさい (12:34)` or `トラック2「耳かき」 15分20秒`. A line counts as a track if
+/// it has a track keyword, a duration or a zero-padded number, and at least two tracks with
+/// increasing numbers must be found. Returns an empty list otherwise.
+pub(crate) fn parse_tracklist(html: &Html) -> Vec<Track> {
+    let container = Selector::parse("[itemprop='description'], .work_parts_container").unwrap();
+    let row = Selector::parse("tr").unwrap();
+    let cell = Selector::parse("th, td").unwrap();
+
+    let mut lines: Vec<String> = vec![];
+    for element in html.select(&container) {
+        // Table rows become one line each
+        for tr in element.select(&row) {
+            let cells: Vec<String> = tr
+                .select(&cell)
+                .map(|c| c.text().collect::<String>().trim().to_string())
+                .collect();
+            lines.push(cells.join(" "));
+        }
+        for text in element.text() {
+            lines.extend(text.lines().map(|l| l.trim().to_string()));
+        }
+    }
+
+    let mut tracks: Vec<Track> = vec![];
+    for line in lines {
+        let Some(track) = parse_track(&line) else {
+            continue;
+        };
+        // Table rows are also found as text nodes
+        if tracks.iter().any(|t| t.index == track.index) {
+            continue;
+        }
+        if tracks.last().is_some_and(|last| track.index <= last.index) {
+            continue;
+        }
+        tracks.push(track);
+    }
+    if tracks.len() < 2 {
+        return vec![];
+    }
+    tracks
+}
+
+fn parse_track(line: &str) -> Option<Track> {
+    static TRACK: OnceLock<Regex> = OnceLock::new();
+    let track = TRACK.get_or_init(|| {
+        Regex::new(
+            r"(?i)^(?:(?P<keyword>track|tr\.?|トラック|ﾄﾗｯｸ)\s*)?(?P<index>\d{1,3})[.:：、)）\]】\-\s]*(?P<rest>[^\d:：].*)$",
+        )
+        .unwrap()
+    });
+    let captures = track.captures(line.trim())?;
+    let index_str = &captures["index"];
+    let (title, duration) = split_duration(&captures["rest"]);
+    let title = title
+        .trim()
+        .trim_matches(['「', '」', '『', '』', '【', '】'])
+        .trim()
+        .to_string();
+    if title.is_empty() {
+        return None;
+    }
+    let looks_like_track = captures.name("keyword").is_some()
+        || duration.is_some()
+        || (index_str.len() > 1 && index_str.starts_with('0'));
+    if !looks_like_track {
+        return None;
+    }
+
+    Some(Track {
+        index: index_str.parse().ok()?,
+        title,
+        duration,
+    })
+}
+
+/// Split a trailing duration (`12:34`, `1:02:03`, `(12分34秒)`, `約15分`) from a title.
+fn split_duration(text: &str) -> (&str, Option<Duration>) {
+    static DURATION: OnceLock<Regex> = OnceLock::new();
+    let duration = DURATION.get_or_init(|| {
+        Regex::new(
+            r"[\s(（\[【/／]*(?:約)?(?:(?P<h>\d{1,2}):(?P<hm>\d{2}):(?P<hs>\d{2})|(?P<m>\d{1,3}):(?P<s>\d{2})|(?P<jm>\d{1,3})分(?:(?P<js>\d{1,2})秒)?|(?P<only_s>\d{1,2})秒)[)）\]】]*\s*$",
+        )
+        .unwrap()
+    });
+    let Some(captures) = duration.captures(text) else {
+        return (text, None);
+    };
+    let get = |name: &str| {
+        captures
+            .name(name)
+            .and_then(|m| m.as_str().parse::<u64>().ok())
+    };
+    let secs = if let Some(h) = get("h") {
+        h * 3600 + get("hm").unwrap_or(0) * 60 + get("hs").unwrap_or(0)
+    } else if let Some(m) = get("m") {
+        m * 60 + get("s").unwrap_or(0)
+    } else if let Some(m) = get("jm") {
+        m * 60 + get("js").unwrap_or(0)
+    } else {
+        get("only_s").unwrap_or(0)
+    };
+    let start = captures.get(0).unwrap().start();
+    (&text[..start], Some(Duration::from_secs(secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use scraper::Html;
+
+    use super::{parse_tracklist, Track};
+
+    fn track(index: u32, title: &str, secs: Option<u64>) -> Track {
+        Track {
+            index,
+            title: title.to_string(),
+            duration: secs.map(Duration::from_secs),
+        }
+    }
+
+    #[test]
+    fn description_lines() {
+        let html = Html::parse_document(
+            r#"<div itemprop="description">
+                ■トラックリスト<br>
+                01. おかえりなさい (12:34)<br>
+                02.「耳かき」 15分20秒<br>
+                03 添い寝 1:02:03<br>
+                特典: 1. 壁紙<br>
+                合計 約30分
+            </div>"#,
+        );
+        assert_eq!(
+            parse_tracklist(&html),
+            vec![
+                track(1, "おかえりなさい", Some(754)),
+                track(2, "耳かき", Some(920)),
+                track(3, "添い寝", Some(3723)),
+            ]
+        );
+    }
+
+    #[test]
+    fn table() {
+        let html = Html::parse_document(
+            r#"<div class="work_parts_container"><table>
+                <tr><th>トラック</th><th>タイトル</th><th>時間</th></tr>
+                <tr><td>Track1</td><td>導入</td><td>3:05</td></tr>
+                <tr><td>Track2</td><td>マッサージ</td><td>20:00</td></tr>
+            </table></div>"#,
+        );
+        assert_eq!(
+            parse_tracklist(&html),
+            vec![
+                track(1, "導入", Some(185)),
+                track(2, "マッサージ", Some(1200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_tracklist() {
+        let html =
+            Html::parse_document(r#"<div itemprop="description">1. 高音質<br>2. 長時間</div>"#);
+        assert!(parse_tracklist(&html).is_empty());
+    }
+}
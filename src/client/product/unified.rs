@@ -0,0 +1,195 @@
+//! Product data merged from the scraped page and the product api.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::{html::ProductHtml, ProductPeople};
+use crate::{
+    client::product_api::interface::ProductApiContent,
+    interface::{
+        genre::Genre,
+        product::{AgeCategory, Platforms, WorkType},
+        site::Site,
+    },
+};
+
+/// Where the value of a field comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// The ajax api (see [`super::ProductClient::get_ajax`]), used when only the fields it
+    /// provides were requested with [`crate::FetchOptions::fields`]
+    Ajax,
+    /// The product api (see [`crate::client::product_api::ProductApiClient`])
+    Api,
+    /// The product page (see [`super::ProductClient::get_html`])
+    #[default]
+    Scraping,
+}
+
+/// A field value with its provenance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Sourced<T> {
+    fn api(value: T) -> Self {
+        Self {
+            value,
+            source: Source::Api,
+        }
+    }
+
+    fn scraped(value: T) -> Self {
+        Self {
+            value,
+            source: Source::Scraping,
+        }
+    }
+}
+
+/// Rankings of a product, as returned by the product api.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductRanks {
+    pub day: Option<i64>,
+    pub week: Option<i64>,
+    pub month: Option<i64>,
+    pub year: Option<i64>,
+    pub total: Option<i64>,
+}
+
+/// A field for which both sources returned different values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conflict {
+    pub field: String,
+    pub api: String,
+    pub scraped: String,
+}
+
+/// A product with data from both the product page and the product api.
+///
+/// Prices, ranks and other structured data are taken from the api, descriptions, credits,
+/// genres and images from the page. When the preferred source lacks a value, the other one is
+/// used; `source` of each field tells which one it was. Disagreements between the two sources
+/// are listed in `conflicts`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnifiedProduct {
+    pub id: String,
+    pub site: Site,
+    pub title: Sourced<String>,
+    pub work_type: Sourced<WorkType>,
+    pub age_rating: Sourced<AgeCategory>,
+    pub released_at: Sourced<NaiveDate>,
+    pub circle_id: Sourced<String>,
+    pub circle_name: Sourced<String>,
+    pub series: Sourced<Option<String>>,
+    /// Current price, discount included
+    pub price: Sourced<i32>,
+    /// Price without discount
+    pub official_price: Sourced<i32>,
+    pub discount_rate: Sourced<Option<i32>>,
+    pub ranks: Sourced<ProductRanks>,
+    pub description_html: Sourced<Option<String>>,
+    pub people: Sourced<ProductPeople>,
+    pub genre: Sourced<Vec<Genre>>,
+    pub images: Sourced<Vec<String>>,
+    pub file_size: Sourced<Option<String>>,
+    pub platforms: Sourced<Platforms>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl UnifiedProduct {
+    pub(crate) fn merge(id: &str, html: ProductHtml, api: ProductApiContent) -> Self {
+        let mut conflicts = vec![];
+        let mut check = |field: &str, api: &str, scraped: &str| {
+            if api != scraped {
+                conflicts.push(Conflict {
+                    field: field.to_string(),
+                    api: api.to_string(),
+                    scraped: scraped.to_string(),
+                });
+            }
+        };
+        check("circle_id", &api.maker_id, &html.circle_id);
+        check("circle_name", &api.maker_name, &html.circle_name);
+        if let Some(age_rating) = &html.age_rating {
+            check(
+                "age_rating",
+                &api.age_category.to_string(),
+                &age_rating.to_string(),
+            );
+        }
+
+        let people = if html.people.is_empty() {
+            Sourced::api(ProductPeople::from_api(api.creators.as_ref()))
+        } else {
+            Sourced::scraped(html.people)
+        };
+
+        let genre = if html.genre.is_empty() {
+            Sourced::api(
+                api.genres
+                    .iter()
+                    .map(|g| Genre {
+                        name: g.name.clone(),
+                        id: g.id.to_string(),
+                    })
+                    .collect(),
+            )
+        } else {
+            Sourced::scraped(html.genre)
+        };
+
+        let description_html = match html.description_html {
+            Some(description) => Sourced::scraped(Some(description)),
+            None => Sourced::api(api.intro_s.clone()),
+        };
+
+        let platforms = if html.platforms.is_empty() {
+            Sourced::api(api.platforms())
+        } else {
+            Sourced::scraped(html.platforms)
+        };
+
+        let series = match api.series_name {
+            Some(series) => Sourced::api(Some(series)),
+            None => Sourced::scraped(html.series),
+        };
+
+        let file_size = match html.file_size {
+            Some(size) => Sourced::scraped(Some(size)),
+            None => Sourced::api(api.file_size),
+        };
+
+        Self {
+            id: id.to_string(),
+            site: api.site,
+            title: Sourced::api(api.work_name),
+            work_type: Sourced::api(api.work_type),
+            age_rating: Sourced::api(api.age_category),
+            released_at: Sourced::scraped(html.released_at),
+            circle_id: Sourced::api(api.maker_id),
+            circle_name: Sourced::api(api.maker_name),
+            series,
+            price: Sourced::api(api.price as i32),
+            official_price: Sourced::api(api.official_price as i32),
+            discount_rate: Sourced::api(api.discount_rate.map(|r| r as i32)),
+            ranks: Sourced::api(ProductRanks {
+                day: api.rank_day,
+                week: api.rank_week,
+                month: api.rank_month,
+                year: api.rank_year,
+                total: api.rank_total,
+            }),
+            description_html,
+            people,
+            genre,
+            images: Sourced::scraped(html.images),
+            file_size,
+            platforms,
+            conflicts,
+        }
+    }
+}
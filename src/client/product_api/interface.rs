@@ -4,7 +4,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_with::{formats::PreferOne, serde_as, DefaultOnError, OneOrMany};
 
-use crate::interface::product::{AgeCategory, FileType, WorkCategory, WorkType};
+use crate::interface::{
+    product::{
+        AgeCategory, FileFormat, FileInfo, FileType, Platforms, RatingDistribution,
+        TranslationPermission, WorkCategory, WorkType,
+    },
+    site::Site,
+};
+use crate::utils::parse_file_size;
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
@@ -373,6 +380,111 @@ pub struct ProductApiContent {
     pub is_android_or_ios_only_work: bool,
     pub genres_replaced: Vec<GenreApi>,
     pub limit_sold_dl_count: i32,
+
+    /// Storefront the product was fetched from (not part of the response)
+    #[serde(skip)]
+    pub site: Site,
+}
+
+impl ProductApiContent {
+    /// Star rating breakdown built from `rate_count_detail`.
+    pub fn rating_distribution(&self) -> RatingDistribution {
+        RatingDistribution::from_pairs(
+            self.rate_count_detail
+                .iter()
+                .filter_map(|(star, count)| Some((star.parse().ok()?, (*count).max(0) as u32))),
+        )
+    }
+
+    /// Supported platforms built from `platform` and the `is_*_work` flags.
+    pub fn platforms(&self) -> Platforms {
+        let mut platforms = Platforms::from_labels(self.platform.iter().map(|p| p.as_str()));
+        platforms.windows |= self.is_pc_work;
+        platforms.android |= self.is_android_only_work;
+        platforms.browser |= self.is_dlsiteplay_work;
+        platforms
+    }
+
+    /// File size and formats built from `contents_file_size`, `file_type_string` and
+    /// `trials`.
+    pub fn file_info(&self) -> FileInfo {
+        let trial = self.trials.iter().flatten().next();
+        FileInfo {
+            total_size_bytes: u64::try_from(self.contents_file_size)
+                .ok()
+                .filter(|size| *size > 0)
+                .or_else(|| self.file_size.as_deref().and_then(parse_file_size)),
+            formats: self
+                .file_type_string
+                .iter()
+                .map(|f| FileFormat::from_label(f))
+                .collect(),
+            has_trial: trial.is_some(),
+            trial_size_bytes: trial.and_then(|file| {
+                let size = file.file_size.as_deref()?;
+                let unit = file.file_size_unit.as_deref().unwrap_or("B");
+                parse_file_size(&format!("{}{}", size, unit))
+            }),
+        }
+    }
+
+    /// Community translation status built from `translation_info`.
+    ///
+    /// The product api doesn't provide the royalty rate, use
+    /// [`crate::client::product::ajax::ProductAjax::translation_permission`] to get it.
+    pub fn translation_permission(&self) -> TranslationPermission {
+        let info = &self.translation_info;
+        let mut languages: Vec<String> = match &info.translation_bonus_langs {
+            Either::Left(langs) => langs.keys().cloned().collect(),
+            Either::Right(_) => vec![],
+        };
+        languages.sort();
+
+        TranslationPermission {
+            allowed: info.is_translation_agree,
+            is_translation: info.is_child,
+            languages,
+            royalty_rate: None,
+        }
+    }
+
+    /// Files contained in the work, built from `contents` and `contents_touch`.
+    pub fn content_entries(&self) -> Vec<ContentEntry> {
+        let touch = self.contents_touch.iter().flatten();
+        self.contents
+            .iter()
+            .map(|c| ContentEntry::new(c, false))
+            .chain(touch.map(|c| ContentEntry::new(c, true)))
+            .collect()
+    }
+}
+
+/// A file contained in a work, see [`super::ProductApiClient::get_contents`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentEntry {
+    pub file_name: String,
+    /// Display name, if the api returns one
+    pub title: Option<String>,
+    /// Size in bytes
+    pub size: Option<u64>,
+    /// File extension in lower case (`zip`, `mp3`, `pdf`...)
+    pub format: String,
+    /// Whether the file is the edition for touch devices
+    pub touch: bool,
+}
+
+impl ContentEntry {
+    fn new(content: &Content, touch: bool) -> Self {
+        let unit = content.file_size_unit.as_deref().unwrap_or("B");
+        let size = parse_file_size(&format!("{}{}", content.file_size, unit));
+        Self {
+            file_name: content.file_name.clone(),
+            title: content.title.clone().filter(|t| !t.is_empty()),
+            size,
+            format: content.extension.to_ascii_lowercase(),
+            touch,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -4,9 +4,24 @@ pub mod interface;
 #[cfg(test)]
 mod test;
 
-use crate::{error::Result, DlsiteClient, DlsiteError};
+use std::collections::{HashMap, HashSet};
 
-use self::interface::ProductApiContent;
+use futures::{Stream, StreamExt as _, TryStreamExt as _};
+
+use crate::{
+    client::product::Product,
+    error::Result,
+    interface::{product_id::ProductId, site::Site},
+    runtime::MaybeSend,
+    utils::decode_json_array,
+    DlsiteClient, DlsiteError, FetchOptions,
+};
+
+use self::interface::{ContentEntry, ProductApiContent};
+
+/// Maximum number of products the api returns for one request, see
+/// [`ProductApiClient::get_many`].
+const MAX_IDS_PER_REQUEST: usize = 100;
 
 /// Client to retrieve DLsite product data using 'scraping' method
 ///
@@ -29,8 +44,8 @@ impl<'a> ProductApiClient<'a> {
     /// This api does not return dl count.
     ///
     /// # Example
-    /// ```
-    /// use dlsite::DlsiteClient;
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
     ///
     /// #[tokio::main]
     /// async fn main() {
@@ -39,11 +54,276 @@ impl<'a> ProductApiClient<'a> {
     ///     assert_eq!(product.creators.unwrap().voice_by.unwrap()[0].name, "佐倉綾音");
     /// }
     /// ```
-    pub async fn get(&self, id: &str) -> Result<ProductApiContent> {
-        let json = self
-            .c
-            .get(&format!("/api/=/product.json?workno={}", id))
-            .await?;
+    pub async fn get(&self, id: impl Into<ProductId>) -> Result<ProductApiContent> {
+        self.get_with(id, &FetchOptions::default()).await
+    }
+
+    /// Same as [`ProductApiClient::get`], with per-call options.
+    ///
+    /// With [`FetchOptions::cross_site_fallback`] enabled, the other storefronts are tried when
+    /// the product doesn't exist on the one it was routed to.
+    pub async fn get_with(
+        &self,
+        id: impl Into<ProductId>,
+        options: &FetchOptions,
+    ) -> Result<ProductApiContent> {
+        let id = id.into().checked()?;
+        let site = id.site().unwrap_or_else(|| self.c.site());
+        let id = id.as_str();
+        for site in options.sites(site) {
+            match self.get_on(id, site, None).await {
+                Err(e) if e.is_not_found() => {
+                    tracing::debug!("{id} not found on {site}");
+                    continue;
+                }
+                result => return result,
+            }
+        }
+        Err(DlsiteError::NotFound(id.to_string()))
+    }
+
+    /// Get a product using the api, converted to a [`Product`] (see [`Product::from_api`]).
+    ///
+    /// With [`FetchOptions::source_fallback`] enabled, the product page is scraped when the api
+    /// fails, e.g. after an endpoint change. [`Product::source`] tells which one was used.
+    pub async fn get_product_with(
+        &self,
+        id: impl Into<ProductId>,
+        options: &FetchOptions,
+    ) -> Result<Product> {
+        let id = id.into().checked()?;
+        let site = id.site().unwrap_or_else(|| self.c.site());
+        let id = id.as_str();
+        for site in options.sites(site) {
+            let mut product = match self.get_on(id, site, None).await {
+                Ok(content) => Product::from_api(content)?,
+                Err(e) if e.is_not_found() => {
+                    tracing::debug!("{id} not found on {site}");
+                    continue;
+                }
+                Err(e) if options.source_fallback && e.is_source_failure() => {
+                    tracing::warn!("Failed to get {id} from api, falling back to scraping: {e}");
+                    self.c.product().get_all_on(id, site).await?
+                }
+                Err(e) => return Err(e),
+            };
+            self.c.normalize_title(&mut product.title);
+            return Ok(product);
+        }
+        Err(DlsiteError::NotFound(id.to_string()))
+    }
+
+    /// Same as [`ProductApiClient::get`], but texts (title, description, genres...) are
+    /// returned in the given locale (e.g. `en_US`) when DLsite has a translation.
+    pub async fn get_localized(
+        &self,
+        id: impl Into<ProductId>,
+        locale: &str,
+    ) -> Result<ProductApiContent> {
+        let id = id.into().checked()?;
+        let site = id.site().unwrap_or_else(|| self.c.site());
+        self.get_on(id.as_str(), site, Some(locale)).await
+    }
+
+    /// Get multiple products with as few requests as possible.
+    ///
+    /// IDs are grouped by storefront and requested 100 at a time, like
+    /// [`ProductApiClient::get_many`]. Each request is only sent once the stream reaches it,
+    /// and products are decoded one at a time from the response body as it is received. The
+    /// responses are not cached, so memory stays flat even when requesting hundreds of
+    /// products. Products which don't exist are missing from the result, and a failed request
+    /// is yielded as an error before the next one is sent. Like [`ProductApiClient::get`],
+    /// each product is requested from the storefront of its ID, and products which fail to
+    /// decode are dumped (see [`crate::DlsiteClientBuilder::dump_failed_responses`]).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    /// use futures::StreamExt as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let products = client
+    ///         .product_api()
+    ///         .get_multiple(["RJ01014447", "RJ01017217"])
+    ///         .await
+    ///         .unwrap();
+    ///     let mut products = std::pin::pin!(products);
+    ///     while let Some(product) = products.next().await {
+    ///         println!("{}", product.unwrap().work_name);
+    ///     }
+    /// }
+    /// ```
+    pub async fn get_multiple(
+        &self,
+        ids: impl IntoIterator<Item = impl Into<ProductId>>,
+    ) -> Result<impl Stream<Item = Result<ProductApiContent>> + MaybeSend + 'static> {
+        let mut by_site: Vec<(Site, Vec<String>)> = vec![];
+        for id in ids {
+            let id = id.into().checked()?;
+            let site = id.site().unwrap_or_else(|| self.c.site());
+            match by_site.iter_mut().find(|(s, _)| *s == site) {
+                Some((_, ids)) => ids.push(id.into()),
+                None => by_site.push((site, vec![id.into()])),
+            }
+        }
+
+        let requests: Vec<_> = by_site
+            .into_iter()
+            .flat_map(|(site, ids)| {
+                ids.chunks(MAX_IDS_PER_REQUEST)
+                    .map(|chunk| (site, api_path(&chunk.join(","))))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let client = self.c.clone();
+        Ok(futures::stream::iter(requests)
+            .then(move |(site, path)| {
+                let client = client.clone();
+                async move {
+                    let body = client.get_streamed_on(site, &path).await;
+                    body.map(|body| {
+                        decode_json_array::<serde_json::Value>(body)
+                            .map(move |value| parse_content(&client, site, &path, value?))
+                    })
+                }
+            })
+            .try_flatten())
+    }
+
+    /// Get many products with as few requests as possible.
+    ///
+    /// IDs are grouped by storefront and requested [`MAX_IDS_PER_REQUEST`] at a time, so 250
+    /// works of the same storefront cost 3 requests. Every distinct ID gets its own result:
+    /// invalid IDs fail with [`DlsiteError::InvalidProductId`], products the api doesn't
+    /// return with [`DlsiteError::NotFound`], and a failed request fails every ID it was
+    /// carrying without affecting the others.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let products = client
+    ///         .product_api()
+    ///         .get_many(["RJ01014447", "VJ01000513"])
+    ///         .await;
+    ///     for (id, product) in products {
+    ///         match product {
+    ///             Ok(product) => println!("{id}: {}", product.work_name),
+    ///             Err(e) => println!("{id}: {e}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn get_many(
+        &self,
+        ids: impl IntoIterator<Item = impl Into<ProductId>>,
+    ) -> HashMap<ProductId, Result<ProductApiContent>> {
+        let mut results = HashMap::new();
+        let mut by_site: Vec<(Site, Vec<ProductId>)> = vec![];
+        let mut seen = HashSet::new();
+        for id in ids {
+            let id = id.into();
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            if !id.is_valid() {
+                let err = DlsiteError::InvalidProductId(id.to_string());
+                results.insert(id, Err(err));
+                continue;
+            }
+            let site = id.site().unwrap_or_else(|| self.c.site());
+            match by_site.iter_mut().find(|(s, _)| *s == site) {
+                Some((_, ids)) => ids.push(id),
+                None => by_site.push((site, vec![id])),
+            }
+        }
+
+        for (site, ids) in by_site {
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                self.get_chunk(chunk, site, &mut results).await;
+            }
+        }
+        results
+    }
+
+    /// Request one chunk of [`ProductApiClient::get_many`] and store a result for each ID.
+    async fn get_chunk(
+        &self,
+        ids: &[ProductId],
+        site: Site,
+        results: &mut HashMap<ProductId, Result<ProductApiContent>>,
+    ) {
+        let workno = ids
+            .iter()
+            .map(|id| id.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let path = api_path(&workno);
+        let items = match self.c.get_streamed_on(site, &path).await {
+            Ok(body) => decode_json_array::<serde_json::Value>(body),
+            Err(e) => {
+                for id in ids {
+                    results.insert(id.clone(), Err(e.duplicate()));
+                }
+                return;
+            }
+        };
+        let mut items = std::pin::pin!(items);
+
+        // Elements are decoded separately so that a malformed product only fails its own ID
+        let mut failure = None;
+        while let Some(item) = items.next().await {
+            let value = match item {
+                Ok(value) => value,
+                Err(e) => {
+                    // Malformed element or body: IDs not returned fail with the error
+                    failure.get_or_insert(e);
+                    continue;
+                }
+            };
+            let Some(id) = value.get("workno").and_then(|w| w.as_str()) else {
+                continue;
+            };
+            let id = ProductId::from(id);
+            results.insert(id, parse_content(self.c, site, &path, value));
+        }
+
+        for id in ids {
+            if !results.contains_key(id) {
+                let err = match &failure {
+                    Some(e) => e.duplicate(),
+                    None => DlsiteError::NotFound(id.to_string()),
+                };
+                results.insert(id.clone(), Err(err));
+            }
+        }
+    }
+
+    /// Get the list of files contained in a work (names, sizes and formats), as shown on the
+    /// product page before purchase.
+    ///
+    /// Works whose files are not listed by DLsite (e.g. browser-only works) return an empty
+    /// list.
+    pub async fn get_contents(&self, id: impl Into<ProductId>) -> Result<Vec<ContentEntry>> {
+        Ok(self.get(id).await?.content_entries())
+    }
+
+    pub(crate) async fn get_on(
+        &self,
+        id: &str,
+        site: Site,
+        locale: Option<&str>,
+    ) -> Result<ProductApiContent> {
+        let mut path = api_path(id);
+        if let Some(locale) = locale {
+            path.push_str(&format!("&locale={}", locale));
+        }
+        let json = self.c.get_on(site, &path).await?;
         let jd = &mut serde_json::Deserializer::from_str(&json);
         #[cfg(feature = "unknown-field-log")]
         let result: std::result::Result<Vec<ProductApiContent>, _> = serde_ignored::deserialize(
@@ -57,13 +337,66 @@ impl<'a> ProductApiClient<'a> {
             serde_path_to_error::deserialize(jd);
         match result {
             Ok(result) => {
-                let Some(json) = result.into_iter().next() else {
-                    return Err(DlsiteError::Parse("No product found".to_string()));
+                let Some(mut json) = result.into_iter().next() else {
+                    return Err(DlsiteError::NotFound(id.to_string()));
                 };
+                json.site = site;
+
+                #[cfg(feature = "tantivy")]
+                self.c.index_work((&json).into());
 
                 Ok(json)
             }
-            Err(e) => Err(DlsiteError::Parse(format!("Failed to parse json: {}", e))),
+            Err(e) => self.c.dump_parse_error(
+                site,
+                &path,
+                &json,
+                Err(DlsiteError::Parse(format!("Failed to parse json: {}", e))),
+            ),
+        }
+    }
+}
+
+/// Decode one product of a bulk api response like [`ProductApiClient::get`] does: unknown fields
+/// are logged with the `unknown-field-log` feature, and the product is dumped if it fails to
+/// decode, or indexed otherwise.
+fn parse_content(
+    client: &DlsiteClient,
+    site: Site,
+    path: &str,
+    value: serde_json::Value,
+) -> Result<ProductApiContent> {
+    let id = value
+        .get("workno")
+        .and_then(|w| w.as_str())
+        .unwrap_or_default();
+    #[cfg(feature = "unknown-field-log")]
+    let result: std::result::Result<ProductApiContent, _> = serde_ignored::deserialize(
+        &value,
+        |path| {
+            tracing::error!("Ignored path: '{}' for '{id}'. Please report this to https://github.com/ozonezone/dlsite-rs", path.to_string());
+        },
+    );
+    #[cfg(not(feature = "unknown-field-log"))]
+    let result: std::result::Result<ProductApiContent, _> =
+        serde_path_to_error::deserialize(&value);
+    match result {
+        Ok(mut content) => {
+            content.site = site;
+            #[cfg(feature = "tantivy")]
+            client.index_work((&content).into());
+            Ok(content)
         }
+        Err(e) => client.dump_parse_error(
+            site,
+            path,
+            &value.to_string(),
+            Err(DlsiteError::Parse(format!("Failed to parse {id}: {e}"))),
+        ),
     }
 }
+
+/// Path of the product api for a single product.
+pub(crate) fn api_path(id: &str) -> String {
+    format!("/api/=/product.json?workno={}", id)
+}
@@ -56,6 +56,24 @@ async fn get_product_api_2() {
     }));
 }
 
+#[tokio::test]
+async fn get_product_api_as_product() {
+    use crate::{client::product::unified::Source, FetchOptions};
+
+    let client = DlsiteClient::default();
+    let res = client
+        .product_api()
+        .get_product_with("RJ403038", &FetchOptions::new().source_fallback(true))
+        .await
+        .unwrap();
+
+    assert_eq!(res.source, Source::Api);
+    assert_eq!(res.circle_id, "RG62982");
+    assert_eq!(res.work_type, WorkType::SOU);
+    assert_eq!(res.people.voice_actor, Some(vec!["春花らん".to_string()]));
+    assert!(!res.images.is_empty());
+}
+
 #[test_case("RJ01084246"; "otome")]
 #[test_case("VJ01000513"; "soft")]
 #[test_case("RJ01060083"; "normal")]
@@ -105,3 +123,174 @@ async fn get_product_api_rand(product_type: &str) {
         }
     }
 }
+
+#[tokio::test]
+async fn get_product_api_contents() {
+    let client = DlsiteClient::default();
+    let contents = client
+        .product_api()
+        .get_contents("RJ01014447")
+        .await
+        .unwrap();
+
+    assert!(!contents.is_empty());
+    assert!(contents.iter().all(|c| !c.file_name.is_empty()));
+    assert!(contents.iter().any(|c| c.size.is_some_and(|size| size > 0)));
+}
+
+#[tokio::test]
+async fn get_product_api_many() {
+    use crate::{interface::product_id::ProductId, DlsiteError};
+
+    let client = DlsiteClient::default();
+    let products = client
+        .product_api()
+        .get_many(["RJ01014447", "RJ403038", "VJ01000513", "RJ000000", "RJ1"])
+        .await;
+
+    assert_eq!(products.len(), 5);
+    assert_eq!(
+        products[&ProductId::from("RJ403038")]
+            .as_ref()
+            .unwrap()
+            .workno,
+        "RJ403038"
+    );
+    assert!(products[&ProductId::from("VJ01000513")].is_ok());
+    assert!(matches!(
+        products[&ProductId::from("RJ000000")],
+        Err(DlsiteError::NotFound(_))
+    ));
+    assert!(matches!(
+        products[&ProductId::from("RJ1")],
+        Err(DlsiteError::InvalidProductId(_))
+    ));
+}
+
+#[tokio::test]
+async fn get_product_api_multiple() {
+    use futures::{StreamExt as _, TryStreamExt as _};
+    use std::time::Duration;
+
+    use crate::{
+        client::LanguageCheck,
+        interface::{locale::Locale, site::Site},
+        testing::FixtureTransport,
+        DlsiteError,
+    };
+
+    let item = |id: &str| {
+        let mut item: serde_json::Value =
+            serde_json::from_str(include_str!("fixture.json")).unwrap();
+        item["workno"] = id.into();
+        item
+    };
+    let transport = FixtureTransport::new()
+        .with_body(
+            "https://www.dlsite.com/maniax/api/=/product.json?workno=RJ01000001,RJ01000002,RJ01000003",
+            serde_json::json!([item("RJ01000001"), item("RJ01000002")]).to_string(),
+        )
+        .with_body(
+            "https://www.dlsite.com/pro/api/=/product.json?workno=VJ01000001",
+            serde_json::json!([item("VJ01000001")]).to_string(),
+        );
+    let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+        .request_interval(Duration::ZERO, Duration::ZERO)
+        .transport(transport)
+        .build();
+
+    let products: Vec<_> = client
+        .product_api()
+        .get_multiple(["RJ01000001", "VJ01000001", "RJ01000002", "RJ01000003"])
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    // Each product is requested from its own storefront
+    let ids: Vec<_> = products
+        .iter()
+        .map(|p| (p.workno.as_str(), p.site))
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            ("RJ01000001", Site::Maniax),
+            ("RJ01000002", Site::Maniax),
+            ("VJ01000001", Site::Pro)
+        ]
+    );
+    // Bulk responses bypass the cache
+    let stats = client.cache_stats();
+    assert_eq!((stats.hits, stats.misses), (0, 0));
+
+    assert!(matches!(
+        client.product_api().get_multiple(["RJ1"]).await,
+        Err(DlsiteError::InvalidProductId(_))
+    ));
+
+    // Pages served in another language are checked like single products
+    let transport = FixtureTransport::new().with_body(
+        "https://www.dlsite.com/maniax/api/=/product.json?workno=RJ01000001&locale=en_US",
+        r#"<html lang="ja-jp"><body></body></html>"#,
+    );
+    let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+        .request_interval(Duration::ZERO, Duration::ZERO)
+        .transport(transport)
+        .locale(Locale::EnUs)
+        .language_check(LanguageCheck::Error)
+        .build();
+    let products: Vec<_> = client
+        .product_api()
+        .get_multiple(["RJ01000001"])
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert!(matches!(
+        products[..],
+        [Err(DlsiteError::LanguageMismatch { .. })]
+    ));
+}
+
+#[tokio::test]
+async fn get_product_api_multiple_chunks() {
+    use futures::TryStreamExt as _;
+    use std::time::Duration;
+
+    use crate::testing::FixtureTransport;
+
+    let item = |id: &str| {
+        let mut item: serde_json::Value =
+            serde_json::from_str(include_str!("fixture.json")).unwrap();
+        item["workno"] = id.into();
+        item
+    };
+    let ids: Vec<_> = (1..=150).map(|n| format!("RJ01{n:06}")).collect();
+    let mut transport = FixtureTransport::new();
+    // At most 100 products per request
+    for chunk in ids.chunks(100) {
+        transport = transport.with_body(
+            &format!(
+                "https://www.dlsite.com/maniax/api/=/product.json?workno={}",
+                chunk.join(",")
+            ),
+            serde_json::Value::from_iter(chunk.iter().map(|id| item(id))).to_string(),
+        );
+    }
+    let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+        .request_interval(Duration::ZERO, Duration::ZERO)
+        .transport(transport)
+        .build();
+
+    let products: Vec<_> = client
+        .product_api()
+        .get_multiple(&ids)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    let worknos: Vec<_> = products.iter().map(|p| &p.workno).collect();
+    assert_eq!(worknos, ids.iter().collect::<Vec<_>>());
+}
@@ -0,0 +1,261 @@
+//! Interfaces related to ranking pages. For more information, see [`RankingClient`].
+
+use scraper::{ElementRef, Html, Selector};
+use strum::{Display, EnumString};
+
+use crate::{
+    client::{Page, Paginated},
+    error::Result,
+    interface::product::WorkType,
+    runtime::MaybeBoxed as _,
+    utils::ToParseError as _,
+    DlsiteClient,
+};
+
+/// Client to scrape ranking pages on DLsite.
+#[derive(Clone, Debug)]
+pub struct RankingClient<'a> {
+    pub(crate) c: &'a DlsiteClient,
+}
+
+/// Aggregation period of a ranking.
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum RankingTerm {
+    /// 24時間
+    Day,
+    /// 7日間
+    Week,
+    /// 30日間
+    Month,
+    /// 年間
+    Year,
+    /// 累計
+    Total,
+}
+
+/// A ranked product.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RankingEntry {
+    pub rank: u32,
+    pub id: String,
+    pub title: String,
+    pub circle_name: String,
+    pub circle_id: String,
+    pub work_type: WorkType,
+    pub price: Option<i32>,
+    pub dl_count: Option<i32>,
+}
+
+impl<'a> RankingClient<'a> {
+    /// Get the sitewide ranking for the given term.
+    pub async fn get(&self, term: RankingTerm) -> Result<Vec<RankingEntry>> {
+        let path = format!("/ranking/{}", term);
+        let html = self.c.get(&path).await?;
+        self.c.dump_parse_error(self.c.site(), &path, &html, parse_ranking_html(&html))
+    }
+
+    /// Get the ranking of a single genre for the given term.
+    ///
+    /// # Arguments
+    /// * `genre_id` - Numeric genre ID. Example: `497` for ASMR.
+    /// * `term` - Aggregation period.
+    pub async fn by_genre(&self, genre_id: u32, term: RankingTerm) -> Result<Vec<RankingEntry>> {
+        let path = format!("/ranking/{}/=/genre/{}", term, genre_id);
+        let html = self.c.get(&path).await?;
+        self.c.dump_parse_error(self.c.site(), &path, &html, parse_ranking_html(&html))
+    }
+
+    /// Same as [`RankingClient::get`], as a [`Paginated`] listing so rankings can be iterated
+    /// like other listings. The whole ranking is on one page, so there is a single page.
+    pub fn paginate(&self, term: RankingTerm) -> Paginated<'a, RankingEntry> {
+        let client = self.clone();
+        Paginated::new(1, None, move |_| {
+            let client = client.clone();
+            async move {
+                let entries = client.get(term).await?;
+                Result::Ok(Page {
+                    total: Some(entries.len()),
+                    items: entries,
+                })
+            }
+            .maybe_boxed()
+        })
+    }
+}
+
+pub(crate) fn parse_ranking_html(html: &str) -> Result<Vec<RankingEntry>> {
+    let html = Html::parse_document(html);
+    let mut result = vec![];
+
+    let row_selector = Selector::parse("#ranking_table tr").unwrap();
+    let product_id_selector = Selector::parse("[data-product_id]").unwrap();
+    // Header rows and ads don't have product ids, and don't count for the rank
+    let rows = html
+        .select(&row_selector)
+        .filter_map(|row| Some((row, row.select(&product_id_selector).next()?)));
+    for (i, (row, product_id_e)) in rows.enumerate() {
+        result.push(parse_ranking_row(row, product_id_e, i as u32 + 1)?);
+    }
+
+    Ok(result)
+}
+
+fn parse_ranking_row(
+    row: ElementRef,
+    product_id_e: ElementRef,
+    fallback_rank: u32,
+) -> Result<RankingEntry> {
+    let id = product_id_e
+        .value()
+        .attr("data-product_id")
+        .to_parse_error("Failed to get product id")?
+        .to_string();
+    let rank = row
+        .select(&Selector::parse(".rank_no").unwrap())
+        .next()
+        .and_then(|e| {
+            e.text()
+                .collect::<String>()
+                .trim()
+                .trim_end_matches('位')
+                .parse()
+                .ok()
+        })
+        .unwrap_or(fallback_rank);
+    let title = row
+        .select(&Selector::parse(".work_name a").unwrap())
+        .next()
+        .to_parse_error("Failed to get title")?;
+    let title = title
+        .value()
+        .attr("title")
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| title.text().collect::<String>().trim().to_string());
+    let maker_e = row
+        .select(&Selector::parse(".maker_name a").unwrap())
+        .next()
+        .to_parse_error("Failed to find maker element")?;
+    let circle_id = maker_e
+        .value()
+        .attr("href")
+        .to_parse_error("Failed to get maker link")?
+        .split('/')
+        .next_back()
+        .to_parse_error("Invalid url")?
+        .split('.')
+        .next()
+        .to_parse_error("Failed to find maker id")?
+        .to_string();
+    let work_type = row
+        .select(&Selector::parse(".work_category").unwrap())
+        .next()
+        .and_then(|e| e.value().attr("class"))
+        .and_then(|class| {
+            class.split(' ').find_map(|c| {
+                let wt = c.strip_prefix("type_")?.parse::<WorkType>().ok()?;
+                (!matches!(wt, WorkType::Unknown(_))).then_some(wt)
+            })
+        })
+        .unwrap_or(WorkType::Unknown("".to_string()));
+    let price = row
+        .select(&Selector::parse(".work_price .work_price_base").unwrap())
+        .next()
+        .and_then(|e| e.text().next()?.replace(',', "").parse().ok());
+    let dl_count = row
+        .select(&Selector::parse(".work_dl span[class*=\"dl_count\"]").unwrap())
+        .next()
+        .and_then(|e| e.text().next()?.replace(',', "").parse().ok());
+
+    Ok(RankingEntry {
+        rank,
+        id,
+        title,
+        circle_name: maker_e.text().next().unwrap_or("").trim().to_string(),
+        circle_id,
+        work_type,
+        price,
+        dl_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ranking_html, RankingTerm};
+    use crate::{interface::product::WorkType, DlsiteClient};
+
+    const RANKING_HTML: &str = r#"
+<table id="ranking_table">
+  <tr><th>順位</th><th>作品</th></tr>
+  <tr>
+    <td><div class="rank_no">1位</div></td>
+    <td>
+      <div data-product_id="RJ01000001"></div>
+      <dt class="work_name"><a href="/work/=/product_id/RJ01000001.html" title="Work A">Work A</a></dt>
+      <dd class="maker_name"><a href="https://www.dlsite.com/maniax/circle/profile/=/maker_id/RG00001.html">Circle A</a></dd>
+      <div class="work_category type_SOU"><a>ボイス・ASMR</a></div>
+      <span class="work_price"><span class="work_price_base">1,320</span></span>
+      <dd class="work_dl"><span class="_dl_count_RJ01000001">12,345</span></dd>
+    </td>
+  </tr>
+  <tr>
+    <td><div class="rank_no">2位</div></td>
+    <td>
+      <div data-product_id="RJ01000002"></div>
+      <dt class="work_name"><a href="/work/=/product_id/RJ01000002.html">Work B</a></dt>
+      <dd class="maker_name"><a href="/maniax/circle/profile/=/maker_id/RG00002.html">Circle B</a></dd>
+    </td>
+  </tr>
+  <tr><td colspan="2">PR</td></tr>
+  <tr>
+    <td></td>
+    <td>
+      <div data-product_id="RJ01000003"></div>
+      <dt class="work_name"><a href="/work/=/product_id/RJ01000003.html">Work C</a></dt>
+      <dd class="maker_name"><a href="/maniax/circle/profile/=/maker_id/RG00003.html">Circle C</a></dd>
+    </td>
+  </tr>
+</table>
+"#;
+
+    #[test]
+    fn parse_ranking() {
+        let entries = parse_ranking_html(RANKING_HTML).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].rank, 1);
+        assert_eq!(entries[0].id, "RJ01000001");
+        assert_eq!(entries[0].title, "Work A");
+        assert_eq!(entries[0].circle_id, "RG00001");
+        assert_eq!(entries[0].circle_name, "Circle A");
+        assert_eq!(entries[0].work_type, WorkType::SOU);
+        assert_eq!(entries[0].price, Some(1320));
+        assert_eq!(entries[0].dl_count, Some(12345));
+
+        assert_eq!(entries[1].rank, 2);
+        assert_eq!(entries[1].title, "Work B");
+        assert_eq!(entries[1].price, None);
+
+        // Without rank number, the rank is the position among works
+        assert_eq!(entries[2].rank, 3);
+        assert_eq!(entries[2].id, "RJ01000003");
+    }
+
+    #[test]
+    fn ranking_term_path() {
+        assert_eq!(RankingTerm::Week.to_string(), "week");
+        assert_eq!(RankingTerm::Total.to_string(), "total");
+    }
+
+    #[tokio::test]
+    async fn get_genre_ranking() {
+        let client = DlsiteClient::default();
+        let res = client
+            .ranking()
+            .by_genre(497, RankingTerm::Week)
+            .await
+            .expect("Failed to get ranking");
+
+        assert!(!res.is_empty());
+    }
+}
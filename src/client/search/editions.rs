@@ -0,0 +1,239 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use regex::Regex;
+
+use super::{SearchProductItem, SearchResult};
+use crate::normalize::Normalizer;
+
+/// Editions of the same work found in a search result, see
+/// [`SearchResult::group_editions`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EditionGroup {
+    /// The original work if it is in the result, otherwise the first edition listed
+    pub primary: SearchProductItem,
+    /// Other editions and translations, in result order
+    pub editions: Vec<SearchProductItem>,
+}
+
+impl SearchResult {
+    /// Collapse editions and translations of the same work into groups.
+    ///
+    /// Works of the same circle are grouped when their titles are equal once edition markers
+    /// such as `【English Ver.】` or `(繁体中文版)` are removed. Groups keep the order of
+    /// their first work in the result.
+    pub fn group_editions(&self) -> Vec<EditionGroup> {
+        self.group_editions_by(|_| None)
+    }
+
+    /// Same as [`SearchResult::group_editions`], additionally grouping each work with the
+    /// work returned by `original` (e.g. `translation_info.original_workno` of
+    /// [`crate::client::product::ajax::ProductAjax`]).
+    pub fn group_editions_by<F>(&self, original: F) -> Vec<EditionGroup>
+    where
+        F: Fn(&SearchProductItem) -> Option<String>,
+    {
+        let products = &self.products;
+        let index: HashMap<&str, usize> = products
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.id.as_str(), i))
+            .collect();
+
+        let mut parent: Vec<usize> = (0..products.len()).collect();
+        let mut is_original = vec![false; products.len()];
+        let mut by_title: HashMap<(&str, String), usize> = HashMap::new();
+        for (i, product) in products.iter().enumerate() {
+            if let Some(title) = normalize_title(&product.title) {
+                let first = *by_title
+                    .entry((product.circle_id.as_str(), title))
+                    .or_insert(i);
+                union(&mut parent, first, i);
+            }
+            if let Some(&j) = original(product).and_then(|id| index.get(id.as_str())) {
+                is_original[j] = true;
+                union(&mut parent, j, i);
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = vec![];
+        let mut group_of: HashMap<usize, usize> = HashMap::new();
+        for i in 0..products.len() {
+            let root = find(&mut parent, i);
+            let group = *group_of.entry(root).or_insert_with(|| {
+                groups.push(vec![]);
+                groups.len() - 1
+            });
+            groups[group].push(i);
+        }
+
+        groups
+            .into_iter()
+            .map(|members| {
+                let primary = members
+                    .iter()
+                    .copied()
+                    .find(|&i| is_original[i])
+                    .unwrap_or(members[0]);
+                EditionGroup {
+                    primary: products[primary].clone(),
+                    editions: members
+                        .into_iter()
+                        .filter(|&i| i != primary)
+                        .map(|i| products[i].clone())
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    parent[i] = root;
+    root
+}
+
+/// Merge the groups of `a` and `b`. The root is the smallest index, i.e. the first work.
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parent, a), find(parent, b));
+    parent[a.max(b)] = a.min(b);
+}
+
+/// Title without edition markers, width-folded, lowercased and without punctuation. `None`
+/// if nothing is left.
+fn normalize_title(title: &str) -> Option<String> {
+    let title = Normalizer {
+        strip_brackets: false,
+        ..Normalizer::for_matching()
+    }
+    .apply(title);
+    static EDITION_MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker = EDITION_MARKER.get_or_init(|| {
+        Regex::new(
+            r"(?i)[【\[(（〔][^】\])）〕]*(?:\bver(?:sion)?\b|\bedition\b|版|english|中文|한국어|translat|翻訳)[^】\])）〕]*[】\])）〕]",
+        )
+        .unwrap()
+    });
+    let title: String = marker
+        .replace_all(&title, "")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    (!title.is_empty()).then_some(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_title;
+    use crate::{
+        client::search::{SearchProductItem, SearchResult},
+        interface::product::{AgeCategory, WorkType},
+        selector::ParseReport,
+    };
+
+    fn item(id: &str, title: &str, circle_id: &str) -> SearchProductItem {
+        SearchProductItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            creator: None,
+            creator_omitted: None,
+            circle_name: String::new(),
+            circle_id: circle_id.to_string(),
+            dl_count: None,
+            rate_count: None,
+            review_count: None,
+            favorite_count: None,
+            price_original: 0,
+            price_sale: None,
+            age_category: AgeCategory::General,
+            work_type: WorkType::SOU,
+            thumbnail_url: String::new(),
+            rating: None,
+        }
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(
+            normalize_title("ねこぐらし。【English Ver.】"),
+            normalize_title("ねこぐらし。")
+        );
+        assert_eq!(
+            normalize_title("ねこぐらし。(繁体中文版)"),
+            normalize_title("ねこぐらし。")
+        );
+        assert_ne!(
+            normalize_title("ねこぐらし。2"),
+            normalize_title("ねこぐらし。")
+        );
+        assert_eq!(
+            normalize_title("ＮＥＫＯ ＧＵＲＡＳＨＩ【English Ver.】"),
+            normalize_title("Neko Gurashi")
+        );
+        assert_eq!(normalize_title("【English Ver.】"), None);
+    }
+
+    #[test]
+    fn group_editions() {
+        let result = SearchResult {
+            products: vec![
+                item("RJ02", "Cat Life【English Ver.】", "RG1"),
+                item("RJ03", "Other work", "RG1"),
+                item("RJ01", "Cat Life", "RG1"),
+                item("RJ04", "Cat Life", "RG2"),
+                item("RJ05", "ネコ生活【簡体中文版】", "RG1"),
+            ],
+            count: 5,
+            query_path: String::new(),
+            report: ParseReport::default(),
+            skipped: vec![],
+        };
+
+        let groups = result.group_editions();
+        let ids: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.primary.id.as_str(),
+                    g.editions.iter().map(|e| e.id.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("RJ02", vec!["RJ01"]),
+                ("RJ03", vec![]),
+                ("RJ04", vec![]),
+                ("RJ05", vec![]),
+            ]
+        );
+
+        // With translation links, the original becomes the primary work
+        let groups = result.group_editions_by(|p| match p.id.as_str() {
+            "RJ02" | "RJ05" => Some("RJ01".to_string()),
+            _ => None,
+        });
+        let ids: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.primary.id.as_str(),
+                    g.editions.iter().map(|e| e.id.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("RJ01", vec!["RJ02", "RJ05"]),
+                ("RJ03", vec![]),
+                ("RJ04", vec![]),
+            ]
+        );
+    }
+}
@@ -1,25 +1,42 @@
 //! Interfaces related to search feature only. For more information, see [`SearchClient`].
 
+mod editions;
 pub(crate) mod macros;
 mod query;
 mod selectors;
 
+use chrono::NaiveDate;
+use futures::{Stream, StreamExt as _};
 use scraper::{Html, Selector};
 use serde::Deserialize;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Duration;
 
 use crate::{
-    error::Result,
-    interface::product::{AgeCategory, WorkType},
+    client::{product::html::parse_age_badge, Page, Paginated},
+    error::{DlsiteError, Result},
+    interface::{
+        product::{AgeCategory, WorkType},
+        query::Order,
+        site::Site,
+    },
+    runtime::MaybeBoxed as _,
+    selector::ParseReport,
     utils::ToParseError,
     DlsiteClient,
     cache::GenericCache,
 };
 
-pub use self::query::SearchProductQuery;
+pub use self::editions::EditionGroup;
+#[cfg(feature = "download")]
+pub(crate) use self::query::percent_decode;
+pub use self::query::{QueryPath, SearchProductQuery};
+
+/// Number of results per page when `per_page` isn't set.
+const DEFAULT_PER_PAGE: u32 = 30;
 
 /// Client to search products on DLsite.
 pub struct SearchClient<'a> {
@@ -50,6 +67,8 @@ pub struct SearchProductItem {
     pub dl_count: Option<i32>,
     pub rate_count: Option<i32>,
     pub review_count: Option<i32>,
+    /// Number of users who added the work to their favorites (お気に入り数)
+    pub favorite_count: Option<i32>,
     pub price_original: i32,
     pub price_sale: Option<i32>,
     pub age_category: AgeCategory,
@@ -58,11 +77,70 @@ pub struct SearchProductItem {
     pub rating: Option<f32>, // pub image_url: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct SearchResult {
     pub products: Vec<SearchProductItem>,
     pub count: i32,
     pub query_path: String,
+    /// Selectors used to parse the results. Empty when the products come from the result
+    /// cache.
+    pub report: ParseReport,
+    /// Items which failed to parse, with their position in the page, when the client parses
+    /// leniently (see [`crate::DlsiteClientBuilder::lenient_parsing`]). Empty when the
+    /// products come from the result cache.
+    #[serde(skip)]
+    pub skipped: Vec<(usize, DlsiteError)>,
+}
+
+/// Number of thumbnails downloaded at once by [`SearchResult::prefetch_thumbnails`]
+const PREFETCH_CONCURRENCY: usize = 8;
+/// Page size used by [`SearchClient::new_arrivals`] (the largest DLsite accepts)
+const NEW_ARRIVALS_PER_PAGE: u32 = 100;
+
+impl SearchResult {
+    /// Download the thumbnails of all products into the client's media cache, so that later
+    /// [`DlsiteClient::get_media`] calls return instantly.
+    ///
+    /// Returns the number of thumbnails now cached. Failed downloads are only logged.
+    ///
+    /// With the `image-hash` feature, cover hashes are also recorded in the client's local
+    /// index (see [`crate::index::LocalIndex::find_by_image_hash`]).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::{DlsiteClient, client::search::SearchProductQuery};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let result = client
+    ///         .search()
+    ///         .search_product(&SearchProductQuery::default())
+    ///         .await
+    ///         .unwrap();
+    ///     let cached = result.prefetch_thumbnails(&client).await;
+    ///     println!("{} / {} thumbnails cached", cached, result.products.len());
+    /// }
+    /// ```
+    pub async fn prefetch_thumbnails(&self, client: &DlsiteClient) -> usize {
+        futures::stream::iter(&self.products)
+            .map(|product| async move {
+                let result = client.get_media(&product.thumbnail_url).await;
+                #[cfg(feature = "image-hash")]
+                if let Ok(image) = &result {
+                    client.index_cover(&product.id, image.clone()).await;
+                }
+                result
+                    .inspect_err(|e| {
+                        tracing::debug!("Failed to prefetch {}: {}", product.thumbnail_url, e)
+                    })
+                    .is_ok()
+            })
+            .buffer_unordered(PREFETCH_CONCURRENCY)
+            .filter(|ok| futures::future::ready(*ok))
+            .count()
+            .await
+    }
 }
 fn parse_count_str(str: &str) -> Result<i32> {
     str.replace(['(', ')', ','], "")
@@ -71,7 +149,9 @@ fn parse_count_str(str: &str) -> Result<i32> {
 }
 
 fn parse_num_str(str: &str) -> Result<i32> {
-    str.replace(',', "")
+    str.trim()
+        .trim_end_matches('円')
+        .replace(',', "")
         .parse()
         .to_parse_error("Failed to parse string to number")
 }
@@ -91,8 +171,8 @@ impl<'a> SearchClient<'a> {
     /// * `options` - Struct of search options.
     ///
     /// # Example
-    /// ```
-    /// use dlsite::{DlsiteClient, client::search::SearchProductQuery, interface::query::*};
+    /// ```no_run
+    /// use dlsite_gamebox::{DlsiteClient, client::search::SearchProductQuery, interface::query::*};
     ///
     /// #[tokio::main]
     /// async fn main() {
@@ -110,33 +190,46 @@ impl<'a> SearchClient<'a> {
     /// }
     /// ```
     pub async fn search_product(&self, options: &SearchProductQuery) -> Result<SearchResult> {
-        let query_path = options.to_path();
+        self.search_path(options.to_path()).await
+    }
 
+    async fn search_path(&self, query_path: String) -> Result<SearchResult> {
         // Check if results are cached
-        {
-            let cache = self.result_cache.lock().unwrap();
-            if let Some(cached_products) = cache.get(&query_path) {
-                // Get count from API (it's small and fast)
-                let json = self.c.get(&query_path).await?;
-                let json = serde_json::from_str::<SearchAjaxResult>(&json)?;
-                let count = json.page_info.count;
-
-                return Ok(SearchResult {
-                    products: cached_products,
-                    count,
-                    query_path,
-                });
-            }
+        let cached_products = self.result_cache.lock().unwrap().get(&query_path);
+        if let Some(cached_products) = cached_products {
+            // Get count from API (it's small and fast)
+            let json = self.c.get(&query_path).await?;
+            let json = serde_json::from_str::<SearchAjaxResult>(&json)?;
+            let count = json.page_info.count;
+
+            return Ok(SearchResult {
+                products: cached_products,
+                count,
+                query_path,
+                report: ParseReport::default(),
+                skipped: vec![],
+            });
         }
 
         // Cache miss - fetch and parse
-        let json = self.c.get(&query_path).await?;
-        let json = serde_json::from_str::<SearchAjaxResult>(&json)?;
+        let body = self.c.get(&query_path).await?;
+        let site = self.c.site();
+        let json = self.c.dump_parse_error(
+            site,
+            &query_path,
+            &body,
+            serde_json::from_str::<SearchAjaxResult>(&body).map_err(Into::into),
+        )?;
         let html = json.search_result;
         let count = json.page_info.count;
 
         // Use parallel parsing for better performance
-        let products = parse_search_html_parallel(&html)?;
+        let (mut products, report, skipped) =
+            self.c
+                .dump_parse_error(site, &query_path, &body, parse_search_page(self.c, &html))?;
+        for product in &mut products {
+            self.c.normalize_title(&mut product.title);
+        }
 
         // Cache the results
         {
@@ -148,9 +241,133 @@ impl<'a> SearchClient<'a> {
             products,
             count,
             query_path,
+            report,
+            skipped,
         })
     }
 
+    /// Search products on DLsite, walking all result pages.
+    ///
+    /// Pages are fetched one at a time as the stream is polled, starting at `options.page`
+    /// (or the first page) with `options.per_page` works per request, until the total count
+    /// of results is reached. Requests go through the rate limiter like any other, and
+    /// dropping the stream stops fetching further pages.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::{DlsiteClient, client::search::SearchProductQuery};
+    /// use futures::TryStreamExt as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let search = client.search();
+    ///     let query = SearchProductQuery {
+    ///         keyword: Some("ねこぐらし".to_string()),
+    ///         per_page: Some(100),
+    ///         ..Default::default()
+    ///     };
+    ///     let works: Vec<_> = search.search_product_paged(&query).try_collect().await.unwrap();
+    ///     println!("{} works", works.len());
+    /// }
+    /// ```
+    pub fn search_product_paged<'s>(
+        &'s self,
+        options: &'s SearchProductQuery,
+    ) -> impl Stream<Item = Result<SearchProductItem>> + 's {
+        self.paginate(options).into_stream()
+    }
+
+    /// Search products on DLsite page by page, see [`Paginated`].
+    ///
+    /// Pages start at `options.page` (or the first page) and hold `options.per_page` works.
+    pub fn paginate<'s>(
+        &'s self,
+        options: &'s SearchProductQuery,
+    ) -> Paginated<'s, SearchProductItem> {
+        let per_page = options.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        Paginated::new(options.page.unwrap_or(1), Some(per_page), move |page| {
+            async move {
+                let result = self.search_path(options.to_path_with_page(Some(page))).await?;
+                Result::Ok(Page {
+                    items: result.products,
+                    total: Some(result.count.max(0) as usize),
+                })
+            }
+            .maybe_boxed()
+        })
+    }
+
+    /// Get the works released since `since` (inclusive), newest first.
+    ///
+    /// Release listings are walked page by page (100 works per request) only as far back as
+    /// `since`, and bypass the response cache, so calling this daily with yesterday's date
+    /// costs one or two requests.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::DlsiteClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let yesterday = chrono::Local::now().date_naive() - chrono::Days::new(1);
+    ///     let works = client.search().new_arrivals(yesterday).await.unwrap();
+    ///     println!("{} new works", works.len());
+    /// }
+    /// ```
+    pub async fn new_arrivals(&self, since: NaiveDate) -> Result<Vec<SearchProductItem>> {
+        let c = self.c;
+        Paginated::new(1, Some(NEW_ARRIVALS_PER_PAGE), move |page| {
+            let query_path = SearchProductQuery {
+                order: Some(Order::Release),
+                release_date_from: Some(since),
+                per_page: Some(NEW_ARRIVALS_PER_PAGE),
+                page: Some(page),
+                ..Default::default()
+            }
+            .to_path();
+            async move {
+                let (products, count) = c.search().fetch_fresh(&query_path).await?;
+                Result::Ok(Page {
+                    items: products,
+                    total: Some(count.max(0) as usize),
+                })
+            }
+            .maybe_boxed()
+        })
+        .collect_all()
+        .await
+    }
+
+    /// First page of the results of `query_path`, bypassing the response and result caches.
+    pub(crate) async fn search_fresh(&self, query_path: &str) -> Result<Vec<SearchProductItem>> {
+        Ok(self.fetch_fresh(query_path).await?.0)
+    }
+
+    /// Results of `query_path` and their total count, bypassing the response and result
+    /// caches. Titles are normalized, and responses failing to parse are dumped.
+    async fn fetch_fresh(&self, query_path: &str) -> Result<(Vec<SearchProductItem>, i32)> {
+        let body = self.c.get_fresh(query_path).await?;
+        let site = self.c.site();
+        let json = self.c.dump_parse_error(
+            site,
+            query_path,
+            &body,
+            serde_json::from_str::<SearchAjaxResult>(&body).map_err(Into::into),
+        )?;
+        let (mut products, _, _) = self.c.dump_parse_error(
+            site,
+            query_path,
+            &body,
+            parse_search_page(self.c, &json.search_result),
+        )?;
+        for product in &mut products {
+            self.c.normalize_title(&mut product.title);
+        }
+        Ok((products, json.page_info.count))
+    }
+
     /// Search multiple queries concurrently for better performance
     /// This method uses tokio::join_all to fetch multiple pages in parallel
     ///
@@ -189,9 +406,10 @@ impl<'a> SearchClient<'a> {
 
         // Parse and stream items
         let html = Html::parse_fragment(&html);
-        for item_element in html.select(&Selector::parse("#search_result_img_box > li").unwrap()) {
+        let mut report = ParseReport::default();
+        for item_element in selectors::search_result_items().select_all(&html, &mut report) {
             let item_html = item_element.html();
-            match parse_search_item_html(&item_html) {
+            match parse_search_item_html(&item_html, self.c.site(), &mut report) {
                 Ok(item) => callback(item),
                 Err(e) => eprintln!("Warning: Failed to parse item: {:?}", e),
             }
@@ -201,33 +419,56 @@ impl<'a> SearchClient<'a> {
     }
 }
 
+/// Parse a page listing works with the search result markup outside of the search (circle
+/// page, campaign page...) of the given storefront. Returns the works of the page and the
+/// total number of works.
+pub(crate) fn parse_work_list_page(
+    html: &Html,
+    site: Site,
+) -> Result<(Vec<SearchProductItem>, i32, ParseReport)> {
+    let products_html = html
+        .select(&Selector::parse("#search_result_list").unwrap())
+        .next()
+        .to_parse_error("Product list not found")?;
+
+    let count: i32 = html
+        .select(&Selector::parse(".page_total > strong").unwrap())
+        .next()
+        .to_parse_error("No total item count found")?
+        .text()
+        .next()
+        .to_parse_error("No total item count found 2")?
+        .parse()
+        .to_parse_error("Failed to parse total item count")?;
+
+    let (products, report) = parse_search_html(&products_html.html(), site)?;
+    Ok((products, count, report))
+}
+
 /// Parse a single search result item from HTML element
 /// This function is designed to be used in parallel processing
-fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
+fn parse_search_item_html(
+    item_html: &str,
+    site: Site,
+    report: &mut ParseReport,
+) -> Result<SearchProductItem> {
     let item_element = Html::parse_fragment(item_html);
     let item_element = item_element
         .root_element();
 
-    let product_id_e = item_element
-        .select(selectors::product_id_element())
-        .next()
+    let product_id_e = selectors::product_id_element()
+        .select(item_element, report)
         .to_parse_error("Failed to find data element")?
         .value();
-    let maker_e = item_element
-        .select(selectors::maker_name())
-        .next()
+    let maker_e = selectors::maker_name()
+        .select(item_element, report)
         .to_parse_error("Failed to find maker element")?;
-    let author_e = item_element
-        .select(selectors::author())
-        .next();
+    let author_e = selectors::author().select(item_element, report);
 
-    let price_e = item_element
-        .select(selectors::work_price())
-        .next()
+    let price_e = selectors::work_price()
+        .select(item_element, report)
         .to_parse_error("Failed to find price element")?;
-    let original_price_e = item_element
-        .select(selectors::original_price())
-        .next();
+    let original_price_e = selectors::original_price().select(item_element, report);
     let (sale_price_e, original_price_e) = if let Some(e) = original_price_e {
         (Some(price_e), e)
     } else {
@@ -240,37 +481,31 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
 
     Ok(SearchProductItem {
         id: id.clone(),
-        title: item_element
-            .select(selectors::work_title())
-            .next()
-            .to_parse_error("Failed to get title")?
-            .value()
-            .attr("title")
-            .unwrap()
-            .to_string(),
+        title: {
+            let title_e = selectors::work_title()
+                .select(item_element, report)
+                .to_parse_error("Failed to get title")?;
+            // Older markup doesn't have the title attribute
+            title_e
+                .value()
+                .attr("title")
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| title_e.text().collect::<String>().trim().to_string())
+        },
         age_category: {
-            if let Some(e) = item_element
-                .select(selectors::age_category())
-                .next()
-            {
-                let title = e.value().attr("title");
-                if let Some(title) = title {
-                    match title {
-                        "全年齢" => AgeCategory::General,
-                        "R-15" => AgeCategory::R15,
-                        _ => {
-                            return Err(crate::DlsiteError::Parse(
-                                "Age category parse error: invalid title".to_string(),
-                            ))
-                        }
+            if let Some(e) = selectors::age_category().select(item_element, report) {
+                match (parse_age_badge(e), e.value().attr("title")) {
+                    (Some(age_category), _) => age_category,
+                    (None, Some(_)) => {
+                        return Err(crate::DlsiteError::Parse(
+                            "Age category parse error: invalid title".to_string(),
+                        ))
                     }
-                } else {
-                    return Err(crate::DlsiteError::Parse(
-                        "Age category parse error".to_string(),
-                    ));
+                    // Girls and BL listings put other genre tags in the same place
+                    (None, None) => site.unmarked_age_category(),
                 }
             } else {
-                AgeCategory::Adult
+                site.unmarked_age_category()
             }
         },
         circle_name: maker_e.text().next().unwrap_or("").to_string(),
@@ -287,9 +522,8 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
             .to_string(),
         creator: {
             if let Some(creator_e) = author_e {
-                let name = creator_e
-                    .select(selectors::creator_link())
-                    .next()
+                let name = selectors::creator_link()
+                    .select(creator_e, report)
                     .to_parse_error("Failed to find creator")?
                     .text()
                     .next()
@@ -314,10 +548,7 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
             }
         },
         dl_count: {
-            if let Some(e) = item_element
-                .select(selectors::dl_count())
-                .next()
-            {
+            if let Some(e) = selectors::dl_count().select(item_element, report) {
                 Some(
                     e.text()
                         .next()
@@ -331,10 +562,7 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
             }
         },
         rate_count: {
-            if let Some(e) = item_element
-                .select(selectors::dl_count())
-                .next()
-            {
+            if let Some(e) = selectors::dl_count().select(item_element, report) {
                 Some(parse_count_str(
                     e.text().next().to_parse_error("Failed to get rate count")?,
                 )?)
@@ -343,10 +571,7 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
             }
         },
         review_count: {
-            if let Some(e) = item_element
-                .select(selectors::review_count())
-                .next()
-            {
+            if let Some(e) = selectors::review_count().select(item_element, report) {
                 Some(parse_count_str(
                     e.text()
                         .next()
@@ -356,6 +581,17 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
                 None
             }
         },
+        favorite_count: {
+            if let Some(e) = selectors::favorite_count().select(item_element, report) {
+                Some(parse_count_str(
+                    e.text()
+                        .next()
+                        .to_parse_error("Failed to get favorite count")?,
+                )?)
+            } else {
+                None
+            }
+        },
         price_original: parse_num_str(
             original_price_e
                 .text()
@@ -370,31 +606,34 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
                 None => None,
             }
         },
-        work_type: item_element
-            .select(selectors::work_category())
-            .next()
-            .to_parse_error("Failed to find work category")?
-            .value()
-            .attr("class")
-            .to_parse_error("Failed to find worktype")?
-            .split(' ')
-            .find_map(|c| {
-                if let Some(c) = c.strip_prefix("type_") {
-                    if let Ok(wt) = c.parse::<WorkType>() {
-                        if let WorkType::Unknown(_) = wt {
-                            return None;
-                        } else {
-                            return Some(wt);
+        work_type: {
+            let category_e = selectors::work_category()
+                .select(item_element, report)
+                .to_parse_error("Failed to find work category")?;
+            category_e
+                .value()
+                .attr("class")
+                .to_parse_error("Failed to find worktype")?
+                .split(' ')
+                .find_map(|c| {
+                    if let Some(c) = c.strip_prefix("type_") {
+                        if let Ok(wt) = c.parse::<WorkType>() {
+                            if let WorkType::Unknown(_) = wt {
+                                return None;
+                            } else {
+                                return Some(wt);
+                            }
                         }
                     }
-                }
-                None
-            })
-            .unwrap_or(WorkType::Unknown("".to_string())),
+                    None
+                })
+                // Girls and BL listings sometimes only have the label
+                .or_else(|| WorkType::from_label(&category_e.text().collect::<String>()))
+                .unwrap_or(WorkType::Unknown("".to_string()))
+        },
         thumbnail_url: {
-            let img_e = item_element
-                .select(selectors::thumbnail_image())
-                .next()
+            let img_e = selectors::thumbnail_image()
+                .select(item_element, report)
                 .to_parse_error("Failed to find thumbnail")?;
 
             let src = img_e.value().attr("src");
@@ -410,10 +649,7 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
             }
         },
         rating: {
-            if let Some(e) = item_element
-                .select(selectors::rating())
-                .next()
-            {
+            if let Some(e) = selectors::rating().select(item_element, report) {
                 e.value()
                     .attr("class")
                     .expect("Failed to get rating")
@@ -433,269 +669,103 @@ fn parse_search_item_html(item_html: &str) -> Result<SearchProductItem> {
     })
 }
 
-pub(crate) fn parse_search_html(html: &str) -> Result<Vec<SearchProductItem>> {
+pub(crate) fn parse_search_html(
+    html: &str,
+    site: Site,
+) -> Result<(Vec<SearchProductItem>, ParseReport)> {
     let html = Html::parse_fragment(html);
+    let mut report = ParseReport::default();
     let mut result: Vec<SearchProductItem> = vec![];
 
-    for item_element in html.select(&Selector::parse("#search_result_img_box > li").unwrap()) {
-        let product_id_e = item_element
-            .select(&Selector::parse("div[data-product_id]").unwrap())
-            .next()
-            .to_parse_error("Failed to find data element")?
-            .value();
-        let maker_e = item_element
-            .select(&Selector::parse(".maker_name a").unwrap())
-            .next()
-            .to_parse_error("Failed to find maker element")?;
-        let author_e = item_element
-            .select(&Selector::parse(".author").unwrap())
-            .next();
-
-        let price_e = item_element
-            .select(&Selector::parse(".work_price .work_price_base").unwrap())
-            .next()
-            .to_parse_error("Failed to find price element")?;
-        let original_price_e = item_element
-            .select(&Selector::parse(".work_price_wrap .strike .work_price_base").unwrap())
-            .next();
-        let (sale_price_e, original_price_e) = if let Some(e) = original_price_e {
-            (Some(price_e), e)
-        } else {
-            (None, price_e)
-        };
-        let id = product_id_e
-            .attr("data-product_id")
-            .to_parse_error("Failed to get product id")?
-            .to_string();
-
-        result.push(SearchProductItem {
-            id: id.clone(),
-            title: item_element
-                .select(&Selector::parse(".work_name a[title]").unwrap())
-                .next()
-                .to_parse_error("Failed to get title")?
-                .value()
-                .attr("title")
-                .unwrap()
-                .to_string(),
-            age_category: {
-                if let Some(e) = item_element
-                    .select(&Selector::parse(".work_genre span").unwrap())
-                    .next()
-                {
-                    let title = e.value().attr("title");
-                    if let Some(title) = title {
-                        match title {
-                            "全年齢" => AgeCategory::General,
-                            "R-15" => AgeCategory::R15,
-                            _ => {
-                                return Err(crate::DlsiteError::Parse(
-                                    "Age category parse error: invalid title".to_string(),
-                                ))
-                            }
-                        }
-                    } else {
-                        return Err(crate::DlsiteError::Parse(
-                            "Age category parse error".to_string(),
-                        ));
-                    }
-                } else {
-                    AgeCategory::Adult
-                }
-            },
-            circle_name: maker_e.text().next().unwrap_or("").to_string(),
-            circle_id: maker_e
-                .value()
-                .attr("href")
-                .to_parse_error("Failed to get maker link")?
-                .split('/')
-                .next_back()
-                .to_parse_error("Invalid url")?
-                .split('.')
-                .next()
-                .to_parse_error("Failed to find maker id")?
-                .to_string(),
-            creator: {
-                if let Some(creator_e) = author_e {
-                    let name = creator_e
-                        .select(&Selector::parse("a").unwrap())
-                        .next()
-                        .to_parse_error("Failed to find creator")?
-                        .text()
-                        .next()
-                        .to_parse_error("Failed to find creator")?
-                        .to_string();
-                    Some(name)
-                } else {
-                    None
-                }
-            },
-            creator_omitted: {
-                if let Some(creator_e) = author_e {
-                    let omitted = creator_e
-                        .value()
-                        .attr("class")
-                        .to_parse_error("Failed to find creator")?
-                        .split(" ")
-                        .any(|x| x == "omit");
-                    Some(omitted)
-                } else {
-                    None
-                }
-            },
-            dl_count: {
-                if let Some(e) = item_element
-                    .select(&Selector::parse(".work_dl span[class*=\"dl_count\"]").unwrap())
-                    .next()
-                {
-                    Some(
-                        e.text()
-                            .next()
-                            .to_parse_error("Failed to get dl count")?
-                            .replace(',', "")
-                            .parse()
-                            .to_parse_error("Invalid dl count")?,
-                    )
-                } else {
-                    None
-                }
-            },
-            rate_count: {
-                if let Some(e) = item_element
-                    .select(&Selector::parse(".work_dl span[class*=\"dl_count\"]").unwrap())
-                    .next()
-                {
-                    Some(parse_count_str(
-                        e.text().next().to_parse_error("Failed to get rate count")?,
-                    )?)
-                } else {
-                    None
-                }
-            },
-            review_count: {
-                if let Some(e) = item_element
-                    .select(&Selector::parse(".work_review div a").unwrap())
-                    .next()
-                {
-                    Some(parse_count_str(
-                        e.text()
-                            .next()
-                            .to_parse_error("Failed to get review count")?,
-                    )?)
-                } else {
-                    None
-                }
-            },
-            price_original: parse_num_str(
-                original_price_e
-                    .text()
-                    .next()
-                    .to_parse_error("Failed to find price")?,
-            )?,
-            price_sale: {
-                match sale_price_e {
-                    Some(e) => Some(parse_num_str(
-                        e.text().next().to_parse_error("Failed to find price")?,
-                    )?),
-                    None => None,
-                }
-            },
-            work_type: item_element
-                .select(&Selector::parse(".work_category").unwrap())
-                .next()
-                .to_parse_error("Failed to find work category")?
-                .value()
-                .attr("class")
-                .to_parse_error("Failed to find worktype")?
-                .split(' ')
-                .find_map(|c| {
-                    if let Some(c) = c.strip_prefix("type_") {
-                        if let Ok(wt) = c.parse::<WorkType>() {
-                            if let WorkType::Unknown(_) = wt {
-                                return None;
-                            } else {
-                                return Some(wt);
-                            }
-                        }
-                    }
-                    None
-                })
-                .unwrap_or(WorkType::Unknown("".to_string())),
-            thumbnail_url: {
-                let img_e = item_element
-                    .select(&Selector::parse(".work_thumb_inner > img").unwrap())
-                    .next()
-                    .to_parse_error("Failed to find thumbnail")?;
-
-                let src = img_e.value().attr("src");
-                let data_src = img_e.value().attr("data-src");
-                match (src, data_src) {
-                    (Some(src), _) => format!("https:{}", src),
-                    (_, Some(data_src)) => format!("https:{}", data_src),
-                    (_, _) => {
-                        return Err(crate::DlsiteError::Parse(
-                            "Failed to find thumbnail".to_string(),
-                        ))
-                    }
-                }
-            },
-            rating: {
-                if let Some(e) = item_element
-                    .select(&Selector::parse(".work_rating .star_rating").unwrap())
-                    .next()
-                {
-                    e.value()
-                        .attr("class")
-                        .expect("Failed to get rating")
-                        .split(' ')
-                        .find_map(|c| {
-                            if let Some(c) = c.strip_prefix("star_") {
-                                if let Ok(r) = c.parse::<f32>() {
-                                    return Some(r / 10.0);
-                                }
-                            }
-                            None
-                        })
-                } else {
-                    None
-                }
-            }, // image_url: {
-               //     if let Some(e) = item_element
-               //         .select(&Selector::parse(".work_img_popover img").unwrap())
-               //         .next()
-               //     {
-               //         Some(
-               //             e.value()
-               //                 .attr("src")
-               //                 .to_parse_error("Failed to get image url")?
-               //                 .to_string(),
-               //         )
-               //     } else {
-               //         None
-               //     }
-               // },
-        })
+    for item_element in selectors::search_result_items().select_all(&html, &mut report) {
+        result.push(parse_search_item_html(
+            &item_element.html(),
+            site,
+            &mut report,
+        )?);
     }
 
-    Ok(result)
+    Ok((result, report))
 }
 
 /// Parse search HTML using parallel processing for better performance
 /// This function is optimized for large result sets (50+ items)
-pub(crate) fn parse_search_html_parallel(html: &str) -> Result<Vec<SearchProductItem>> {
+pub(crate) fn parse_search_html_parallel(
+    html: &str,
+    site: Site,
+) -> Result<(Vec<SearchProductItem>, ParseReport)> {
+    let (products, report, skipped) = parse_search_html_lenient(html, site);
+    match skipped.into_iter().next() {
+        Some((_, e)) => Err(e),
+        None => Ok((products, report)),
+    }
+}
+
+/// Items of a search page, its parse report and the items which failed to parse with their
+/// position.
+type LenientPage = (
+    Vec<SearchProductItem>,
+    ParseReport,
+    Vec<(usize, DlsiteError)>,
+);
+
+/// Parse a search page of `c`, leniently if [`DlsiteClient::lenient_parsing`] is set.
+fn parse_search_page(c: &DlsiteClient, html: &str) -> Result<LenientPage> {
+    if !c.lenient_parsing() {
+        let (products, report) = parse_search_html_parallel(html, c.site())?;
+        return Ok((products, report, vec![]));
+    }
+    let (products, report, skipped) = parse_search_html_lenient(html, c.site());
+    for (position, e) in &skipped {
+        tracing::warn!("Skipped search result {position}: {e}");
+    }
+    Ok((products, report, skipped))
+}
+
+/// Parse search HTML like [`parse_search_html_parallel`], skipping the items which fail to
+/// parse. They are returned with their position in the page.
+pub(crate) fn parse_search_html_lenient(html: &str, site: Site) -> LenientPage {
     let html = Html::parse_fragment(html);
+    let mut report = ParseReport::default();
 
     // Collect all item elements as HTML strings
-    let items: Vec<String> = html
-        .select(&Selector::parse("#search_result_img_box > li").unwrap())
+    let items: Vec<String> = selectors::search_result_items()
+        .select_all(&html, &mut report)
+        .into_iter()
         .map(|elem| elem.html())
         .collect();
 
     // Process items in parallel
-    items
-        .par_iter()
-        .map(|item_html| parse_search_item_html(item_html))
-        .collect()
+    #[cfg(feature = "parallel")]
+    let items = items.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let items = items.iter();
+    let parsed: Vec<Result<(SearchProductItem, ParseReport)>> = items
+        .map(|item_html| {
+            let mut report = ParseReport::default();
+            parse_search_item_html(item_html, site, &mut report).map(|item| (item, report))
+        })
+        .collect();
+
+    let mut products = Vec::with_capacity(parsed.len());
+    let mut skipped = vec![];
+    for (position, item) in parsed.into_iter().enumerate() {
+        match item {
+            Ok((item, item_report)) => {
+                products.push(item);
+                report.merge(item_report);
+            }
+            Err(e) => skipped.push((position, e)),
+        }
+    }
+    if report.used_fallback() {
+        tracing::warn!(
+            "Search results parsed with fallback selectors: {:?}",
+            report.fallbacks().collect::<Vec<_>>()
+        );
+    }
+
+    (products, report, skipped)
 }
 
 #[cfg(test)]
@@ -703,11 +773,93 @@ mod tests {
     use crate::{
         client::DlsiteClient,
         interface::{
-            product::WorkType,
+            product::{AgeCategory, WorkType},
             query::{Order, SexCategory},
+            site::Site,
         },
     };
 
+    /// Search result item as listed on `site`.
+    fn item(site: &str, id: &str, age_badge: &str, category: &str) -> String {
+        format!(
+            r#"<ul id="search_result_img_box"><li>
+                <div data-product_id="{id}"></div>
+                <div class="work_thumb"><img src="//img.dlsite.jp/{site}/{id}.jpg"></div>
+                <div class="work_name"><a href="/{site}/work/=/product_id/{id}.html" title="{id} title">{id} title</a></div>
+                <div class="maker_name"><a href="https://www.dlsite.com/{site}/circle/profile/=/maker_id/RG60001.html">Circle</a></div>
+                <div class="work_price">1,320</div>
+                {category}
+                <div class="work_genre">{age_badge}</div>
+            </li></ul>"#
+        )
+    }
+
+    #[test]
+    fn parse_girls_items() {
+        let html = item(
+            "girls",
+            "RJ01100001",
+            r#"<span class="icon_ADL"></span>"#,
+            r#"<div class="work_category type_SOU"><a>ボイス・ASMR</a></div>"#,
+        );
+        let (items, _) = super::parse_search_html(&html, Site::Girls).unwrap();
+        assert_eq!(items[0].age_category, AgeCategory::Adult);
+        assert_eq!(items[0].work_type, WorkType::SOU);
+
+        // Genre tag instead of an age badge: unmarked work
+        let html = item(
+            "girls",
+            "RJ01100002",
+            r#"<span class="icon_TOW">乙女向け</span>"#,
+            r#"<div class="work_category"><a>ドラマCD</a></div>"#,
+        );
+        let (items, _) = super::parse_search_html(&html, Site::Girls).unwrap();
+        assert_eq!(items[0].age_category, AgeCategory::Adult);
+        assert_eq!(items[0].work_type, WorkType::SOU);
+    }
+
+    #[test]
+    fn parse_leniently() {
+        let category = r#"<div class="work_category type_SOU"><a>ボイス・ASMR</a></div>"#;
+        let html = [
+            item("maniax", "RJ01100005", "", category),
+            r#"<ul id="search_result_img_box"><li><div>broken</div></li></ul>"#.to_string(),
+            item("maniax", "RJ01100006", "", category),
+        ]
+        .concat();
+        assert!(super::parse_search_html_parallel(&html, Site::Maniax).is_err());
+
+        let (items, _, skipped) = super::parse_search_html_lenient(&html, Site::Maniax);
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["RJ01100005", "RJ01100006"]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, 1);
+    }
+
+    #[test]
+    fn parse_bl_items() {
+        let html = item(
+            "bl",
+            "RJ01100003",
+            r#"<span class="icon_GEN" title="全年齢">全年齢</span>"#,
+            r#"<div class="work_category type_bl"><a>マンガ</a></div>"#,
+        );
+        let (items, _) = super::parse_search_html_parallel(&html, Site::Bl).unwrap();
+        assert_eq!(items[0].age_category, AgeCategory::General);
+        assert_eq!(items[0].work_type, WorkType::MNG);
+        assert_eq!(items[0].circle_id, "RG60001");
+
+        let html = item(
+            "bl",
+            "RJ01100004",
+            r#"<span class="icon_R15" title="R15"></span>"#,
+            r#"<div class="work_category type_MOV">動画</div>"#,
+        );
+        let (items, _) = super::parse_search_html(&html, Site::Bl).unwrap();
+        assert_eq!(items[0].age_category, AgeCategory::R15);
+        assert_eq!(items[0].work_type, WorkType::MOV);
+    }
+
     #[tokio::test]
     async fn search_product_1() {
         let client = DlsiteClient::default();
@@ -746,6 +898,51 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn search_product_favorites() {
+        let client = DlsiteClient::default();
+        let res = client
+            .search()
+            .search_product(&super::SearchProductQuery {
+                order: Some(Order::FavoriteD),
+                ..Default::default()
+            })
+            .await
+            .expect("Failed to search");
+
+        assert!(!res.products.is_empty());
+        let counts: Vec<i32> = res.products.iter().filter_map(|r| r.favorite_count).collect();
+        assert!(counts.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[tokio::test]
+    async fn search_product_sites() {
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .site(Site::Books)
+            .build();
+        let res = client
+            .search()
+            .search_product(&Default::default())
+            .await
+            .expect("Failed to search books");
+        assert!(!res.products.is_empty());
+        assert!(res.products.iter().all(|r| r.id.starts_with("BJ")));
+
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .site(Site::Home)
+            .build();
+        let res = client
+            .search()
+            .search_product(&Default::default())
+            .await
+            .expect("Failed to search home");
+        assert!(!res.products.is_empty());
+        assert!(res
+            .products
+            .iter()
+            .all(|r| r.age_category != AgeCategory::Adult));
+    }
+
     #[tokio::test]
     async fn search_product_2() {
         let client = DlsiteClient::default();
@@ -778,4 +975,95 @@ mod tests {
         });
         assert_eq!(50, res.products.len());
     }
+
+    #[tokio::test]
+    async fn new_arrivals() {
+        let client = DlsiteClient::default();
+        let since = chrono::Local::now().date_naive() - chrono::Days::new(3);
+        let works = client.new_arrivals(since).await.unwrap();
+        assert!(!works.is_empty());
+
+        let mut ids: Vec<_> = works.iter().map(|w| &w.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), works.len());
+    }
+
+    #[tokio::test]
+    async fn new_arrivals_post_processing() {
+        use std::time::Duration;
+
+        use chrono::NaiveDate;
+
+        use crate::{normalize::Normalizer, testing::FixtureTransport, DlsiteError};
+
+        let since = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        let url = format!(
+            "https://www.dlsite.com/maniax{}",
+            super::SearchProductQuery {
+                order: Some(Order::Release),
+                release_date_from: Some(since),
+                per_page: Some(100),
+                page: Some(1),
+                ..Default::default()
+            }
+            .to_path()
+        );
+        let category = r#"<div class="work_category type_SOU"><a>ボイス・ASMR</a></div>"#;
+        let html = item("maniax", "RJ01100001", "", category)
+            .replace("RJ01100001 title", "ねこぐらし　ＡＳＭＲ");
+        let body = serde_json::json!({ "search_result": html, "page_info": { "count": 1 } });
+
+        // Titles are normalized like other searches
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::ZERO, Duration::ZERO)
+            .transport(FixtureTransport::new().with_body(&url, body.to_string()))
+            .normalize_titles(Normalizer::default())
+            .build();
+        let works = client.new_arrivals(since).await.unwrap();
+        assert_eq!(works[0].title, "ねこぐらし ASMR");
+
+        // Responses failing to parse are dumped
+        let dir = std::env::temp_dir().join(format!("dlsite-new-arrivals-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::ZERO, Duration::ZERO)
+            .transport(FixtureTransport::new().with_body(&url, "{}"))
+            .dump_failed_responses(&dir)
+            .build();
+        assert!(matches!(
+            client.new_arrivals(since).await,
+            Err(DlsiteError::SerdeJson(_) | DlsiteError::Parse(_))
+        ));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn search_product_paged() {
+        use futures::{StreamExt as _, TryStreamExt as _};
+
+        let client = DlsiteClient::default();
+        let search = client.search();
+        let query = super::SearchProductQuery {
+            sex_category: Some(vec![SexCategory::Male]),
+            keyword: Some("ねこぐらし".to_string()),
+            per_page: Some(30),
+            ..Default::default()
+        };
+
+        // Crosses the first page boundary, then stops early
+        let works: Vec<_> = search
+            .search_product_paged(&query)
+            .take(40)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(works.len(), 40);
+
+        let mut ids: Vec<_> = works.iter().map(|w| &w.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), works.len());
+    }
 }
@@ -1,8 +1,15 @@
 //! Search options for dlsite product search
 
+use std::{fmt, str::FromStr};
+
+use chrono::NaiveDate;
+
 use crate::client::search::macros::*;
+use crate::error::Result;
+use crate::interface::genre::GenreCode;
 use crate::interface::product::*;
 use crate::interface::query::*;
+use crate::DlsiteError;
 
 // Struct that can be converted dlsite url (below is example). All params are optional.
 // https://www.dlsite.com/maniax/fsr/=
@@ -46,7 +53,13 @@ pub struct SearchProductQuery {
     pub sex_category: Option<Vec<SexCategory>>,
     pub keyword: Option<String>,
     pub regist_date_end: Option<String>,
+    /// Only works released on or after this date
+    pub release_date_from: Option<NaiveDate>,
+    /// Only works released on or before this date. Ignored if `regist_date_end` is set.
+    pub release_date_to: Option<NaiveDate>,
+    /// Minimum price in yen
     pub price_low: Option<u32>,
+    /// Maximum price in yen
     pub price_high: Option<u32>,
     /// Sales status
     pub ana_flg: Option<AnaFlg>,
@@ -55,7 +68,7 @@ pub struct SearchProductQuery {
     pub order: Option<Order>,
     pub work_type: Option<Vec<WorkType>>,
     pub work_type_category: Option<Vec<WorkTypeCategory>>,
-    pub genre: Option<Vec<u32>>,
+    pub genre: Option<Vec<GenreCode>>,
     pub options_and_or: Option<OptionAndOr>,
     pub options: Option<Vec<String>>,
     pub options_not: Option<Vec<String>>,
@@ -69,19 +82,36 @@ pub struct SearchProductQuery {
     pub soon: Option<bool>,
     pub is_pointup: Option<bool>,
     pub is_free: Option<bool>,
+    /// Only works on sale
+    pub discount_only: Option<bool>,
+    /// Minimum discount rate in percent (e.g. 50 for "50% off or more")
+    pub discount_rate_min: Option<u32>,
     pub release_term: Option<ReleaseTerm>,
+    /// Only works supporting one of these platforms
+    pub platform: Option<Vec<Platform>>,
 }
 
 impl SearchProductQuery {
     /// Convert the struct to a path, which can be used to make a request to the dlsite.
     pub fn to_path(&self) -> String {
+        self.to_path_with_page(self.page)
+    }
+
+    /// Same as [`SearchProductQuery::to_path`], for the given page instead of `page`.
+    pub(crate) fn to_path_with_page(&self, page: Option<u32>) -> String {
         let mut path = "/fsr/ajax/=".to_string();
 
         push!(path, self, language);
         push_option!(path, self, keyword_creator);
         push_option_array!(path, self, sex_category);
         push_option!(path, self, keyword);
+        if let Some(date) = self.release_date_from {
+            path.push_str(&format!("/regist_date_start/{}", date.format("%Y-%m-%d")));
+        }
         push_option!(path, self, regist_date_end);
+        if let (None, Some(date)) = (&self.regist_date_end, self.release_date_to) {
+            path.push_str(&format!("/regist_date_end/{}", date.format("%Y-%m-%d")));
+        }
         push_option!(path, self, price_low);
         push_option!(path, self, price_high);
         push_option!(path, self, ana_flg);
@@ -97,22 +127,221 @@ impl SearchProductQuery {
         push_option_array!(path, self, file_type);
         push_option!(path, self, rate_average);
         push_option!(path, self, per_page);
-        push_option!(path, self, page);
+        if let Some(page) = page {
+            path.push_str(&format!("/page/{}", page));
+        }
         push_option_bool!(path, self, campagin);
         push_option_bool!(path, self, soon);
         push_option_bool!(path, self, is_pointup);
         push_option_bool!(path, self, is_free);
+        push_option_bool!(path, self, discount_only);
+        push_option!(path, self, discount_rate_min);
         push_option!(path, self, release_term);
+        push_option_array!(path, self, platform);
 
         path
     }
+
+    /// Parse a DLsite search URL, such as one copied from a browser. See [`QueryPath::parse`].
+    pub fn from_url(url: &str) -> Result<Self> {
+        QueryPath::parse(url)?.to_query()
+    }
+}
+
+/// Path segments of a DLsite search, as decoded `(key, value)` pairs.
+///
+/// Converts between [`SearchProductQuery`] and the `/fsr/=/...` paths used by DLsite search
+/// pages, in both directions.
+///
+/// # Example
+/// ```
+/// use dlsite_gamebox::client::search::QueryPath;
+///
+/// let url = "https://www.dlsite.com/maniax/fsr/=/language/jp/keyword/%E7%8C%AB/genre%5B0%5D/497/order%5B0%5D/trend";
+/// let query = QueryPath::parse(url).unwrap().to_query().unwrap();
+/// assert_eq!(query.keyword.as_deref(), Some("猫"));
+/// assert_eq!(query.to_path(), "/fsr/ajax/=/language/jp/keyword/猫/order/trend/genre[0]/497");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryPath {
+    segments: Vec<(String, String)>,
+}
+
+impl QueryPath {
+    /// Parse the search part of a URL or path (everything after `/fsr/=` or `/fsr/ajax/=`).
+    ///
+    /// Percent-encoded segments are decoded, and the query string and fragment are ignored.
+    pub fn parse(url_or_path: &str) -> Result<Self> {
+        let path = url_or_path.split(['?', '#']).next().unwrap_or_default();
+        let parts: Vec<&str> = path.split('/').collect();
+        let start = parts
+            .iter()
+            .position(|&p| p == "fsr")
+            .and_then(|i| match parts.get(i + 1..) {
+                Some(["=", ..]) => Some(i + 2),
+                Some(["ajax", "=", ..]) => Some(i + 3),
+                _ => None,
+            })
+            .ok_or_else(|| DlsiteError::Parse(format!("Not a search URL: {}", url_or_path)))?;
+
+        let parts: Vec<&str> = parts[start..]
+            .iter()
+            .copied()
+            .filter(|p| !p.is_empty())
+            .collect();
+        let segments = parts
+            .chunks(2)
+            .filter_map(|pair| match pair {
+                [key, value] => Some((percent_decode(key), percent_decode(value))),
+                _ => None,
+            })
+            .collect();
+        Ok(Self { segments })
+    }
+
+    /// Decoded `(key, value)` pairs, in the order of the path. Array keys keep their index,
+    /// e.g. `genre[0]`.
+    pub fn segments(&self) -> &[(String, String)] {
+        &self.segments
+    }
+
+    /// Convert to a typed query.
+    ///
+    /// Keys DLsite only uses for display (`genre_name`, `show_type`, `from`...) and unknown
+    /// keys are ignored.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Parse`] if a value is invalid for its key.
+    pub fn to_query(&self) -> Result<SearchProductQuery> {
+        let mut query = SearchProductQuery::default();
+        for (key, value) in &self.segments {
+            let name = key.split_once('[').map_or(key.as_str(), |(name, _)| name);
+            match name {
+                "language" => query.language = parse_value(name, value)?,
+                "keyword_creator" => query.keyword_creator = Some(value.clone()),
+                "sex_category" => push_value(&mut query.sex_category, name, value)?,
+                "keyword" => query.keyword = Some(value.clone()),
+                "regist_date_start" => query.release_date_from = Some(parse_value(name, value)?),
+                "regist_date_end" => match value.parse() {
+                    Ok(date) => query.release_date_to = Some(date),
+                    Err(_) => query.regist_date_end = Some(value.clone()),
+                },
+                "price_low" => query.price_low = Some(parse_value(name, value)?),
+                "price_high" => query.price_high = Some(parse_value(name, value)?),
+                "ana_flg" => query.ana_flg = Some(parse_value(name, value)?),
+                "age_category" => push_value(&mut query.age_category, name, value)?,
+                "work_category" => push_value(&mut query.work_category, name, value)?,
+                "order" => query.order = Some(parse_value(name, value)?),
+                "work_type" => push_value(&mut query.work_type, name, value)?,
+                "work_type_category" => push_value(&mut query.work_type_category, name, value)?,
+                "genre" => push_value(&mut query.genre, name, value)?,
+                "options_and_or" => query.options_and_or = Some(parse_value(name, value)?),
+                "options" => push_value(&mut query.options, name, value)?,
+                "options_not" => push_value(&mut query.options_not, name, value)?,
+                "file_type" => push_value(&mut query.file_type, name, value)?,
+                "rate_average" => query.rate_average = Some(parse_value(name, value)?),
+                "per_page" => query.per_page = Some(parse_value(name, value)?),
+                "page" => query.page = Some(parse_value(name, value)?),
+                // Browsers send `campaign/campaign`, the query renders `campagin/1`
+                "campaign" | "campagin" => query.campagin = Some(true),
+                "soon" => query.soon = Some(value == "1"),
+                "is_pointup" => query.is_pointup = Some(value == "1"),
+                "is_free" => query.is_free = Some(value == "1"),
+                "discount_only" => query.discount_only = Some(value == "1"),
+                "discount_rate_min" => query.discount_rate_min = Some(parse_value(name, value)?),
+                "release_term" => query.release_term = Some(parse_value(name, value)?),
+                "platform" => push_value(&mut query.platform, name, value)?,
+                _ => {}
+            }
+        }
+        Ok(query)
+    }
+}
+
+impl From<&SearchProductQuery> for QueryPath {
+    fn from(query: &SearchProductQuery) -> Self {
+        let path = query.to_path();
+        let parts: Vec<&str> = path
+            .trim_start_matches("/fsr/ajax/=")
+            .split('/')
+            .filter(|p| !p.is_empty())
+            .collect();
+        Self {
+            segments: parts
+                .chunks_exact(2)
+                .map(|pair| (pair[0].to_string(), pair[1].to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Renders the same path as [`SearchProductQuery::to_path`].
+impl fmt::Display for QueryPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("/fsr/ajax/=")?;
+        for (key, value) in &self.segments {
+            write!(f, "/{}/{}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for QueryPath {
+    type Err = DlsiteError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| DlsiteError::Parse(format!("Invalid {}: {}", key, value)))
+}
+
+fn push_value<T: FromStr>(values: &mut Option<Vec<T>>, key: &str, value: &str) -> Result<()> {
+    values
+        .get_or_insert_with(Vec::new)
+        .push(parse_value(key, value)?);
+    Ok(())
+}
+
+/// Decode `%XX` escapes. Invalid escapes are kept as is.
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::NaiveDate;
+
     use crate::{
-        client::search::SearchProductQuery,
-        interface::{product::FileType, query::SexCategory},
+        client::search::{QueryPath, SearchProductQuery},
+        interface::{
+            genre::GenreCode,
+            product::{AgeCategory, FileType, Platform},
+            query::{Order, ReleaseTerm, SexCategory},
+        },
     };
 
     #[test]
@@ -137,4 +366,109 @@ mod tests {
             .to_path()
         );
     }
+
+    #[test]
+    fn product_search_param_platform() {
+        assert_eq!(
+            "/fsr/ajax/=/language/jp/platform[0]/android/platform[1]/browser",
+            SearchProductQuery {
+                platform: Some(vec![Platform::Android, Platform::Browser]),
+                ..Default::default()
+            }
+            .to_path()
+        );
+    }
+
+    #[test]
+    fn product_search_param_genre() {
+        let query = SearchProductQuery {
+            genre: Some(vec![GenreCode::ASMR, GenreCode(60)]),
+            ..Default::default()
+        };
+        let path = query.to_path();
+        assert_eq!("/fsr/ajax/=/language/jp/genre[0]/497/genre[1]/60", path);
+
+        // Codes read back from the path match the query
+        let codes: Vec<GenreCode> = path
+            .split('/')
+            .collect::<Vec<_>>()
+            .windows(2)
+            .filter(|w| w[0].starts_with("genre["))
+            .map(|w| w[1].parse().unwrap())
+            .collect();
+        assert_eq!(Some(codes), query.genre);
+    }
+
+    #[test]
+    fn product_search_param_discount() {
+        assert_eq!(
+            "/fsr/ajax/=/language/jp/price_high/1000/discount_only/1/discount_rate_min/50",
+            SearchProductQuery {
+                price_high: Some(1000),
+                discount_only: Some(true),
+                discount_rate_min: Some(50),
+                ..Default::default()
+            }
+            .to_path()
+        );
+    }
+
+    #[test]
+    fn product_search_param_release_date() {
+        let query = SearchProductQuery {
+            release_date_from: NaiveDate::from_ymd_opt(2024, 6, 1),
+            release_date_to: NaiveDate::from_ymd_opt(2024, 6, 7),
+            ..Default::default()
+        };
+        let path = query.to_path();
+        assert_eq!(
+            "/fsr/ajax/=/language/jp/regist_date_start/2024-06-01/regist_date_end/2024-06-07",
+            path
+        );
+
+        let parsed = SearchProductQuery::from_url(&path).unwrap();
+        assert_eq!(parsed.release_date_from, query.release_date_from);
+        assert_eq!(parsed.release_date_to, query.release_date_to);
+    }
+
+    #[test]
+    fn query_path_roundtrip() {
+        let query = SearchProductQuery {
+            keyword: Some("ねこ".to_string()),
+            sex_category: Some(vec![SexCategory::Male]),
+            age_category: Some(vec![AgeCategory::General, AgeCategory::R15]),
+            order: Some(Order::DlD),
+            genre: Some(vec![GenreCode::ASMR]),
+            per_page: Some(50),
+            page: Some(2),
+            campagin: Some(true),
+            release_term: Some(ReleaseTerm::Week),
+            platform: Some(vec![Platform::Android]),
+            ..Default::default()
+        };
+        let path = QueryPath::from(&query);
+        assert_eq!(path.to_string(), query.to_path());
+
+        let parsed: QueryPath = query.to_path().parse().unwrap();
+        assert_eq!(parsed, path);
+        assert_eq!(parsed.to_query().unwrap().to_path(), query.to_path());
+    }
+
+    #[test]
+    fn query_path_browser_url() {
+        let url = "https://www.dlsite.com/maniax/fsr/=/language/jp/sex_category%5B0%5D/male\
+                   /keyword/%E3%83%8D%E3%82%B3/genre%5B0%5D/497/genre_name%5B0%5D/ASMR\
+                   /order%5B0%5D/trend/per_page/30/page/1/campaign/campaign/from/fs.detail?locale=ja_JP";
+        let query = SearchProductQuery::from_url(url).unwrap();
+        assert_eq!(query.keyword.as_deref(), Some("ネコ"));
+        assert_eq!(query.genre, Some(vec![GenreCode::ASMR]));
+        assert!(matches!(query.order, Some(Order::Trend)));
+        assert_eq!(query.per_page, Some(30));
+        assert_eq!(query.campagin, Some(true));
+
+        assert!(
+            QueryPath::parse("https://www.dlsite.com/maniax/work/=/product_id/RJ1.html").is_err()
+        );
+        assert!(SearchProductQuery::from_url("/fsr/=/order/newest").is_err());
+    }
 }
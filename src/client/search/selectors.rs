@@ -1,118 +1,140 @@
-/// Cached CSS selectors for search result parsing
-/// This module provides pre-compiled selectors to avoid recompiling them on every parse
+//! Cached CSS selectors for search result parsing
+//! This module provides pre-compiled selectors to avoid recompiling them on every parse
+//!
+//! Each selector is a fallback chain: the current DLsite markup first, then older variants.
 
-use scraper::Selector;
+use crate::selector::SelectorChain;
 use std::sync::OnceLock;
 
 /// Get the selector for search result items
-pub fn search_result_items() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn search_result_items() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse("#search_result_img_box > li").expect("Failed to parse selector")
+        SelectorChain::new(
+            "items",
+            &[
+                "#search_result_img_box > li",
+                ".n_worklist > li",
+                "#search_result_list .search_result_img_box_inner",
+            ],
+        )
     })
 }
 
 /// Get the selector for product ID element
-pub fn product_id_element() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn product_id_element() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse("div[data-product_id]").expect("Failed to parse selector")
+        SelectorChain::new("id", &["div[data-product_id]", "[data-product_id]"])
     })
 }
 
 /// Get the selector for maker name
-pub fn maker_name() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn maker_name() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".maker_name a").expect("Failed to parse selector")
+        SelectorChain::new("circle", &[".maker_name a", ".circle_name a"])
     })
 }
 
 /// Get the selector for author
-pub fn author() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn author() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".author").expect("Failed to parse selector")
+        SelectorChain::new("creator", &[".author"])
     })
 }
 
 /// Get the selector for work price
-pub fn work_price() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn work_price() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_price .work_price_base").expect("Failed to parse selector")
+        SelectorChain::new("price", &[".work_price .work_price_base", ".work_price"])
     })
 }
 
 /// Get the selector for original price
-pub fn original_price() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn original_price() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_price_wrap .strike .work_price_base").expect("Failed to parse selector")
+        SelectorChain::new(
+            "original_price",
+            &[
+                ".work_price_wrap .strike .work_price_base",
+                ".work_price_wrap .strike",
+            ],
+        )
     })
 }
 
 /// Get the selector for work title
-pub fn work_title() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn work_title() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_name a[title]").expect("Failed to parse selector")
+        SelectorChain::new("title", &[".work_name a[title]", ".work_name a"])
     })
 }
 
 /// Get the selector for age category
-pub fn age_category() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn age_category() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_genre span").expect("Failed to parse selector")
+        SelectorChain::new("age_category", &[".work_genre span"])
     })
 }
 
 /// Get the selector for download count
-pub fn dl_count() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn dl_count() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_dl span[class*=\"dl_count\"]").expect("Failed to parse selector")
+        SelectorChain::new("dl_count", &[".work_dl span[class*=\"dl_count\"]", "._dl_count"])
     })
 }
 
 /// Get the selector for review count
-pub fn review_count() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn review_count() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_review div a").expect("Failed to parse selector")
+        SelectorChain::new("review_count", &[".work_review div a", ".work_review a"])
+    })
+}
+
+/// Get the selector for favorite count
+pub fn favorite_count() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
+    SELECTOR.get_or_init(|| {
+        SelectorChain::new("favorite_count", &[".work_favorite span", "._favorite_count"])
     })
 }
 
 /// Get the selector for work category
-pub fn work_category() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn work_category() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_category").expect("Failed to parse selector")
+        SelectorChain::new("work_type", &[".work_category", "[class*=\"type_\"]"])
     })
 }
 
 /// Get the selector for thumbnail image
-pub fn thumbnail_image() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn thumbnail_image() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_thumb_inner > img").expect("Failed to parse selector")
+        SelectorChain::new("thumbnail", &[".work_thumb_inner > img", ".work_thumb img"])
     })
 }
 
 /// Get the selector for rating
-pub fn rating() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn rating() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse(".work_rating .star_rating").expect("Failed to parse selector")
+        SelectorChain::new("rating", &[".work_rating .star_rating", ".star_rating"])
     })
 }
 
 /// Get the selector for creator link
-pub fn creator_link() -> &'static Selector {
-    static SELECTOR: OnceLock<Selector> = OnceLock::new();
+pub fn creator_link() -> &'static SelectorChain {
+    static SELECTOR: OnceLock<SelectorChain> = OnceLock::new();
     SELECTOR.get_or_init(|| {
-        Selector::parse("a").expect("Failed to parse selector")
+        SelectorChain::new("creator_link", &["a"])
     })
 }
-
@@ -0,0 +1,195 @@
+//! Helpers shared by the clients of storefronts with their own page layout ([`super::pro`],
+//! [`super::app`] and [`super::book`]).
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use scraper::{ElementRef, Html, Selector};
+
+use super::{
+    product::{
+        ajax::ProductAjax,
+        html::{get_work_outline_table, parse_release_date, total_size},
+        html_path,
+    },
+    DlsiteClient,
+};
+use crate::{
+    error::Result,
+    interface::{genre::Genre, product::AgeCategory, product_id::ProductId, site::Site},
+    utils::ToParseError as _,
+    DlsiteError,
+};
+
+/// Fields of the ajax api common to every storefront work.
+pub(crate) struct AjaxSummary {
+    /// Normalized title, see [`DlsiteClient::normalize_title`]
+    pub title: String,
+    pub price: i32,
+    pub sale_count: Option<i32>,
+    pub rating: Option<f32>,
+    pub rate_count: Option<i32>,
+}
+
+impl DlsiteClient {
+    /// Fetch the page of a product on `site` and parse it with `parse`.
+    pub(crate) async fn fetch_storefront_page<T>(
+        &self,
+        site: Site,
+        product_id: &ProductId,
+        parse: fn(&Html) -> Result<T>,
+    ) -> Result<T> {
+        let path = html_path(product_id.as_str());
+        let body = self.get_on(site, &path).await?;
+        let html = Html::parse_document(&body);
+        self.dump_parse_error(site, &path, &body, parse(&html))
+    }
+
+    /// Fetch the page and the ajax api of a product on `site` concurrently.
+    pub(crate) async fn fetch_storefront_work<T>(
+        &self,
+        site: Site,
+        product_id: &ProductId,
+        parse: fn(&Html) -> Result<T>,
+    ) -> Result<(T, AjaxSummary)> {
+        let product = self.product();
+        let (html, ajax) = tokio::try_join!(
+            self.fetch_storefront_page(site, product_id, parse),
+            product.get_ajax_on(product_id.as_str(), site)
+        )?;
+        let ProductAjax {
+            work_name,
+            price,
+            dl_count,
+            rate_average_2dp,
+            rate_count,
+            ..
+        } = ajax;
+        let mut title = work_name;
+        self.normalize_title(&mut title);
+        let ajax = AjaxSummary {
+            title,
+            price,
+            sale_count: dl_count,
+            rating: rate_average_2dp,
+            rate_count,
+        };
+        Ok((html, ajax))
+    }
+}
+
+/// The `#work_outline` table of a page, with accessors for the rows every storefront has.
+pub(crate) struct OutlineTable<'a>(HashMap<String, ElementRef<'a>>);
+
+impl<'a> OutlineTable<'a> {
+    pub fn new(html: &'a Html) -> Self {
+        Self(get_work_outline_table(html))
+    }
+
+    pub fn get(&self, label: &str) -> Option<&ElementRef<'a>> {
+        self.0.get(label)
+    }
+
+    /// Trimmed text of a cell, `None` if missing or empty.
+    pub fn text(&self, label: &str) -> Option<String> {
+        self.get(label)
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Texts of the links in the cells of any of `labels`.
+    pub fn links(&self, labels: &[&str]) -> Vec<String> {
+        let a = Selector::parse("a").unwrap();
+        labels
+            .iter()
+            .filter_map(|label| self.get(label))
+            .flat_map(|e| {
+                e.select(&a)
+                    .map(|a| a.text().collect::<String>().trim().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    /// Texts of the `.work_genre` tags of a cell.
+    pub fn work_genre(&self, label: &str) -> Vec<String> {
+        self.get(label)
+            .and_then(|e| e.select(&Selector::parse(".work_genre").unwrap()).next())
+            .map(|e| {
+                e.child_elements()
+                    .map(|e| e.text().collect::<String>().trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 販売日
+    pub fn released_at(&self) -> Result<NaiveDate> {
+        let released_at = self
+            .get("販売日")
+            .to_parse_error("No released_at found")?
+            .text()
+            .collect::<String>();
+        parse_release_date(released_at.trim()).to_parse_error("Failed to parse released_at")
+    }
+
+    /// 年齢指定
+    pub fn age_rating(&self) -> Result<Option<AgeCategory>> {
+        self.text("年齢指定")
+            .map(|label| {
+                AgeCategory::from_label(&label)
+                    .ok_or_else(|| DlsiteError::Parse(format!("failed to convert {label} to enum")))
+            })
+            .transpose()
+    }
+
+    /// ジャンル
+    pub fn genres(&self) -> Vec<Genre> {
+        self.get("ジャンル")
+            .map(|e| {
+                e.select(&Selector::parse("a").unwrap())
+                    .filter_map(|a| {
+                        let href = a.value().attr("href")?;
+                        let (_, rest) = href.split_once("/genre/")?;
+                        Some(Genre {
+                            name: a.text().collect::<String>().trim().to_string(),
+                            id: rest.split('/').next()?.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// ファイル容量: the total size when the cell lists several files, its raw text otherwise.
+    pub fn file_size(&self) -> Option<String> {
+        self.get("ファイル容量").and_then(|e| {
+            let text = e.text().collect::<String>();
+            total_size(&text).or_else(|| Some(text.trim().to_string()).filter(|s| !s.is_empty()))
+        })
+    }
+}
+
+/// Link to the maker (circle, brand or publisher) of a page.
+pub(crate) fn maker_link(html: &Html) -> Option<ElementRef<'_>> {
+    html.select(&Selector::parse("#work_maker .maker_name a").unwrap())
+        .next()
+}
+
+/// Maker ID of a maker link, e.g. `RG12345`.
+pub(crate) fn maker_id<'a>(link: &ElementRef<'a>) -> Option<&'a str> {
+    link.value()
+        .attr("href")
+        .and_then(|href| href.rsplit('/').next())
+        .and_then(|file| file.split('.').next())
+        .filter(|id| !id.is_empty())
+}
+
+/// Inner html of the description of a page.
+pub(crate) fn description_html(html: &Html) -> Option<String> {
+    html.select(&Selector::parse("[itemprop='description']").unwrap())
+        .next()
+        .map(|e| e.inner_html())
+}
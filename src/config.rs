@@ -0,0 +1,302 @@
+//! Client settings loaded from a TOML profile, so tools built on the crate share one
+//! configuration format. See [`crate::DlsiteClientBuilder::from_config`].
+//!
+//! Every setting is optional; missing ones keep the defaults of [`DlsiteClientBuilder`].
+//! Passwords are never read from the profile, only from the environment variable it names.
+//!
+//! ```toml
+//! base_url = "https://www.dlsite.com/maniax"
+//! locale = "en_US"
+//! timeout_secs = 30
+//! proxy = "http://localhost:3128"
+//! request_budget = 10000
+//!
+//! [rate_limit]
+//! requests = 2
+//! per_ms = 1000
+//!
+//! [cache]
+//! backend = "disk"
+//! dir = "/var/cache/dlsite"
+//! ttl_secs = 86400
+//!
+//! [retry]
+//! max_retries = 5
+//! initial_delay_ms = 200
+//! jitter = "full"
+//!
+//! [credentials]
+//! login_id = "user@example.com"
+//! password_env = "DLSITE_PASSWORD"
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    cache::DiskCache,
+    error::Result,
+    interface::{locale::Locale, site::Site},
+    persist::io_error,
+    retry::{Jitter, RetryConfig},
+    DlsiteClientBuilder, DlsiteError,
+};
+
+/// Base URL used when the profile doesn't set one.
+const DEFAULT_BASE_URL: &str = "https://www.dlsite.com/maniax";
+
+/// Client settings of a TOML profile, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Base URL of the client, `https://www.dlsite.com/maniax` if unset
+    pub base_url: Option<String>,
+    /// Storefront, replacing the one of the base URL
+    pub site: Option<Site>,
+    pub locale: Option<Locale>,
+    pub timeout_secs: Option<u64>,
+    /// Proxy URL all requests are sent through
+    pub proxy: Option<String>,
+    /// See [`DlsiteClientBuilder::request_budget`]
+    pub request_budget: Option<u64>,
+    pub rate_limit: Option<RateLimitConfig>,
+    pub cache: Option<CacheConfig>,
+    pub retry: Option<RetryProfile>,
+    pub credentials: Option<CredentialsConfig>,
+}
+
+/// Request rate of a [`ClientConfig`]: either `requests` per `per_ms` (token bucket, see
+/// [`DlsiteClientBuilder::rate_limit`]), or a gap between `min_interval_ms` and
+/// `max_interval_ms` (see [`DlsiteClientBuilder::request_interval`]).
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub requests: Option<u32>,
+    pub per_ms: Option<u64>,
+    pub min_interval_ms: Option<u64>,
+    /// `min_interval_ms` if unset
+    pub max_interval_ms: Option<u64>,
+}
+
+/// Where a [`ClientConfig`] caches responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheKind {
+    /// In memory, see [`crate::cache::MemoryCache`]
+    #[default]
+    Memory,
+    /// On disk, see [`DiskCache`]
+    Disk,
+}
+
+/// Response cache of a [`ClientConfig`].
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub backend: CacheKind,
+    /// Number of responses kept in memory
+    pub capacity: Option<usize>,
+    pub ttl_secs: Option<u64>,
+    /// Directory of the disk cache, required by [`CacheKind::Disk`]
+    pub dir: Option<PathBuf>,
+}
+
+/// Retry policy of a [`ClientConfig`], unset fields keeping the [`RetryConfig`] defaults.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryProfile {
+    pub max_retries: Option<u32>,
+    pub initial_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>,
+    pub backoff_multiplier: Option<f64>,
+    pub jitter: Option<Jitter>,
+}
+
+/// Account of a [`ClientConfig`], used for [`DlsiteClientBuilder::auto_relogin`]. The
+/// password is read from an environment variable when the builder is made.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CredentialsConfig {
+    pub login_id: Option<String>,
+    /// Environment variable holding the login ID, when `login_id` is unset
+    pub login_id_env: Option<String>,
+    /// Environment variable holding the password
+    pub password_env: String,
+}
+
+impl ClientConfig {
+    /// Parse a TOML profile.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Persist`] if the profile is invalid or has unknown settings.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| DlsiteError::Persist(format!("Invalid config: {e}")))
+    }
+
+    /// Read and parse the TOML profile at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+        Self::from_toml(&text).map_err(|e| DlsiteError::Persist(format!("{}: {e}", path.display())))
+    }
+
+    /// Make a builder with these settings.
+    ///
+    /// # Errors
+    /// Returns [`DlsiteError::Persist`] if a setting can't be applied: invalid proxy URL,
+    /// disk cache without directory, or missing credential environment variable.
+    pub fn builder(&self) -> Result<DlsiteClientBuilder> {
+        let invalid = |message: String| DlsiteError::Persist(format!("Invalid config: {message}"));
+        let mut builder =
+            DlsiteClientBuilder::new(self.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL));
+        if let Some(site) = self.site {
+            builder = builder.site(site);
+        }
+        if let Some(locale) = self.locale {
+            builder = builder.locale(locale);
+        }
+        if let Some(secs) = self.timeout_secs {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(proxy) = &self.proxy {
+            let proxy =
+                reqwest::Proxy::all(proxy).map_err(|e| invalid(format!("proxy {proxy}: {e}")))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(requests) = self.request_budget {
+            builder = builder.request_budget(requests);
+        }
+
+        if let Some(rate) = &self.rate_limit {
+            match (rate.requests, rate.per_ms, rate.min_interval_ms) {
+                (Some(requests), Some(per), _) => {
+                    builder = builder.rate_limit(requests, Duration::from_millis(per));
+                }
+                (None, None, Some(min)) => {
+                    let max = rate.max_interval_ms.unwrap_or(min);
+                    builder = builder
+                        .request_interval(Duration::from_millis(min), Duration::from_millis(max));
+                }
+                _ => {
+                    return Err(invalid(
+                        "rate_limit needs requests and per_ms, or min_interval_ms".to_string(),
+                    ))
+                }
+            }
+        }
+
+        if let Some(cache) = &self.cache {
+            let ttl = Duration::from_secs(cache.ttl_secs.unwrap_or(3600));
+            match cache.backend {
+                CacheKind::Memory => builder = builder.cache(cache.capacity.unwrap_or(100), ttl),
+                CacheKind::Disk => {
+                    let dir = cache
+                        .dir
+                        .clone()
+                        .ok_or_else(|| invalid("the disk cache needs a dir".to_string()))?;
+                    builder = builder.cache_backend(DiskCache::new(dir, ttl));
+                }
+            }
+        }
+
+        if let Some(retry) = &self.retry {
+            let default = RetryConfig::default();
+            builder = builder.retry_config(RetryConfig {
+                max_retries: retry.max_retries.unwrap_or(default.max_retries),
+                initial_delay: retry
+                    .initial_delay_ms
+                    .map_or(default.initial_delay, Duration::from_millis),
+                max_delay: retry
+                    .max_delay_ms
+                    .map_or(default.max_delay, Duration::from_millis),
+                backoff_multiplier: retry
+                    .backoff_multiplier
+                    .unwrap_or(default.backoff_multiplier),
+                jitter: retry.jitter.unwrap_or(default.jitter),
+                ..default
+            });
+        }
+
+        if let Some(credentials) = &self.credentials {
+            let env = |name: &str| {
+                std::env::var(name)
+                    .map_err(|_| invalid(format!("environment variable {name} is not set")))
+            };
+            let login_id = match (&credentials.login_id, &credentials.login_id_env) {
+                (Some(login_id), _) => login_id.clone(),
+                (None, Some(name)) => env(name)?,
+                (None, None) => {
+                    return Err(invalid(
+                        "credentials need login_id or login_id_env".to_string(),
+                    ))
+                }
+            };
+            let password = env(&credentials.password_env)?;
+            builder = builder.auto_relogin(&login_id, &password);
+        }
+
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheKind, ClientConfig};
+    use crate::{
+        interface::{locale::Locale, site::Site},
+        DlsiteError,
+    };
+
+    #[test]
+    fn parse_profile() {
+        let config = ClientConfig::from_toml(
+            r#"
+            site = "pro"
+            locale = "en_US"
+
+            [rate_limit]
+            requests = 4
+            per_ms = 1000
+
+            [cache]
+            backend = "disk"
+            dir = "cache"
+
+            [retry]
+            max_retries = 5
+
+            [credentials]
+            login_id = "user@example.com"
+            password_env = "DLSITE_CONFIG_TEST_PASSWORD"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.site, Some(Site::Pro));
+        assert_eq!(config.locale, Some(Locale::EnUs));
+        assert_eq!(config.cache.as_ref().unwrap().backend, CacheKind::Disk);
+        assert_eq!(config.retry.as_ref().unwrap().max_retries, Some(5));
+
+        // The password must be in the environment
+        assert!(matches!(config.builder(), Err(DlsiteError::Persist(_))));
+        let client = ClientConfig {
+            credentials: None,
+            ..config
+        }
+        .builder()
+        .unwrap()
+        .build();
+        assert_eq!(client.site(), Site::Pro);
+        assert_eq!(client.locale(), Locale::EnUs);
+
+        assert!(ClientConfig::from_toml("unknown = 1").is_err());
+        let invalid = ClientConfig::from_toml("[rate_limit]\nrequests = 2").unwrap();
+        assert!(invalid.builder().is_err());
+        assert_eq!(
+            ClientConfig::from_toml("").unwrap(),
+            ClientConfig::default()
+        );
+    }
+}
@@ -0,0 +1,466 @@
+//! Regression harness running the parsers of this crate against stored pages.
+//!
+//! DLsite changes its markup without notice. Storing real responses as fixtures and running
+//! [`run`] in CI reveals such changes before they break production: every parser output is
+//! compared with a snapshot written by [`update_snapshots`], and the result is a
+//! [`ConformanceReport`] which can be serialized to JSON.
+//!
+//! Fixtures are grouped in one directory per [`Parser`], named after it:
+//!
+//! ```text
+//! fixtures/
+//! ├── search/
+//! │   ├── voice.html        # stored response
+//! │   ├── voice.snap.json   # snapshot of the parser output
+//! │   └── bl_voice.html     # recorded on the bl storefront (maniax by default)
+//! ├── circle_profile/
+//! │   └── RG24350.html      # the file name is the circle ID
+//! └── product_api/
+//!     └── RJ01017217.json
+//! ```
+//!
+//! # Example
+//! ```no_run
+//! let report = dlsite_gamebox::conformance::run("tests/fixtures").unwrap();
+//! println!("{}", serde_json::to_string_pretty(&report).unwrap());
+//! assert!(report.is_ok());
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator as _};
+
+use crate::{
+    client::{
+        account::{parse_cart_response, parse_favorites_response, parse_purchase_html},
+        app::parse_app_html,
+        book::parse_book_html,
+        campaign::parse_campaign_list_html,
+        circle::parse_circle_profile,
+        coupon::parse_coupon_list_html,
+        follow::parse_follow_html,
+        play::{parse_purchases_response, parse_ziptree},
+        pro::parse_pro_html,
+        product::html::parse_product_html,
+        product_api::interface::ProductApiContent,
+        ranking::parse_ranking_html,
+        search::parse_search_html,
+    },
+    error::Result,
+    interface::{locale::Locale, site::Site},
+    selector::ParseReport,
+    DlsiteError,
+};
+
+/// Extension of snapshot files.
+const SNAPSHOT_EXTENSION: &str = ".snap.json";
+
+/// Parsers covered by the harness. The name of each is the name of its fixture directory.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumIter, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Parser {
+    /// Search result page
+    Search,
+    /// Product page
+    Product,
+    /// Ranking page
+    Ranking,
+    /// Campaign list
+    Campaign,
+    /// Feed of followed circles
+    Follow,
+    /// Coupon list
+    Coupon,
+    /// Purchase history
+    Purchases,
+    /// Favorite works (JSON)
+    Favorites,
+    /// Cart (JSON)
+    Cart,
+    /// Circle profile page
+    CircleProfile,
+    /// Book page (DLsite Books)
+    Book,
+    /// Page of a commercial work (DLsite Pro)
+    Pro,
+    /// Page of an Android app (appx storefront)
+    App,
+    /// Works of the DLsite Play library (JSON)
+    PlayLibrary,
+    /// File tree of a work on DLsite Play (JSON). The file name is the product ID.
+    PlayFileTree,
+    /// Product JSON API. Only checked for parse errors, its output has no snapshot.
+    ProductApi,
+}
+
+/// Output of a parser, and how its selectors matched if the parser reports them.
+struct Parsed {
+    output: Option<Value>,
+    selectors: Option<ParseReport>,
+}
+
+impl Parser {
+    /// Run the parser on a fixture. `name` is the file name without extension.
+    fn parse(self, name: &str, body: &str) -> Result<Parsed> {
+        let output = match self {
+            Parser::Search => {
+                let (items, report) = parse_search_html(body, fixture_site(name))?;
+                return Ok(Parsed {
+                    output: Some(to_value(&items)?),
+                    selectors: Some(report),
+                });
+            }
+            Parser::Product => {
+                let product = parse_product_html(&Html::parse_document(body))?;
+                return Ok(Parsed {
+                    output: Some(to_value(&product)?),
+                    selectors: Some(product.report),
+                });
+            }
+            Parser::Ranking => to_value(&parse_ranking_html(body)?)?,
+            Parser::Campaign => to_value(&parse_campaign_list_html(body)?)?,
+            Parser::Follow => to_value(&parse_follow_html(body)?)?,
+            Parser::Coupon => to_value(&parse_coupon_list_html(body)?)?,
+            Parser::Purchases => to_value(&parse_purchase_html(body)?)?,
+            Parser::Favorites => to_value(&parse_favorites_response(body)?.items)?,
+            Parser::Cart => to_value(&parse_cart_response(body)?)?,
+            Parser::CircleProfile => to_value(&parse_circle_profile(body, name)?)?,
+            Parser::Book => to_value(&parse_book_html(&Html::parse_document(body))?)?,
+            Parser::Pro => to_value(&parse_pro_html(&Html::parse_document(body))?)?,
+            Parser::App => to_value(&parse_app_html(&Html::parse_document(body))?)?,
+            Parser::PlayLibrary => {
+                to_value(&parse_purchases_response(body, Locale::default())?.items)?
+            }
+            // The base url comes from a download token which changes on every request, so file
+            // urls are left relative
+            Parser::PlayFileTree => to_value(&parse_ziptree(body, name, "")?)?,
+            Parser::ProductApi => {
+                let jd = &mut serde_json::Deserializer::from_str(body);
+                serde_path_to_error::deserialize::<_, Vec<ProductApiContent>>(jd)
+                    .map_err(|e| DlsiteError::Parse(format!("Failed to parse json: {}", e)))?;
+                return Ok(Parsed {
+                    output: None,
+                    selectors: None,
+                });
+            }
+        };
+        Ok(Parsed {
+            output: Some(output),
+            selectors: None,
+        })
+    }
+}
+
+/// Storefront a search fixture was recorded on: the prefix of its name before `_` (e.g.
+/// `girls_voice`), or maniax.
+fn fixture_site(name: &str) -> Site {
+    name.split_once('_')
+        .and_then(|(site, _)| site.parse().ok())
+        .unwrap_or(Site::Maniax)
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+/// Result of running the parsers on a fixture directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConformanceReport {
+    pub fixtures: Vec<FixtureResult>,
+}
+
+impl ConformanceReport {
+    /// Whether every fixture was parsed and matched its snapshot.
+    ///
+    /// Fixtures without a snapshot don't count as failures.
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Fixtures which failed to parse or whose output changed.
+    pub fn failures(&self) -> impl Iterator<Item = &FixtureResult> {
+        self.fixtures
+            .iter()
+            .filter(|f| matches!(f.outcome, Outcome::Failed { .. } | Outcome::Changed { .. }))
+    }
+}
+
+/// Result of running a parser on one fixture.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureResult {
+    pub parser: Parser,
+    pub path: PathBuf,
+    pub outcome: Outcome,
+    /// How the selectors matched, for parsers which report it
+    pub selectors: Option<ParseReport>,
+}
+
+/// How the output of a parser compares with its snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum Outcome {
+    /// Same output as the snapshot
+    Unchanged,
+    /// No snapshot to compare with (written by [`update_snapshots`])
+    New,
+    /// The output differs from the snapshot
+    Changed { changes: Vec<FieldChange> },
+    /// The parser returned an error
+    Failed { error: String },
+}
+
+/// A value which differs between the snapshot and the parser output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// JSON pointer to the value, e.g. `/0/title`
+    pub path: String,
+    /// Value in the snapshot, `None` if it was added
+    pub expected: Option<Value>,
+    /// Value parsed now, `None` if it was removed
+    pub actual: Option<Value>,
+}
+
+/// Run the parsers on the fixtures in `dir` and compare their output with the snapshots.
+///
+/// Subdirectories not named after a [`Parser`] are ignored.
+pub fn run(dir: impl AsRef<Path>) -> std::io::Result<ConformanceReport> {
+    check(dir.as_ref(), false)
+}
+
+/// Same as [`run`], but the snapshots of all fixtures which parsed successfully are
+/// (re)written with the current output. Use it after reviewing the changes reported by
+/// [`run`].
+pub fn update_snapshots(dir: impl AsRef<Path>) -> std::io::Result<ConformanceReport> {
+    check(dir.as_ref(), true)
+}
+
+fn check(dir: &Path, update: bool) -> std::io::Result<ConformanceReport> {
+    let mut report = ConformanceReport::default();
+    for parser in Parser::iter() {
+        let parser_dir = dir.join(parser.to_string());
+        if !parser_dir.is_dir() {
+            continue;
+        }
+        let mut fixtures: Vec<PathBuf> = fs::read_dir(&parser_dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        fixtures.retain(|path| path.is_file() && !is_snapshot(path));
+        fixtures.sort();
+
+        for path in fixtures {
+            report.fixtures.push(check_fixture(parser, path, update)?);
+        }
+    }
+    Ok(report)
+}
+
+fn check_fixture(parser: Parser, path: PathBuf, update: bool) -> std::io::Result<FixtureResult> {
+    let body = fs::read_to_string(&path)?;
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let parsed = match parser.parse(&name, &body) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return Ok(FixtureResult {
+                parser,
+                path,
+                outcome: Outcome::Failed {
+                    error: e.to_string(),
+                },
+                selectors: None,
+            })
+        }
+    };
+
+    let snapshot_path = path.with_file_name(format!("{name}{SNAPSHOT_EXTENSION}"));
+    let outcome = match &parsed.output {
+        None => Outcome::Unchanged,
+        Some(output) if update => {
+            fs::write(&snapshot_path, serde_json::to_string_pretty(output)?)?;
+            Outcome::Unchanged
+        }
+        Some(output) => match fs::read_to_string(&snapshot_path) {
+            Ok(snapshot) => {
+                let expected: Value = serde_json::from_str(&snapshot)?;
+                let mut changes = vec![];
+                diff("", &expected, output, &mut changes);
+                if changes.is_empty() {
+                    Outcome::Unchanged
+                } else {
+                    Outcome::Changed { changes }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Outcome::New,
+            Err(e) => return Err(e),
+        },
+    };
+
+    Ok(FixtureResult {
+        parser,
+        path,
+        outcome,
+        selectors: parsed.selectors,
+    })
+}
+
+fn is_snapshot(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(SNAPSHOT_EXTENSION))
+}
+
+/// Collect the values which differ between `expected` and `actual`, down to the leaves.
+fn diff(path: &str, expected: &Value, actual: &Value, changes: &mut Vec<FieldChange>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let path = format!("{path}/{}", escape_pointer(key));
+                match actual.get(key) {
+                    Some(other) => diff(&path, value, other, changes),
+                    None => changes.push(FieldChange {
+                        path,
+                        expected: Some(value.clone()),
+                        actual: None,
+                    }),
+                }
+            }
+            for (key, value) in actual {
+                if !expected.contains_key(key) {
+                    changes.push(FieldChange {
+                        path: format!("{path}/{}", escape_pointer(key)),
+                        expected: None,
+                        actual: Some(value.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for i in 0..expected.len().max(actual.len()) {
+                let path = format!("{path}/{i}");
+                match (expected.get(i), actual.get(i)) {
+                    (Some(e), Some(a)) => diff(&path, e, a, changes),
+                    (e, a) => changes.push(FieldChange {
+                        path,
+                        expected: e.cloned(),
+                        actual: a.cloned(),
+                    }),
+                }
+            }
+        }
+        _ if expected != actual => changes.push(FieldChange {
+            path: path.to_string(),
+            expected: Some(expected.clone()),
+            actual: Some(actual.clone()),
+        }),
+        _ => {}
+    }
+}
+
+/// Escape a key for use in a JSON pointer (RFC 6901).
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{diff, fixture_site, run, update_snapshots, FieldChange, Outcome, Parser};
+    use crate::interface::site::Site;
+
+    #[test]
+    fn diff_values() {
+        let expected = json!([{ "title": "A", "tags": ["x"], "a/b": 1 }]);
+        let actual = json!([{ "title": "B", "tags": ["x", "y"], "price": 100 }, {}]);
+        let mut changes = vec![];
+        diff("", &expected, &actual, &mut changes);
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    path: "/0/a~1b".to_string(),
+                    expected: Some(json!(1)),
+                    actual: None,
+                },
+                FieldChange {
+                    path: "/0/tags/1".to_string(),
+                    expected: None,
+                    actual: Some(json!("y")),
+                },
+                FieldChange {
+                    path: "/0/title".to_string(),
+                    expected: Some(json!("A")),
+                    actual: Some(json!("B")),
+                },
+                FieldChange {
+                    path: "/0/price".to_string(),
+                    expected: None,
+                    actual: Some(json!(100)),
+                },
+                FieldChange {
+                    path: "/1".to_string(),
+                    expected: None,
+                    actual: Some(json!({})),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fixture_sites() {
+        assert_eq!(fixture_site("girls_voice"), Site::Girls);
+        assert_eq!(fixture_site("bl-pro_game"), Site::BlPro);
+        assert_eq!(fixture_site("voice"), Site::Maniax);
+        assert_eq!(fixture_site("new_arrivals"), Site::Maniax);
+    }
+
+    #[test]
+    fn run_fixtures() {
+        let dir = std::env::temp_dir().join(format!("dlsite-conformance-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let circle = dir.join("circle_profile");
+        std::fs::create_dir_all(&circle).unwrap();
+        std::fs::create_dir_all(dir.join("unrelated")).unwrap();
+        let page = r#"<h1 class="prof_maker_name">Circle A</h1>
+<div class="prof_related"><a href="/maniax/circle/profile/=/maker_id/RG00002.html">B</a></div>"#;
+        std::fs::write(circle.join("RG00001.html"), page).unwrap();
+        std::fs::write(circle.join("RG00003.html"), "<p>Not a circle</p>").unwrap();
+
+        let report = run(&dir).unwrap();
+        assert_eq!(report.fixtures.len(), 2);
+        assert_eq!(report.fixtures[0].parser, Parser::CircleProfile);
+        assert_eq!(report.fixtures[0].outcome, Outcome::New);
+        assert!(matches!(report.fixtures[1].outcome, Outcome::Failed { .. }));
+        assert!(!report.is_ok());
+
+        std::fs::remove_file(circle.join("RG00003.html")).unwrap();
+        update_snapshots(&dir).unwrap();
+        assert!(circle.join("RG00001.snap.json").exists());
+        assert!(run(&dir).unwrap().is_ok());
+
+        std::fs::write(
+            circle.join("RG00001.html"),
+            page.replace("RG00002", "RG00004"),
+        )
+        .unwrap();
+        let report = run(&dir).unwrap();
+        let Outcome::Changed { changes } = &report.fixtures[0].outcome else {
+            panic!("{:?}", report.fixtures[0].outcome);
+        };
+        assert_eq!(changes[0].path, "/related/0");
+        assert_eq!(changes[0].actual, Some(serde_json::json!("RG00004")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -16,8 +16,12 @@ pub enum DlsiteError {
     HttpStatus(u16),
 
     /// Rate limit error - too many requests
-    #[error("Rate limited: {0}")]
-    RateLimit(String),
+    #[error("Rate limited: {message}")]
+    RateLimit {
+        message: String,
+        /// Delay requested by the `Retry-After` header of the response, if any
+        retry_after: Option<std::time::Duration>,
+    },
 
     /// Request timeout error
     #[error("Request timeout")]
@@ -30,6 +34,131 @@ pub enum DlsiteError {
     /// Server-side error
     #[error("Server error: {0}")]
     Server(String),
+
+    /// The requested resource does not exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// A product ID is malformed, see [`crate::interface::product_id::ProductId`]
+    #[error("Invalid product ID: {0}")]
+    InvalidProductId(String),
+
+    /// The request needs a logged-in session
+    #[error("Login required")]
+    Unauthenticated,
+
+    /// Login was rejected (wrong credentials, captcha...)
+    #[error("Login failed: {0}")]
+    LoginFailed(String),
+
+    /// DLsite served a page in another language than the requested one, see
+    /// [`crate::client::LanguageCheck`]
+    #[error("Requested language {requested}, but DLsite served {served}")]
+    LanguageMismatch { requested: String, served: String },
+
+    /// Local data could not be read, written or migrated
+    #[error("Persistence error: {0}")]
+    Persist(String),
+
+    /// The client already sent as many requests as allowed, see
+    /// [`crate::DlsiteClientBuilder::request_budget`]
+    #[error("Request budget of {limit} requests exhausted")]
+    BudgetExhausted { limit: u64 },
+
+    /// DLsite served a CAPTCHA or bot challenge instead of the page. The client stops sending
+    /// requests for a while, see [`crate::DlsiteClientBuilder::challenge_cooldown`]
+    #[error("DLsite served a bot challenge: {url}")]
+    Challenge { url: String },
+
+    /// A downloaded file doesn't match the checksum given by DLsite
+    #[error("Checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// Local full-text index error
+    #[cfg(feature = "tantivy")]
+    #[error("Index error: {0}")]
+    Index(String),
+}
+
+#[cfg(feature = "tantivy")]
+impl From<tantivy::TantivyError> for DlsiteError {
+    fn from(e: tantivy::TantivyError) -> Self {
+        DlsiteError::Index(e.to_string())
+    }
+}
+
+impl DlsiteError {
+    /// Copy of this error, for failures shared by several items (e.g. a batch request).
+    /// Wrapped errors which can't be cloned are turned into their message.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            DlsiteError::Reqwest(e) if e.is_timeout() => DlsiteError::Timeout,
+            DlsiteError::Reqwest(e) => DlsiteError::Server(e.to_string()),
+            DlsiteError::SerdeJson(e) => DlsiteError::Parse(e.to_string()),
+            DlsiteError::HttpStatus(status) => DlsiteError::HttpStatus(*status),
+            DlsiteError::RateLimit {
+                message,
+                retry_after,
+            } => DlsiteError::RateLimit {
+                message: message.clone(),
+                retry_after: *retry_after,
+            },
+            DlsiteError::Timeout => DlsiteError::Timeout,
+            DlsiteError::Parse(s) => DlsiteError::Parse(s.clone()),
+            DlsiteError::Server(s) => DlsiteError::Server(s.clone()),
+            DlsiteError::NotFound(s) => DlsiteError::NotFound(s.clone()),
+            DlsiteError::InvalidProductId(s) => DlsiteError::InvalidProductId(s.clone()),
+            DlsiteError::Unauthenticated => DlsiteError::Unauthenticated,
+            DlsiteError::LoginFailed(s) => DlsiteError::LoginFailed(s.clone()),
+            DlsiteError::LanguageMismatch { requested, served } => DlsiteError::LanguageMismatch {
+                requested: requested.clone(),
+                served: served.clone(),
+            },
+            DlsiteError::Persist(s) => DlsiteError::Persist(s.clone()),
+            DlsiteError::BudgetExhausted { limit } => {
+                DlsiteError::BudgetExhausted { limit: *limit }
+            }
+            DlsiteError::Challenge { url } => DlsiteError::Challenge { url: url.clone() },
+            DlsiteError::ChecksumMismatch {
+                file,
+                expected,
+                actual,
+            } => DlsiteError::ChecksumMismatch {
+                file: file.clone(),
+                expected: expected.clone(),
+                actual: actual.clone(),
+            },
+            #[cfg(feature = "tantivy")]
+            DlsiteError::Index(s) => DlsiteError::Index(s.clone()),
+        }
+    }
+
+    /// Whether this error means the requested resource does not exist (HTTP 404 included).
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, DlsiteError::NotFound(_) | DlsiteError::HttpStatus(404))
+    }
+
+    /// How long the server asked to wait before retrying, for rate limit errors.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            DlsiteError::RateLimit { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether this error comes from the data source itself (unexpected response, server
+    /// error), so that another source may still work.
+    pub(crate) fn is_source_failure(&self) -> bool {
+        match self {
+            DlsiteError::Parse(_) | DlsiteError::SerdeJson(_) | DlsiteError::Server(_) => true,
+            DlsiteError::HttpStatus(status) => *status != 404 && *status != 429,
+            _ => false,
+        }
+    }
 }
 
 pub(crate) type Result<T> = std::result::Result<T, DlsiteError>;
@@ -0,0 +1,226 @@
+//! Progress and completion time estimates for bulk operations.
+//!
+//! Fetching hundreds of works is mostly waiting for the rate limiter, so the time left can be
+//! estimated before the first response arrives: [`Eta`] starts from the configured request
+//! interval and the retries seen so far, then switches to the measured throughput once a few
+//! items are done.
+//!
+//! # Example
+//! ```no_run
+//! use dlsite_gamebox::{eta::Eta, library, DlsiteClient};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = DlsiteClient::default();
+//!     let items = library::scan("./library").unwrap();
+//!     let eta = Eta::new(&client, items.len(), 1.0);
+//!
+//!     let progress = {
+//!         let eta = eta.clone();
+//!         tokio::spawn(async move {
+//!             loop {
+//!                 tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+//!                 println!("{:.0}% - {:?} left", eta.fraction() * 100.0, eta.remaining_time());
+//!             }
+//!         })
+//!     };
+//!     let enriched = library::enrich_with_eta(&client, items, 4, &eta).await;
+//!     progress.abort();
+//!     println!("{} works in {:?}", enriched.len(), eta.elapsed());
+//! }
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{runtime::Instant, DlsiteClient};
+
+/// Number of completed items after which the measured throughput replaces the estimate
+/// derived from the rate limit.
+const MIN_SAMPLES: usize = 3;
+
+/// Requests sent by a client and its clones, counted once per attempt.
+#[derive(Debug, Default)]
+pub(crate) struct RequestStats {
+    requests: AtomicU64,
+    retries: AtomicU64,
+}
+
+impl RequestStats {
+    /// Record a finished request which took `attempts` attempts.
+    pub(crate) fn record(&self, attempts: u32) {
+        self.requests
+            .fetch_add(u64::from(attempts), Ordering::Relaxed);
+        self.retries
+            .fetch_add(u64::from(attempts.saturating_sub(1)), Ordering::Relaxed);
+    }
+
+    /// Requests and retries so far.
+    pub(crate) fn snapshot(&self) -> (u64, u64) {
+        (
+            self.requests.load(Ordering::Relaxed),
+            self.retries.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Shared handle tracking the progress of a bulk operation.
+///
+/// Clones share the same progress, so one can be given to the operation while another is
+/// polled by a progress bar.
+#[derive(Debug, Clone)]
+pub struct Eta {
+    state: Arc<Mutex<State>>,
+    stats: Arc<RequestStats>,
+    interval: Option<Duration>,
+    requests_per_item: f64,
+}
+
+#[derive(Debug)]
+struct State {
+    total: usize,
+    completed: usize,
+    started: Instant,
+    /// Requests and retries of the client when the operation started
+    baseline: (u64, u64),
+}
+
+impl Eta {
+    /// Track an operation on `total` items made with `client`.
+    ///
+    /// # Arguments
+    /// * `requests_per_item` - Requests needed for one item, e.g. `2.0` when each product is
+    ///   fetched from both the HTML page and the AJAX endpoint, or `1.0 / 100.0` for search
+    ///   results listed 100 per page.
+    pub fn new(client: &DlsiteClient, total: usize, requests_per_item: f64) -> Self {
+        let stats = client.request_stats();
+        Self {
+            state: Arc::new(Mutex::new(State {
+                total,
+                completed: 0,
+                started: Instant::now(),
+                baseline: stats.snapshot(),
+            })),
+            stats,
+            interval: client.request_interval(),
+            requests_per_item: requests_per_item.max(0.0),
+        }
+    }
+
+    /// Mark `n` more items as done.
+    pub fn complete(&self, n: usize) {
+        self.state.lock().unwrap().completed += n;
+    }
+
+    /// Change the number of items, e.g. once a listing reports its total.
+    pub fn set_total(&self, total: usize) {
+        self.state.lock().unwrap().total = total;
+    }
+
+    /// Number of items of the operation.
+    pub fn total(&self) -> usize {
+        self.state.lock().unwrap().total
+    }
+
+    /// Number of items done so far.
+    pub fn completed(&self) -> usize {
+        self.state.lock().unwrap().completed
+    }
+
+    /// Number of items left.
+    pub fn remaining(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.total.saturating_sub(state.completed)
+    }
+
+    /// Done fraction between 0 and 1. An empty operation is done.
+    pub fn fraction(&self) -> f64 {
+        let state = self.state.lock().unwrap();
+        if state.total == 0 {
+            1.0
+        } else {
+            (state.completed as f64 / state.total as f64).min(1.0)
+        }
+    }
+
+    /// Time since the operation started.
+    pub fn elapsed(&self) -> Duration {
+        self.state.lock().unwrap().started.elapsed()
+    }
+
+    /// Estimated time until all items are done.
+    ///
+    /// Uses the measured time per item once a few items are done. Before that, the time per
+    /// item is derived from the rate limit of the client and the share of requests retried
+    /// since the operation started. `None` if neither is known, e.g. with a custom rate
+    /// limiter which does not report its interval.
+    pub fn remaining_time(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        let remaining = state.total.saturating_sub(state.completed);
+        if remaining == 0 {
+            return Some(Duration::ZERO);
+        }
+
+        let per_item = if state.completed >= MIN_SAMPLES {
+            state.started.elapsed().as_secs_f64() / state.completed as f64
+        } else {
+            let interval = self.interval?.as_secs_f64();
+            let (requests, retries) = self.stats.snapshot();
+            let requests = requests.saturating_sub(state.baseline.0);
+            let retries = retries.saturating_sub(state.baseline.1);
+            // Attempts needed per successful request
+            let attempts = if requests > retries {
+                requests as f64 / (requests - retries) as f64
+            } else {
+                1.0
+            };
+            interval * self.requests_per_item * attempts
+        };
+        Some(Duration::from_secs_f64(per_item * remaining as f64))
+    }
+
+    /// Estimated local time at which all items are done, see [`Eta::remaining_time`].
+    pub fn finishes_at(&self) -> Option<DateTime<Local>> {
+        let remaining = chrono::Duration::from_std(self.remaining_time()?).ok()?;
+        Some(Local::now() + remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Eta;
+    use crate::DlsiteClient;
+
+    #[test]
+    fn estimate_from_rate_limit() {
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::from_millis(400), Duration::from_millis(600))
+            .build();
+        let eta = Eta::new(&client, 10, 2.0);
+        assert_eq!(eta.remaining(), 10);
+        assert_eq!(eta.fraction(), 0.0);
+        assert_eq!(eta.remaining_time(), Some(Duration::from_secs(10)));
+
+        // 2 requests sent in 4 attempts
+        client.request_stats().record(1);
+        client.request_stats().record(3);
+        assert_eq!(eta.remaining_time(), Some(Duration::from_secs(20)));
+
+        // Measured throughput replaces the estimate
+        eta.complete(5);
+        assert_eq!(eta.fraction(), 0.5);
+        assert!(eta.remaining_time().unwrap() < Duration::from_secs(1));
+
+        eta.set_total(5);
+        assert_eq!(eta.remaining_time(), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,99 @@
+//! Hooks to observe what a client is doing, e.g. to export metrics.
+//!
+//! # Example
+//! ```
+//! use dlsite_gamebox::{events::{EventListener, RequestEvent}, DlsiteClient};
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! #[derive(Default)]
+//! struct Metrics {
+//!     cache_hits: AtomicUsize,
+//!     requests: AtomicUsize,
+//! }
+//!
+//! impl EventListener for Metrics {
+//!     fn on_cache_hit(&self, _url: &str) {
+//!         self.cache_hits.fetch_add(1, Ordering::Relaxed);
+//!     }
+//!
+//!     fn on_request_complete(&self, _event: &RequestEvent) {
+//!         self.requests.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+//!     .event_listener(Metrics::default())
+//!     .build();
+//! ```
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::DlsiteError;
+
+/// A finished HTTP request, including all of its retries.
+#[derive(Debug)]
+pub struct RequestEvent<'a> {
+    pub url: &'a str,
+    /// Status of the last response, `None` if no response was received
+    pub status: Option<u16>,
+    /// Number of attempts made (1 if the first attempt succeeded)
+    pub attempts: u32,
+    /// Time from the first attempt to the end of the last one, rate limiter waits included
+    pub elapsed: Duration,
+    /// Error returned to the caller, if the request failed
+    pub error: Option<&'a DlsiteError>,
+}
+
+/// Receives events from a [`crate::DlsiteClient`]. All methods do nothing by default.
+///
+/// Listeners are called synchronously from the request path, so they should return quickly.
+pub trait EventListener: Send + Sync {
+    /// A response was served from the response cache.
+    fn on_cache_hit(&self, url: &str) {
+        let _ = url;
+    }
+
+    /// A response was not in the response cache and will be requested.
+    fn on_cache_miss(&self, url: &str) {
+        let _ = url;
+    }
+
+    /// An attempt failed and the request will be retried after `delay`.
+    ///
+    /// `attempt` is the number of the failed attempt, starting from 1.
+    fn on_retry(&self, url: &str, attempt: u32, error: &DlsiteError, delay: Duration) {
+        let _ = (url, attempt, error, delay);
+    }
+
+    /// A request had to wait for the rate limiter (or for foreground requests).
+    fn on_rate_limit_wait(&self, waited: Duration) {
+        let _ = waited;
+    }
+
+    /// A request finished, successfully or not.
+    fn on_request_complete(&self, event: &RequestEvent) {
+        let _ = event;
+    }
+}
+
+/// Listeners registered on a client.
+#[derive(Clone, Default)]
+pub(crate) struct EventListeners(Arc<Vec<Arc<dyn EventListener>>>);
+
+impl fmt::Debug for EventListeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EventListeners({})", self.0.len())
+    }
+}
+
+impl EventListeners {
+    pub(crate) fn new(listeners: Vec<Arc<dyn EventListener>>) -> Self {
+        Self(Arc::new(listeners))
+    }
+
+    pub(crate) fn emit(&self, f: impl Fn(&dyn EventListener)) {
+        for listener in self.0.iter() {
+            f(listener.as_ref());
+        }
+    }
+}
@@ -0,0 +1,699 @@
+//! Local full-text index of fetched products, for offline searching of previously seen works.
+//!
+//! Text is split into character uni/bi-grams, so Japanese titles can be searched without a
+//! dictionary-based tokenizer. User tags of products are indexed too if
+//! [`crate::DlsiteClientBuilder::index_user_tags`] is set.
+//!
+//! Added works are committed in batches rather than one by one, so indexing doesn't block the
+//! requests of a client. Searches see every added work; call [`LocalIndex::flush`] to write
+//! pending works to disk without searching.
+//!
+//! The index also keeps perceptual hashes of cover images, so that a local image can be matched
+//! to a work (see [`LocalIndex::find_by_image_hash`], behind the `image-hash` feature). They are
+//! saved along with the works, at each commit.
+//!
+//! # Example
+//! ```no_run
+//! use dlsite_gamebox::{index::LocalIndex, DlsiteClient};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let index = LocalIndex::open("./dlsite-index").unwrap();
+//!     let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+//!         .local_index(index.clone())
+//!         .build();
+//!     client.product_api().get("RJ01014447").await.unwrap();
+//!
+//!     for work in index.search("ASMR", 10).unwrap() {
+//!         println!("{} {}", work.id, work.title);
+//!     }
+//! }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tantivy::{
+    collector::TopDocs,
+    directory::MmapDirectory,
+    query::{BooleanQuery, Occur, Query, TermQuery},
+    schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value as _, STORED,
+        STRING,
+    },
+    tokenizer::{LowerCaser, NgramTokenizer, TextAnalyzer, TokenStream as _},
+    Index, IndexReader, IndexSettings, IndexWriter, ReloadPolicy, TantivyDocument, Term,
+};
+
+use crate::{
+    client::{product::Product, product_api::interface::ProductApiContent},
+    error::Result,
+    persist::{self, Persisted},
+    DlsiteError,
+};
+
+const TOKENIZER: &str = "ja_ngram";
+/// File holding cover hashes, next to the tantivy files
+const COVER_HASHES_FILE: &str = "cover_hashes.json";
+/// Maximum number of differing bits for two cover hashes to be considered the same image
+#[cfg(feature = "image-hash")]
+const MAX_HASH_DISTANCE: u32 = 10;
+/// Number of added works committed together, see [`LocalIndex::add`]
+const COMMIT_EVERY: usize = 100;
+
+/// A work stored in the [`LocalIndex`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IndexedWork {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub genres: Vec<String>,
+    pub circle_name: String,
+    pub creators: Vec<String>,
+    /// User tags (みんなのタグ)
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl From<&ProductApiContent> for IndexedWork {
+    fn from(product: &ProductApiContent) -> Self {
+        let mut creators: Vec<String> = vec![];
+        if let Some(c) = &product.creators {
+            for list in [&c.created_by, &c.voice_by, &c.illust_by, &c.scenario_by]
+                .into_iter()
+                .flatten()
+            {
+                for creator in list {
+                    if !creators.contains(&creator.name) {
+                        creators.push(creator.name.clone());
+                    }
+                }
+            }
+        }
+
+        Self {
+            id: product.workno.clone(),
+            title: product.work_name.clone(),
+            description: product.intro_s.clone(),
+            genres: product.genres.iter().map(|g| g.name.clone()).collect(),
+            circle_name: product.maker_name.clone(),
+            creators,
+            tags: vec![],
+        }
+    }
+}
+
+impl From<&Product> for IndexedWork {
+    fn from(product: &Product) -> Self {
+        let people = &product.people;
+        let mut creators: Vec<String> = vec![];
+        for list in [
+            &people.author,
+            &people.scenario,
+            &people.illustrator,
+            &people.voice_actor,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for name in list {
+                if !creators.contains(name) {
+                    creators.push(name.clone());
+                }
+            }
+        }
+
+        Self {
+            id: product.id.clone(),
+            title: product.title.clone(),
+            description: None,
+            genres: product.genre.iter().map(|g| g.name.clone()).collect(),
+            circle_name: product.circle_name.clone(),
+            creators,
+            tags: product
+                .user_tags
+                .iter()
+                .map(|(tag, _)| tag.clone())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    id: Field,
+    title: Field,
+    description: Field,
+    genres: Field,
+    circle_name: Field,
+    creators: Field,
+    /// `None` for indexes created before user tags were indexed
+    tags: Option<Field>,
+}
+
+impl Fields {
+    fn from_schema(schema: &Schema) -> Result<Self> {
+        Ok(Self {
+            id: schema.get_field("id")?,
+            title: schema.get_field("title")?,
+            description: schema.get_field("description")?,
+            genres: schema.get_field("genres")?,
+            circle_name: schema.get_field("circle_name")?,
+            creators: schema.get_field("creators")?,
+            tags: schema.get_field("tags").ok(),
+        })
+    }
+
+    fn text_fields(&self) -> Vec<Field> {
+        [
+            self.title,
+            self.description,
+            self.genres,
+            self.circle_name,
+            self.creators,
+        ]
+        .into_iter()
+        .chain(self.tags)
+        .collect()
+    }
+}
+
+/// Cover image hashes by product ID.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct CoverHashes(BTreeMap<String, u64>);
+
+impl Persisted for CoverHashes {
+    const KIND: &'static str = "cover_hashes";
+    const VERSION: u32 = 1;
+}
+
+/// A work whose cover matches an image.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ImageMatch {
+    pub id: String,
+    /// Number of differing bits between the hashes (0 = same image)
+    pub distance: u32,
+    /// The work, if it is in the full-text index
+    pub work: Option<IndexedWork>,
+}
+
+struct Inner {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    /// Works added since the last commit
+    pending: AtomicUsize,
+    fields: Fields,
+    covers: Mutex<CoverHashes>,
+    /// Cover hashes were recorded since they were last saved
+    covers_changed: AtomicBool,
+    /// Where cover hashes are saved, `None` for in-memory indexes
+    covers_path: Option<PathBuf>,
+}
+
+/// Full-text index of works, stored on disk or in memory.
+///
+/// Cloning is cheap and clones share the same index. Set it with
+/// [`crate::DlsiteClientBuilder::local_index`] to index every product fetched by a client.
+#[derive(Clone)]
+pub struct LocalIndex {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for LocalIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalIndex").finish_non_exhaustive()
+    }
+}
+
+impl Inner {
+    fn commit(&self, writer: &mut IndexWriter) -> Result<()> {
+        writer.commit()?;
+        self.pending.store(0, Ordering::SeqCst);
+        self.reader.reload()?;
+        self.save_covers()
+    }
+
+    /// Write the cover hashes if some were recorded since the last save.
+    fn save_covers(&self) -> Result<()> {
+        let covers = self.covers.lock().unwrap();
+        if !self.covers_changed.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        if let Some(path) = &self.covers_path {
+            persist::save(path, &*covers).inspect_err(|_| {
+                self.covers_changed.store(true, Ordering::SeqCst);
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if *self.pending.get_mut() > 0 {
+            let writer = self.writer.get_mut().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = writer.commit() {
+                tracing::warn!("Failed to commit the local index: {e}");
+            }
+        }
+        if let Err(e) = self.save_covers() {
+            tracing::warn!("Failed to save cover hashes: {e}");
+        }
+    }
+}
+
+fn schema() -> Schema {
+    let text = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqs),
+        )
+        .set_stored();
+
+    let mut builder = Schema::builder();
+    builder.add_text_field("id", STRING | STORED);
+    builder.add_text_field("title", text.clone());
+    builder.add_text_field("description", text.clone());
+    builder.add_text_field("genres", text.clone());
+    builder.add_text_field("circle_name", text.clone());
+    builder.add_text_field("creators", text.clone());
+    builder.add_text_field("tags", text);
+    builder.build()
+}
+
+fn analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(NgramTokenizer::new(1, 2, false).expect("valid ngram range"))
+        .filter(LowerCaser)
+        .build()
+}
+
+impl LocalIndex {
+    /// Open the index stored in `dir`, creating it if it doesn't exist.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())
+            .map_err(|e| DlsiteError::Index(e.to_string()))?;
+        let directory =
+            MmapDirectory::open(dir.as_ref()).map_err(|e| DlsiteError::Index(e.to_string()))?;
+        // Existing indexes are opened with their own schema, which may lack newer fields
+        let index = if Index::exists(&directory).map_err(|e| DlsiteError::Index(e.to_string()))? {
+            Index::open(directory)?
+        } else {
+            Index::create(directory, schema(), IndexSettings::default())?
+        };
+        let covers_path = dir.as_ref().join(COVER_HASHES_FILE);
+        let covers = persist::load(&covers_path)?.unwrap_or_default();
+        Self::from_index(index, covers, Some(covers_path))
+    }
+
+    /// Create an index kept in memory only.
+    pub fn in_memory() -> Result<Self> {
+        Self::from_index(Index::create_in_ram(schema()), CoverHashes::default(), None)
+    }
+
+    fn from_index(index: Index, covers: CoverHashes, covers_path: Option<PathBuf>) -> Result<Self> {
+        let fields = Fields::from_schema(&index.schema())?;
+        index.tokenizers().register(TOKENIZER, analyzer());
+        let writer = index.writer(15_000_000)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                index,
+                reader,
+                writer: Mutex::new(writer),
+                pending: AtomicUsize::new(0),
+                fields,
+                covers: Mutex::new(covers),
+                covers_changed: AtomicBool::new(false),
+                covers_path,
+            }),
+        })
+    }
+
+    /// Add a work to the index, replacing the previous entry with the same ID.
+    ///
+    /// Works are committed by batches of 100, or by the next search or
+    /// [`LocalIndex::flush`].
+    pub fn add(&self, work: &IndexedWork) -> Result<()> {
+        let f = &self.inner.fields;
+        let mut doc = TantivyDocument::default();
+        doc.add_text(f.id, &work.id);
+        doc.add_text(f.title, &work.title);
+        if let Some(description) = &work.description {
+            doc.add_text(f.description, description);
+        }
+        for genre in &work.genres {
+            doc.add_text(f.genres, genre);
+        }
+        doc.add_text(f.circle_name, &work.circle_name);
+        for creator in &work.creators {
+            doc.add_text(f.creators, creator);
+        }
+        if let Some(tags) = f.tags {
+            for tag in &work.tags {
+                doc.add_text(tags, tag);
+            }
+        }
+
+        let mut writer = self.inner.writer.lock().unwrap();
+        writer.delete_term(Term::from_field_text(f.id, &work.id));
+        writer.add_document(doc)?;
+        if self.inner.pending.fetch_add(1, Ordering::SeqCst) + 1 >= COMMIT_EVERY {
+            self.inner.commit(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Commit the works added since the last commit, so they are saved and searchable, and save
+    /// the recorded cover hashes.
+    pub fn flush(&self) -> Result<()> {
+        if self.inner.pending.load(Ordering::SeqCst) == 0 {
+            return self.inner.save_covers();
+        }
+        self.inner.commit(&mut self.inner.writer.lock().unwrap())
+    }
+
+    /// Number of works in the index.
+    pub fn len(&self) -> u64 {
+        if let Err(e) = self.flush() {
+            tracing::warn!("Failed to commit the local index: {e}");
+        }
+        self.inner.reader.searcher().num_docs()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Search works whose title, description, genres, circle, creators or tags contain every
+    /// character and character pair of `query`. Results are sorted by relevance.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<IndexedWork>> {
+        let f = self.inner.fields;
+
+        let mut tokens: Vec<String> = vec![];
+        let mut analyzer = analyzer();
+        let mut stream = analyzer.token_stream(query);
+        while let Some(token) = stream.next() {
+            if !token.text.trim().is_empty() && !tokens.contains(&token.text) {
+                tokens.push(token.text.clone());
+            }
+        }
+        if tokens.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let query = BooleanQuery::new(
+            tokens
+                .iter()
+                .map(|token| {
+                    let any_field: Box<dyn Query> = Box::new(BooleanQuery::new(
+                        f.text_fields()
+                            .into_iter()
+                            .map(|field| {
+                                let q: Box<dyn Query> = Box::new(TermQuery::new(
+                                    Term::from_field_text(field, token),
+                                    IndexRecordOption::WithFreqs,
+                                ));
+                                (Occur::Should, q)
+                            })
+                            .collect(),
+                    ));
+                    (Occur::Must, any_field)
+                })
+                .collect(),
+        );
+
+        self.flush()?;
+        let searcher = self.inner.reader.searcher();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+
+        let mut works = Vec::with_capacity(top_docs.len());
+        for (_, address) in top_docs {
+            works.push(self.to_work(&searcher.doc(address)?));
+        }
+
+        Ok(works)
+    }
+
+    /// Get a work by product ID.
+    pub fn get(&self, id: &str) -> Result<Option<IndexedWork>> {
+        let query = TermQuery::new(
+            Term::from_field_text(self.inner.fields.id, id),
+            IndexRecordOption::Basic,
+        );
+        self.flush()?;
+        let searcher = self.inner.reader.searcher();
+        let Some((_, address)) = searcher.search(&query, &TopDocs::with_limit(1))?.pop() else {
+            return Ok(None);
+        };
+        Ok(Some(self.to_work(&searcher.doc(address)?)))
+    }
+
+    fn to_work(&self, doc: &TantivyDocument) -> IndexedWork {
+        let f = self.inner.fields;
+        let first = |field: Field| {
+            doc.get_first(field)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let all = |field: Field| {
+            doc.get_all(field)
+                .filter_map(|v| v.as_str())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        IndexedWork {
+            id: first(f.id).unwrap_or_default(),
+            title: first(f.title).unwrap_or_default(),
+            description: first(f.description),
+            genres: all(f.genres),
+            circle_name: first(f.circle_name).unwrap_or_default(),
+            creators: all(f.creators),
+            tags: f.tags.map(all).unwrap_or_default(),
+        }
+    }
+
+    /// Record the perceptual hash of the cover image of a work (see `image_hash`).
+    ///
+    /// Hashes are saved with the next commit of the works, or by [`LocalIndex::flush`].
+    pub fn add_cover_hash(&self, id: &str, hash: u64) -> Result<()> {
+        let mut covers = self.inner.covers.lock().unwrap();
+        if covers.0.insert(id.to_string(), hash) != Some(hash) {
+            self.inner.covers_changed.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// Hash a cover image and record it for the work. Returns the hash.
+    #[cfg(feature = "image-hash")]
+    pub fn add_cover(&self, id: &str, image: &[u8]) -> Result<u64> {
+        let hash = image_hash(image)?;
+        self.add_cover_hash(id, hash)?;
+        Ok(hash)
+    }
+
+    /// Find works whose recorded cover looks like the image at `path`, closest first.
+    ///
+    /// Covers are recorded while fetching thumbnails through a client using this index (see
+    /// [`crate::client::search::SearchResult::prefetch_thumbnails`]) or with
+    /// [`LocalIndex::add_cover`]. The perceptual hash tolerates resizing and recompression, so
+    /// a cover saved in a local folder still matches the thumbnail seen on DLsite.
+    #[cfg(feature = "image-hash")]
+    pub fn find_by_image_hash(&self, path: impl AsRef<Path>) -> Result<Vec<ImageMatch>> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| DlsiteError::Index(format!("{}: {}", path.display(), e)))?;
+        self.find_by_hash(image_hash(&bytes)?, MAX_HASH_DISTANCE)
+    }
+
+    /// Find works whose recorded cover hash differs from `hash` by at most `max_distance`
+    /// bits, closest first.
+    pub fn find_by_hash(&self, hash: u64, max_distance: u32) -> Result<Vec<ImageMatch>> {
+        let mut matches: Vec<(String, u32)> = self
+            .inner
+            .covers
+            .lock()
+            .unwrap()
+            .0
+            .iter()
+            .map(|(id, cover)| (id.clone(), (cover ^ hash).count_ones()))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .collect();
+        matches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+        matches
+            .into_iter()
+            .map(|(id, distance)| {
+                Ok(ImageMatch {
+                    work: self.get(&id)?,
+                    id,
+                    distance,
+                })
+            })
+            .collect()
+    }
+
+    /// Underlying tantivy index
+    pub fn tantivy_index(&self) -> &Index {
+        &self.inner.index
+    }
+}
+
+/// Perceptual hash (dHash) of an image: the image is shrunk to 9x8 grayscale pixels, and each
+/// bit tells whether a pixel is brighter than its right neighbour.
+///
+/// Similar images have hashes differing by few bits, which can be counted with
+/// `(a ^ b).count_ones()`.
+#[cfg(feature = "image-hash")]
+pub fn image_hash(image: &[u8]) -> Result<u64> {
+    let image = image::load_from_memory(image)
+        .map_err(|e| DlsiteError::Index(format!("Failed to decode image: {e}")))?;
+    let small = image
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .into_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexedWork, LocalIndex};
+
+    fn work(id: &str, title: &str, circle_name: &str) -> IndexedWork {
+        IndexedWork {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: None,
+            genres: vec!["ASMR".to_string()],
+            circle_name: circle_name.to_string(),
+            creators: vec!["佐倉綾音".to_string()],
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn search_japanese() {
+        let index = LocalIndex::in_memory().unwrap();
+        index
+            .add(&work("RJ01014447", "【耳かき】ユウカASMR", "Yostar"))
+            .unwrap();
+        index
+            .add(&work("RJ403038", "癒やしの耳かき", "テストサークル"))
+            .unwrap();
+        // Re-adding replaces the existing entry
+        index
+            .add(&work("RJ403038", "癒やしの耳かき", "テストサークル"))
+            .unwrap();
+        assert_eq!(index.len(), 2);
+
+        let ids = |q: &str| {
+            let mut ids: Vec<String> = index
+                .search(q, 10)
+                .unwrap()
+                .into_iter()
+                .map(|w| w.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids("耳かき"), vec!["RJ01014447", "RJ403038"]);
+        assert_eq!(ids("ユウカ"), vec!["RJ01014447"]);
+        assert_eq!(ids("yostar"), vec!["RJ01014447"]);
+        assert_eq!(ids("佐倉"), vec!["RJ01014447", "RJ403038"]);
+        assert!(ids("存在しない").is_empty());
+    }
+
+    #[test]
+    fn search_tags() {
+        let index = LocalIndex::in_memory().unwrap();
+        index
+            .add(&IndexedWork {
+                tags: vec!["ダウナー".to_string()],
+                ..work("RJ403038", "癒やしの耳かき", "テストサークル")
+            })
+            .unwrap();
+        let found = index.search("ダウナー", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tags, vec!["ダウナー"]);
+    }
+
+    #[test]
+    fn cover_hashes() {
+        let index = LocalIndex::in_memory().unwrap();
+        index
+            .add(&work("RJ403038", "癒やしの耳かき", "テストサークル"))
+            .unwrap();
+        index.add_cover_hash("RJ403038", 0b1111_0000).unwrap();
+        index.add_cover_hash("RJ01014447", u64::MAX).unwrap();
+
+        let matches = index.find_by_hash(0b1111_0001, 10).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, "RJ403038");
+        assert_eq!(matches[0].distance, 1);
+        assert_eq!(matches[0].work.as_ref().unwrap().title, "癒やしの耳かき");
+    }
+
+    #[test]
+    fn cover_hashes_saved_on_flush() {
+        let dir = std::env::temp_dir().join(format!("dlsite-index-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let index = LocalIndex::open(&dir).unwrap();
+        index.add_cover_hash("RJ403038", 0b1111_0000).unwrap();
+        assert!(!dir.join(super::COVER_HASHES_FILE).exists());
+        index.flush().unwrap();
+        drop(index);
+
+        let index = LocalIndex::open(&dir).unwrap();
+        let matches = index.find_by_hash(0b1111_0000, 0).unwrap();
+        assert_eq!(matches[0].id, "RJ403038");
+        drop(index);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "image-hash")]
+    #[test]
+    fn image_hash_resized() {
+        use super::image_hash;
+        use image::{ImageFormat, RgbImage};
+        use std::io::Cursor;
+
+        let encode = |size: u32, flip: bool| {
+            let img = RgbImage::from_fn(size, size, |x, y| {
+                let (x, y) = (x as f32 / size as f32, y as f32 / size as f32);
+                let v = ((x * 9.0).sin() * 80.0 + (y * 5.0).cos() * 40.0 + 128.0) as u8;
+                let v = if flip { 255 - v } else { v };
+                image::Rgb([v, v / 2, 255 - v])
+            });
+            let mut bytes = Cursor::new(vec![]);
+            img.write_to(&mut bytes, ImageFormat::Png).unwrap();
+            bytes.into_inner()
+        };
+
+        let original = image_hash(&encode(240, false)).unwrap();
+        let resized = image_hash(&encode(560, false)).unwrap();
+        let other = image_hash(&encode(240, true)).unwrap();
+        assert!((original ^ resized).count_ones() <= 4);
+        assert!((original ^ other).count_ones() > 20);
+    }
+}
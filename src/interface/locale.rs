@@ -0,0 +1,75 @@
+//! Languages DLsite is displayed in.
+
+use strum::{Display, EnumString};
+
+/// Locale of the DLsite pages, sent as the `locale` query parameter and cookie.
+///
+/// DLsite translates titles, genres and descriptions when a translation exists, and the
+/// labels of product pages and search results. See [`crate::DlsiteClientBuilder::locale`].
+#[derive(
+    Debug,
+    Display,
+    EnumString,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Locale {
+    /// 日本語
+    #[default]
+    #[strum(serialize = "ja_JP")]
+    #[serde(rename = "ja_JP")]
+    JaJp,
+    /// English
+    #[strum(serialize = "en_US")]
+    #[serde(rename = "en_US")]
+    EnUs,
+    /// 简体中文
+    #[strum(serialize = "zh_CN")]
+    #[serde(rename = "zh_CN")]
+    ZhCn,
+    /// 繁體中文
+    #[strum(serialize = "zh_TW")]
+    #[serde(rename = "zh_TW")]
+    ZhTw,
+    /// 한국어
+    #[strum(serialize = "ko_KR")]
+    #[serde(rename = "ko_KR")]
+    KoKr,
+}
+
+impl Locale {
+    /// Value of the `locale` cookie (`en_US` → `en-us`).
+    pub fn cookie_value(&self) -> String {
+        self.to_string().replace('_', "-").to_ascii_lowercase()
+    }
+
+    /// Primary language subtag (`en_US` → `en`), as found in the `lang` attribute of pages.
+    pub fn language(&self) -> &'static str {
+        match self {
+            Locale::JaJp => "ja",
+            Locale::EnUs => "en",
+            Locale::ZhCn | Locale::ZhTw => "zh",
+            Locale::KoKr => "ko",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Locale;
+
+    #[test]
+    fn locale_strings() {
+        assert_eq!(Locale::EnUs.to_string(), "en_US");
+        assert_eq!("zh_TW".parse::<Locale>().unwrap(), Locale::ZhTw);
+        assert_eq!(Locale::ZhCn.cookie_value(), "zh-cn");
+        assert_eq!(Locale::KoKr.language(), "ko");
+        assert_eq!(serde_json::to_string(&Locale::JaJp).unwrap(), "\"ja_JP\"");
+    }
+}
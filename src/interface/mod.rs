@@ -1,14 +1,97 @@
 //! Common interfaces
 
+pub mod locale;
 pub mod product;
+pub mod product_id;
 pub mod query;
+pub mod site;
 pub mod genre {
     //! Interfaces related to genre.
 
+    use std::{fmt, num::ParseIntError, str::FromStr};
+
     /// Genre struct
     #[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
     pub struct Genre {
         pub name: String,
         pub id: String,
     }
+
+    impl Genre {
+        /// Numeric code of the genre, `None` if the ID isn't numeric.
+        pub fn code(&self) -> Option<GenreCode> {
+            self.id.parse().ok()
+        }
+    }
+
+    /// Numeric genre ID used by DLsite, e.g. in search filters.
+    ///
+    /// Common genres have constants; any other ID can be used with `GenreCode(id)`.
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        serde::Serialize,
+        serde::Deserialize,
+    )]
+    #[serde(transparent)]
+    pub struct GenreCode(pub u32);
+
+    impl GenreCode {
+        /// バイノーラル/ダミヘ
+        pub const BINAURAL: GenreCode = GenreCode(496);
+        /// ASMR
+        pub const ASMR: GenreCode = GenreCode(497);
+    }
+
+    impl fmt::Display for GenreCode {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt(f)
+        }
+    }
+
+    impl FromStr for GenreCode {
+        type Err = ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.trim().parse().map(GenreCode)
+        }
+    }
+
+    impl From<u32> for GenreCode {
+        fn from(id: u32) -> Self {
+            GenreCode(id)
+        }
+    }
+
+    impl From<GenreCode> for u32 {
+        fn from(code: GenreCode) -> Self {
+            code.0
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{Genre, GenreCode};
+
+        #[test]
+        fn genre_code_round_trip() {
+            assert_eq!(GenreCode::ASMR.to_string(), "497");
+            assert_eq!("497".parse::<GenreCode>().unwrap(), GenreCode::ASMR);
+            assert!("ASMR".parse::<GenreCode>().is_err());
+            assert_eq!(serde_json::to_string(&GenreCode(60)).unwrap(), "60");
+            assert_eq!(serde_json::from_str::<GenreCode>("60").unwrap(), GenreCode(60));
+
+            let genre = Genre {
+                name: "ASMR".to_string(),
+                id: "497".to_string(),
+            };
+            assert_eq!(genre.code(), Some(GenreCode::ASMR));
+        }
+    }
 }
@@ -140,8 +140,48 @@ pub enum WorkType {
     Unknown(String),
 }
 
+impl WorkType {
+    /// Build from a label shown by DLsite (Japanese or English), e.g. `ボイス・ASMR` or
+    /// `Manga`. Girls and BL listings label some works without the `type_` class, and use a
+    /// few labels of their own (`ドラマCD`, `シチュエーションCD`). `None` if the label is unknown.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim();
+        let work_type = match label.to_lowercase().as_str() {
+            "アクション" | "action" => WorkType::ACN,
+            "クイズ" | "quiz" => WorkType::QIZ,
+            "アドベンチャー" | "adventure" => WorkType::ADV,
+            "ロールプレイング" | "role-playing" => WorkType::RPG,
+            "テーブル" | "table" => WorkType::TBL,
+            "デジタルノベル" | "digital novel" => WorkType::DNV,
+            "シミュレーション" | "simulation" => WorkType::SLN,
+            "タイピング" | "typing" => WorkType::TYP,
+            "シューティング" | "shooting" => WorkType::STG,
+            "パズル" | "puzzle" => WorkType::PZL,
+            "その他ゲーム" | "miscellaneous games" => WorkType::ETC,
+            "マンガ" | "manga" => WorkType::MNG,
+            "劇画" | "gekiga" => WorkType::SCM,
+            "webtoon" => WorkType::WBT,
+            "cg・イラスト" | "cg + illustrations" => WorkType::ICG,
+            "ノベル" | "novel" => WorkType::NRE,
+            "官能小説" | "erotic novel" => WorkType::KSV,
+            "動画" | "video" => WorkType::MOV,
+            "ボイス・asmr" | "voice / asmr" | "音声作品" | "ドラマcd" | "シチュエーションcd" => {
+                WorkType::SOU
+            }
+            "音楽" | "music" => WorkType::MUS,
+            "ツール/アクセサリ" | "tools / accessories" => WorkType::TOL,
+            "画像素材" | "illustration materials" => WorkType::IMT,
+            "音素材" | "music materials" => WorkType::AMT,
+            "その他" | "miscellaneous" => WorkType::ET3,
+            "ボイスコミック" | "voiced comics" => WorkType::VCM,
+            _ => return None,
+        };
+        Some(work_type)
+    }
+}
+
 /// Age category
-#[derive(Display, Debug, Clone, PartialEq, Deserialize_repr, Serialize_repr)]
+#[derive(Display, EnumString, Debug, Clone, PartialEq, Deserialize_repr, Serialize_repr)]
 #[repr(u16)]
 #[strum(serialize_all = "snake_case")]
 pub enum AgeCategory {
@@ -153,6 +193,35 @@ pub enum AgeCategory {
     Adult = 3,
 }
 
+impl AgeCategory {
+    /// Build from a label shown by DLsite, in any language DLsite is displayed in, e.g.
+    /// `全年齢`, `All ages`, `全年龄`, `R-15` or `18禁`. `None` if the label is unknown.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim();
+        match label.to_ascii_lowercase().replace(['-', ' '], "").as_str() {
+            "全年齢" | "全年龄" | "全年齡" | "一般向け" | "allages" | "전연령" | "전체이용가" => {
+                Some(AgeCategory::General)
+            }
+            "r15" => Some(AgeCategory::R15),
+            "r18" | "18禁" | "adult" | "成人向け" | "成人向" | "성인" => {
+                Some(AgeCategory::Adult)
+            }
+            _ => None,
+        }
+    }
+
+    /// Build from the code of an age badge class, e.g. `ADL` for `icon_ADL`. `None` if the
+    /// code is unknown.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.to_ascii_uppercase().as_str() {
+            "GEN" | "ALL" => Some(AgeCategory::General),
+            "R15" => Some(AgeCategory::R15),
+            "ADL" | "R18" => Some(AgeCategory::Adult),
+            _ => None,
+        }
+    }
+}
+
 /// Work category (parent category)
 #[derive(Display, EnumString, PartialEq, DeserializeFromStr, Debug, Clone)]
 #[strum(serialize_all = "snake_case")]
@@ -201,3 +270,423 @@ pub enum FileType {
     #[strum(default)]
     Unknown(String),
 }
+
+/// Star rating breakdown (評価の内訳)
+///
+/// Holds the number of ratings per star (1★..5★), which makes it possible to tell a
+/// consistently good work from one with polarized ratings that happen to average the same.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RatingDistribution {
+    /// Rating counts, indexed by `star - 1`.
+    pub counts: [u32; 5],
+}
+
+impl RatingDistribution {
+    /// Create a distribution from an iterator of `(star, count)` pairs.
+    ///
+    /// Entries with a star outside of `1..=5` are ignored.
+    pub fn from_pairs(pairs: impl IntoIterator<Item = (u32, u32)>) -> Self {
+        let mut counts = [0; 5];
+        for (star, count) in pairs {
+            if (1..=5).contains(&star) {
+                counts[(star - 1) as usize] += count;
+            }
+        }
+        Self { counts }
+    }
+
+    /// Number of ratings with the given star (1-5).
+    pub fn count(&self, star: u32) -> u32 {
+        match star {
+            1..=5 => self.counts[(star - 1) as usize],
+            _ => 0,
+        }
+    }
+
+    /// Total number of ratings.
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Average rating, or `None` if there are no ratings.
+    pub fn average(&self) -> Option<f32> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let sum: u32 = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i as u32 + 1) * c)
+            .sum();
+        Some(sum as f32 / total as f32)
+    }
+
+    /// Standard deviation of the ratings, or `None` if there are no ratings.
+    pub fn std_dev(&self) -> Option<f32> {
+        let average = self.average()?;
+        let variance = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let diff = (i + 1) as f32 - average;
+                diff * diff * *c as f32
+            })
+            .sum::<f32>()
+            / self.total() as f32;
+        Some(variance.sqrt())
+    }
+
+    /// Share of ratings at the extremes (1★ and 5★), in `0.0..=1.0`.
+    ///
+    /// A high value together with a middling average indicates polarized ratings.
+    pub fn polarization(&self) -> Option<f32> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        Some((self.counts[0] + self.counts[4]) as f32 / total as f32)
+    }
+}
+
+/// Community translation ("みんなで翻訳") status of a product.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TranslationPermission {
+    /// Whether the creator allows community translations of the work
+    pub allowed: bool,
+    /// Whether this product itself is a community translation
+    pub is_translation: bool,
+    /// Language codes with an open translation request/bonus (e.g. `ENG`, `CHI_HANS`)
+    pub languages: Vec<String>,
+    /// Share of translated edition sales (in percent) going to the original creator, when
+    /// provided by DLsite (`production_trade_price_rate`)
+    pub royalty_rate: Option<u32>,
+}
+
+/// A platform a work can run on.
+#[derive(
+    Display, EnumString, Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    Windows,
+    Mac,
+    Android,
+    Ios,
+    /// Playable in a browser (DLsite Play)
+    Browser,
+}
+
+/// Platforms supported by a work (対応OS).
+///
+/// All fields are false when DLsite doesn't list any platform, which is common for works
+/// that don't need one (e.g. audio files or images).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Platforms {
+    pub windows: bool,
+    pub mac: bool,
+    pub android: bool,
+    pub ios: bool,
+    pub browser: bool,
+}
+
+impl Platforms {
+    /// Build from labels shown by DLsite, e.g. `Windows`, `Android専用`, `iOS対応` or
+    /// `ブラウザ視聴`. Unknown labels are ignored.
+    pub fn from_labels<'a>(labels: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut platforms = Self::default();
+        for label in labels {
+            let label = label.to_lowercase();
+            if label.contains("ブラウザ") || label.contains("browser") || label.contains("dlsite play")
+            {
+                platforms.browser = true;
+            }
+            for token in label.split(|c: char| !c.is_ascii_alphanumeric()) {
+                match token {
+                    t if t.starts_with("windows") || t == "win" || t == "pc" => {
+                        platforms.windows = true
+                    }
+                    t if t.starts_with("mac") => platforms.mac = true,
+                    t if t.starts_with("android") => platforms.android = true,
+                    "ios" | "iphone" | "ipad" => platforms.ios = true,
+                    _ => {}
+                }
+            }
+        }
+        platforms
+    }
+
+    /// Whether the work runs on `platform`.
+    pub fn supports(&self, platform: Platform) -> bool {
+        match platform {
+            Platform::Windows => self.windows,
+            Platform::Mac => self.mac,
+            Platform::Android => self.android,
+            Platform::Ios => self.ios,
+            Platform::Browser => self.browser,
+        }
+    }
+
+    /// Whether no platform is known.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Format of the files of a work (ファイル形式).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    Wav,
+    Mp3,
+    Flac,
+    Mp4,
+    Pdf,
+    Html,
+    /// 画像ファイル (JPEG, PNG...)
+    Image,
+    /// アプリケーション (executable)
+    Application,
+    /// Any other label, as shown by DLsite
+    Other(String),
+}
+
+impl FileFormat {
+    /// Build from a label shown by DLsite, e.g. `WAV`, `画像ファイル` or `アプリケーション`.
+    pub fn from_label(label: &str) -> Self {
+        let label = label.trim();
+        match label.to_ascii_uppercase().as_str() {
+            "WAV" => FileFormat::Wav,
+            "MP3" => FileFormat::Mp3,
+            "FLAC" => FileFormat::Flac,
+            "MP4" => FileFormat::Mp4,
+            "PDF" => FileFormat::Pdf,
+            "HTML" => FileFormat::Html,
+            _ if label.contains("画像") || label.eq_ignore_ascii_case("jpeg") => {
+                FileFormat::Image
+            }
+            _ if label.contains("アプリケーション") || label.eq_ignore_ascii_case("exe") => {
+                FileFormat::Application
+            }
+            _ => FileFormat::Other(label.to_string()),
+        }
+    }
+}
+
+/// Size and formats of the files of a work, as shown on the product page.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FileInfo {
+    /// Total size of the files in bytes (ファイル容量)
+    pub total_size_bytes: Option<u64>,
+    pub formats: Vec<FileFormat>,
+    /// Whether a trial version (体験版) can be downloaded
+    pub has_trial: bool,
+    /// Size of the trial version in bytes
+    pub trial_size_bytes: Option<u64>,
+}
+
+/// External protection a work depends on (online activation, serial code, third-party
+/// launcher), as noted on its product page.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DrmInfo {
+    /// Needs an online activation (オンライン認証, ライセンス認証...)
+    pub online_activation: bool,
+    /// Needs a serial code or product key (シリアルコード)
+    pub serial_code: bool,
+    /// Third-party launchers or stores needed to play, e.g. `Steam`
+    pub launchers: Vec<String>,
+    /// Number of devices the work can be activated on, when given (`3台まで`)
+    pub activation_limit: Option<u32>,
+    /// Sentences of the page mentioning the protection
+    pub notes: Vec<String>,
+}
+
+impl DrmInfo {
+    /// Find the protection mentioned in texts of a product page (description, system
+    /// requirements...). Sentences stating the work has no protection (`DRMフリー`, `認証不要`)
+    /// are ignored.
+    pub fn from_notes<'a>(texts: impl IntoIterator<Item = &'a str>) -> Self {
+        const ACTIVATION: [&str; 6] = [
+            "オンライン認証",
+            "ライセンス認証",
+            "アクティベーション",
+            "認証が必要",
+            "online activation",
+            "drm",
+        ];
+        const SERIAL: [&str; 5] = [
+            "シリアルコード",
+            "シリアルキー",
+            "プロダクトキー",
+            "serial code",
+            "product key",
+        ];
+        const LAUNCHERS: [(&str, &str); 4] = [
+            ("steam", "Steam"),
+            ("dmm game player", "DMM GAME PLAYER"),
+            ("dmm gameplayer", "DMM GAME PLAYER"),
+            ("dlsite nest", "DLsite Nest"),
+        ];
+        const FREE: [&str; 5] = ["drmフリー", "drm free", "drm-free", "認証不要", "認証なし"];
+        static LIMIT_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+        let limit_re = LIMIT_RE.get_or_init(|| {
+            regex::Regex::new(r"(\d+)\s*(?:台まで|台の|台で|(?:pcs|devices))").unwrap()
+        });
+
+        let mut drm = Self::default();
+        let sentences = texts
+            .into_iter()
+            .flat_map(|text| text.split(['\n', '。', '！', '※']))
+            .map(str::trim)
+            .filter(|sentence| !sentence.is_empty());
+        for sentence in sentences {
+            let lower = sentence.to_lowercase();
+            if FREE.iter().any(|m| lower.contains(m)) {
+                continue;
+            }
+            let activation = ACTIVATION.iter().any(|m| lower.contains(m));
+            let serial = SERIAL.iter().any(|m| lower.contains(m));
+            let launchers: Vec<&str> = LAUNCHERS
+                .iter()
+                .filter(|(marker, _)| lower.contains(marker))
+                .map(|(_, name)| *name)
+                .collect();
+            let limit = (lower.contains("認証") || lower.contains("activat"))
+                .then(|| limit_re.captures(&lower)?[1].parse().ok())
+                .flatten();
+            if !activation && !serial && launchers.is_empty() && limit.is_none() {
+                continue;
+            }
+            drm.online_activation |= activation;
+            drm.serial_code |= serial;
+            for launcher in launchers {
+                if !drm.launchers.iter().any(|l| l == launcher) {
+                    drm.launchers.push(launcher.to_string());
+                }
+            }
+            drm.activation_limit = drm.activation_limit.or(limit);
+            if !drm.notes.iter().any(|n| n == sentence) {
+                drm.notes.push(sentence.to_string());
+            }
+        }
+        drm
+    }
+
+    /// Whether something beyond downloading is needed to use the work.
+    pub fn requires_activation(&self) -> bool {
+        self.online_activation || self.serial_code || !self.launchers.is_empty()
+    }
+
+    /// Whether no protection was found.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AgeCategory, DrmInfo, FileFormat, Platform, Platforms, RatingDistribution, WorkType,
+    };
+
+    #[test]
+    fn platforms_from_labels() {
+        let platforms = Platforms::from_labels(["Windows10 / 11", "Android専用", "ブラウザ視聴"]);
+        assert!(platforms.supports(Platform::Windows));
+        assert!(platforms.supports(Platform::Android));
+        assert!(platforms.supports(Platform::Browser));
+        assert!(!platforms.supports(Platform::Ios));
+        assert!(!platforms.supports(Platform::Mac));
+
+        assert!(Platforms::from_labels(["iOS対応", "Mac OS X"]).ios);
+        assert!(Platforms::from_labels(["日本語", "音声あり"]).is_empty());
+    }
+
+    #[test]
+    fn file_format_from_label() {
+        assert_eq!(FileFormat::from_label("wav"), FileFormat::Wav);
+        assert_eq!(FileFormat::from_label("画像ファイル"), FileFormat::Image);
+        assert_eq!(
+            FileFormat::from_label("アプリケーション"),
+            FileFormat::Application
+        );
+        assert_eq!(
+            FileFormat::from_label("独自形式 "),
+            FileFormat::Other("独自形式".to_string())
+        );
+    }
+
+    #[test]
+    fn age_category_from_label() {
+        assert_eq!(
+            AgeCategory::from_label("全年齢"),
+            Some(AgeCategory::General)
+        );
+        assert_eq!(
+            AgeCategory::from_label(" All Ages "),
+            Some(AgeCategory::General)
+        );
+        assert_eq!(
+            AgeCategory::from_label("全年龄"),
+            Some(AgeCategory::General)
+        );
+        assert_eq!(AgeCategory::from_label("R-15"), Some(AgeCategory::R15));
+        assert_eq!(AgeCategory::from_label("R18"), Some(AgeCategory::Adult));
+        assert_eq!(AgeCategory::from_label("18禁"), Some(AgeCategory::Adult));
+        assert_eq!(AgeCategory::from_label("R-12"), None);
+        assert_eq!(AgeCategory::from_code("adl"), Some(AgeCategory::Adult));
+        assert_eq!(AgeCategory::from_code("GEN"), Some(AgeCategory::General));
+        assert_eq!(AgeCategory::from_code("NEW"), None);
+    }
+
+    #[test]
+    fn work_type_from_label() {
+        assert_eq!(WorkType::from_label("ボイス・ASMR"), Some(WorkType::SOU));
+        assert_eq!(WorkType::from_label("ドラマCD"), Some(WorkType::SOU));
+        assert_eq!(WorkType::from_label(" Manga "), Some(WorkType::MNG));
+        assert_eq!(WorkType::from_label("乙女"), None);
+    }
+
+    #[test]
+    fn rating_distribution_stats() {
+        let dist = RatingDistribution::from_pairs([(5, 6), (4, 0), (3, 0), (2, 0), (1, 4), (7, 100)]);
+        assert_eq!(dist.total(), 10);
+        assert_eq!(dist.count(5), 6);
+        assert_eq!(dist.count(0), 0);
+        assert_eq!(dist.average(), Some(3.4));
+        assert_eq!(dist.polarization(), Some(1.0));
+
+        let flat = RatingDistribution::from_pairs([(3, 4), (4, 6)]);
+        assert!(flat.std_dev().unwrap() < dist.std_dev().unwrap());
+        assert_eq!(flat.polarization(), Some(0.0));
+    }
+
+    #[test]
+    fn rating_distribution_empty() {
+        let dist = RatingDistribution::default();
+        assert_eq!(dist.average(), None);
+        assert_eq!(dist.std_dev(), None);
+        assert_eq!(dist.polarization(), None);
+    }
+
+    #[test]
+    fn drm_from_notes() {
+        let drm = DrmInfo::from_notes([
+            "本作品はオンライン認証が必要です。認証は3台までとなります。",
+            "※起動にはSteamクライアントが必要です\nプレイにはシリアルコードを入力してください",
+        ]);
+        assert!(drm.online_activation);
+        assert!(drm.serial_code);
+        assert_eq!(drm.launchers, vec!["Steam"]);
+        assert_eq!(drm.activation_limit, Some(3));
+        assert_eq!(drm.notes.len(), 4);
+        assert!(drm.requires_activation());
+
+        let free = DrmInfo::from_notes(["DRMフリーです。Windows10で動作確認済み"]);
+        assert!(free.is_empty());
+        assert!(!free.requires_activation());
+    }
+}
@@ -0,0 +1,275 @@
+//! Typed product IDs.
+
+use std::{fmt, str::FromStr};
+
+use crate::{error::Result, interface::site::Site, DlsiteError};
+
+/// Kind of product, given by the two-letter prefix of its ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductKind {
+    /// `RJ`: 同人 work
+    Doujin,
+    /// `RE`: 同人 work of the former English storefront
+    DoujinEnglish,
+    /// `VJ`: 美少女ゲーム / PC software
+    Pro,
+    /// `VE`: PC software of the former English storefront
+    ProEnglish,
+    /// `BJ`: 成年コミック / books
+    Books,
+    /// `AJ`: スマホゲーム / Android apps
+    App,
+}
+
+impl ProductKind {
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "RJ" => Some(ProductKind::Doujin),
+            "RE" => Some(ProductKind::DoujinEnglish),
+            "VJ" => Some(ProductKind::Pro),
+            "VE" => Some(ProductKind::ProEnglish),
+            "BJ" => Some(ProductKind::Books),
+            "AJ" => Some(ProductKind::App),
+            _ => None,
+        }
+    }
+
+    /// Two-letter prefix of the IDs of this kind.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            ProductKind::Doujin => "RJ",
+            ProductKind::DoujinEnglish => "RE",
+            ProductKind::Pro => "VJ",
+            ProductKind::ProEnglish => "VE",
+            ProductKind::Books => "BJ",
+            ProductKind::App => "AJ",
+        }
+    }
+}
+
+/// A product ID such as `RJ403038` or `RJ01014447`.
+///
+/// IDs are a known prefix (see [`ProductKind`]) followed by 6 digits, or 8 digits starting with
+/// `0` for products registered after the 6 digit numbers ran out (`RJ01000000` and later).
+///
+/// Client methods accept anything convertible into a `ProductId` (`&str`, `String`...). The
+/// conversion only normalizes the ID (trimmed, uppercase); it is validated by the method before
+/// any request is sent, failing with [`DlsiteError::InvalidProductId`]. Use [`str::parse`] to
+/// validate an ID upfront.
+///
+/// # Example
+/// ```
+/// use dlsite_gamebox::interface::{product_id::{ProductId, ProductKind}, site::Site};
+///
+/// let id: ProductId = "vj01000513".parse().unwrap();
+/// assert_eq!(id.as_str(), "VJ01000513");
+/// assert_eq!(id.kind(), Some(ProductKind::Pro));
+/// assert_eq!(id.site(), Some(Site::Pro));
+/// assert_eq!(id.url(), "https://www.dlsite.com/pro/work/=/product_id/VJ01000513.html");
+///
+/// assert!("RJ4030381".parse::<ProductId>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProductId(String);
+
+impl ProductId {
+    /// The normalized ID.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Kind of product, `None` if the prefix is unknown.
+    pub fn kind(&self) -> Option<ProductKind> {
+        ProductKind::from_prefix(self.0.get(..2)?)
+    }
+
+    /// Number of the product, `None` if the ID is invalid.
+    pub fn number(&self) -> Option<u32> {
+        if !self.is_valid() {
+            return None;
+        }
+        self.0[2..].parse().ok()
+    }
+
+    /// Whether the ID uses the 8 digit format introduced after `RJ999999`.
+    pub fn is_eight_digit(&self) -> bool {
+        self.is_valid() && self.0.len() == 10
+    }
+
+    /// Whether the ID has a known prefix and a valid number.
+    pub fn is_valid(&self) -> bool {
+        let Some(digits) = self.0.get(2..) else {
+            return false;
+        };
+        self.kind().is_some()
+            && digits.bytes().all(|b| b.is_ascii_digit())
+            && match digits.len() {
+                6 => true,
+                8 => digits.starts_with('0'),
+                _ => false,
+            }
+    }
+
+    /// Storefront the product belongs to, if its prefix implies one.
+    ///
+    /// `RJ` works are reachable from both maniax and home, so `None` is returned for them, see
+    /// [`Site::from_product_id`].
+    pub fn site(&self) -> Option<Site> {
+        Site::from_product_id(&self.0)
+    }
+
+    /// URL of the product page. Products without a specific storefront use maniax, which
+    /// redirects to home for all-ages works.
+    pub fn url(&self) -> String {
+        format!(
+            "https://www.dlsite.com/{}/work/=/product_id/{}.html",
+            self.site().unwrap_or_default(),
+            self.0
+        )
+    }
+
+    /// Fail with [`DlsiteError::InvalidProductId`] if the ID is invalid.
+    pub(crate) fn checked(self) -> Result<Self> {
+        if self.is_valid() {
+            Ok(self)
+        } else {
+            Err(DlsiteError::InvalidProductId(self.0))
+        }
+    }
+}
+
+impl FromStr for ProductId {
+    type Err = DlsiteError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ProductId::from(s).checked()
+    }
+}
+
+impl fmt::Display for ProductId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ProductId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for ProductId {
+    fn from(s: &str) -> Self {
+        ProductId(s.trim().to_ascii_uppercase())
+    }
+}
+
+impl From<&String> for ProductId {
+    fn from(s: &String) -> Self {
+        ProductId::from(s.as_str())
+    }
+}
+
+impl From<String> for ProductId {
+    fn from(s: String) -> Self {
+        ProductId::from(s.as_str())
+    }
+}
+
+impl From<&ProductId> for ProductId {
+    fn from(id: &ProductId) -> Self {
+        id.clone()
+    }
+}
+
+impl From<ProductId> for String {
+    fn from(id: ProductId) -> Self {
+        id.0
+    }
+}
+
+impl serde::Serialize for ProductId {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ProductId {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProductId, ProductKind};
+    use crate::interface::site::Site;
+
+    #[test]
+    fn validate() {
+        for id in [
+            "RJ403038",
+            "RJ01014447",
+            "rj01014447",
+            " VJ01000513 ",
+            "BJ123456",
+            "RE123456",
+            "AJ01000123",
+        ] {
+            assert!(id.parse::<ProductId>().is_ok(), "{id}");
+        }
+        for id in [
+            "RJ4030381",
+            "RJ11014447",
+            "RJ40303",
+            "RG62982",
+            "RJ",
+            "",
+            "RJ40303x",
+        ] {
+            assert!(id.parse::<ProductId>().is_err(), "{id}");
+        }
+    }
+
+    #[test]
+    fn classify() {
+        let id: ProductId = "rj01014447".parse().unwrap();
+        assert_eq!(id.to_string(), "RJ01014447");
+        assert_eq!(id.kind(), Some(ProductKind::Doujin));
+        assert_eq!(id.number(), Some(1014447));
+        assert!(id.is_eight_digit());
+        assert_eq!(id.site(), None);
+        assert_eq!(
+            id.url(),
+            "https://www.dlsite.com/maniax/work/=/product_id/RJ01014447.html"
+        );
+
+        let id: ProductId = "BJ123456".parse().unwrap();
+        assert_eq!(id.kind().map(|k| k.prefix()), Some("BJ"));
+        assert!(!id.is_eight_digit());
+        assert_eq!(id.site(), Some(Site::Books));
+
+        let id: ProductId = "aj01000123".parse().unwrap();
+        assert_eq!(id.kind(), Some(ProductKind::App));
+        assert_eq!(id.site(), Some(Site::AppX));
+        assert_eq!(
+            id.url(),
+            "https://www.dlsite.com/appx/work/=/product_id/AJ01000123.html"
+        );
+    }
+
+    #[test]
+    fn serde() {
+        let id: ProductId = serde_json::from_str("\"rj403038\"").unwrap();
+        assert_eq!(id.as_str(), "RJ403038");
+        assert_eq!(serde_json::to_string(&id).unwrap(), "\"RJ403038\"");
+        assert!(serde_json::from_str::<ProductId>("\"RJ1\"").is_err());
+    }
+}
@@ -1,13 +1,13 @@
-use strum::Display;
+use strum::{Display, EnumString};
 
-#[derive(Display, Default)]
+#[derive(Display, EnumString, Default)]
 #[strum(serialize_all = "snake_case")]
 pub enum Language {
     #[default]
     Jp,
 }
 
-#[derive(Display)]
+#[derive(Display, EnumString)]
 #[strum(serialize_all = "snake_case")]
 pub enum SexCategory {
     Male,
@@ -15,7 +15,7 @@ pub enum SexCategory {
 }
 
 /// Flag to represent sales status
-#[derive(Display)]
+#[derive(Display, EnumString)]
 #[strum(serialize_all = "snake_case")]
 pub enum AnaFlg {
     Off,
@@ -24,7 +24,7 @@ pub enum AnaFlg {
     All,
 }
 
-#[derive(Display)]
+#[derive(Display, EnumString)]
 #[strum(serialize_all = "snake_case")]
 pub enum Order {
     Trend,
@@ -44,16 +44,19 @@ pub enum Order {
     RateD,
     /// レビューが多い
     ReviewD,
+    /// お気に入りが多い
+    FavoriteD,
 }
 
-#[derive(Display)]
+#[derive(Display, EnumString)]
 #[strum(serialize_all = "snake_case")]
 pub enum OptionAndOr {
     And,
     Or,
 }
 
-#[derive(Display)]
+#[derive(Display, EnumString)]
+#[strum(ascii_case_insensitive)]
 pub enum ReleaseTerm {
     None,
     Week,
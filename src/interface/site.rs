@@ -0,0 +1,125 @@
+//! Interfaces related to DLsite storefronts.
+
+use strum::{Display, EnumString};
+
+use crate::interface::product::AgeCategory;
+
+/// DLsite storefront (the first path segment after `www.dlsite.com`).
+#[derive(
+    Debug,
+    Display,
+    EnumString,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum Site {
+    /// 同人 (adult)
+    #[default]
+    Maniax,
+    /// 同人 (all ages)
+    Home,
+    /// 美少女ゲーム
+    Pro,
+    /// 成年コミック
+    Books,
+    /// PCソフト (all ages)
+    Soft,
+    /// コミック (all ages)
+    Comic,
+    /// 乙女 (同人)
+    Girls,
+    /// 乙女 (商業)
+    GirlsPro,
+    /// BL (同人)
+    Bl,
+    /// BL (商業)
+    BlPro,
+    /// スマホゲーム
+    #[strum(serialize = "appx")]
+    #[serde(rename = "appx")]
+    AppX,
+}
+
+impl Site {
+    /// Every storefront.
+    pub const ALL: [Site; 11] = [
+        Site::Maniax,
+        Site::Home,
+        Site::Pro,
+        Site::Books,
+        Site::Soft,
+        Site::Comic,
+        Site::Girls,
+        Site::GirlsPro,
+        Site::Bl,
+        Site::BlPro,
+        Site::AppX,
+    ];
+
+    /// Whether the storefront only sells all-ages works.
+    ///
+    /// Search results of these storefronts don't mark works with an age icon.
+    pub fn is_all_ages(&self) -> bool {
+        matches!(self, Site::Home | Site::Soft | Site::Comic)
+    }
+
+    /// Age category of a search result without age icon: adult works are not marked on
+    /// adult storefronts, while all-ages storefronts don't mark anything.
+    pub(crate) fn unmarked_age_category(&self) -> AgeCategory {
+        if self.is_all_ages() {
+            AgeCategory::General
+        } else {
+            AgeCategory::Adult
+        }
+    }
+
+    /// Guess the storefront a product belongs to from its ID prefix.
+    ///
+    /// Returns `None` if the prefix doesn't imply a specific storefront (e.g. `RJ` works are
+    /// reachable from both maniax and home), in which case the configured site should be used.
+    pub fn from_product_id(product_id: &str) -> Option<Self> {
+        let prefix = product_id.get(..2)?.to_ascii_uppercase();
+        match prefix.as_str() {
+            "VJ" => Some(Site::Pro),
+            "BJ" => Some(Site::Books),
+            "AJ" => Some(Site::AppX),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Site;
+
+    #[test]
+    fn site_from_product_id() {
+        assert_eq!(Site::from_product_id("VJ01000513"), Some(Site::Pro));
+        assert_eq!(Site::from_product_id("BJ123456"), Some(Site::Books));
+        assert_eq!(Site::from_product_id("bj123456"), Some(Site::Books));
+        assert_eq!(Site::from_product_id("AJ01000123"), Some(Site::AppX));
+        assert_eq!(Site::from_product_id("RJ403038"), None);
+        assert_eq!(Site::from_product_id("R"), None);
+    }
+
+    #[test]
+    fn site_path() {
+        assert_eq!(Site::Maniax.to_string(), "maniax");
+        assert_eq!("pro".parse::<Site>().unwrap(), Site::Pro);
+        assert_eq!(Site::GirlsPro.to_string(), "girls-pro");
+        assert_eq!(Site::AppX.to_string(), "appx");
+        assert_eq!("bl-pro".parse::<Site>().unwrap(), Site::BlPro);
+        for site in Site::ALL {
+            assert_eq!(site.to_string().parse::<Site>().unwrap(), site);
+            assert_eq!(serde_json::to_string(&site).unwrap(), format!("\"{site}\""));
+        }
+    }
+}
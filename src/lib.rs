@@ -6,14 +6,48 @@
     cfg_attr(doc, doc = ::document_features::document_features!())
 )]
 
+pub mod analytics;
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod cache;
 pub mod client;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod conformance;
 pub mod error;
+pub mod eta;
+pub mod events;
+#[cfg(feature = "tantivy")]
+pub mod index;
 pub mod interface;
+pub mod library;
+pub mod normalize;
+pub mod persist;
+pub mod pipeline;
+pub mod planner;
+pub mod ratelimit;
+pub mod recommend;
+pub mod reports;
 pub mod retry;
+pub mod runtime;
+pub mod selector;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shutdown;
+pub mod testing;
+pub mod tracker;
+pub mod transport;
 mod utils;
+pub mod watch;
 
-pub use cache::{ResponseCache, GenericCache};
-pub use client::{DlsiteClient, DlsiteClientBuilder};
+pub use cache::{
+    CacheBackend, CacheStats, DiskCache, GenericCache, MediaCache, MemoryCache, ResponseCache,
+};
+pub use client::{pool::ClientPool, DlsiteClient, DlsiteClientBuilder, FetchOptions};
 pub use error::DlsiteError;
 pub use retry::RetryConfig;
+pub use utils::parse_file_size;
+#[cfg(feature = "sanitize")]
+pub use utils::sanitize_html;
@@ -0,0 +1,223 @@
+//! Helpers to manage a local library of DLsite works.
+//!
+//! A library is a directory tree where each work lives in a file or folder whose name contains
+//! its product ID (e.g. `RJ403038 ユウカASMR/` or `[RJ01017217].zip`).
+//!
+//! A [`LibraryStore`] keeps the works found across runs, and [`sync`] marks them as owned or
+//! not by the logged-in account.
+
+mod store;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use futures::StreamExt as _;
+use regex::Regex;
+
+use crate::{
+    client::product_api::interface::ProductApiContent,
+    error::Result,
+    eta::Eta,
+    interface::{
+        genre::Genre,
+        product::{AgeCategory, Platforms, WorkType},
+    },
+    DlsiteClient,
+};
+
+pub use self::store::{sync, LibraryStore, StoredItem, SyncReport};
+
+/// A work found in a local library.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LibraryItem {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+/// Compact, serializable metadata of a work.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WorkMetadata {
+    pub id: String,
+    pub title: String,
+    pub circle_id: Option<String>,
+    pub circle_name: String,
+    pub work_type: WorkType,
+    pub age_category: AgeCategory,
+    pub genres: Vec<Genre>,
+    pub creators: Vec<String>,
+    pub price: i64,
+    pub rating: Option<f32>,
+    pub released_at: Option<String>,
+    #[serde(default)]
+    pub platforms: Platforms,
+}
+
+impl From<&ProductApiContent> for WorkMetadata {
+    fn from(product: &ProductApiContent) -> Self {
+        let mut creators: Vec<String> = vec![];
+        if let Some(c) = &product.creators {
+            for list in [&c.created_by, &c.voice_by, &c.illust_by, &c.scenario_by]
+                .into_iter()
+                .flatten()
+            {
+                for creator in list {
+                    if !creators.contains(&creator.name) {
+                        creators.push(creator.name.clone());
+                    }
+                }
+            }
+        }
+
+        Self {
+            id: product.workno.clone(),
+            title: product.work_name.clone(),
+            circle_id: product.circle_id.clone(),
+            circle_name: product.maker_name.clone(),
+            work_type: product.work_type.clone(),
+            age_category: product.age_category.clone(),
+            genres: product
+                .genres
+                .iter()
+                .map(|g| Genre {
+                    name: g.name.clone(),
+                    id: g.id.to_string(),
+                })
+                .collect(),
+            creators,
+            price: product.price,
+            rating: product.rating_distribution().average(),
+            released_at: product.regist_date.clone(),
+            platforms: product.platforms(),
+        }
+    }
+}
+
+/// A library item together with its fetched metadata.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EnrichedItem {
+    pub item: LibraryItem,
+    pub metadata: Option<WorkMetadata>,
+    /// Error message if the metadata could not be fetched
+    pub error: Option<String>,
+}
+
+/// Find a product ID (RJ/RE/VJ/BJ followed by 6 or 8 digits) in a file or folder name.
+///
+/// The returned ID is uppercased.
+pub fn find_product_id(name: &str) -> Option<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"(?i)(?:^|[^a-z0-9])((?:rj|re|vj|bj)(?:\d{8}|\d{6}))(?:$|[^0-9])").unwrap()
+    });
+    re.captures(name)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().to_ascii_uppercase())
+}
+
+/// Scan a directory tree for works.
+///
+/// Directories whose name contains a product ID are treated as a single work and are not
+/// descended into.
+pub fn scan(root: impl AsRef<Path>) -> std::io::Result<Vec<LibraryItem>> {
+    let mut items = vec![];
+    scan_dir(root.as_ref(), &mut items)?;
+    items.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(items)
+}
+
+fn scan_dir(dir: &Path, items: &mut Vec<LibraryItem>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        if let Some(id) = find_product_id(&name.to_string_lossy()) {
+            items.push(LibraryItem { id, path });
+        } else if entry.file_type()?.is_dir() {
+            scan_dir(&path, items)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch metadata for library items using the product api.
+///
+/// Requests still go through the client's rate limiter; `concurrency` only bounds the number
+/// of in-flight requests. Failures are recorded per item instead of aborting the whole run.
+pub async fn enrich(
+    client: &DlsiteClient,
+    items: Vec<LibraryItem>,
+    concurrency: usize,
+) -> Vec<EnrichedItem> {
+    let eta = Eta::new(client, items.len(), 1.0);
+    enrich_with_eta(client, items, concurrency, &eta).await
+}
+
+/// Same as [`enrich`], reporting progress to `eta` (one request per item).
+pub async fn enrich_with_eta(
+    client: &DlsiteClient,
+    items: Vec<LibraryItem>,
+    concurrency: usize,
+    eta: &Eta,
+) -> Vec<EnrichedItem> {
+    futures::stream::iter(items)
+        .map(|item| async move {
+            let result = fetch_metadata(client, &item.id).await;
+            eta.complete(1);
+            match result {
+                Ok(metadata) => EnrichedItem {
+                    item,
+                    metadata: Some(metadata),
+                    error: None,
+                },
+                Err(e) => EnrichedItem {
+                    item,
+                    metadata: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+async fn fetch_metadata(client: &DlsiteClient, id: &str) -> Result<WorkMetadata> {
+    let product = client.product_api().get(id).await?;
+    Ok(WorkMetadata::from(&product))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_product_id, scan};
+
+    #[test]
+    fn product_id_in_name() {
+        assert_eq!(find_product_id("RJ403038"), Some("RJ403038".to_string()));
+        assert_eq!(
+            find_product_id("[circle] title (rj01017217).zip"),
+            Some("RJ01017217".to_string())
+        );
+        assert_eq!(find_product_id("VJ01000513_setup"), Some("VJ01000513".to_string()));
+        assert_eq!(find_product_id("XRJ403038"), None);
+        assert_eq!(find_product_id("RJ4030381"), None);
+        assert_eq!(find_product_id("no id here"), None);
+    }
+
+    #[test]
+    fn scan_library() {
+        let root = std::env::temp_dir().join(format!("dlsite-scan-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("voice/RJ403038 ユウカ/inner RJ000001")).unwrap();
+        std::fs::create_dir_all(root.join("games")).unwrap();
+        std::fs::write(root.join("games/[RJ01017217].zip"), b"").unwrap();
+        std::fs::write(root.join("games/readme.txt"), b"").unwrap();
+
+        let items = scan(&root).unwrap();
+        let ids: Vec<_> = items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["RJ01017217", "RJ403038"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+//! Local library state kept across runs, and its synchronization with the purchase history
+//! of the account.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+
+use super::{LibraryItem, WorkMetadata};
+use crate::{
+    client::account::PurchasedWork,
+    error::Result,
+    persist::{self, Persisted},
+    DlsiteClient,
+};
+
+/// A work of the [`LibraryStore`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StoredItem {
+    pub id: String,
+    /// Files and folders holding the work. Empty if the work was purchased but isn't in the
+    /// library.
+    pub paths: Vec<PathBuf>,
+    pub metadata: Option<WorkMetadata>,
+    /// Whether the account owns the work, `None` until the first [`sync`]
+    pub owned: Option<bool>,
+}
+
+/// Works of a local library with their metadata and ownership, saved with
+/// [`crate::persist`].
+///
+/// Fill it with [`scan`](super::scan) results, then [`sync`] it with the purchase history.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LibraryStore {
+    items: BTreeMap<String, StoredItem>,
+    /// Time of the last [`sync`]
+    #[serde(default)]
+    synced_at: Option<DateTime<Utc>>,
+}
+
+impl Persisted for LibraryStore {
+    const KIND: &'static str = "library_store";
+    const VERSION: u32 = 1;
+}
+
+/// Result of a [`sync`], product IDs sorted.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncReport {
+    /// Local works owned by the account
+    pub owned: Vec<String>,
+    /// Local works the account doesn't own
+    pub not_owned: Vec<String>,
+    /// Purchased works missing from the library
+    pub missing_locally: Vec<PurchasedWork>,
+}
+
+impl LibraryStore {
+    /// Load the store saved at `path`, or an empty one if the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(persist::load(path)?.unwrap_or_default())
+    }
+
+    /// Save the store to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        persist::save(path, self)
+    }
+
+    /// Replace the local files of the store with the result of a scan. Works no longer found
+    /// keep their metadata and ownership, without paths.
+    pub fn update_items(&mut self, items: Vec<LibraryItem>) {
+        for item in self.items.values_mut() {
+            item.paths.clear();
+        }
+        for LibraryItem { id, path } in items {
+            let item = self.items.entry(id.clone()).or_insert_with(|| StoredItem {
+                id,
+                paths: vec![],
+                metadata: None,
+                owned: None,
+            });
+            if !item.paths.contains(&path) {
+                item.paths.push(path);
+            }
+        }
+    }
+
+    /// Set the metadata of a work, e.g. from [`super::enrich`].
+    pub fn set_metadata(&mut self, metadata: WorkMetadata) {
+        if let Some(item) = self.items.get_mut(&metadata.id) {
+            item.metadata = Some(metadata);
+        }
+    }
+
+    /// A work of the store.
+    pub fn get(&self, id: &str) -> Option<&StoredItem> {
+        self.items.get(id)
+    }
+
+    /// All works of the store, sorted by product ID.
+    pub fn items(&self) -> impl Iterator<Item = &StoredItem> {
+        self.items.values()
+    }
+
+    /// Works with local files, sorted by product ID.
+    pub fn local_items(&self) -> impl Iterator<Item = &StoredItem> {
+        self.items().filter(|item| !item.paths.is_empty())
+    }
+
+    /// Time of the last [`sync`].
+    pub fn synced_at(&self) -> Option<DateTime<Utc>> {
+        self.synced_at
+    }
+
+    /// Mark the works of the store as owned or not according to `purchases`, the whole
+    /// purchase history. Purchased works missing from the library are added without paths.
+    pub fn apply_purchases(
+        &mut self,
+        purchases: Vec<PurchasedWork>,
+        at: DateTime<Utc>,
+    ) -> SyncReport {
+        let purchased: BTreeSet<&str> = purchases.iter().map(|work| work.id.as_str()).collect();
+        let mut report = SyncReport::default();
+        for item in self.items.values_mut() {
+            let owned = purchased.contains(item.id.as_str());
+            item.owned = Some(owned);
+            match (owned, item.paths.is_empty()) {
+                (true, false) => report.owned.push(item.id.clone()),
+                (false, false) => report.not_owned.push(item.id.clone()),
+                _ => {}
+            }
+        }
+
+        for work in purchases {
+            let item = self
+                .items
+                .entry(work.id.clone())
+                .or_insert_with(|| StoredItem {
+                    id: work.id.clone(),
+                    paths: vec![],
+                    metadata: None,
+                    owned: Some(true),
+                });
+            if item.paths.is_empty() && !report.missing_locally.iter().any(|w| w.id == work.id) {
+                report.missing_locally.push(work);
+            }
+        }
+        report.missing_locally.sort_by(|a, b| a.id.cmp(&b.id));
+        self.synced_at = Some(at);
+        report
+    }
+}
+
+/// Compare the store with the purchase history of the logged-in account: mark local works as
+/// owned or not, and report the purchases missing locally.
+///
+/// The whole purchase history is fetched, one request per page. The store is only updated
+/// once it is fetched, so a failed sync changes nothing.
+///
+/// # Errors
+/// Returns [`crate::DlsiteError::Unauthenticated`] if the client is not logged in.
+pub async fn sync(client: &DlsiteClient, store: &mut LibraryStore) -> Result<SyncReport> {
+    let purchases = client.account().purchased_works().collect_all().await?;
+    Ok(store.apply_purchases(purchases, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::{DateTime, Utc};
+
+    use super::LibraryStore;
+    use crate::{
+        client::account::{DownloadStatus, PurchasedWork},
+        library::LibraryItem,
+    };
+
+    fn purchase(id: &str) -> PurchasedWork {
+        PurchasedWork {
+            id: id.to_string(),
+            title: format!("title of {id}"),
+            circle_name: "Circle".to_string(),
+            purchased_at: None,
+            price: Some(1_100),
+            download_status: DownloadStatus::Available,
+        }
+    }
+
+    fn local(id: &str, path: &str) -> LibraryItem {
+        LibraryItem {
+            id: id.to_string(),
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn apply_purchases() {
+        let at: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let mut store = LibraryStore::default();
+        store.update_items(vec![
+            local("RJ01000001", "voice/RJ01000001"),
+            local("RJ01000001", "backup/RJ01000001.zip"),
+            local("RJ01000002", "games/RJ01000002"),
+        ]);
+        assert_eq!(store.get("RJ01000001").unwrap().paths.len(), 2);
+        assert_eq!(store.get("RJ01000001").unwrap().owned, None);
+
+        let report =
+            store.apply_purchases(vec![purchase("RJ01000003"), purchase("RJ01000001")], at);
+        assert_eq!(report.owned, vec!["RJ01000001"]);
+        assert_eq!(report.not_owned, vec!["RJ01000002"]);
+        assert_eq!(report.missing_locally, vec![purchase("RJ01000003")]);
+        assert_eq!(store.get("RJ01000002").unwrap().owned, Some(false));
+        assert_eq!(store.get("RJ01000003").unwrap().owned, Some(true));
+        assert_eq!(store.synced_at(), Some(at));
+
+        // The missing work was downloaded since
+        store.update_items(vec![
+            local("RJ01000001", "voice/RJ01000001"),
+            local("RJ01000003", "voice/RJ01000003"),
+        ]);
+        assert_eq!(store.local_items().count(), 2);
+        let report =
+            store.apply_purchases(vec![purchase("RJ01000001"), purchase("RJ01000003")], at);
+        assert_eq!(report.owned, vec!["RJ01000001", "RJ01000003"]);
+        assert!(report.not_owned.is_empty());
+        assert!(report.missing_locally.is_empty());
+    }
+}
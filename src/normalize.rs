@@ -0,0 +1,170 @@
+//! Text normalization for titles and other free text.
+//!
+//! DLsite titles mix full-width and half-width characters (`ＡＳＭＲ` / `ASMR`, `ｶﾞｰﾙ` /
+//! `ガール`), decorate titles with bracketed notes (`【English Ver.】`, `(CV:春花らん)`) and use
+//! all kinds of spaces. [`Normalizer`] folds these differences away. The crate uses it to
+//! group editions of a work (see [`crate::client::search::SearchResult::group_editions`]) and,
+//! when enabled with [`crate::DlsiteClientBuilder::normalize_titles`], on parsed titles, so
+//! downstream indexes can normalize their own text the same way.
+//!
+//! # Example
+//! ```
+//! use dlsite_gamebox::normalize::{normalize, Normalizer};
+//!
+//! assert_eq!(normalize("ＡＳＭＲ　ｶﾞｰﾙ"), "ASMR ガール");
+//! assert_eq!(
+//!     Normalizer::for_matching().apply("【English Ver.】 Cat  Life"),
+//!     "cat life"
+//! );
+//! ```
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Normalization steps, applied in the order of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Normalizer {
+    /// Fold full-width ASCII (`ＡＳＭＲ１２`) and the ideographic space to half-width, and
+    /// half-width katakana (`ｶﾞｰﾙ`) to full-width
+    pub fold_width: bool,
+    /// Remove bracketed notes such as `【English Ver.】`, `[期間限定]` or `(CV:春花らん)`.
+    /// Quotes (`「」`, `『』`) are kept.
+    pub strip_brackets: bool,
+    /// Trim and replace runs of whitespace with a single space
+    pub collapse_whitespace: bool,
+    /// Lowercase letters
+    pub lowercase: bool,
+}
+
+impl Default for Normalizer {
+    /// Width folding and whitespace collapsing, which keep titles displayable.
+    fn default() -> Self {
+        Self {
+            fold_width: true,
+            strip_brackets: false,
+            collapse_whitespace: true,
+            lowercase: false,
+        }
+    }
+}
+
+impl Normalizer {
+    /// Every step, for comparing titles rather than displaying them.
+    pub fn for_matching() -> Self {
+        Self {
+            fold_width: true,
+            strip_brackets: true,
+            collapse_whitespace: true,
+            lowercase: true,
+        }
+    }
+
+    /// Normalize `text`.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = if self.fold_width {
+            fold_width(text)
+        } else {
+            text.to_string()
+        };
+        if self.strip_brackets {
+            static BRACKETS: OnceLock<Regex> = OnceLock::new();
+            let brackets = BRACKETS.get_or_init(|| {
+                Regex::new(r"[【\[(（〔［〈《][^】\])）〕］〉》]*[】\])）〕］〉》]").unwrap()
+            });
+            text = brackets.replace_all(&text, " ").into_owned();
+        }
+        if self.collapse_whitespace {
+            text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        if self.lowercase {
+            text = text.to_lowercase();
+        }
+        text
+    }
+}
+
+/// Normalize `text` with the default [`Normalizer`].
+pub fn normalize(text: &str) -> String {
+    Normalizer::default().apply(text)
+}
+
+/// Half-width katakana from U+FF66 (`ｦ`) to U+FF9D (`ﾝ`), in full-width.
+const HALF_WIDTH_KATAKANA: &str =
+    "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+
+fn fold_width(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            '｡' => '。',
+            '｢' => '「',
+            '｣' => '」',
+            '､' => '、',
+            '･' => '・',
+            '\u{FF66}'..='\u{FF9D}' => {
+                let kana = HALF_WIDTH_KATAKANA
+                    .chars()
+                    .nth((c as u32 - 0xFF66) as usize)
+                    .unwrap_or(c);
+                // Combine with a following (semi-)voiced sound mark
+                match chars.peek() {
+                    Some('ﾞ') if kana == 'ウ' => {
+                        chars.next();
+                        'ヴ'
+                    }
+                    Some('ﾞ') if matches!(kana, 'カ'..='ト' | 'ハ'..='ホ') && kana != 'ッ' =>
+                    {
+                        chars.next();
+                        char::from_u32(kana as u32 + 1).unwrap_or(kana)
+                    }
+                    Some('ﾟ') if matches!(kana, 'ハ'..='ホ') => {
+                        chars.next();
+                        char::from_u32(kana as u32 + 2).unwrap_or(kana)
+                    }
+                    _ => kana,
+                }
+            }
+            'ﾞ' => '゛',
+            'ﾟ' => '゜',
+            c => c,
+        };
+        folded.push(c);
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, Normalizer};
+
+    #[test]
+    fn fold_width() {
+        assert_eq!(normalize("ＡＳＭＲ！１２３"), "ASMR!123");
+        assert_eq!(normalize("ｶﾞｰﾙ･ﾊﾟﾝﾁ ｳﾞｧｲｵﾘﾝ"), "ガール・パンチ ヴァイオリン");
+        assert_eq!(normalize("ｱﾞ"), "ア゛");
+    }
+
+    #[test]
+    fn steps() {
+        assert_eq!(normalize("  ねこぐらし。\u{3000}\n2 "), "ねこぐらし。 2");
+        assert_eq!(normalize("【CV:春花らん】耳かき"), "【CV:春花らん】耳かき");
+
+        let matching = Normalizer::for_matching();
+        assert_eq!(
+            matching.apply("【CV:春花らん】 「耳かき」ＡＳＭＲ （体験版）"),
+            "「耳かき」asmr"
+        );
+
+        let none = Normalizer {
+            fold_width: false,
+            strip_brackets: false,
+            collapse_whitespace: false,
+            lowercase: false,
+        };
+        assert_eq!(none.apply(" ＡＳＭＲ "), " ＡＳＭＲ ");
+    }
+}
@@ -0,0 +1,251 @@
+//! Versioned storage for local data (disk cache, library store, tracker history, ...).
+//!
+//! Every persisted artifact is written as a JSON envelope holding its kind and schema version:
+//!
+//! ```json
+//! { "kind": "price_history", "schema_version": 2, "data": { ... } }
+//! ```
+//!
+//! When an artifact written by an older version of this crate is loaded, its
+//! [`Persisted::migrate`] steps are applied one version at a time, the original file is kept as
+//! `<file>.v<old version>.bak`, and the upgraded data is written back. Files written by a newer
+//! version are rejected rather than overwritten, so downgrading never destroys data.
+
+use std::{
+    fs,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{error::Result, DlsiteError};
+
+/// A type which can be stored with [`save`] and loaded with [`load`].
+pub trait Persisted: Serialize + DeserializeOwned {
+    /// Name identifying the kind of artifact, stored in the envelope.
+    const KIND: &'static str;
+    /// Current schema version. Increment it whenever the serialized format changes and add a
+    /// step to [`Persisted::migrate`].
+    const VERSION: u32;
+
+    /// Convert data of schema version `from` to version `from + 1`.
+    ///
+    /// Version 0 is data written before versioning was introduced (i.e. without an envelope).
+    /// The default implementation accepts the data as is.
+    fn migrate(from: u32, data: Value) -> Result<Value> {
+        let _ = from;
+        Ok(data)
+    }
+}
+
+#[derive(Serialize)]
+struct EnvelopeRef<'a, T> {
+    kind: &'a str,
+    schema_version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    kind: String,
+    schema_version: u32,
+    data: Value,
+}
+
+pub(crate) fn io_error(path: &Path, e: std::io::Error) -> DlsiteError {
+    DlsiteError::Persist(format!("{}: {}", path.display(), e))
+}
+
+/// Write `value` to `path`, replacing the file atomically.
+pub fn save<T: Persisted>(path: impl AsRef<Path>, value: &T) -> Result<()> {
+    write(path.as_ref(), value, false)
+}
+
+/// Like [`save`], but the file is only readable and writable by its owner on unix.
+pub(crate) fn save_private<T: Persisted>(path: impl AsRef<Path>, value: &T) -> Result<()> {
+    write(path.as_ref(), value, true)
+}
+
+fn write<T: Persisted>(path: &Path, value: &T, private: bool) -> Result<()> {
+    let json = serde_json::to_vec_pretty(&EnvelopeRef {
+        kind: T::KIND,
+        schema_version: T::VERSION,
+        data: value,
+    })?;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+    }
+    let tmp = with_suffix(path, ".tmp");
+    if private {
+        write_private(&tmp, &json)
+    } else {
+        fs::write(&tmp, json)
+    }
+    .map_err(|e| io_error(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| io_error(path, e))?;
+    Ok(())
+}
+
+/// Create `path` with mode 0600 on unix and write `contents` to it.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    // The mode only applies to new files
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents)
+}
+
+/// Read a value from `path`, migrating it to the current schema version if needed.
+///
+/// Returns `None` if the file doesn't exist.
+pub fn load<T: Persisted>(path: impl AsRef<Path>) -> Result<Option<T>> {
+    let path = path.as_ref();
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(io_error(path, e)),
+    };
+    let raw: Value = serde_json::from_slice(&bytes)?;
+
+    let (version, data) = match serde_json::from_value::<Envelope>(raw.clone()) {
+        Ok(envelope) => {
+            if envelope.kind != T::KIND {
+                return Err(DlsiteError::Persist(format!(
+                    "{}: expected {}, found {}",
+                    path.display(),
+                    T::KIND,
+                    envelope.kind
+                )));
+            }
+            (envelope.schema_version, envelope.data)
+        }
+        // Written before versioning was introduced
+        Err(_) => (0, raw),
+    };
+
+    if version > T::VERSION {
+        return Err(DlsiteError::Persist(format!(
+            "{}: schema version {} is newer than supported version {}",
+            path.display(),
+            version,
+            T::VERSION
+        )));
+    }
+    if version == T::VERSION {
+        return Ok(Some(serde_json::from_value(data)?));
+    }
+
+    let mut data = data;
+    for from in version..T::VERSION {
+        data = T::migrate(from, data)?;
+    }
+    let value: T = serde_json::from_value(data)?;
+
+    let backup = with_suffix(path, &format!(".v{}.bak", version));
+    fs::copy(path, &backup).map_err(|e| io_error(&backup, e))?;
+    save(path, &value)?;
+    tracing::info!(
+        "Migrated {} from schema version {} to {}",
+        path.display(),
+        version,
+        T::VERSION
+    );
+
+    Ok(Some(value))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{load, save, Persisted};
+    use crate::error::Result;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct History {
+        entries: Vec<(String, i64)>,
+    }
+
+    impl Persisted for History {
+        const KIND: &'static str = "test_history";
+        const VERSION: u32 = 2;
+
+        fn migrate(from: u32, data: Value) -> Result<Value> {
+            Ok(match from {
+                // v0 was a bare map of id -> price
+                0 => json!({
+                    "prices": data
+                        .as_object()
+                        .map(|m| m.iter().map(|(k, v)| json!([k, v])).collect::<Vec<_>>())
+                        .unwrap_or_default()
+                }),
+                // v1 named the field `prices`
+                1 => json!({ "entries": data["prices"] }),
+                _ => data,
+            })
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "dlsite-persist-{}-{}",
+            name,
+            rand::random::<u32>()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("history.json")
+    }
+
+    #[test]
+    fn roundtrip() {
+        let path = temp_path("roundtrip");
+        assert_eq!(load::<History>(&path).unwrap(), None);
+
+        let history = History {
+            entries: vec![("RJ123456".to_string(), 1100)],
+        };
+        save(&path, &history).unwrap();
+        assert_eq!(load::<History>(&path).unwrap(), Some(history));
+    }
+
+    #[test]
+    fn migrates_legacy_data() {
+        let path = temp_path("migrate");
+        std::fs::write(&path, r#"{"RJ123456": 1100}"#).unwrap();
+
+        let history = load::<History>(&path).unwrap().unwrap();
+        assert_eq!(history.entries, vec![("RJ123456".to_string(), 1100)]);
+
+        // Upgraded in place, with a backup of the original
+        let raw: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(raw["schema_version"], 2);
+        assert!(path.with_file_name("history.json.v0.bak").exists());
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let path = temp_path("newer");
+        std::fs::write(
+            &path,
+            r#"{"kind": "test_history", "schema_version": 3, "data": {}}"#,
+        )
+        .unwrap();
+        assert!(load::<History>(&path).is_err());
+        // The file is left untouched
+        assert!(std::fs::read_to_string(&path).unwrap().contains("\"schema_version\": 3"));
+    }
+}
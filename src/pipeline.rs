@@ -0,0 +1,364 @@
+//! Streaming enrichment of many works with bounded memory.
+//!
+//! A [`Pipeline`] takes a stream of product IDs (a search, a scanned library, a list...) and
+//! passes each work through [`Stage`]s (api fetch, scraping, image download) into a sink.
+//! Stages are connected by bounded channels and each runs a bounded number of works at once,
+//! so a crawl of any size holds at most a few buffers of works in memory, and a slow sink
+//! slows the stages down instead of piling works up.
+//!
+//! # Example
+//! ```no_run
+//! use dlsite_gamebox::{
+//!     client::search::SearchProductQuery,
+//!     pipeline::{self, Pipeline, Stage},
+//!     DlsiteClient,
+//! };
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = DlsiteClient::default();
+//!     let query = SearchProductQuery {
+//!         keyword: Some("ASMR".to_string()),
+//!         per_page: Some(100),
+//!         ..Default::default()
+//!     };
+//!     let search = client.search();
+//!     let source = pipeline::search(&search, &query);
+//!
+//!     let file = std::fs::File::create("works.jsonl").unwrap();
+//!     let stats = Pipeline::new(&client)
+//!         .stage(Stage::Api)
+//!         .stage(Stage::Images { max: 1 })
+//!         .parallelism(4)
+//!         .run(source, pipeline::json_lines(std::io::BufWriter::new(file)))
+//!         .await
+//!         .unwrap();
+//!     println!("{} works, {} with errors", stats.processed, stats.failed);
+//! }
+//! ```
+
+use std::{io::Write, sync::Arc};
+
+use futures::{future::LocalBoxFuture, Stream, StreamExt as _};
+use tokio::sync::mpsc;
+
+use crate::{
+    client::{
+        product::html::ProductHtml,
+        product_api::interface::ProductApiContent,
+        search::{SearchClient, SearchProductQuery},
+    },
+    error::Result,
+    interface::product_id::ProductId,
+    library::{LibraryItem, WorkMetadata},
+    DlsiteClient,
+};
+
+/// Default capacity of the channels between stages.
+const DEFAULT_BUFFER: usize = 16;
+/// Default number of works processed at once by each stage.
+const DEFAULT_PARALLELISM: usize = 4;
+
+/// Enrichment step applied to each work. Failures are recorded in
+/// [`PipelineItem::errors`] and the work moves on to the next stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Fetch the work with the product api into [`PipelineItem::api`]
+    Api,
+    /// Scrape the product page into [`PipelineItem::html`]
+    Scrape,
+    /// Download up to `max` images into [`PipelineItem::images`]: the images of the product
+    /// page if it was scraped, otherwise the main image of the api
+    Images { max: usize },
+}
+
+/// A work going through a [`Pipeline`].
+#[derive(Debug)]
+pub struct PipelineItem {
+    pub id: ProductId,
+    pub api: Option<ProductApiContent>,
+    pub html: Option<ProductHtml>,
+    /// Downloaded images: `(url, bytes)`
+    pub images: Vec<(String, Arc<[u8]>)>,
+    /// Errors of the stages, in order
+    pub errors: Vec<String>,
+}
+
+impl PipelineItem {
+    fn new(id: ProductId) -> Self {
+        Self {
+            id,
+            api: None,
+            html: None,
+            images: vec![],
+            errors: vec![],
+        }
+    }
+
+    /// Compact metadata of the work, if it was fetched with [`Stage::Api`].
+    pub fn metadata(&self) -> Option<WorkMetadata> {
+        self.api.as_ref().map(WorkMetadata::from)
+    }
+
+    /// URLs of the images to download with [`Stage::Images`].
+    fn image_urls(&self) -> Vec<String> {
+        match (&self.html, &self.api) {
+            (Some(html), _) if !html.images.is_empty() => html.images.clone(),
+            (_, Some(api)) => vec![absolute_url(&api.image_main.url)],
+            _ => vec![],
+        }
+    }
+}
+
+/// Counts of a finished [`Pipeline::run`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineStats {
+    /// Works passed to the sink
+    pub processed: usize,
+    /// Works passed to the sink with at least one error
+    pub failed: usize,
+}
+
+/// Chain of [`Stage`]s, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Pipeline<'c> {
+    client: &'c DlsiteClient,
+    stages: Vec<Stage>,
+    parallelism: usize,
+    buffer: usize,
+}
+
+impl<'c> Pipeline<'c> {
+    /// A pipeline without stages: works go straight to the sink.
+    pub fn new(client: &'c DlsiteClient) -> Self {
+        Self {
+            client,
+            stages: vec![],
+            parallelism: DEFAULT_PARALLELISM,
+            buffer: DEFAULT_BUFFER,
+        }
+    }
+
+    /// Add a stage after the current ones.
+    pub fn stage(mut self, stage: Stage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Number of works processed at once by each stage. Requests still go through the rate
+    /// limiter of the client.
+    pub fn parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Capacity of the channels between the source, the stages and the sink.
+    pub fn buffer(mut self, buffer: usize) -> Self {
+        self.buffer = buffer.max(1);
+        self
+    }
+
+    /// Pass every work of `source` through the stages into `sink`.
+    ///
+    /// Works reach the sink in completion order, not source order. Runs until the source is
+    /// exhausted, or the sink fails: the error is returned and the works in flight are
+    /// dropped.
+    pub async fn run<S, F>(&self, source: S, mut sink: F) -> Result<PipelineStats>
+    where
+        S: Stream<Item = ProductId>,
+        F: FnMut(PipelineItem) -> Result<()>,
+    {
+        let (tx, mut rx) = mpsc::channel(self.buffer);
+        let feed = async move {
+            let mut source = std::pin::pin!(source);
+            while let Some(id) = source.next().await {
+                if tx.send(PipelineItem::new(id)).await.is_err() {
+                    break;
+                }
+            }
+        };
+
+        let mut stages: Vec<LocalBoxFuture<'_, ()>> = vec![];
+        for &stage in &self.stages {
+            let (tx, next_rx) = mpsc::channel(self.buffer);
+            let input = receiver_stream(rx);
+            stages.push(Box::pin(async move {
+                let mut output = std::pin::pin!(input
+                    .map(|item| self.apply(stage, item))
+                    .buffer_unordered(self.parallelism));
+                while let Some(item) = output.next().await {
+                    // The sink stopped
+                    if tx.send(item).await.is_err() {
+                        break;
+                    }
+                }
+            }));
+            rx = next_rx;
+        }
+
+        let drain = async move {
+            let mut stats = PipelineStats::default();
+            while let Some(item) = rx.recv().await {
+                stats.processed += 1;
+                if !item.errors.is_empty() {
+                    stats.failed += 1;
+                }
+                sink(item)?;
+            }
+            Ok(stats)
+        };
+
+        let (_, _, stats) = futures::join!(feed, futures::future::join_all(stages), drain);
+        stats
+    }
+
+    async fn apply(&self, stage: Stage, mut item: PipelineItem) -> PipelineItem {
+        let c = self.client;
+        match stage {
+            Stage::Api => match c.product_api().get(&item.id).await {
+                Ok(api) => item.api = Some(api),
+                Err(e) => item.errors.push(format!("api: {e}")),
+            },
+            Stage::Scrape => match c.product().get_html(&item.id).await {
+                Ok(html) => item.html = Some(html),
+                Err(e) => item.errors.push(format!("scrape: {e}")),
+            },
+            Stage::Images { max } => {
+                for url in item.image_urls().into_iter().take(max) {
+                    match c.get_media(&url).await {
+                        Ok(bytes) => item.images.push((url, bytes)),
+                        Err(e) => item.errors.push(format!("image {url}: {e}")),
+                    }
+                }
+            }
+        }
+        item
+    }
+}
+
+/// Stream of the values received by `rx`.
+fn receiver_stream<T>(rx: mpsc::Receiver<T>) -> impl Stream<Item = T> {
+    futures::stream::unfold(rx, |mut rx| async move {
+        let value = rx.recv().await?;
+        Some((value, rx))
+    })
+}
+
+fn absolute_url(url: &str) -> String {
+    match url.strip_prefix("//") {
+        Some(rest) => format!("https://{rest}"),
+        None => url.to_string(),
+    }
+}
+
+/// Source of the given IDs.
+pub fn ids<I>(ids: I) -> impl Stream<Item = ProductId>
+where
+    I: IntoIterator,
+    I::Item: Into<ProductId>,
+{
+    futures::stream::iter(ids.into_iter().map(Into::into))
+}
+
+/// Source of the works of a scanned library, see [`crate::library::scan`].
+pub fn library(items: Vec<LibraryItem>) -> impl Stream<Item = ProductId> {
+    ids(items.into_iter().map(|item| item.id))
+}
+
+/// Source of the works of a search, walking its pages as the pipeline asks for more works
+/// (see [`SearchClient::search_product_paged`]). A page which fails to load ends the source.
+pub fn search<'s>(
+    search: &'s SearchClient<'s>,
+    query: &'s SearchProductQuery,
+) -> impl Stream<Item = ProductId> + 's {
+    search
+        .search_product_paged(query)
+        .take_while(|item| {
+            if let Err(e) = item {
+                tracing::warn!("Search failed, stopping the source: {e}");
+            }
+            futures::future::ready(item.is_ok())
+        })
+        .filter_map(|item| futures::future::ready(item.ok().map(|item| ProductId::from(item.id))))
+}
+
+/// Record written by [`json_lines`].
+#[derive(Debug, serde::Serialize)]
+struct ExportRecord<'a> {
+    id: &'a ProductId,
+    metadata: Option<WorkMetadata>,
+    images: Vec<&'a str>,
+    errors: &'a [String],
+}
+
+/// Sink writing one JSON object per work: its ID, [`WorkMetadata`], downloaded image URLs
+/// and errors.
+pub fn json_lines<W: Write>(mut writer: W) -> impl FnMut(PipelineItem) -> Result<()> {
+    move |item| {
+        let record = ExportRecord {
+            id: &item.id,
+            metadata: item.metadata(),
+            images: item.images.iter().map(|(url, _)| url.as_str()).collect(),
+            errors: &item.errors,
+        };
+        serde_json::to_writer(&mut writer, &record)?;
+        writer
+            .write_all(b"\n")
+            .and_then(|()| writer.flush())
+            .map_err(|e| crate::DlsiteError::Persist(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ids, json_lines, Pipeline};
+    use crate::{interface::product_id::ProductId, DlsiteClient};
+
+    #[tokio::test]
+    async fn without_stages() {
+        let client = DlsiteClient::default();
+        let mut seen = vec![];
+        let stats = Pipeline::new(&client)
+            .buffer(1)
+            .run(ids(["RJ403038", "RJ01014447"]), |item| {
+                seen.push(item.id);
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(
+            seen,
+            vec![ProductId::from("RJ403038"), ProductId::from("RJ01014447")]
+        );
+    }
+
+    #[tokio::test]
+    async fn sink_error_stops() {
+        let client = DlsiteClient::default();
+        let mut calls = 0;
+        let result = Pipeline::new(&client)
+            .run(ids((0..1000).map(|i| format!("RJ{i:06}"))), |_| {
+                calls += 1;
+                Err(crate::DlsiteError::Persist("disk full".to_string()))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn api_stage() {
+        let client = DlsiteClient::default();
+        let mut out = vec![];
+        let stats = Pipeline::new(&client)
+            .stage(super::Stage::Api)
+            .run(ids(["RJ403038", "RJ000000"]), json_lines(&mut out))
+            .await
+            .unwrap();
+        assert_eq!(stats.processed, 2);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+    }
+}
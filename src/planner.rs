@@ -0,0 +1,357 @@
+//! Purchase planning: how to split a wishlist into carts to get the most out of coupons and
+//! points.
+//!
+//! DLsite applies at most one coupon per purchase, so buying a wishlist in several carts can be
+//! cheaper than buying it at once. A [`Planner`] takes the works to buy with their current
+//! prices (e.g. from [`crate::client::product_api::ProductApiClient`]), the held coupons (e.g.
+//! from [`crate::client::coupon::CouponClient`]) and the point balance, and computes which
+//! coupon to use on which cart. Nothing is requested from DLsite.
+//!
+//! # Example
+//! ```
+//! use dlsite_gamebox::planner::{HeldCoupon, Planner, WishlistItem};
+//! use dlsite_gamebox::client::coupon::CouponDiscount;
+//!
+//! let plan = Planner::new(vec![
+//!     WishlistItem::new("RJ01014447", 1980),
+//!     WishlistItem::new("RJ291224", 1320),
+//! ])
+//! .coupon(HeldCoupon::new("CP001", CouponDiscount::Percent(30)))
+//! .points(500)
+//! .plan();
+//! println!("{} carts, {}円", plan.carts.len(), plan.total);
+//! ```
+
+use std::collections::HashSet;
+
+use crate::client::coupon::{Coupon, CouponDiscount};
+
+/// Above this number of coupon/cart combinations, plans are searched heuristically.
+const EXHAUSTIVE_LIMIT: u64 = 100_000;
+
+/// A work to buy.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WishlistItem {
+    pub id: String,
+    /// Current price in yen, sale discount included
+    pub price: i32,
+}
+
+impl WishlistItem {
+    pub fn new(id: &str, price: i32) -> Self {
+        Self {
+            id: id.to_string(),
+            price,
+        }
+    }
+}
+
+/// A coupon which can be used for a purchase.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HeldCoupon {
+    pub id: String,
+    pub discount: CouponDiscount,
+    /// Minimum cart total for the coupon to be usable
+    pub min_total: Option<i32>,
+    /// Maximum discount of a percent coupon
+    pub max_discount: Option<i32>,
+}
+
+impl HeldCoupon {
+    pub fn new(id: &str, discount: CouponDiscount) -> Self {
+        Self {
+            id: id.to_string(),
+            discount,
+            min_total: None,
+            max_discount: None,
+        }
+    }
+
+    /// Convert a listed coupon. The minimum total is read from conditions like
+    /// `2,000円以上のお買い物で利用可能`. Returns `None` if the discount is unknown.
+    pub fn from_coupon(coupon: &Coupon) -> Option<Self> {
+        Some(Self {
+            min_total: coupon.conditions.as_deref().and_then(parse_min_total),
+            ..Self::new(&coupon.id, coupon.discount?)
+        })
+    }
+
+    /// Discount on a cart of the given total, 0 if the coupon can't be used.
+    pub fn discount_on(&self, subtotal: i32) -> i32 {
+        if subtotal <= 0 || subtotal < self.min_total.unwrap_or(0) {
+            return 0;
+        }
+        let discount = match self.discount {
+            CouponDiscount::Percent(rate) => (i64::from(subtotal) * i64::from(rate) / 100) as i32,
+            CouponDiscount::Amount(amount) => amount.min(subtotal),
+        };
+        self.max_discount.map_or(discount, |max| discount.min(max))
+    }
+}
+
+/// Parse `2,000円以上` in coupon conditions.
+fn parse_min_total(conditions: &str) -> Option<i32> {
+    let (before, _) = conditions.split_once("円以上")?;
+    let digits: String = before
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(char::is_ascii_digit)
+        .collect();
+    digits.chars().rev().collect::<String>().parse().ok()
+}
+
+/// One purchase of a plan.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlannedCart {
+    /// Coupon to use, `None` to buy without coupon
+    pub coupon: Option<String>,
+    /// IDs of the works in the cart
+    pub items: Vec<String>,
+    pub subtotal: i32,
+    pub discount: i32,
+    pub points_used: i32,
+    /// Amount to pay
+    pub total: i32,
+}
+
+/// Carts to buy a wishlist with, and the expected totals.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PurchasePlan {
+    pub carts: Vec<PlannedCart>,
+    /// Sum of the prices
+    pub subtotal: i32,
+    /// Sum of the coupon discounts
+    pub discount: i32,
+    pub points_used: i32,
+    /// Amount to pay
+    pub total: i32,
+}
+
+/// Computes a [`PurchasePlan`].
+#[derive(Debug, Clone, Default)]
+pub struct Planner {
+    items: Vec<WishlistItem>,
+    coupons: Vec<HeldCoupon>,
+    points: i32,
+}
+
+impl Planner {
+    pub fn new(items: Vec<WishlistItem>) -> Self {
+        let mut seen = HashSet::new();
+        Self {
+            items: items
+                .into_iter()
+                .filter(|item| seen.insert(item.id.clone()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a held coupon. Each coupon is used at most once.
+    pub fn coupon(mut self, coupon: HeldCoupon) -> Self {
+        self.coupons.push(coupon);
+        self
+    }
+
+    /// Set the point balance. Points (1 point = 1 yen) are spent after coupon discounts.
+    pub fn points(mut self, points: i32) -> Self {
+        self.points = points.max(0);
+        self
+    }
+
+    /// Compute the plan with the largest total discount.
+    ///
+    /// Small wishlists are solved exactly; for large ones (many works and coupons) the plan is
+    /// searched heuristically and may be slightly worse than the optimum.
+    pub fn plan(&self) -> PurchasePlan {
+        let bins = self.coupons.len() + 1;
+        let exhaustive = (bins as u64)
+            .checked_pow(self.items.len() as u32)
+            .is_some_and(|n| n <= EXHAUSTIVE_LIMIT);
+        let assignment = if exhaustive {
+            self.search_exhaustive()
+        } else {
+            self.search_local()
+        };
+        self.build(&assignment)
+    }
+
+    /// Total coupon discount of an assignment. `assignment[i]` is the cart of item `i`: 0
+    /// without coupon, `c + 1` for coupon `c`.
+    fn savings(&self, assignment: &[usize]) -> i32 {
+        let mut subtotals = vec![0; self.coupons.len()];
+        for (item, &bin) in self.items.iter().zip(assignment) {
+            if bin > 0 {
+                subtotals[bin - 1] += item.price;
+            }
+        }
+        self.coupons
+            .iter()
+            .zip(subtotals)
+            .map(|(coupon, subtotal)| coupon.discount_on(subtotal))
+            .sum()
+    }
+
+    fn search_exhaustive(&self) -> Vec<usize> {
+        let bins = self.coupons.len() + 1;
+        let mut assignment = vec![0; self.items.len()];
+        let mut best = (self.savings(&assignment), assignment.clone());
+        // Count in base `bins` through all assignments
+        while let Some(i) = assignment.iter().position(|&bin| bin + 1 < bins) {
+            assignment[i] += 1;
+            assignment[..i].fill(0);
+            let savings = self.savings(&assignment);
+            if savings > best.0 {
+                best = (savings, assignment.clone());
+            }
+        }
+        best.1
+    }
+
+    /// Hill climbing by moving single items, starting with every item in the same cart.
+    fn search_local(&self) -> Vec<usize> {
+        let bins = self.coupons.len() + 1;
+        let mut best = (0, vec![0; self.items.len()]);
+        for start in 0..bins {
+            let mut assignment = vec![start; self.items.len()];
+            let mut savings = self.savings(&assignment);
+            let mut improved = true;
+            while improved {
+                improved = false;
+                for i in 0..assignment.len() {
+                    let current = assignment[i];
+                    for bin in (0..bins).filter(|&bin| bin != current) {
+                        assignment[i] = bin;
+                        let candidate = self.savings(&assignment);
+                        if candidate > savings {
+                            savings = candidate;
+                            improved = true;
+                            break;
+                        }
+                        assignment[i] = current;
+                    }
+                }
+            }
+            if savings > best.0 {
+                best = (savings, assignment);
+            }
+        }
+        best.1
+    }
+
+    fn build(&self, assignment: &[usize]) -> PurchasePlan {
+        let mut carts: Vec<PlannedCart> = vec![];
+        let mut points = self.points;
+        for bin in 0..=self.coupons.len() {
+            let items: Vec<_> = self
+                .items
+                .iter()
+                .zip(assignment)
+                .filter(|(_, &b)| b == bin)
+                .map(|(item, _)| item)
+                .collect();
+            if items.is_empty() {
+                continue;
+            }
+            let subtotal = items.iter().map(|item| item.price).sum();
+            let coupon = bin.checked_sub(1).map(|c| &self.coupons[c]);
+            let discount = coupon.map_or(0, |c| c.discount_on(subtotal));
+            let points_used = points.min(subtotal - discount);
+            points -= points_used;
+            carts.push(PlannedCart {
+                coupon: coupon.filter(|_| discount > 0).map(|c| c.id.clone()),
+                items: items.iter().map(|item| item.id.clone()).collect(),
+                subtotal,
+                discount,
+                points_used,
+                total: subtotal - discount - points_used,
+            });
+        }
+
+        PurchasePlan {
+            subtotal: carts.iter().map(|c| c.subtotal).sum(),
+            discount: carts.iter().map(|c| c.discount).sum(),
+            points_used: carts.iter().map(|c| c.points_used).sum(),
+            total: carts.iter().map(|c| c.total).sum(),
+            carts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeldCoupon, Planner, WishlistItem};
+    use crate::client::coupon::{Coupon, CouponDiscount};
+
+    fn wishlist() -> Vec<WishlistItem> {
+        vec![
+            WishlistItem::new("RJ1", 1000),
+            WishlistItem::new("RJ2", 2000),
+            WishlistItem::new("RJ3", 3000),
+        ]
+    }
+
+    fn coupons() -> [HeldCoupon; 2] {
+        [
+            HeldCoupon {
+                max_discount: Some(500),
+                ..HeldCoupon::new("CP30", CouponDiscount::Percent(30))
+            },
+            HeldCoupon {
+                min_total: Some(2000),
+                ..HeldCoupon::new("CP300", CouponDiscount::Amount(300))
+            },
+        ]
+    }
+
+    #[test]
+    fn plan_batches() {
+        let [percent, amount] = coupons();
+        let plan = Planner::new(wishlist())
+            .coupon(percent)
+            .coupon(amount)
+            .points(1000)
+            .plan();
+
+        assert_eq!(plan.subtotal, 6000);
+        assert_eq!(plan.discount, 800);
+        assert_eq!(plan.points_used, 1000);
+        assert_eq!(plan.total, 4200);
+        let mut used: Vec<_> = plan.carts.iter().filter_map(|c| c.coupon.clone()).collect();
+        used.sort();
+        assert_eq!(used, vec!["CP30", "CP300"]);
+        assert_eq!(plan.carts.iter().map(|c| c.items.len()).sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn plan_heuristic_matches_exhaustive() {
+        let [percent, amount] = coupons();
+        let planner = Planner::new(wishlist()).coupon(percent).coupon(amount);
+        let exhaustive = planner.search_exhaustive();
+        let local = planner.search_local();
+        assert_eq!(planner.savings(&exhaustive), planner.savings(&local));
+    }
+
+    #[test]
+    fn coupon_min_total() {
+        let coupon = HeldCoupon::from_coupon(&Coupon {
+            id: "CP002".to_string(),
+            name: "ASMRクーポン".to_string(),
+            discount: Some(CouponDiscount::Amount(300)),
+            conditions: Some("2,000円以上のお買い物で利用可能".to_string()),
+            expires_at: None,
+            claimable: false,
+        })
+        .unwrap();
+        assert_eq!(coupon.min_total, Some(2000));
+        assert_eq!(coupon.discount_on(1980), 0);
+        assert_eq!(coupon.discount_on(2000), 300);
+
+        let plan = Planner::new(vec![WishlistItem::new("RJ1", 1000)])
+            .coupon(coupon)
+            .plan();
+        assert_eq!(plan.carts[0].coupon, None);
+        assert_eq!(plan.total, 1000);
+    }
+}
@@ -0,0 +1,321 @@
+//! Rate limiters deciding when the next request to DLsite may be sent.
+//!
+//! By default each [`crate::DlsiteClient`] uses an [`IntervalLimiter`] kept in memory, shared by
+//! its clones and sub-clients. [`TokenBucketLimiter`] allows short bursts instead, see
+//! [`crate::DlsiteClientBuilder::rate_limit`]. Implement [`RateLimiter`] and set it with
+//! [`crate::DlsiteClientBuilder::rate_limiter`] to coordinate requests differently, e.g. across
+//! processes with [`RedisRateLimiter`] (behind the `redis` feature).
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+use rand::Rng as _;
+
+use crate::runtime::{self, Instant, SystemTime, UNIX_EPOCH};
+
+/// Decides when the next request may be sent.
+pub trait RateLimiter: Send + Sync + fmt::Debug {
+    /// Wait until the next request may be sent. Called once before every request attempt.
+    fn acquire(&self) -> BoxFuture<'_, ()>;
+
+    /// Average gap between requests when the limiter is saturated, used to estimate how long
+    /// bulk operations take (see [`crate::eta::Eta`]). `None` if unknown.
+    fn interval(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// In-memory limiter keeping a (possibly random) gap between consecutive requests.
+///
+/// Concurrent callers each reserve their own slot, so they are spread out instead of all
+/// waiting for the same one.
+#[derive(Debug)]
+pub struct IntervalLimiter {
+    /// Timestamp of the next free slot in milliseconds
+    next_slot: AtomicU64,
+    /// Minimum and maximum gap between requests
+    interval: (Duration, Duration),
+}
+
+impl IntervalLimiter {
+    /// Create a limiter picking each gap at random from `min..=max`.
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            next_slot: AtomicU64::new(0),
+            interval: if min <= max { (min, max) } else { (max, min) },
+        }
+    }
+
+    /// Pick the gap before the next request in milliseconds.
+    pub(crate) fn next_interval(&self) -> u64 {
+        let (min, max) = self.interval;
+        let (min, max) = (min.as_millis() as u64, max.as_millis() as u64);
+        if min == max {
+            min
+        } else {
+            rand::rng().random_range(min..=max)
+        }
+    }
+}
+
+impl Default for IntervalLimiter {
+    /// 2 requests per second
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_millis(500))
+    }
+}
+
+impl RateLimiter for IntervalLimiter {
+    fn acquire(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            let interval = self.next_interval();
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64;
+
+            // Reserve the next free slot and push it back by one interval
+            let next_slot = self
+                .next_slot
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |next| {
+                    Some(next.max(now) + interval)
+                })
+                .unwrap_or_else(|next| next);
+            let slot = next_slot.max(now);
+
+            if slot > now {
+                runtime::sleep(Duration::from_millis(slot - now)).await;
+            }
+        })
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        let (min, max) = self.interval;
+        Some((min + max) / 2)
+    }
+}
+
+/// In-memory token bucket: up to `requests` requests at once, refilled at `requests` per
+/// `per`.
+///
+/// Unlike [`IntervalLimiter`], idle time is saved up, so a burst of requests after a pause is
+/// sent immediately, while the average rate stays bounded.
+#[derive(Debug)]
+pub struct TokenBucketLimiter {
+    capacity: f64,
+    /// Time to refill one token
+    refill: Duration,
+    /// Available tokens (negative when callers are waiting) and when they were counted
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucketLimiter {
+    /// Create a full bucket allowing `requests` requests per `per`.
+    ///
+    /// # Panics
+    /// Panics if `requests` is 0.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        assert!(
+            requests > 0,
+            "TokenBucketLimiter needs at least one request per period"
+        );
+        Self {
+            capacity: f64::from(requests),
+            refill: per / requests,
+            state: Mutex::new((f64::from(requests), Instant::now())),
+        }
+    }
+
+    /// Take a token and return how long to wait until it is available.
+    pub(crate) fn reserve(&self) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, counted_at) = *state;
+        let now = Instant::now();
+        let refilled = now.duration_since(counted_at).as_secs_f64() / self.refill.as_secs_f64();
+        let tokens = (tokens + refilled).min(self.capacity) - 1.0;
+        *state = (tokens, now);
+
+        if tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            self.refill.mul_f64(-tokens)
+        }
+    }
+}
+
+impl RateLimiter for TokenBucketLimiter {
+    fn acquire(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            let wait = self.reserve();
+            if !wait.is_zero() {
+                runtime::sleep(wait).await;
+            }
+        })
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        Some(self.refill)
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use self::redis_limiter::RedisRateLimiter;
+
+#[cfg(feature = "redis")]
+mod redis_limiter {
+    use std::time::Duration;
+
+    use futures::future::BoxFuture;
+    use redis::{aio::ConnectionManager, Script};
+    use tokio::sync::OnceCell;
+
+    use super::{IntervalLimiter, RateLimiter};
+
+    /// Reserves the next free slot atomically using the Redis server clock, and returns how
+    /// long the caller has to wait for it in milliseconds.
+    const RESERVE_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local interval = tonumber(ARGV[1])
+local next_slot = tonumber(redis.call('GET', KEYS[1]) or '0')
+local slot = math.max(now, next_slot)
+redis.call('SET', KEYS[1], slot + interval, 'PX', slot + interval - now + 60000)
+return slot - now
+";
+
+    /// Limiter sharing its state through Redis, so that the aggregate request rate of
+    /// multiple processes or machines stays bounded.
+    ///
+    /// All limiters using the same Redis key share one budget. If Redis can't be reached,
+    /// the limiter falls back to a local [`IntervalLimiter`] with the same interval.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dlsite_gamebox::{ratelimit::RedisRateLimiter, DlsiteClient};
+    /// use std::time::Duration;
+    ///
+    /// let limiter = RedisRateLimiter::new(
+    ///     "redis://127.0.0.1/",
+    ///     "dlsite:ratelimit",
+    ///     Duration::from_millis(500),
+    /// )
+    /// .unwrap();
+    /// let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+    ///     .rate_limiter(limiter)
+    ///     .build();
+    /// ```
+    pub struct RedisRateLimiter {
+        client: redis::Client,
+        connection: OnceCell<ConnectionManager>,
+        key: String,
+        interval: Duration,
+        fallback: IntervalLimiter,
+    }
+
+    impl std::fmt::Debug for RedisRateLimiter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("RedisRateLimiter")
+                .field("key", &self.key)
+                .field("interval", &self.interval)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl RedisRateLimiter {
+        /// Create a limiter allowing one request per `interval` across everyone using `key`.
+        ///
+        /// # Arguments
+        /// * `url` - Redis connection URL. Example: `redis://127.0.0.1/`.
+        /// * `key` - Redis key holding the shared state.
+        /// * `interval` - Gap between consecutive requests.
+        pub fn new(url: &str, key: &str, interval: Duration) -> redis::RedisResult<Self> {
+            Ok(Self {
+                client: redis::Client::open(url)?,
+                connection: OnceCell::new(),
+                key: key.to_string(),
+                interval,
+                fallback: IntervalLimiter::new(interval, interval),
+            })
+        }
+
+        async fn reserve(&self) -> redis::RedisResult<u64> {
+            let connection = self
+                .connection
+                .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+                .await?;
+            Script::new(RESERVE_SCRIPT)
+                .key(&self.key)
+                .arg(self.interval.as_millis() as u64)
+                .invoke_async(&mut connection.clone())
+                .await
+        }
+    }
+
+    impl RateLimiter for RedisRateLimiter {
+        fn acquire(&self) -> BoxFuture<'_, ()> {
+            Box::pin(async move {
+                match self.reserve().await {
+                    Ok(wait) => {
+                        if wait > 0 {
+                            tokio::time::sleep(Duration::from_millis(wait)).await;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Redis rate limiter unavailable, using local limiter: {e}");
+                        self.fallback.acquire().await;
+                    }
+                }
+            })
+        }
+
+        fn interval(&self) -> Option<Duration> {
+            Some(self.interval)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{IntervalLimiter, RateLimiter, TokenBucketLimiter};
+
+    #[test]
+    fn request_interval_range() {
+        let limiter = IntervalLimiter::new(Duration::from_millis(900), Duration::from_millis(400));
+        for _ in 0..100 {
+            let interval = limiter.next_interval();
+            assert!((400..=900).contains(&interval));
+        }
+
+        assert_eq!(IntervalLimiter::default().next_interval(), 500);
+    }
+
+    #[tokio::test]
+    async fn interval_concurrent_callers() {
+        let limiter = IntervalLimiter::new(Duration::from_millis(50), Duration::from_millis(50));
+        let started = Instant::now();
+        futures::future::join_all((0..5).map(|_| limiter.acquire())).await;
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn token_bucket() {
+        let limiter = TokenBucketLimiter::new(2, Duration::from_millis(1000));
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+
+        // Each caller waits for its own token
+        let third = limiter.reserve();
+        let fourth = limiter.reserve();
+        assert!(third > Duration::from_millis(400) && third <= Duration::from_millis(500));
+        assert!(fourth > Duration::from_millis(900) && fourth <= Duration::from_millis(1000));
+    }
+}
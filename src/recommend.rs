@@ -0,0 +1,254 @@
+//! Offline recommendations of works based on a local library.
+//!
+//! A [`Recommender`] learns which genres, circles and creators appear in the works you own
+//! (e.g. the metadata from [`crate::library::enrich`]), then ranks other known works by how
+//! much they share with them. Nothing is requested from DLsite.
+//!
+//! # Example
+//! ```
+//! use dlsite_gamebox::{library::WorkMetadata, recommend::Recommender};
+//!
+//! fn suggest(owned: &[WorkMetadata], known: &[WorkMetadata]) {
+//!     let recommender = Recommender::new(owned);
+//!     for r in recommender.recommend(known, 10) {
+//!         println!("{:.2} {} ({:?})", r.score, r.work.title, r.reasons);
+//!     }
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::library::WorkMetadata;
+
+/// Weights and thresholds used to score works.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecommendOptions {
+    /// Weight of each shared genre
+    pub genre_weight: f32,
+    /// Weight of a circle you own works from
+    pub circle_weight: f32,
+    /// Weight of each shared creator
+    pub creator_weight: f32,
+    /// Weight of the work's rating (scaled to 0..=1)
+    pub rating_weight: f32,
+    /// Works rated below this are never recommended. Unrated works are kept.
+    pub min_rating: Option<f32>,
+}
+
+impl Default for RecommendOptions {
+    fn default() -> Self {
+        Self {
+            genre_weight: 1.0,
+            circle_weight: 2.0,
+            creator_weight: 1.5,
+            rating_weight: 0.5,
+            min_rating: Some(3.5),
+        }
+    }
+}
+
+/// Why a work was recommended.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "name")]
+pub enum Reason {
+    Genre(String),
+    Circle(String),
+    Creator(String),
+}
+
+/// A recommended work with its score.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Recommendation {
+    pub work: WorkMetadata,
+    pub score: f32,
+    /// Shared genres, circle and creators, most significant first
+    pub reasons: Vec<Reason>,
+}
+
+/// Scores works against the tastes learned from a library.
+#[derive(Debug, Clone)]
+pub struct Recommender {
+    owned: HashSet<String>,
+    owned_count: usize,
+    genres: HashMap<String, u32>,
+    circles: HashMap<String, u32>,
+    creators: HashMap<String, u32>,
+    options: RecommendOptions,
+}
+
+fn circle_key(work: &WorkMetadata) -> &str {
+    work.circle_id.as_deref().unwrap_or(&work.circle_name)
+}
+
+impl Recommender {
+    /// Learn tastes from the works owned by the user.
+    pub fn new<'a>(owned: impl IntoIterator<Item = &'a WorkMetadata>) -> Self {
+        let mut recommender = Self {
+            owned: HashSet::new(),
+            owned_count: 0,
+            genres: HashMap::new(),
+            circles: HashMap::new(),
+            creators: HashMap::new(),
+            options: RecommendOptions::default(),
+        };
+        for work in owned {
+            if !recommender.owned.insert(work.id.clone()) {
+                continue;
+            }
+            recommender.owned_count += 1;
+            for genre in &work.genres {
+                *recommender.genres.entry(genre.id.clone()).or_default() += 1;
+            }
+            *recommender
+                .circles
+                .entry(circle_key(work).to_string())
+                .or_default() += 1;
+            for creator in &work.creators {
+                *recommender.creators.entry(creator.clone()).or_default() += 1;
+            }
+        }
+        recommender
+    }
+
+    /// Set the weights and thresholds.
+    pub fn options(mut self, options: RecommendOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Share of owned works having a feature.
+    fn affinity(&self, counts: &HashMap<String, u32>, key: &str) -> f32 {
+        match counts.get(key) {
+            Some(count) if self.owned_count > 0 => *count as f32 / self.owned_count as f32,
+            _ => 0.0,
+        }
+    }
+
+    /// Score a work. Returns `None` if it is owned, rated below the threshold, or shares
+    /// nothing with the library.
+    pub fn score(&self, work: &WorkMetadata) -> Option<Recommendation> {
+        let o = &self.options;
+        if self.owned.contains(&work.id) {
+            return None;
+        }
+        if let (Some(min), Some(rating)) = (o.min_rating, work.rating) {
+            if rating < min {
+                return None;
+            }
+        }
+
+        let mut reasons: Vec<(f32, Reason)> = vec![];
+        for genre in &work.genres {
+            let affinity = self.affinity(&self.genres, &genre.id);
+            if affinity > 0.0 {
+                reasons.push((o.genre_weight * affinity, Reason::Genre(genre.name.clone())));
+            }
+        }
+        let affinity = self.affinity(&self.circles, circle_key(work));
+        if affinity > 0.0 {
+            reasons.push((
+                o.circle_weight * affinity,
+                Reason::Circle(work.circle_name.clone()),
+            ));
+        }
+        for creator in &work.creators {
+            let affinity = self.affinity(&self.creators, creator);
+            if affinity > 0.0 {
+                reasons.push((o.creator_weight * affinity, Reason::Creator(creator.clone())));
+            }
+        }
+        if reasons.is_empty() {
+            return None;
+        }
+
+        let rating_score = work.rating.map_or(0.0, |r| o.rating_weight * r / 5.0);
+        let score = reasons.iter().map(|(s, _)| s).sum::<f32>() + rating_score;
+        reasons.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Some(Recommendation {
+            work: work.clone(),
+            score,
+            reasons: reasons.into_iter().map(|(_, r)| r).collect(),
+        })
+    }
+
+    /// Rank `candidates`, best first, keeping at most `limit` works.
+    pub fn recommend<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = &'a WorkMetadata>,
+        limit: usize,
+    ) -> Vec<Recommendation> {
+        let mut seen = HashSet::new();
+        let mut recommendations: Vec<_> = candidates
+            .into_iter()
+            .filter(|work| seen.insert(work.id.clone()))
+            .filter_map(|work| self.score(work))
+            .collect();
+        recommendations.sort_by(|a, b| b.score.total_cmp(&a.score));
+        recommendations.truncate(limit);
+        recommendations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reason, Recommender};
+    use crate::{
+        interface::{
+            genre::Genre,
+            product::{AgeCategory, WorkType},
+        },
+        library::WorkMetadata,
+    };
+
+    fn work(
+        id: &str,
+        circle: &str,
+        genres: &[&str],
+        creators: &[&str],
+        rating: f32,
+    ) -> WorkMetadata {
+        WorkMetadata {
+            id: id.to_string(),
+            title: id.to_string(),
+            circle_id: Some(circle.to_string()),
+            circle_name: circle.to_string(),
+            work_type: WorkType::SOU,
+            age_category: AgeCategory::General,
+            genres: genres
+                .iter()
+                .map(|g| Genre {
+                    name: g.to_string(),
+                    id: g.to_string(),
+                })
+                .collect(),
+            creators: creators.iter().map(|c| c.to_string()).collect(),
+            price: 1000,
+            rating: Some(rating),
+            released_at: None,
+            platforms: Default::default(),
+        }
+    }
+
+    #[test]
+    fn recommend_ranking() {
+        let owned = [
+            work("RJ1", "RG1", &["ASMR", "癒し"], &["春花らん"], 4.5),
+            work("RJ2", "RG1", &["ASMR"], &["佐倉綾音"], 4.8),
+        ];
+        let candidates = [
+            work("RJ1", "RG1", &["ASMR"], &["春花らん"], 4.5),
+            work("RJ3", "RG1", &["ASMR"], &["春花らん"], 4.0),
+            work("RJ4", "RG2", &["癒し"], &[], 4.9),
+            work("RJ5", "RG2", &["ASMR"], &["春花らん"], 2.0),
+            work("RJ6", "RG3", &["ゲーム"], &[], 5.0),
+        ];
+
+        let recommendations = Recommender::new(&owned).recommend(&candidates, 10);
+        let ids: Vec<_> = recommendations.iter().map(|r| r.work.id.as_str()).collect();
+        // Owned, low rated and unrelated works are excluded
+        assert_eq!(ids, vec!["RJ3", "RJ4"]);
+        assert_eq!(recommendations[0].reasons[0], Reason::Circle("RG1".to_string()));
+        assert_eq!(recommendations[1].reasons, vec![Reason::Genre("癒し".to_string())]);
+    }
+}
@@ -0,0 +1,321 @@
+//! Summaries of tracked prices, for posting to Discord or blogs.
+//!
+//! [`sale_report`] goes through the price history recorded by a
+//! [`crate::tracker::PriceTracker`] and lists the works on sale: biggest discounts, works at
+//! their lowest recorded price and sales ending soon. The [`SaleReport`] is plain data, and
+//! can be rendered as Markdown or HTML. Nothing is requested from DLsite.
+//!
+//! # Example
+//! ```no_run
+//! use dlsite_gamebox::{
+//!     reports::{self, ReportItem},
+//!     tracker::JsonPriceStore,
+//! };
+//!
+//! let store = JsonPriceStore::open("prices.json").unwrap();
+//! let items = [ReportItem::new("RJ403038", "Some voice work")];
+//! let report = reports::sale_report(&items, &store).unwrap();
+//! println!("{}", report.to_markdown());
+//! ```
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+
+use crate::{
+    error::Result,
+    interface::product_id::ProductId,
+    tracker::{PriceSnapshot, PriceStore},
+};
+
+/// Sales ending within this delay are listed in [`SaleReport::expiring_soon`].
+pub const EXPIRING_WITHIN: TimeDelta = TimeDelta::days(3);
+
+/// A tracked work to include in a [`SaleReport`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReportItem {
+    pub product_id: ProductId,
+    pub title: String,
+}
+
+impl ReportItem {
+    pub fn new(product_id: impl Into<ProductId>, title: impl Into<String>) -> Self {
+        Self {
+            product_id: product_id.into(),
+            title: title.into(),
+        }
+    }
+}
+
+/// A work on sale, as listed in a [`SaleReport`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SaleEntry {
+    pub product_id: ProductId,
+    pub title: String,
+    /// Current price in yen, discount included
+    pub price: i64,
+    pub official_price: i64,
+    /// Discount in percent
+    pub discount_rate: i64,
+    /// Lowest price in the history, current price included
+    pub lowest_price: i64,
+    /// End of the sale, if DLsite gives one
+    pub campaign_end: Option<DateTime<Utc>>,
+}
+
+impl SaleEntry {
+    /// Whether the work is sold at its lowest recorded price.
+    pub fn is_historical_low(&self) -> bool {
+        self.price <= self.lowest_price
+    }
+}
+
+/// Works on sale among the tracked ones, see [`sale_report`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SaleReport {
+    pub generated_at: DateTime<Utc>,
+    /// Every work on sale, biggest discount first
+    pub biggest_discounts: Vec<SaleEntry>,
+    /// Works on sale at their lowest recorded price, cheapest first
+    pub historical_lows: Vec<SaleEntry>,
+    /// Sales ending within [`EXPIRING_WITHIN`], soonest first
+    pub expiring_soon: Vec<SaleEntry>,
+}
+
+/// Build a report of the works of `items` on sale, from their latest snapshot in `history`.
+///
+/// Works without history are left out.
+pub fn sale_report<S: PriceStore + ?Sized>(
+    items: &[ReportItem],
+    history: &S,
+) -> Result<SaleReport> {
+    sale_report_at(items, history, Utc::now())
+}
+
+/// Same as [`sale_report`], with sales ending within [`EXPIRING_WITHIN`] of `now` listed as
+/// expiring.
+pub fn sale_report_at<S: PriceStore + ?Sized>(
+    items: &[ReportItem],
+    history: &S,
+    now: DateTime<Utc>,
+) -> Result<SaleReport> {
+    let mut entries = vec![];
+    for item in items {
+        let snapshots = history.history(&item.product_id)?;
+        let Some(latest) = snapshots.last().filter(|s| s.is_discounted()) else {
+            continue;
+        };
+        entries.push(SaleEntry {
+            product_id: item.product_id.clone(),
+            title: item.title.clone(),
+            price: latest.price,
+            official_price: latest.official_price,
+            discount_rate: discount_rate(latest),
+            lowest_price: snapshots
+                .iter()
+                .map(|s| s.price)
+                .min()
+                .unwrap_or(latest.price),
+            campaign_end: latest.campaign_end.as_deref().and_then(parse_campaign_end),
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        (b.discount_rate, a.price, &a.product_id).cmp(&(a.discount_rate, b.price, &b.product_id))
+    });
+    let mut historical_lows: Vec<SaleEntry> = entries
+        .iter()
+        .filter(|e| e.is_historical_low())
+        .cloned()
+        .collect();
+    historical_lows.sort_by(|a, b| (a.price, &a.product_id).cmp(&(b.price, &b.product_id)));
+    let mut expiring_soon: Vec<SaleEntry> = entries
+        .iter()
+        .filter(|e| {
+            e.campaign_end
+                .is_some_and(|end| end > now && end - now <= EXPIRING_WITHIN)
+        })
+        .cloned()
+        .collect();
+    expiring_soon
+        .sort_by(|a, b| (a.campaign_end, &a.product_id).cmp(&(b.campaign_end, &b.product_id)));
+
+    Ok(SaleReport {
+        generated_at: now,
+        biggest_discounts: entries,
+        historical_lows,
+        expiring_soon,
+    })
+}
+
+impl SaleReport {
+    /// Whether no tracked work is on sale.
+    pub fn is_empty(&self) -> bool {
+        self.biggest_discounts.is_empty()
+    }
+
+    fn sections(&self) -> [(&'static str, &[SaleEntry]); 3] {
+        [
+            ("Biggest discounts", &self.biggest_discounts),
+            ("Historical lows", &self.historical_lows),
+            ("Ending soon", &self.expiring_soon),
+        ]
+    }
+
+    /// Render the report as Markdown, one list per non-empty section.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        for (heading, entries) in self.sections() {
+            if entries.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("## {heading}\n\n"));
+            for entry in entries {
+                let title = entry.title.replace('[', "\\[").replace(']', "\\]");
+                out.push_str(&format!(
+                    "- [{title}]({}): {}\n",
+                    entry.product_id.url(),
+                    describe(entry)
+                ));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render the report as an HTML fragment, one list per non-empty section.
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        for (heading, entries) in self.sections() {
+            if entries.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("<h2>{heading}</h2>\n<ul>\n"));
+            for entry in entries {
+                out.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a>: {}</li>\n",
+                    entry.product_id.url(),
+                    escape_html(&entry.title),
+                    escape_html(&describe(entry))
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+        out
+    }
+}
+
+/// Discount of a snapshot in percent, computed from the prices if DLsite doesn't give it.
+fn discount_rate(snapshot: &PriceSnapshot) -> i64 {
+    snapshot.discount_rate.unwrap_or_else(|| {
+        (snapshot.official_price - snapshot.price) * 100 / snapshot.official_price.max(1)
+    })
+}
+
+/// Parse a campaign end as given by DLsite (`2024-06-30 23:59:59`, Japan time).
+fn parse_campaign_end(end: &str) -> Option<DateTime<Utc>> {
+    let end = end.trim();
+    let naive = NaiveDateTime::parse_from_str(end, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(end, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(end, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(23, 59, 59)
+        })?;
+    let jst = FixedOffset::east_opt(9 * 3600)?;
+    Some(naive.and_local_timezone(jst).single()?.with_timezone(&Utc))
+}
+
+fn describe(entry: &SaleEntry) -> String {
+    let mut text = format!(
+        "{} yen (-{}%, usually {} yen)",
+        entry.price, entry.discount_rate, entry.official_price
+    );
+    if let Some(end) = entry.campaign_end {
+        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+        text.push_str(&format!(
+            ", until {} JST",
+            end.with_timezone(&jst).format("%Y-%m-%d %H:%M")
+        ));
+    }
+    text
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Days, Utc};
+
+    use super::{sale_report_at, ReportItem};
+    use crate::tracker::{MemoryPriceStore, PriceSnapshot, PriceStore};
+
+    fn snapshot(at: DateTime<Utc>, price: i64, campaign_end: Option<&str>) -> PriceSnapshot {
+        PriceSnapshot {
+            at,
+            price,
+            official_price: 2000,
+            discount_rate: None,
+            campaign_end: campaign_end.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn sale_report() {
+        let now: DateTime<Utc> = "2024-06-28T00:00:00Z".parse().unwrap();
+        let before = now - Days::new(30);
+        let mut store = MemoryPriceStore::default();
+        // Was cheaper before
+        store
+            .record(&"RJ01000001".into(), snapshot(before, 1000, None))
+            .unwrap();
+        store
+            .record(&"RJ01000001".into(), snapshot(now, 1400, None))
+            .unwrap();
+        // Lowest price, sale ending the day after
+        store
+            .record(
+                &"RJ01000002".into(),
+                snapshot(now, 1200, Some("2024-06-29 23:59:59")),
+            )
+            .unwrap();
+        // Not on sale
+        store
+            .record(&"RJ01000003".into(), snapshot(now, 2000, None))
+            .unwrap();
+        let items = [
+            ReportItem::new("RJ01000001", "First"),
+            ReportItem::new("RJ01000002", "<Second>"),
+            ReportItem::new("RJ01000003", "Third"),
+            ReportItem::new("RJ01000004", "Untracked"),
+        ];
+
+        let report = sale_report_at(&items, &store, now).unwrap();
+        let ids = |entries: &[super::SaleEntry]| {
+            entries
+                .iter()
+                .map(|e| e.product_id.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&report.biggest_discounts), ["RJ01000002", "RJ01000001"]);
+        assert_eq!(report.biggest_discounts[0].discount_rate, 40);
+        assert_eq!(ids(&report.historical_lows), ["RJ01000002"]);
+        assert_eq!(ids(&report.expiring_soon), ["RJ01000002"]);
+        assert_eq!(
+            report.expiring_soon[0].campaign_end,
+            Some("2024-06-29T14:59:59Z".parse().unwrap())
+        );
+
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("## Biggest discounts\n\n- [<Second>]("));
+        assert!(markdown.contains("1200 yen (-40%, usually 2000 yen), until 2024-06-29 23:59 JST"));
+        let html = report.to_html();
+        assert!(html.contains(">&lt;Second&gt;</a>"));
+        assert!(!report.is_empty());
+        assert!(sale_report_at(&items[2..], &store, now).unwrap().is_empty());
+    }
+}
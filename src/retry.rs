@@ -1,8 +1,66 @@
-use std::time::Duration;
+use std::{fmt, sync::Arc, time::Duration};
+use chrono::{DateTime, Utc};
+use rand::Rng as _;
 use crate::error::DlsiteError;
 
+type PredicateFn = dyn Fn(&DlsiteError) -> bool + Send + Sync;
+
+/// User-supplied predicate deciding which errors are retried, see [`RetryConfig::retry_if`].
+#[derive(Clone)]
+pub struct RetryPredicate(Arc<PredicateFn>);
+
+impl fmt::Debug for RetryPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RetryPredicate(..)")
+    }
+}
+
+type CallbackFn = dyn Fn(u32, &DlsiteError, Duration) + Send + Sync;
+
+/// User-supplied callback called before each retry, see [`RetryConfig::on_retry`].
+#[derive(Clone)]
+pub struct RetryCallback(Arc<CallbackFn>);
+
+impl fmt::Debug for RetryCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RetryCallback(..)")
+    }
+}
+
+/// Randomization of the delay between retries, so that many clients failing at the same
+/// time don't retry at the same time too.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Jitter {
+    /// Exactly the exponential backoff delay
+    #[default]
+    None,
+    /// Random delay between zero and the backoff delay
+    Full,
+    /// Half the backoff delay, plus a random delay up to the other half
+    Equal,
+}
+
+impl Jitter {
+    /// Randomize a backoff delay.
+    pub fn apply(self, delay: Duration) -> Duration {
+        match self {
+            Jitter::None => delay,
+            Jitter::Full => rand::rng().random_range(Duration::ZERO..=delay),
+            Jitter::Equal => {
+                let half = delay / 2;
+                half + rand::rng().random_range(Duration::ZERO..=delay - half)
+            }
+        }
+    }
+}
+
 /// Retry configuration for HTTP requests
+///
+/// Build it with [`RetryConfig::new`] or [`RetryConfig::default`] and the builder methods such
+/// as [`RetryConfig::retry_if`]: new options may be added without a breaking release.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct RetryConfig {
     /// Maximum number of retry attempts
     pub max_retries: u32,
@@ -12,6 +70,12 @@ pub struct RetryConfig {
     pub max_delay: Duration,
     /// Backoff multiplier (exponential backoff)
     pub backoff_multiplier: f64,
+    /// Overrides [`RetryConfig::is_retryable_default`] when set
+    pub retry_if: Option<RetryPredicate>,
+    /// Randomization of the backoff delay, none by default
+    pub jitter: Jitter,
+    /// Called before each retry, see [`RetryConfig::on_retry`]
+    pub on_retry: Option<RetryCallback>,
 }
 
 impl Default for RetryConfig {
@@ -21,6 +85,9 @@ impl Default for RetryConfig {
             initial_delay: Duration::from_millis(100),
             max_delay: Duration::from_secs(10),
             backoff_multiplier: 2.0,
+            retry_if: None,
+            jitter: Jitter::None,
+            on_retry: None,
         }
     }
 }
@@ -33,10 +100,74 @@ impl RetryConfig {
             initial_delay,
             max_delay,
             backoff_multiplier: 2.0,
+            retry_if: None,
+            jitter: Jitter::None,
+            on_retry: None,
+        }
+    }
+
+    /// Decide which errors are retried with `predicate` instead of
+    /// [`RetryConfig::is_retryable_default`].
+    ///
+    /// # Example
+    /// ```
+    /// use dlsite_gamebox::{DlsiteError, RetryConfig};
+    ///
+    /// // Also retry parse errors, which may come from truncated responses
+    /// let config = RetryConfig::default().retry_if(|e| {
+    ///     RetryConfig::is_retryable_default(e) || matches!(e, DlsiteError::Parse(_))
+    /// });
+    /// assert!(config.is_retryable(&DlsiteError::Parse("truncated".to_string())));
+    /// ```
+    pub fn retry_if(
+        mut self,
+        predicate: impl Fn(&DlsiteError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_if = Some(RetryPredicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Randomize the delay between retries, see [`Jitter`].
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Call `callback` with the number of the failed attempt (starting from 1), its error and
+    /// the delay before the next one, before each retry.
+    ///
+    /// # Example
+    /// ```
+    /// use dlsite_gamebox::{retry::Jitter, RetryConfig};
+    ///
+    /// let config = RetryConfig::default()
+    ///     .jitter(Jitter::Full)
+    ///     .on_retry(|attempt, error, delay| {
+    ///         eprintln!("Attempt {attempt} failed ({error}), retrying in {delay:?}");
+    ///     });
+    /// ```
+    pub fn on_retry(
+        mut self,
+        callback: impl Fn(u32, &DlsiteError, Duration) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_retry = Some(RetryCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Delay before retrying after the given attempt (starting from 0), with the jitter
+    /// applied to [`RetryConfig::calculate_delay`].
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        self.jitter.apply(self.calculate_delay(attempt))
+    }
+
+    /// Call the [`RetryConfig::on_retry`] callback, if any.
+    pub(crate) fn notify_retry(&self, attempt: u32, error: &DlsiteError, delay: Duration) {
+        if let Some(callback) = &self.on_retry {
+            (callback.0)(attempt, error, delay);
         }
     }
 
-    /// Calculate the delay for a given retry attempt
+    /// Calculate the delay for a given retry attempt, without jitter
     pub fn calculate_delay(&self, attempt: u32) -> Duration {
         let delay_ms = self.initial_delay.as_millis() as f64
             * self.backoff_multiplier.powi(attempt as i32);
@@ -44,13 +175,22 @@ impl RetryConfig {
         Duration::from_millis(delay_ms as u64)
     }
 
-    /// Check if an error is retryable
+    /// Check if an error is retryable, using the predicate set with [`RetryConfig::retry_if`]
+    /// if any.
     pub fn is_retryable(&self, error: &DlsiteError) -> bool {
+        match &self.retry_if {
+            Some(predicate) => (predicate.0)(error),
+            None => Self::is_retryable_default(error),
+        }
+    }
+
+    /// Default set of retryable errors: timeouts, rate limiting and HTTP 5xx.
+    pub fn is_retryable_default(error: &DlsiteError) -> bool {
         match error {
             // Timeout errors are retryable
             DlsiteError::Timeout => true,
             // Rate limit errors are retryable
-            DlsiteError::RateLimit(_) => true,
+            DlsiteError::RateLimit { .. } => true,
             // HTTP 5xx errors are retryable
             DlsiteError::HttpStatus(code) => *code >= 500,
             // Other errors are not retryable
@@ -59,6 +199,17 @@ impl RetryConfig {
     }
 }
 
+/// Parse a `Retry-After` header, either a number of seconds or an HTTP date.
+pub(crate) fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means no wait
+    Some((date.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,12 +250,76 @@ mod tests {
         let config = RetryConfig::default();
         
         assert!(config.is_retryable(&DlsiteError::Timeout));
-        assert!(config.is_retryable(&DlsiteError::RateLimit("test".to_string())));
+        assert!(config.is_retryable(&DlsiteError::RateLimit {
+            message: "test".to_string(),
+            retry_after: None,
+        }));
         assert!(config.is_retryable(&DlsiteError::HttpStatus(500)));
         assert!(config.is_retryable(&DlsiteError::HttpStatus(503)));
         
         assert!(!config.is_retryable(&DlsiteError::HttpStatus(404)));
         assert!(!config.is_retryable(&DlsiteError::HttpStatus(400)));
     }
-}
 
+    #[test]
+    fn test_retry_if() {
+        let config = RetryConfig::default().retry_if(|e| {
+            RetryConfig::is_retryable_default(e) || matches!(e, DlsiteError::Parse(_))
+        });
+        assert!(config.is_retryable(&DlsiteError::Parse("truncated".to_string())));
+        assert!(config.is_retryable(&DlsiteError::Timeout));
+
+        let config = RetryConfig::default().retry_if(|e| matches!(e, DlsiteError::Timeout));
+        assert!(config.is_retryable(&DlsiteError::Timeout));
+        assert!(!config.is_retryable(&DlsiteError::HttpStatus(503)));
+    }
+
+    #[test]
+    fn test_jitter() {
+        let config = RetryConfig::default().jitter(Jitter::Full);
+        for attempt in 0..5 {
+            assert!(config.retry_delay(attempt) <= config.calculate_delay(attempt));
+        }
+        let config = RetryConfig::default().jitter(Jitter::Equal);
+        for attempt in 0..5 {
+            let delay = config.retry_delay(attempt);
+            let max = config.calculate_delay(attempt);
+            assert!(delay >= max / 2 && delay <= max);
+        }
+        assert_eq!(
+            RetryConfig::default().retry_delay(1),
+            Duration::from_millis(200)
+        );
+    }
+
+    #[test]
+    fn test_on_retry() {
+        let calls = Arc::new(std::sync::Mutex::new(vec![]));
+        let config = RetryConfig::default().on_retry({
+            let calls = calls.clone();
+            move |attempt, error, delay| {
+                calls.lock().unwrap().push((attempt, error.to_string(), delay));
+            }
+        });
+        config.notify_retry(1, &DlsiteError::Timeout, Duration::from_millis(100));
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, 1);
+        assert_eq!(calls[0].2, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now: DateTime<Utc> = "2024-06-01T12:00:00Z".parse().unwrap();
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Sat, 01 Jun 2024 12:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Sat, 01 Jun 2024 11:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
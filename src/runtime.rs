@@ -0,0 +1,149 @@
+//! Portability layer between native targets and `wasm32-unknown-unknown`.
+//!
+//! On native targets, timers are tokio's and clocks are [`std::time`]. In the browser neither
+//! works, so timers use `setTimeout` and clocks use `performance.now()` and `Date.now()`.
+//!
+//! # WebAssembly
+//! Build for the browser without the default features, then re-enable the ones you need:
+//! ```toml
+//! dlsite-gamebox = { version = "0.2", default-features = false, features = ["unknown-field-log"] }
+//! ```
+//! `rand` needs `RUSTFLAGS='--cfg getrandom_backend="wasm_js"'` on this target. The browser
+//! manages cookies, proxies and timeouts itself, so
+//! [`crate::DlsiteClientBuilder::cookie`], [`crate::DlsiteClientBuilder::confirm_adult`],
+//! [`crate::DlsiteClientBuilder::timeout`] and the session export/import of
+//! [`crate::client::auth`] have no effect or are unavailable there. The `blocking`, `cli`,
+//! `server` and `redis` features need a native target.
+
+use std::{future::Future, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// `Send` on native targets, where futures of the client are `Send`. Browser futures are not,
+/// so nothing is required there.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// `Send` on native targets, where futures of the client are `Send`. Browser futures are not,
+/// so nothing is required there.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// Boxed future, `Send` on native targets only (see [`MaybeSend`]).
+#[cfg(not(target_arch = "wasm32"))]
+pub type MaybeBoxFuture<'a, T> = futures::future::BoxFuture<'a, T>;
+#[cfg(target_arch = "wasm32")]
+pub type MaybeBoxFuture<'a, T> = futures::future::LocalBoxFuture<'a, T>;
+
+/// Boxed stream, `Send` on native targets only (see [`MaybeSend`]).
+#[cfg(not(target_arch = "wasm32"))]
+pub type MaybeBoxStream<'a, T> = futures::stream::BoxStream<'a, T>;
+#[cfg(target_arch = "wasm32")]
+pub type MaybeBoxStream<'a, T> = futures::stream::LocalBoxStream<'a, T>;
+
+/// Boxing into a [`MaybeBoxFuture`], like [`futures::FutureExt::boxed`] on native targets.
+pub(crate) trait MaybeBoxed<'a>: Future + MaybeSend + Sized + 'a {
+    fn maybe_boxed(self) -> MaybeBoxFuture<'a, Self::Output> {
+        Box::pin(self)
+    }
+}
+
+impl<'a, F: Future + MaybeSend + 'a> MaybeBoxed<'a> for F {}
+
+/// Wait for `duration`.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+    #[cfg(target_arch = "wasm32")]
+    futures_timer::Delay::new(duration).await;
+}
+
+/// Run blocking file system work on tokio's blocking pool without waiting for it, or in place
+/// outside of a tokio runtime and in the browser.
+pub(crate) fn spawn_blocking(f: impl FnOnce() + Send + 'static) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn_blocking(f);
+        return;
+    }
+    f();
+}
+
+/// Run blocking file system work on tokio's blocking pool and wait for its result, or in place
+/// outside of a tokio runtime and in the browser.
+#[cfg(feature = "download")]
+pub(crate) async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        return match handle.spawn_blocking(f).await {
+            Ok(value) => value,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        };
+    }
+    f()
+}
+
+/// Ticker firing every `period`, starting immediately. Like [`tokio::time::interval`], missed
+/// ticks fire right away until it catches up.
+pub(crate) fn interval(period: Duration) -> Interval {
+    Interval {
+        #[cfg(not(target_arch = "wasm32"))]
+        inner: tokio::time::interval(period),
+        #[cfg(target_arch = "wasm32")]
+        next: Instant::now(),
+        #[cfg(target_arch = "wasm32")]
+        period,
+    }
+}
+
+/// See [`interval`].
+#[derive(Debug)]
+pub(crate) struct Interval {
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: tokio::time::Interval,
+    #[cfg(target_arch = "wasm32")]
+    next: Instant,
+    #[cfg(target_arch = "wasm32")]
+    period: Duration,
+}
+
+impl Interval {
+    /// Wait for the next tick.
+    pub(crate) async fn tick(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.inner.tick().await;
+        #[cfg(target_arch = "wasm32")]
+        {
+            let now = Instant::now();
+            if self.next > now {
+                sleep(self.next - now).await;
+            }
+            self.next += self.period;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{interval, Instant};
+
+    #[tokio::test]
+    async fn interval_ticks() {
+        let started = Instant::now();
+        let mut ticker = interval(Duration::from_millis(50));
+        ticker.tick().await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+        ticker.tick().await;
+        ticker.tick().await;
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,196 @@
+//! Selector fallback chains and parse reports, to keep parsers working when DLsite changes
+//! its markup.
+//!
+//! Each logical field of a parser is backed by a [`SelectorChain`]: the selector matching the
+//! current DOM first, followed by known older (or alternative) variants. The first candidate
+//! that matches is used, and which one it was is recorded in a [`ParseReport`], so markup
+//! changes show up as fallbacks in the report before they turn into parse errors.
+
+use std::collections::BTreeMap;
+
+use scraper::{ElementRef, Html, Selector};
+
+/// Ordered list of candidate selectors for one logical field.
+#[derive(Debug)]
+pub struct SelectorChain {
+    field: &'static str,
+    candidates: Vec<(&'static str, Selector)>,
+}
+
+impl SelectorChain {
+    /// Create a chain. `candidates` are tried in order.
+    ///
+    /// # Panics
+    /// Panics if a candidate is not a valid selector.
+    pub fn new(field: &'static str, candidates: &[&'static str]) -> Self {
+        Self {
+            field,
+            candidates: candidates
+                .iter()
+                .map(|css| {
+                    let selector = Selector::parse(css)
+                        .unwrap_or_else(|_| panic!("Failed to parse selector {css}"));
+                    (*css, selector)
+                })
+                .collect(),
+        }
+    }
+
+    /// Name of the field, used as key in the [`ParseReport`].
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Select the first element under `scope` matched by the first matching candidate.
+    pub fn select<'a>(
+        &self,
+        scope: ElementRef<'a>,
+        report: &mut ParseReport,
+    ) -> Option<ElementRef<'a>> {
+        for (i, (css, selector)) in self.candidates.iter().enumerate() {
+            if let Some(element) = scope.select(selector).next() {
+                report.record(
+                    self.field,
+                    FieldMatch::Matched {
+                        selector: css.to_string(),
+                        fallback: i > 0,
+                    },
+                );
+                return Some(element);
+            }
+        }
+        report.record(self.field, FieldMatch::Missing);
+        None
+    }
+
+    /// Select all elements of a document matched by the first candidate matching anything.
+    pub fn select_all<'a>(&self, html: &'a Html, report: &mut ParseReport) -> Vec<ElementRef<'a>> {
+        for (i, (css, selector)) in self.candidates.iter().enumerate() {
+            let elements: Vec<_> = html.select(selector).collect();
+            if !elements.is_empty() {
+                report.record(
+                    self.field,
+                    FieldMatch::Matched {
+                        selector: css.to_string(),
+                        fallback: i > 0,
+                    },
+                );
+                return elements;
+            }
+        }
+        report.record(self.field, FieldMatch::Missing);
+        vec![]
+    }
+}
+
+/// How a field was found while parsing.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum FieldMatch {
+    /// Found with `selector`. `fallback` is true if it isn't the primary selector.
+    Matched { selector: String, fallback: bool },
+    /// No candidate matched. This is not always an error: many fields are optional.
+    Missing,
+}
+
+impl FieldMatch {
+    /// Higher is worse. Used to keep the worst observation when merging reports.
+    fn severity(&self) -> u8 {
+        match self {
+            FieldMatch::Matched { fallback: false, .. } => 0,
+            FieldMatch::Matched { fallback: true, .. } => 1,
+            FieldMatch::Missing => 2,
+        }
+    }
+}
+
+/// Which selector matched each field while parsing a page.
+///
+/// When the same field is parsed several times (e.g. once per search result item), the worst
+/// observation is kept: missing, then fallback, then primary selector.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParseReport {
+    pub fields: BTreeMap<String, FieldMatch>,
+}
+
+impl ParseReport {
+    /// Record how a field was found.
+    pub fn record(&mut self, field: &str, found: FieldMatch) {
+        match self.fields.get(field) {
+            Some(existing) if existing.severity() >= found.severity() => {}
+            _ => {
+                self.fields.insert(field.to_string(), found);
+            }
+        }
+    }
+
+    /// Merge another report into this one.
+    pub fn merge(&mut self, other: ParseReport) {
+        for (field, found) in other.fields {
+            self.record(&field, found);
+        }
+    }
+
+    /// Whether any field was found using a fallback selector.
+    pub fn used_fallback(&self) -> bool {
+        self.fields
+            .values()
+            .any(|f| matches!(f, FieldMatch::Matched { fallback: true, .. }))
+    }
+
+    /// Fields found using a fallback selector, with the selector used.
+    pub fn fallbacks(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().filter_map(|(field, found)| match found {
+            FieldMatch::Matched {
+                selector,
+                fallback: true,
+            } => Some((field.as_str(), selector.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Fields no candidate selector matched.
+    pub fn missing(&self) -> impl Iterator<Item = &str> {
+        self.fields
+            .iter()
+            .filter(|(_, found)| **found == FieldMatch::Missing)
+            .map(|(field, _)| field.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Html;
+
+    use super::{FieldMatch, ParseReport, SelectorChain};
+
+    #[test]
+    fn fallback_chain() {
+        let chain = SelectorChain::new("title", &[".work_name a[title]", ".work_name a"]);
+        let mut report = ParseReport::default();
+
+        let html = Html::parse_fragment(r#"<div class="work_name"><a title="New">New</a></div>"#);
+        assert!(chain.select(html.root_element(), &mut report).is_some());
+        assert!(!report.used_fallback());
+
+        let html = Html::parse_fragment(r#"<div class="work_name"><a>Old</a></div>"#);
+        let e = chain.select(html.root_element(), &mut report).unwrap();
+        assert_eq!(e.text().collect::<String>(), "Old");
+        assert!(report.used_fallback());
+        assert_eq!(
+            report.fallbacks().collect::<Vec<_>>(),
+            vec![("title", ".work_name a")]
+        );
+
+        // A later primary match doesn't hide the fallback
+        let html = Html::parse_fragment(r#"<div class="work_name"><a title="New">New</a></div>"#);
+        chain.select(html.root_element(), &mut report);
+        assert!(report.used_fallback());
+
+        let mut other = ParseReport::default();
+        chain.select(Html::parse_fragment("<p></p>").root_element(), &mut other);
+        report.merge(other);
+        assert_eq!(report.fields["title"], FieldMatch::Missing);
+        assert_eq!(report.missing().collect::<Vec<_>>(), vec!["title"]);
+    }
+}
@@ -0,0 +1,357 @@
+//! Local HTTP API server exposing the client as JSON endpoints.
+//!
+//! All requests share one [`DlsiteClient`], so responses are cached and DLsite is accessed
+//! through a single rate limiter no matter how many frontends use the server.
+//!
+//! | Method | Path | Response |
+//! |--------|------|----------|
+//! | GET | `/search?keyword=&genre=497,..&page=&per_page=` | [`SearchResult`] |
+//! | GET | `/products/{id}` | [`Product`] |
+//! | GET | `/products/{id}/metadata` | [`WorkMetadata`] |
+//! | GET | `/circles/{id}?page=` | [`SearchResult`] |
+//! | GET | `/rankings/{term}?genre=` | `Vec<`[`RankingEntry`]`>` |
+//! | POST | `/library/scan` `{"dir": ".."}` | `Vec<`[`LibraryItem`]`>` |
+//! | POST | `/library/enrich` `{"dir": ".."}` | `Vec<`[`EnrichedItem`]`>` |
+//!
+//! Library endpoints only accept directories inside the library root given to [`router`] or
+//! [`serve`]. Relative `dir`s are resolved against the root.
+
+use std::path::{Path as FsPath, PathBuf};
+
+use axum::{
+    extract::{FromRef, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    client::{
+        circle::CircleQuery,
+        product::Product,
+        ranking::{RankingEntry, RankingTerm},
+        search::{SearchProductQuery, SearchResult},
+    },
+    interface::genre::GenreCode,
+    library::{self, EnrichedItem, LibraryItem, WorkMetadata},
+    DlsiteClient, DlsiteError,
+};
+
+/// Error response of the server
+struct ApiError(StatusCode, String);
+
+impl From<DlsiteError> for ApiError {
+    fn from(e: DlsiteError) -> Self {
+        let status = match &e {
+            e if e.is_not_found() => StatusCode::NOT_FOUND,
+            DlsiteError::RateLimit { .. } | DlsiteError::BudgetExhausted { .. } => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            DlsiteError::InvalidProductId(_) => StatusCode::BAD_REQUEST,
+            DlsiteError::Unauthenticated => StatusCode::UNAUTHORIZED,
+            DlsiteError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            DlsiteError::Challenge { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            DlsiteError::HttpStatus(_) | DlsiteError::Reqwest(_) | DlsiteError::Server(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, e.to_string())
+    }
+}
+
+impl From<std::io::Error> for ApiError {
+    fn from(e: std::io::Error) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+/// Shared state of the handlers
+#[derive(Clone)]
+struct ServerState {
+    client: DlsiteClient,
+    library_root: PathBuf,
+}
+
+impl FromRef<ServerState> for DlsiteClient {
+    fn from_ref(state: &ServerState) -> Self {
+        state.client.clone()
+    }
+}
+
+/// Build the router of the server. Useful to mount the API into an existing axum application.
+///
+/// `library_root` is the only directory tree the library endpoints are allowed to scan.
+pub fn router(client: DlsiteClient, library_root: impl Into<PathBuf>) -> Router {
+    Router::new()
+        .route("/search", get(search))
+        .route("/products/{id}", get(product))
+        .route("/products/{id}/metadata", get(product_metadata))
+        .route("/circles/{id}", get(circle))
+        .route("/rankings/{term}", get(ranking))
+        .route("/library/scan", post(library_scan))
+        .route("/library/enrich", post(library_enrich))
+        .with_state(ServerState {
+            client,
+            library_root: library_root.into(),
+        })
+}
+
+/// Serve the API on the given address until the server fails.
+///
+/// # Example
+/// ```no_run
+/// use dlsite_gamebox::DlsiteClient;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     dlsite_gamebox::server::serve(DlsiteClient::default(), "/data/dlsite", "127.0.0.1:8080")
+///         .await
+///         .unwrap();
+/// }
+/// ```
+pub async fn serve(
+    client: DlsiteClient,
+    library_root: impl Into<PathBuf>,
+    addr: impl tokio::net::ToSocketAddrs,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(client, library_root)).await
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    keyword: Option<String>,
+    /// Comma separated genre IDs
+    genre: Option<String>,
+    page: Option<u32>,
+    per_page: Option<u32>,
+}
+
+async fn search(
+    State(client): State<DlsiteClient>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<SearchResult> {
+    let genre = params
+        .genre
+        .map(|g| {
+            g.split(',')
+                .map(|id| id.parse::<GenreCode>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("Invalid genre: {}", e)))?;
+    let result = client
+        .search()
+        .search_product(&SearchProductQuery {
+            keyword: params.keyword,
+            genre,
+            page: params.page,
+            per_page: params.per_page,
+            ..Default::default()
+        })
+        .await?;
+    Ok(Json(result))
+}
+
+async fn product(State(client): State<DlsiteClient>, Path(id): Path<String>) -> ApiResult<Product> {
+    Ok(Json(client.product().get_all(&id).await?))
+}
+
+async fn product_metadata(
+    State(client): State<DlsiteClient>,
+    Path(id): Path<String>,
+) -> ApiResult<WorkMetadata> {
+    let product = client.product_api().get(&id).await?;
+    Ok(Json(WorkMetadata::from(&product)))
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    page: Option<u32>,
+}
+
+async fn circle(
+    State(client): State<DlsiteClient>,
+    Path(id): Path<String>,
+    Query(params): Query<PageParams>,
+) -> ApiResult<SearchResult> {
+    let result = client
+        .circle()
+        .get_circle(
+            &id,
+            &CircleQuery {
+                page: params.page,
+                ..Default::default()
+            },
+        )
+        .await?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+struct RankingParams {
+    genre: Option<u32>,
+}
+
+async fn ranking(
+    State(client): State<DlsiteClient>,
+    Path(term): Path<String>,
+    Query(params): Query<RankingParams>,
+) -> ApiResult<Vec<RankingEntry>> {
+    let term: RankingTerm = term
+        .parse()
+        .map_err(|_| ApiError(StatusCode::BAD_REQUEST, format!("Invalid term: {}", term)))?;
+    let entries = match params.genre {
+        Some(genre) => client.ranking().by_genre(genre, term).await?,
+        None => client.ranking().get(term).await?,
+    };
+    Ok(Json(entries))
+}
+
+#[derive(Deserialize)]
+struct LibraryParams {
+    dir: PathBuf,
+    concurrency: Option<usize>,
+}
+
+/// Resolve `dir` against the library root, rejecting directories outside of it.
+fn library_dir(root: &FsPath, dir: &FsPath) -> std::result::Result<PathBuf, ApiError> {
+    let root = root.canonicalize()?;
+    // Symlinks and `..` are resolved before checking, so they can't escape the root
+    let dir = root.join(dir).canonicalize()?;
+    if !dir.starts_with(&root) {
+        return Err(ApiError(
+            StatusCode::FORBIDDEN,
+            format!("{} is outside of the library root", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+async fn scan_library(
+    root: PathBuf,
+    dir: PathBuf,
+) -> std::result::Result<Vec<LibraryItem>, ApiError> {
+    tokio::task::spawn_blocking(move || Ok(library::scan(library_dir(&root, &dir)?)?))
+        .await
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+}
+
+async fn library_scan(
+    State(state): State<ServerState>,
+    Json(params): Json<LibraryParams>,
+) -> ApiResult<Vec<LibraryItem>> {
+    Ok(Json(scan_library(state.library_root, params.dir).await?))
+}
+
+async fn library_enrich(
+    State(state): State<ServerState>,
+    Json(params): Json<LibraryParams>,
+) -> ApiResult<Vec<EnrichedItem>> {
+    let items = scan_library(state.library_root, params.dir).await?;
+    let items = library::enrich(&state.client, items, params.concurrency.unwrap_or(4)).await;
+    Ok(Json(items))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        Router,
+    };
+    use tower::ServiceExt as _;
+
+    use super::router;
+    use crate::{testing::FixtureTransport, DlsiteClient};
+
+    fn client(transport: FixtureTransport) -> DlsiteClient {
+        DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .request_interval(Duration::ZERO, Duration::ZERO)
+            .transport(transport)
+            .build()
+    }
+
+    async fn call(app: Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn scan_request(dir: &str) -> Request<Body> {
+        Request::post("/library/scan")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "dir": dir }).to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn product_metadata() {
+        let mut item: serde_json::Value =
+            serde_json::from_str(include_str!("client/product_api/fixture.json")).unwrap();
+        item["workno"] = serde_json::json!("RJ01000001");
+        item["work_name"] = serde_json::json!("title of RJ01000001");
+        let transport = FixtureTransport::new().with_body(
+            "https://www.dlsite.com/maniax/api/=/product.json?workno=RJ01000001",
+            serde_json::json!([item]).to_string(),
+        );
+        let app = router(client(transport), std::env::temp_dir());
+
+        let request = Request::get("/products/RJ01000001/metadata")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = call(app, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "RJ01000001");
+        assert_eq!(body["title"], "title of RJ01000001");
+    }
+
+    #[tokio::test]
+    async fn invalid_ranking_term() {
+        let app = router(DlsiteClient::default(), std::env::temp_dir());
+        let request = Request::get("/rankings/forever").body(Body::empty()).unwrap();
+        let (status, body) = call(app, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid term: forever");
+    }
+
+    #[tokio::test]
+    async fn library_scan_is_confined_to_root() {
+        let base = std::env::temp_dir().join(format!("dlsite-server-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        let root = base.join("library");
+        std::fs::create_dir_all(root.join("voice/RJ403038")).unwrap();
+        std::fs::create_dir_all(base.join("private/RJ01017217")).unwrap();
+        let app = router(DlsiteClient::default(), &root);
+
+        let (status, body) = call(app.clone(), scan_request("voice")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["id"], "RJ403038");
+
+        let (status, _) = call(app.clone(), scan_request("../private")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let outside = base.join("private");
+        let (status, _) = call(app.clone(), scan_request(outside.to_str().unwrap())).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(app, scan_request("missing")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
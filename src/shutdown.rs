@@ -0,0 +1,144 @@
+//! Graceful shutdown of background tasks.
+//!
+//! Long-running tasks of the crate (trackers, watchers...) take a [`Shutdown`] handle. Calling
+//! [`Shutdown::shutdown`] asks every task to stop, and waits until they all saved their state
+//! and returned.
+//!
+//! # Example
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use dlsite_gamebox::{shutdown::Shutdown, tracker::CircleGrowth, DlsiteClient};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let shutdown = Shutdown::new();
+//!     let task = tokio::spawn({
+//!         let shutdown = shutdown.clone();
+//!         async move {
+//!             let client = DlsiteClient::default();
+//!             let mut growth = CircleGrowth::load("circle_growth.json").unwrap();
+//!             growth
+//!                 .run(&client, "circle_growth.json", Duration::from_secs(3600), &shutdown)
+//!                 .await
+//!         }
+//!     });
+//!
+//!     tokio::signal::ctrl_c().await.unwrap();
+//!     shutdown.shutdown().await;
+//!     task.await.unwrap().unwrap();
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Handle asking background tasks to stop. Clones share the same state.
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+    /// Number of live [`ShutdownSignal`]s
+    tasks: Arc<watch::Sender<usize>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            triggered: Arc::new(watch::Sender::new(false)),
+            tasks: Arc::new(watch::Sender::new(0)),
+        }
+    }
+
+    /// Register a task. [`Shutdown::shutdown`] waits until the returned signal is dropped.
+    pub fn signal(&self) -> ShutdownSignal {
+        self.tasks.send_modify(|n| *n += 1);
+        ShutdownSignal {
+            triggered: self.triggered.subscribe(),
+            tasks: self.tasks.clone(),
+        }
+    }
+
+    /// Whether [`Shutdown::shutdown`] was called.
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Ask all tasks to stop and wait until they finished flushing their state.
+    ///
+    /// Tasks registered after this call stop immediately.
+    pub async fn shutdown(&self) {
+        self.triggered.send_replace(true);
+        let mut tasks = self.tasks.subscribe();
+        // The sender is owned by `self`, so waiting can't fail
+        let _ = tasks.wait_for(|n| *n == 0).await;
+    }
+}
+
+/// Held by a background task registered with [`Shutdown::signal`].
+#[derive(Debug)]
+pub struct ShutdownSignal {
+    triggered: watch::Receiver<bool>,
+    tasks: Arc<watch::Sender<usize>>,
+}
+
+impl ShutdownSignal {
+    /// Whether the task was asked to stop.
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Wait until the task is asked to stop.
+    pub async fn triggered(&mut self) {
+        // The sender lives as long as the `Shutdown` handles, which own `tasks` too
+        let _ = self.triggered.wait_for(|t| *t).await;
+    }
+}
+
+impl Drop for ShutdownSignal {
+    fn drop(&mut self) {
+        self.tasks.send_modify(|n| *n -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::Shutdown;
+
+    #[tokio::test]
+    async fn shutdown_waits_for_tasks() {
+        let shutdown = Shutdown::new();
+        let flushed = Arc::new(AtomicBool::new(false));
+
+        let mut signal = shutdown.signal();
+        let task_flushed = flushed.clone();
+        tokio::spawn(async move {
+            signal.triggered().await;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            task_flushed.store(true, Ordering::SeqCst);
+        });
+
+        assert!(!shutdown.is_triggered());
+        shutdown.shutdown().await;
+        assert!(shutdown.is_triggered());
+        assert!(flushed.load(Ordering::SeqCst));
+
+        // Nothing to wait for anymore
+        shutdown.shutdown().await;
+        assert!(shutdown.signal().is_triggered());
+    }
+}
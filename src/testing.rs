@@ -0,0 +1,387 @@
+//! Offline testing with recorded responses.
+//!
+//! [`FixtureTransport`] is a [`Transport`] serving responses recorded earlier (VCR style), so
+//! tests of code using [`crate::DlsiteClient`] run without network access and against stable
+//! pages. Record once against DLsite, commit the fixture directory, and replay in CI:
+//!
+//! ```no_run
+//! use dlsite_gamebox::{testing::FixtureTransport, transport::ReqwestTransport, DlsiteClient};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     // Sends missing requests to DLsite and stores their responses...
+//!     let transport =
+//!         FixtureTransport::replay_or_record("tests/fixtures/http", ReqwestTransport::default());
+//!     // ...or only serves stored responses, failing on unknown requests
+//!     let transport = FixtureTransport::replay("tests/fixtures/http");
+//!
+//!     let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+//!         .transport(transport)
+//!         .build();
+//!     let product = client.product().get_all("RJ01014447").await.unwrap();
+//!     assert_eq!(product.id, "RJ01014447");
+//! }
+//! ```
+//!
+//! Each exchange is stored as one JSON file named after the request. `Set-Cookie` headers and
+//! password fields are left out of the files.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    persist::{self, Persisted},
+    runtime::MaybeBoxFuture,
+    transport::{HttpRequest, HttpResponse, Method, Transport},
+    DlsiteError,
+};
+
+/// Maximum length of the URL part of fixture file names.
+const MAX_SLUG_LEN: usize = 80;
+
+/// Where [`FixtureTransport`] gets its responses from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Only serve stored responses
+    Replay,
+    /// Send every request and store its response
+    Record,
+    /// Serve stored responses, send and store the others
+    ReplayOrRecord,
+}
+
+/// [`Transport`] replaying recorded responses, see the [module documentation](self).
+pub struct FixtureTransport {
+    mode: FixtureMode,
+    /// Directory of the fixture files, `None` for in-memory fixtures only
+    dir: Option<PathBuf>,
+    inner: Option<Arc<dyn Transport>>,
+    /// Responses added with [`FixtureTransport::with_response`], by request key
+    memory: HashMap<String, HttpResponse>,
+    /// Requests received, in order
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl fmt::Debug for FixtureTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixtureTransport")
+            .field("mode", &self.mode)
+            .field("dir", &self.dir)
+            .field("memory", &self.memory.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for FixtureTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixtureTransport {
+    /// Transport serving only the responses added with [`FixtureTransport::with_response`].
+    pub fn new() -> Self {
+        Self {
+            mode: FixtureMode::Replay,
+            dir: None,
+            inner: None,
+            memory: HashMap::new(),
+            requests: Mutex::default(),
+        }
+    }
+
+    /// Serve the responses stored in `dir`. Unknown requests fail with
+    /// [`DlsiteError::Persist`].
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            ..Self::new()
+        }
+    }
+
+    /// Send every request with `inner` and store the responses in `dir`, replacing the stored
+    /// ones.
+    pub fn record(dir: impl Into<PathBuf>, inner: impl Transport + 'static) -> Self {
+        Self {
+            mode: FixtureMode::Record,
+            dir: Some(dir.into()),
+            inner: Some(Arc::new(inner)),
+            ..Self::new()
+        }
+    }
+
+    /// Serve the responses stored in `dir`, and send the other requests with `inner`, storing
+    /// their responses.
+    pub fn replay_or_record(dir: impl Into<PathBuf>, inner: impl Transport + 'static) -> Self {
+        Self {
+            mode: FixtureMode::ReplayOrRecord,
+            ..Self::record(dir, inner)
+        }
+    }
+
+    /// Serve `response` to `request`, before looking at the fixture directory.
+    pub fn with_response(mut self, request: HttpRequest, response: HttpResponse) -> Self {
+        self.memory.insert(request_key(&request), response);
+        self
+    }
+
+    /// Serve `body` with status 200 to `GET` requests of `url`.
+    pub fn with_body(self, url: &str, body: impl Into<Vec<u8>>) -> Self {
+        self.with_response(HttpRequest::get(url), HttpResponse::new(200, body))
+    }
+
+    /// Where responses come from.
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// File storing the response to `request`.
+    pub fn fixture_path(&self, request: &HttpRequest) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(file_name(request)))
+    }
+
+    fn stored(&self, request: &HttpRequest) -> Result<Option<HttpResponse>> {
+        if let Some(response) = self.memory.get(&request_key(request)) {
+            return Ok(Some(response.clone()));
+        }
+        match self.fixture_path(request) {
+            Some(path) => {
+                Ok(persist::load::<Recording>(path)?.map(|recording| recording.response.into()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn send_inner(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let (Some(inner), Some(path)) = (&self.inner, self.fixture_path(&request)) else {
+            return Err(DlsiteError::Persist(format!(
+                "No fixture for {} {}",
+                request.method, request.url
+            )));
+        };
+        let response = inner.send(request.clone()).await?;
+        persist::save(path, &Recording::new(&request, &response))?;
+        Ok(response)
+    }
+}
+
+impl Transport for FixtureTransport {
+    fn send(&self, request: HttpRequest) -> MaybeBoxFuture<'_, Result<HttpResponse>> {
+        Box::pin(async move {
+            self.requests.lock().unwrap().push(request.clone());
+            if self.mode != FixtureMode::Record {
+                if let Some(response) = self.stored(&request)? {
+                    return Ok(response);
+                }
+            }
+            self.send_inner(request).await
+        })
+    }
+}
+
+/// Stored exchange.
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    request: HttpRequest,
+    response: RecordedResponse,
+}
+
+impl Persisted for Recording {
+    const KIND: &'static str = "http_fixture";
+    const VERSION: u32 = 1;
+}
+
+impl Recording {
+    fn new(request: &HttpRequest, response: &HttpResponse) -> Self {
+        let mut request = request.clone();
+        for (name, value) in &mut request.form {
+            if is_secret(name) {
+                *value = "***".to_string();
+            }
+        }
+        Recording {
+            request,
+            response: RecordedResponse {
+                status: response.status,
+                headers: response
+                    .headers
+                    .iter()
+                    .filter(|(name, _)| !name.eq_ignore_ascii_case("set-cookie"))
+                    .cloned()
+                    .collect(),
+                body: match String::from_utf8(response.body.clone()) {
+                    Ok(text) => Body::Text(text),
+                    Err(e) => Body::Binary(e.into_bytes()),
+                },
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Body,
+}
+
+/// Body stored as text when possible, so HTML and JSON fixtures stay readable.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Body {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl From<RecordedResponse> for HttpResponse {
+    fn from(recorded: RecordedResponse) -> Self {
+        HttpResponse {
+            status: recorded.status,
+            headers: recorded.headers,
+            body: match recorded.body {
+                Body::Text(text) => text.into_bytes(),
+                Body::Binary(bytes) => bytes,
+            },
+        }
+    }
+}
+
+/// Identity of a request: method, URL and form. Passwords are left out, so that fixtures
+/// don't depend on them.
+fn request_key(request: &HttpRequest) -> String {
+    let mut key = format!("{} {}", request.method, request.url);
+    for (name, value) in &request.form {
+        let value = if is_secret(name) { "" } else { value };
+        key.push_str(&format!("\n{name}={value}"));
+    }
+    for (name, value) in &request.headers {
+        key.push_str(&format!("\n{name}: {value}"));
+    }
+    key
+}
+
+fn is_secret(field: &str) -> bool {
+    field.contains("password")
+}
+
+/// File name of the fixture of `request`: readable URL part and a hash of the whole request.
+fn file_name(request: &HttpRequest) -> String {
+    let url = request
+        .url
+        .split_once("://")
+        .map_or(&*request.url, |(_, rest)| rest);
+    let mut slug: String = url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    slug.truncate(MAX_SLUG_LEN);
+    let method = match request.method {
+        Method::Get => "get",
+        Method::Post => "post",
+    };
+    format!(
+        "{method}-{slug}-{:016x}.json",
+        fnv1a(request_key(request).as_bytes())
+    )
+}
+
+/// 64-bit FNV-1a, stable across Rust versions unlike the std hasher.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Whether `dir` contains a fixture for `request`, e.g. to skip a test which needs one.
+pub fn has_fixture(dir: impl AsRef<Path>, request: &HttpRequest) -> bool {
+    dir.as_ref().join(file_name(request)).is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_name, has_fixture, FixtureTransport};
+    use crate::{
+        transport::{HttpRequest, HttpResponse, Transport as _},
+        DlsiteClient,
+    };
+
+    #[tokio::test]
+    async fn in_memory() {
+        let transport =
+            FixtureTransport::new().with_body("https://www.dlsite.com/maniax/hello", "<p>hi</p>");
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .transport(transport)
+            .build();
+        assert_eq!(client.get("/hello").await.unwrap(), "<p>hi</p>");
+        assert!(client.get("/unknown").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn record_then_replay() {
+        let dir = std::env::temp_dir().join(format!("dlsite-fixtures-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let request =
+            HttpRequest::post_form("https://login.dlsite.com/login", &[("password", "hunter2")]);
+        let upstream = FixtureTransport::new()
+            .with_body("https://www.dlsite.com/maniax/page", "page")
+            .with_response(
+                request.clone(),
+                HttpResponse::new(302, "").with_header("Set-Cookie", "__DLsite_SID=secret"),
+            )
+            .with_response(
+                HttpRequest::get("https://img.dlsite.jp/a.jpg"),
+                HttpResponse::new(200, vec![0xff, 0xd8, 0xff]),
+            );
+
+        let recorder = FixtureTransport::replay_or_record(&dir, upstream);
+        assert_eq!(recorder.send(request.clone()).await.unwrap().status, 302);
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .transport(recorder)
+            .build();
+        assert_eq!(client.get("/page").await.unwrap(), "page");
+        assert_eq!(
+            &*client
+                .get_media("https://img.dlsite.jp/a.jpg")
+                .await
+                .unwrap(),
+            &[0xff, 0xd8, 0xff]
+        );
+
+        // No secret is stored
+        let stored = std::fs::read_to_string(dir.join(file_name(&request))).unwrap();
+        assert!(!stored.contains("hunter2"));
+        assert!(!stored.contains("secret"));
+        assert!(has_fixture(
+            &dir,
+            &HttpRequest::get("https://www.dlsite.com/maniax/page")
+        ));
+
+        let replay = FixtureTransport::replay(&dir);
+        let client = DlsiteClient::builder("https://www.dlsite.com/maniax")
+            .transport(replay)
+            .build();
+        assert_eq!(client.get("/page").await.unwrap(), "page");
+        assert_eq!(
+            &*client
+                .get_media("https://img.dlsite.jp/a.jpg")
+                .await
+                .unwrap(),
+            &[0xff, 0xd8, 0xff]
+        );
+        assert!(client.get("/other").await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+//! Time series of DLsite data recorded over time, stored with [`crate::persist`]: follower
+//! and download counts of circles ([`CircleGrowth`]) and prices of works ([`PriceTracker`]).
+
+mod price;
+
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    client::circle::CircleStats,
+    error::Result,
+    persist::{self, Persisted},
+    shutdown::Shutdown,
+    DlsiteClient,
+};
+
+pub use self::price::{
+    JsonPriceStore, MemoryPriceStore, PriceChange, PriceSnapshot, PriceStore, PriceTracker,
+};
+
+/// Follower and download counts of a circle at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CircleSnapshot {
+    pub at: DateTime<Utc>,
+    pub followers: Option<i32>,
+    pub works: i32,
+    pub downloads: i64,
+}
+
+/// Counts compared by [`CircleGrowth::fastest_growing`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrowthMetric {
+    Followers,
+    Downloads,
+}
+
+impl GrowthMetric {
+    fn of(&self, snapshot: &CircleSnapshot) -> Option<i64> {
+        match self {
+            GrowthMetric::Followers => snapshot.followers.map(i64::from),
+            GrowthMetric::Downloads => Some(snapshot.downloads),
+        }
+    }
+}
+
+/// Growth of a circle between two snapshots.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GrowthRate {
+    pub circle_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Increase of the count between the two snapshots
+    pub delta: i64,
+    /// Average increase per day
+    pub per_day: f64,
+}
+
+/// Follower and download counts of a set of circles, recorded over time.
+///
+/// Call [`CircleGrowth::snapshot`] periodically (or use [`CircleGrowth::run`]) and
+/// [`CircleGrowth::save`] the history to compare circles with
+/// [`CircleGrowth::fastest_growing`].
+///
+/// # Example
+/// ```no_run
+/// use dlsite_gamebox::{tracker::{CircleGrowth, GrowthMetric}, DlsiteClient};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = DlsiteClient::default();
+///     let mut growth = CircleGrowth::load("circle_growth.json").unwrap();
+///     growth.track("RG24350");
+///     growth.snapshot(&client).await;
+///     growth.save("circle_growth.json").unwrap();
+///
+///     let since = chrono::Utc::now() - chrono::Days::new(30);
+///     for rate in growth.fastest_growing(GrowthMetric::Followers, since, 10) {
+///         println!("{}: +{:.1}/day", rate.circle_id, rate.per_day);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CircleGrowth {
+    /// Snapshots by circle ID, oldest first
+    circles: BTreeMap<String, Vec<CircleSnapshot>>,
+}
+
+impl Persisted for CircleGrowth {
+    const KIND: &'static str = "circle_growth";
+    const VERSION: u32 = 1;
+}
+
+impl CircleGrowth {
+    /// Load the history saved at `path`, or an empty one if the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(persist::load(path)?.unwrap_or_default())
+    }
+
+    /// Save the history to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        persist::save(path, self)
+    }
+
+    /// Start recording a circle. Does nothing if it is already tracked.
+    pub fn track(&mut self, circle_id: &str) {
+        self.circles.entry(circle_id.to_string()).or_default();
+    }
+
+    /// Stop recording a circle and drop its history.
+    pub fn untrack(&mut self, circle_id: &str) {
+        self.circles.remove(circle_id);
+    }
+
+    /// IDs of the tracked circles.
+    pub fn circles(&self) -> impl Iterator<Item = &str> {
+        self.circles.keys().map(String::as_str)
+    }
+
+    /// Snapshots of a circle, oldest first.
+    pub fn history(&self, circle_id: &str) -> &[CircleSnapshot] {
+        self.circles
+            .get(circle_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Add a snapshot of a circle, tracking it if needed.
+    pub fn record(&mut self, circle_id: &str, at: DateTime<Utc>, stats: CircleStats) {
+        let history = self.circles.entry(circle_id.to_string()).or_default();
+        history.push(CircleSnapshot {
+            at,
+            followers: stats.followers,
+            works: stats.works,
+            downloads: stats.downloads,
+        });
+        history.sort_by_key(|s| s.at);
+    }
+
+    /// Fetch the current counts of every tracked circle and record them.
+    ///
+    /// Circles which fail to load are logged and skipped. Returns the number of snapshots
+    /// recorded.
+    pub async fn snapshot(&mut self, client: &DlsiteClient) -> usize {
+        let ids: Vec<String> = self.circles.keys().cloned().collect();
+        let mut recorded = 0;
+        for id in ids {
+            match client.circle().stats(&id).await {
+                Ok(stats) => {
+                    self.record(&id, Utc::now(), stats);
+                    recorded += 1;
+                }
+                Err(e) => tracing::warn!("Failed to get stats of circle {id}: {e}"),
+            }
+        }
+        recorded
+    }
+
+    /// Record a snapshot every `interval` and save the history to `path` after each one.
+    ///
+    /// Runs until saving fails or `shutdown` is triggered, in which case the history is saved
+    /// one last time.
+    pub async fn run(
+        &mut self,
+        client: &DlsiteClient,
+        path: impl AsRef<Path>,
+        interval: Duration,
+        shutdown: &Shutdown,
+    ) -> Result<()> {
+        let mut signal = shutdown.signal();
+        let mut ticker = crate::runtime::interval(interval);
+        loop {
+            tokio::select! {
+                biased;
+                _ = signal.triggered() => return self.save(path.as_ref()),
+                _ = ticker.tick() => {}
+            }
+            self.snapshot(client).await;
+            self.save(path.as_ref())?;
+        }
+    }
+
+    /// Circles whose count grew the most per day, comparing the first snapshot taken at or
+    /// after `since` with the latest one. Circles with fewer than two such snapshots are
+    /// skipped.
+    pub fn fastest_growing(
+        &self,
+        metric: GrowthMetric,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Vec<GrowthRate> {
+        let mut rates: Vec<GrowthRate> = self
+            .circles
+            .iter()
+            .filter_map(|(id, history)| {
+                let mut points = history
+                    .iter()
+                    .filter(|s| s.at >= since)
+                    .filter_map(|s| Some((s.at, metric.of(s)?)));
+                let (from, first) = points.next()?;
+                let (to, last) = points.next_back()?;
+                let days = (to - from).num_seconds() as f64 / 86400.0;
+                if days <= 0.0 {
+                    return None;
+                }
+                Some(GrowthRate {
+                    circle_id: id.clone(),
+                    from,
+                    to,
+                    delta: last - first,
+                    per_day: (last - first) as f64 / days,
+                })
+            })
+            .collect();
+        rates.sort_by(|a, b| b.per_day.total_cmp(&a.per_day));
+        rates.truncate(limit);
+        rates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Days, Utc};
+
+    use super::{CircleGrowth, GrowthMetric};
+    use crate::client::circle::CircleStats;
+
+    fn stats(followers: i32, downloads: i64) -> CircleStats {
+        CircleStats {
+            followers: Some(followers),
+            works: 10,
+            downloads,
+        }
+    }
+
+    #[test]
+    fn fastest_growing() {
+        let start: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let mut growth = CircleGrowth::default();
+        growth.record("RG1", start, stats(100, 5000));
+        growth.record("RG1", start + Days::new(10), stats(200, 5100));
+        growth.record("RG2", start, stats(1000, 9000));
+        growth.record("RG2", start + Days::new(10), stats(1050, 12000));
+        growth.record("RG3", start + Days::new(10), stats(10, 10));
+        assert_eq!(growth.circles().count(), 3);
+
+        let rates = growth.fastest_growing(GrowthMetric::Followers, start, 10);
+        let ids: Vec<_> = rates.iter().map(|r| r.circle_id.as_str()).collect();
+        assert_eq!(ids, vec!["RG1", "RG2"]);
+        assert_eq!(rates[0].delta, 100);
+        assert_eq!(rates[0].per_day, 10.0);
+
+        let rates = growth.fastest_growing(GrowthMetric::Downloads, start, 1);
+        assert_eq!(rates.len(), 1);
+        assert_eq!(rates[0].circle_id, "RG2");
+
+        // Snapshots before `since` are ignored
+        let rates = growth.fastest_growing(GrowthMetric::Followers, start + Days::new(1), 10);
+        assert!(rates.is_empty());
+    }
+}
@@ -0,0 +1,403 @@
+//! Price history of works, see [`PriceTracker`].
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    client::product_api::interface::ProductApiContent,
+    error::Result,
+    interface::product_id::ProductId,
+    persist::{self, Persisted},
+    shutdown::Shutdown,
+    DlsiteClient,
+};
+
+/// Price of a work at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PriceSnapshot {
+    pub at: DateTime<Utc>,
+    /// Current price in yen, discount included
+    pub price: i64,
+    /// Price without discount
+    pub official_price: i64,
+    /// Discount in percent, if the work is on sale
+    pub discount_rate: Option<i64>,
+    /// End of the sale as given by DLsite, if the work is on sale
+    pub campaign_end: Option<String>,
+}
+
+impl PriceSnapshot {
+    /// Snapshot of the price given by the product api.
+    pub fn from_api(at: DateTime<Utc>, api: &ProductApiContent) -> Self {
+        let on_sale = api.is_discount_work;
+        Self {
+            at,
+            price: api.price,
+            official_price: api.official_price,
+            discount_rate: api.discount_rate.filter(|_| on_sale),
+            campaign_end: api.campaign_end_date.clone().filter(|_| on_sale),
+        }
+    }
+
+    /// Whether the work is sold below its official price.
+    pub fn is_discounted(&self) -> bool {
+        self.price < self.official_price
+    }
+
+    /// Whether the price and the sale are the same as in `other`, ignoring the time.
+    fn same_price(&self, other: &PriceSnapshot) -> bool {
+        self.price == other.price
+            && self.official_price == other.official_price
+            && self.discount_rate == other.discount_rate
+            && self.campaign_end == other.campaign_end
+    }
+}
+
+/// Change of the price of a work between two polls, see [`PriceTracker::poll`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PriceChange {
+    pub product_id: ProductId,
+    pub previous: PriceSnapshot,
+    pub current: PriceSnapshot,
+}
+
+impl PriceChange {
+    /// Difference of the current price from the previous one, negative if cheaper.
+    pub fn delta(&self) -> i64 {
+        self.current.price - self.previous.price
+    }
+
+    /// Whether the work went on sale.
+    pub fn sale_started(&self) -> bool {
+        !self.previous.is_discounted() && self.current.is_discounted()
+    }
+
+    /// Whether the sale of the work ended.
+    pub fn sale_ended(&self) -> bool {
+        self.previous.is_discounted() && !self.current.is_discounted()
+    }
+}
+
+/// Storage of the price history of works.
+///
+/// [`MemoryPriceStore`] keeps it in memory and [`JsonPriceStore`] in a file; implement this
+/// trait to keep it in a database.
+pub trait PriceStore {
+    /// Append a snapshot to the history of a work.
+    fn record(&mut self, product_id: &ProductId, snapshot: PriceSnapshot) -> Result<()>;
+    /// Snapshots of a work, oldest first.
+    fn history(&self, product_id: &ProductId) -> Result<Vec<PriceSnapshot>>;
+    /// Latest snapshot of a work.
+    fn latest(&self, product_id: &ProductId) -> Result<Option<PriceSnapshot>> {
+        Ok(self.history(product_id)?.pop())
+    }
+    /// Write pending changes, called by [`PriceTracker`] after each poll.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// In-memory [`PriceStore`].
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MemoryPriceStore {
+    /// Snapshots by work, oldest first
+    works: BTreeMap<ProductId, Vec<PriceSnapshot>>,
+}
+
+impl Persisted for MemoryPriceStore {
+    const KIND: &'static str = "price_history";
+    const VERSION: u32 = 1;
+}
+
+impl PriceStore for MemoryPriceStore {
+    fn record(&mut self, product_id: &ProductId, snapshot: PriceSnapshot) -> Result<()> {
+        let history = self.works.entry(product_id.clone()).or_default();
+        history.push(snapshot);
+        history.sort_by_key(|s| s.at);
+        Ok(())
+    }
+
+    fn history(&self, product_id: &ProductId) -> Result<Vec<PriceSnapshot>> {
+        Ok(self.works.get(product_id).cloned().unwrap_or_default())
+    }
+
+    fn latest(&self, product_id: &ProductId) -> Result<Option<PriceSnapshot>> {
+        Ok(self.works.get(product_id).and_then(|h| h.last()).cloned())
+    }
+}
+
+/// [`PriceStore`] kept in memory and saved to a JSON file with [`crate::persist`] on
+/// [`PriceStore::flush`].
+#[derive(Debug, Clone)]
+pub struct JsonPriceStore {
+    path: PathBuf,
+    store: MemoryPriceStore,
+    dirty: bool,
+}
+
+impl JsonPriceStore {
+    /// Open the history saved at `path`, or an empty one if the file doesn't exist.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        Ok(Self {
+            store: persist::load(&path)?.unwrap_or_default(),
+            path,
+            dirty: false,
+        })
+    }
+
+    /// File the history is saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl PriceStore for JsonPriceStore {
+    fn record(&mut self, product_id: &ProductId, snapshot: PriceSnapshot) -> Result<()> {
+        self.dirty = true;
+        self.store.record(product_id, snapshot)
+    }
+
+    fn history(&self, product_id: &ProductId) -> Result<Vec<PriceSnapshot>> {
+        self.store.history(product_id)
+    }
+
+    fn latest(&self, product_id: &ProductId) -> Result<Option<PriceSnapshot>> {
+        self.store.latest(product_id)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.dirty {
+            persist::save(&self.path, &self.store)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+/// Prices of a set of works, recorded over time.
+///
+/// [`PriceTracker::poll`] fetches the works from the product api and records a snapshot
+/// when the price or the sale of a work changed since the last one, so the history holds one
+/// entry per price period. [`PriceTracker::run`] does so periodically.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use dlsite_gamebox::{
+///     shutdown::Shutdown,
+///     tracker::{JsonPriceStore, PriceTracker},
+///     DlsiteClient,
+/// };
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = DlsiteClient::default();
+///     let mut tracker = PriceTracker::new(JsonPriceStore::open("prices.json").unwrap());
+///     tracker.track("RJ403038");
+///     tracker
+///         .run(&client, Duration::from_secs(6 * 3600), &Shutdown::new(), |change| {
+///             if change.sale_started() {
+///                 println!("{} is on sale: {} yen", change.product_id, change.current.price);
+///             }
+///         })
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PriceTracker<S = MemoryPriceStore> {
+    products: Vec<ProductId>,
+    store: S,
+}
+
+impl<S: PriceStore> PriceTracker<S> {
+    /// Create a tracker recording to `store`, without tracked works.
+    pub fn new(store: S) -> Self {
+        Self {
+            products: vec![],
+            store,
+        }
+    }
+
+    /// Start tracking a work. Does nothing if it is already tracked.
+    pub fn track(&mut self, product_id: impl Into<ProductId>) {
+        let product_id = product_id.into();
+        if !self.products.contains(&product_id) {
+            self.products.push(product_id);
+        }
+    }
+
+    /// Stop tracking a work. Its history is kept in the store.
+    pub fn untrack(&mut self, product_id: impl Into<ProductId>) {
+        let product_id = product_id.into();
+        self.products.retain(|p| p != &product_id);
+    }
+
+    /// Tracked works.
+    pub fn products(&self) -> &[ProductId] {
+        &self.products
+    }
+
+    /// Store the history is recorded to.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Price history of a work, oldest first.
+    pub fn history(&self, product_id: impl Into<ProductId>) -> Result<Vec<PriceSnapshot>> {
+        self.store.history(&product_id.into())
+    }
+
+    /// Record a snapshot if the price differs from the latest one, and return the change.
+    ///
+    /// The first snapshot of a work is recorded without change.
+    pub fn observe(
+        &mut self,
+        product_id: impl Into<ProductId>,
+        snapshot: PriceSnapshot,
+    ) -> Result<Option<PriceChange>> {
+        let product_id = product_id.into();
+        let previous = self.store.latest(&product_id)?;
+        if previous.as_ref().is_some_and(|p| p.same_price(&snapshot)) {
+            return Ok(None);
+        }
+        self.store.record(&product_id, snapshot.clone())?;
+        Ok(previous.map(|previous| PriceChange {
+            product_id,
+            previous,
+            current: snapshot,
+        }))
+    }
+
+    /// Fetch every tracked work, record the new prices and flush the store.
+    ///
+    /// Works which fail to load are logged and skipped.
+    pub async fn poll(&mut self, client: &DlsiteClient) -> Result<Vec<PriceChange>> {
+        let mut changes = vec![];
+        for product_id in self.products.clone() {
+            match client.product_api().get(&product_id).await {
+                Ok(api) => {
+                    let snapshot = PriceSnapshot::from_api(Utc::now(), &api);
+                    changes.extend(self.observe(product_id, snapshot)?);
+                }
+                Err(e) => tracing::warn!("Failed to get the price of {product_id}: {e}"),
+            }
+        }
+        self.store.flush()?;
+        Ok(changes)
+    }
+
+    /// Poll every `interval` and pass the changes to `on_change`.
+    ///
+    /// Runs until the store fails or `shutdown` is triggered, in which case the store is
+    /// flushed one last time.
+    pub async fn run<F>(
+        &mut self,
+        client: &DlsiteClient,
+        interval: Duration,
+        shutdown: &Shutdown,
+        mut on_change: F,
+    ) -> Result<()>
+    where
+        F: FnMut(PriceChange),
+    {
+        let mut signal = shutdown.signal();
+        let mut ticker = crate::runtime::interval(interval);
+        loop {
+            tokio::select! {
+                biased;
+                _ = signal.triggered() => return self.store.flush(),
+                _ = ticker.tick() => {}
+            }
+            for change in self.poll(client).await? {
+                on_change(change);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Days, Utc};
+
+    use super::{JsonPriceStore, PriceSnapshot, PriceStore, PriceTracker};
+    use crate::interface::product_id::ProductId;
+
+    fn snapshot(at: DateTime<Utc>, price: i64, discount_rate: Option<i64>) -> PriceSnapshot {
+        PriceSnapshot {
+            at,
+            price,
+            official_price: 1980,
+            discount_rate,
+            campaign_end: None,
+        }
+    }
+
+    #[test]
+    fn observe_changes() {
+        let start: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let mut tracker: PriceTracker = PriceTracker::default();
+        tracker.track("RJ403038");
+        tracker.track("rj403038");
+        assert_eq!(tracker.products().len(), 1);
+
+        assert_eq!(
+            tracker
+                .observe("RJ403038", snapshot(start, 1980, None))
+                .unwrap(),
+            None
+        );
+        // Same price: nothing recorded
+        let next = start + Days::new(1);
+        assert_eq!(
+            tracker
+                .observe("RJ403038", snapshot(next, 1980, None))
+                .unwrap(),
+            None
+        );
+
+        let sale = start + Days::new(2);
+        let change = tracker
+            .observe("RJ403038", snapshot(sale, 990, Some(50)))
+            .unwrap()
+            .unwrap();
+        assert!(change.sale_started());
+        assert_eq!(change.delta(), -990);
+
+        let end = start + Days::new(9);
+        let change = tracker
+            .observe("RJ403038", snapshot(end, 1980, None))
+            .unwrap()
+            .unwrap();
+        assert!(change.sale_ended());
+
+        let history = tracker.history("RJ403038").unwrap();
+        let prices: Vec<i64> = history.iter().map(|s| s.price).collect();
+        assert_eq!(prices, vec![1980, 990, 1980]);
+    }
+
+    #[test]
+    fn json_store() {
+        let path = std::env::temp_dir().join(format!("dlsite-prices-{}.json", std::process::id()));
+        let id = ProductId::from("RJ403038");
+        let at: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+
+        let mut store = JsonPriceStore::open(&path).unwrap();
+        store.record(&id, snapshot(at, 990, Some(50))).unwrap();
+        store.flush().unwrap();
+
+        let store = JsonPriceStore::open(&path).unwrap();
+        assert_eq!(
+            store.latest(&id).unwrap(),
+            Some(snapshot(at, 990, Some(50)))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
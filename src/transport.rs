@@ -0,0 +1,301 @@
+//! HTTP layer of [`crate::DlsiteClient`].
+//!
+//! Every request of the client (pages, api calls, media, login forms) goes through a
+//! [`Transport`]. The default one, [`ReqwestTransport`], sends them with reqwest. Set another
+//! one with [`crate::DlsiteClientBuilder::transport`] to serve recorded responses instead, see
+//! [`crate::testing::FixtureTransport`].
+//!
+//! The rate limiter, cache and retries of the client apply whatever the transport is.
+
+use std::fmt;
+
+use strum::Display;
+
+use futures::StreamExt as _;
+
+use crate::{
+    error::Result,
+    runtime::{MaybeBoxFuture, MaybeBoxStream},
+};
+
+/// Sends the requests of a client.
+pub trait Transport: Send + Sync + fmt::Debug {
+    /// Send `request` and return the response, whatever its status. Fails only if no
+    /// response was received.
+    fn send(&self, request: HttpRequest) -> MaybeBoxFuture<'_, Result<HttpResponse>>;
+
+    /// Same as [`Transport::send`], but the body is returned as chunks as they arrive, so
+    /// that large responses are never held in memory at once.
+    ///
+    /// The default implementation sends the request with [`Transport::send`] and returns the
+    /// body as a single chunk.
+    fn send_streamed(&self, request: HttpRequest) -> MaybeBoxFuture<'_, Result<StreamedResponse>> {
+        Box::pin(async move { Ok(self.send(request).await?.into()) })
+    }
+}
+
+/// HTTP method of an [`HttpRequest`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Display, serde::Serialize, serde::Deserialize,
+)]
+#[strum(serialize_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// Request sent through a [`Transport`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HttpRequest {
+    pub method: Method,
+    /// Absolute URL
+    pub url: String,
+    /// Form fields, sent URL-encoded with `POST` requests
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub form: Vec<(String, String)>,
+    /// Extra `(name, value)` headers, e.g. `Range`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpRequest {
+    /// `GET` request of `url`.
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            method: Method::Get,
+            url: url.into(),
+            form: vec![],
+            headers: vec![],
+        }
+    }
+
+    /// `POST` request of a form to `url`.
+    pub fn post_form(url: impl Into<String>, form: &[(&str, &str)]) -> Self {
+        Self {
+            method: Method::Post,
+            url: url.into(),
+            form: form
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            headers: vec![],
+        }
+    }
+
+    /// Add a header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Response returned by a [`Transport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// `(name, value)` pairs, names in lowercase
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Response with the given status and body, without headers.
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    /// Add a header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers
+            .push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+
+    /// Whether the status is 2xx.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// First value of the header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The body as text. DLsite serves UTF-8, invalid sequences are replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Body of a [`StreamedResponse`], as chunks of bytes.
+pub type BodyStream = MaybeBoxStream<'static, Result<Vec<u8>>>;
+
+/// Response returned by [`Transport::send_streamed`], whose body is read as it arrives.
+pub struct StreamedResponse {
+    pub status: u16,
+    /// `(name, value)` pairs, names in lowercase
+    pub headers: Vec<(String, String)>,
+    pub body: BodyStream,
+}
+
+impl StreamedResponse {
+    /// Whether the status is 2xx.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// First value of the header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Read the whole body.
+    pub async fn buffer(self) -> Result<HttpResponse> {
+        let Self {
+            status,
+            headers,
+            mut body,
+        } = self;
+        let mut buffered = vec![];
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            if buffered.is_empty() {
+                buffered = chunk;
+            } else {
+                buffered.extend_from_slice(&chunk);
+            }
+        }
+        Ok(HttpResponse {
+            status,
+            headers,
+            body: buffered,
+        })
+    }
+}
+
+impl fmt::Debug for StreamedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamedResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<HttpResponse> for StreamedResponse {
+    fn from(response: HttpResponse) -> Self {
+        Self {
+            status: response.status,
+            headers: response.headers,
+            body: Box::pin(futures::stream::iter([Ok(response.body)])),
+        }
+    }
+}
+
+/// Default [`Transport`], sending requests with reqwest.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Send requests with `client`.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    async fn send_reqwest(&self, request: HttpRequest) -> Result<reqwest::Response> {
+        let mut builder = match request.method {
+            Method::Get => self.client.get(&request.url),
+            Method::Post => self.client.post(&request.url).form(&request.form),
+        };
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        Ok(builder.send().await?)
+    }
+}
+
+/// `(name, value)` pairs of the headers of `response`, dropping non-text values.
+fn response_headers(response: &reqwest::Response) -> Vec<(String, String)> {
+    response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+        })
+        .collect()
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> MaybeBoxFuture<'_, Result<HttpResponse>> {
+        Box::pin(async move {
+            let response = self.send_reqwest(request).await?;
+            let status = response.status().as_u16();
+            let headers = response_headers(&response);
+            let body = response.bytes().await?.to_vec();
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+
+    // reqwest can't read bodies by chunks in the browser, where the default implementation
+    // is used
+    #[cfg(not(target_arch = "wasm32"))]
+    fn send_streamed(&self, request: HttpRequest) -> MaybeBoxFuture<'_, Result<StreamedResponse>> {
+        Box::pin(async move {
+            let response = self.send_reqwest(request).await?;
+            let status = response.status().as_u16();
+            let headers = response_headers(&response);
+            let body = futures::stream::try_unfold(response, |mut response| async move {
+                Ok(response
+                    .chunk()
+                    .await?
+                    .map(|chunk| (chunk.to_vec(), response)))
+            });
+            Ok(StreamedResponse {
+                status,
+                headers,
+                body: Box::pin(body),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HttpRequest, HttpResponse, Method};
+
+    #[test]
+    fn response_helpers() {
+        let response = HttpResponse::new(200, "ok").with_header("Retry-After", "5");
+        assert!(response.is_success());
+        assert_eq!(response.header("retry-after"), Some("5"));
+        assert_eq!(response.text(), "ok");
+        assert!(!HttpResponse::new(404, "").is_success());
+
+        let request = HttpRequest::post_form("https://example.com", &[("a", "b")]);
+        assert_eq!(request.method.to_string(), "POST");
+        assert_eq!(request.form, vec![("a".to_string(), "b".to_string())]);
+        assert_eq!(HttpRequest::get("https://example.com").method, Method::Get);
+        let request = HttpRequest::get("https://example.com").with_header("Range", "bytes=0-");
+        assert_eq!(
+            request.headers,
+            vec![("Range".to_string(), "bytes=0-".to_string())]
+        );
+    }
+}
@@ -1,4 +1,12 @@
-use crate::error::{DlsiteError, Result};
+//! Miscellaneous helpers.
+
+use futures::{Stream, StreamExt as _};
+
+use crate::{
+    error::{DlsiteError, Result},
+    runtime::MaybeSend,
+    transport::BodyStream,
+};
 
 pub(crate) trait ToParseError<T> {
     fn to_parse_error(self, msg: &str) -> Result<T>;
@@ -15,3 +23,305 @@ impl<T, E> ToParseError<T> for std::result::Result<T, E> {
         self.map_err(|_| DlsiteError::Parse(msg.to_string()))
     }
 }
+
+/// Parse the first file size found in a text (`1.5GB`, `総計 512.3MB`, `1,024 KB`...) into
+/// bytes. Units are binary (1KB = 1024 bytes).
+///
+/// # Example
+/// ```
+/// use dlsite_gamebox::parse_file_size;
+///
+/// assert_eq!(parse_file_size("1.5GB"), Some(1_610_612_736));
+/// assert_eq!(parse_file_size("総計 2,048 KB"), Some(2_097_152));
+/// assert_eq!(parse_file_size("不明"), None);
+/// ```
+pub fn parse_file_size(text: &str) -> Option<u64> {
+    static SIZE_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let re = SIZE_RE.get_or_init(|| {
+        regex::Regex::new(r"(?i)(\d[\d,]*(?:\.\d+)?)\s*(TB|GB|MB|KB|B|バイト)").unwrap()
+    });
+    let cap = re.captures(text)?;
+    let size: f64 = cap[1].replace(',', "").parse().ok()?;
+    let multiplier = match cap[2].to_ascii_uppercase().as_str() {
+        "TB" => 1u64 << 40,
+        "GB" => 1 << 30,
+        "MB" => 1 << 20,
+        "KB" => 1 << 10,
+        _ => 1,
+    };
+    Some((size * multiplier as f64).round() as u64)
+}
+
+/// Decode the elements of the JSON array sent in `body` as its chunks arrive.
+///
+/// Large api responses (hundreds of products) are much bigger once decoded than as text, so
+/// only the bytes of the element being received are kept and each element is yielded as soon
+/// as it is complete: memory stays flat whatever the size of the array. An element which
+/// fails to decode is yielded as an error without stopping the stream.
+///
+/// # Errors
+/// Yields [`DlsiteError::Parse`] if `body` is not a JSON array or ends before the array, and
+/// [`DlsiteError::SerdeJson`] if an element is not valid JSON. The stream ends after them.
+pub(crate) fn decode_json_array<T: serde::de::DeserializeOwned + 'static>(
+    body: BodyStream,
+) -> impl Stream<Item = Result<T>> + MaybeSend + 'static {
+    futures::stream::unfold(
+        (body, JsonArrayDecoder::<T>::new()),
+        |(mut body, mut decoder)| async move {
+            loop {
+                if let Some(item) = decoder.next_item() {
+                    return Some((item, (body, decoder)));
+                }
+                if matches!(decoder.state, ArrayState::End | ArrayState::Failed) {
+                    return None;
+                }
+                let err = match body.next().await {
+                    Some(Ok(chunk)) => {
+                        decoder.buf.extend_from_slice(&chunk);
+                        continue;
+                    }
+                    Some(Err(e)) => e,
+                    None => DlsiteError::Parse("Unexpected end of JSON array".to_string()),
+                };
+                decoder.state = ArrayState::Failed;
+                return Some((Err(err), (body, decoder)));
+            }
+        },
+    )
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ArrayState {
+    /// Before the opening `[`
+    Start,
+    Elements,
+    /// After the closing `]`
+    End,
+    Failed,
+}
+
+/// Incremental decoder of [`decode_json_array`]. `buf` holds the bytes received after the
+/// last decoded element.
+struct JsonArrayDecoder<T> {
+    buf: Vec<u8>,
+    state: ArrayState,
+    _item: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T: serde::de::DeserializeOwned> JsonArrayDecoder<T> {
+    fn new() -> Self {
+        Self {
+            buf: vec![],
+            state: ArrayState::Start,
+            _item: std::marker::PhantomData,
+        }
+    }
+
+    /// Next complete element of `buf`, `None` if more bytes are needed or the array ended.
+    fn next_item(&mut self) -> Option<Result<T>> {
+        loop {
+            match self.state {
+                ArrayState::End | ArrayState::Failed => return None,
+                ArrayState::Start => {
+                    let start = self.buf.iter().position(|b| !b.is_ascii_whitespace())?;
+                    if self.buf[start] != b'[' {
+                        self.state = ArrayState::Failed;
+                        let err = DlsiteError::Parse("Expected a JSON array".to_string());
+                        return Some(Err(err));
+                    }
+                    self.buf.drain(..=start);
+                    self.state = ArrayState::Elements;
+                }
+                ArrayState::Elements => {
+                    // Skip the separator before the element
+                    let Some(start) = self
+                        .buf
+                        .iter()
+                        .position(|b| !b.is_ascii_whitespace() && *b != b',')
+                    else {
+                        self.buf.clear();
+                        return None;
+                    };
+                    if self.buf[start] == b']' {
+                        self.state = ArrayState::End;
+                        self.buf = vec![];
+                        return None;
+                    }
+                    self.buf.drain(..start);
+
+                    // Find the end of the element without decoding it, so that an element of
+                    // the wrong type is skipped rather than stopping the stream
+                    let mut values = serde_json::Deserializer::from_slice(&self.buf)
+                        .into_iter::<serde::de::IgnoredAny>();
+                    let end = match values.next()? {
+                        // A number at the end of `buf` may continue in the next chunk
+                        Ok(_) if values.byte_offset() == self.buf.len() => return None,
+                        Ok(_) => values.byte_offset(),
+                        Err(e) if e.is_eof() => return None,
+                        Err(e) => {
+                            self.state = ArrayState::Failed;
+                            return Some(Err(e.into()));
+                        }
+                    };
+                    let item = serde_json::from_slice(&self.buf[..end]).map_err(Into::into);
+                    self.buf.drain(..end);
+                    return Some(item);
+                }
+            }
+        }
+    }
+}
+
+/// Whether `host` is `domain` or one of its subdomains. `evildlsite.com` is not in
+/// `dlsite.com`.
+pub(crate) fn is_in_domain(host: &str, domain: &str) -> bool {
+    host.strip_suffix(domain)
+        .is_some_and(|rest| rest.is_empty() || rest.ends_with('.'))
+}
+
+/// Strip scripts, event handlers, iframes and third-party images (trackers) from scraped
+/// rich text such as product descriptions, keeping basic formatting (paragraphs, line
+/// breaks, emphasis, lists, links and images hosted on DLsite).
+///
+/// Protocol-relative URLs are rewritten to https, so the result can be rendered in a webview
+/// as is.
+///
+/// # Example
+/// ```
+/// use dlsite_gamebox::sanitize_html;
+///
+/// let html = r#"<p onclick="x()">Hello<script>alert(1)</script><br><b>world</b></p>"#;
+/// assert_eq!(sanitize_html(html), "<p>Hello<br><b>world</b></p>");
+/// ```
+#[cfg(feature = "sanitize")]
+pub fn sanitize_html(html: &str) -> String {
+    use std::borrow::Cow;
+
+    fn is_dlsite_host(url: &str) -> bool {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .is_some_and(|host| {
+                is_in_domain(&host, "dlsite.jp") || is_in_domain(&host, "dlsite.com")
+            })
+    }
+
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .attribute_filter(|element, attribute, value| {
+            let value = match value.strip_prefix("//") {
+                Some(rest) if matches!(attribute, "src" | "href") => {
+                    Cow::Owned(format!("https://{}", rest))
+                }
+                _ => Cow::Borrowed(value),
+            };
+            if element == "img" && attribute == "src" && !is_dlsite_host(&value) {
+                return None;
+            }
+            Some(Cow::Owned(value.into_owned()))
+        })
+        .clean(html)
+        .to_string()
+}
+
+#[cfg(all(test, feature = "sanitize"))]
+mod tests {
+    use super::sanitize_html;
+
+    #[test]
+    fn sanitize_description() {
+        let html = r#"<div class="work_parts_area">
+<p>説明文<br><strong>強調</strong></p>
+<script>track()</script>
+<img src="//img.dlsite.jp/modpub/images2/parts/RJ403038/img1.jpg">
+<img src="https://tracker.example.com/pixel.gif" width="1" height="1">
+<img src="https://evildlsite.com/pixel.gif">
+<a href="https://www.dlsite.com/maniax/" onclick="evil()">link</a>
+<iframe src="https://example.com"></iframe>
+</div>"#;
+        let clean = sanitize_html(html);
+        assert!(clean.contains("<p>説明文<br><strong>強調</strong></p>"));
+        assert!(!clean.contains("script"));
+        assert!(!clean.contains("iframe"));
+        assert!(!clean.contains("onclick"));
+        assert!(!clean.contains("tracker.example.com"));
+        assert!(!clean.contains("evildlsite.com"));
+        assert!(clean.contains(r#"src="https://img.dlsite.jp/modpub/images2/parts/RJ403038/img1.jpg""#));
+        assert!(clean.contains(r#"rel="noopener noreferrer nofollow""#));
+    }
+}
+
+#[cfg(test)]
+mod domain_tests {
+    use super::is_in_domain;
+
+    #[test]
+    fn in_domain() {
+        assert!(is_in_domain("dlsite.com", "dlsite.com"));
+        assert!(is_in_domain("www.dlsite.com", "dlsite.com"));
+        assert!(is_in_domain("img.dlsite.jp", "dlsite.jp"));
+        assert!(!is_in_domain("evildlsite.com", "dlsite.com"));
+        assert!(!is_in_domain("dlsite.com.example.com", "dlsite.com"));
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use futures::StreamExt as _;
+
+    use super::decode_json_array;
+    use crate::transport::BodyStream;
+
+    fn body(chunks: &[&str]) -> BodyStream {
+        let chunks: Vec<_> = chunks.iter().map(|c| Ok(c.as_bytes().to_vec())).collect();
+        Box::pin(futures::stream::iter(chunks))
+    }
+
+    async fn decode<T: serde::de::DeserializeOwned + 'static>(
+        chunks: &[&str],
+    ) -> Vec<crate::error::Result<T>> {
+        decode_json_array(body(chunks)).collect().await
+    }
+
+    #[tokio::test]
+    async fn json_array_stream() {
+        // Elements split across chunks, with brackets and quotes in strings
+        let items = decode::<serde_json::Value>(&[
+            r#" [ {"a": 1}, {"a""#,
+            r#": "x]\"}"} ,"#,
+            r#"{"a":3}] "#,
+        ])
+        .await;
+        let items: Vec<_> = items.into_iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1]["a"], "x]\"}");
+        assert_eq!(items[2]["a"], 3);
+
+        assert!(decode::<u32>(&["[", "]"]).await.is_empty());
+        let items = decode::<u32>(&["{}"]).await;
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+
+        // An invalid element doesn't stop the stream
+        let items = decode::<u32>(&["[1, \"x\", 3]"]).await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(*items[0].as_ref().unwrap(), 1);
+        assert!(items[1].is_err());
+        assert_eq!(*items[2].as_ref().unwrap(), 3);
+
+        // Truncated body
+        let items = decode::<u32>(&["[1, 2"]).await;
+        assert_eq!(items.len(), 2);
+        assert!(items[1].is_err());
+
+        // Numbers split across chunks
+        let items = decode::<u32>(&["[1", "2, 3", "4]"]).await;
+        let items: Vec<_> = items.into_iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(items, vec![12, 34]);
+
+        // Invalid JSON stops the stream
+        let items = decode::<u32>(&["[1, }, 3]"]).await;
+        assert_eq!(items.len(), 2);
+        assert!(items[1].is_err());
+    }
+}
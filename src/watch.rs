@@ -0,0 +1,941 @@
+//! Watch works and searches, and get notified when they change.
+//!
+//! A [`Watcher`] holds a set of [`WatchRule`]s and the state of the watched works and searches
+//! at the last poll. [`Watcher::poll`] fetches them again and returns the [`WatchEvent`]s fired
+//! since then; [`Watcher::stream`] and [`Watcher::run`] do so periodically. The state is saved
+//! with [`crate::persist`], so events are not fired twice across restarts.
+//!
+//! [`WatchRule::release`] follows an announced work until its release, and keeps the
+//! [`Watcher::timeline`] of its state changes (release date, price, preorder discount), e.g.
+//! for a release calendar.
+//!
+//! # Example
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use dlsite_gamebox::{
+//!     client::search::SearchProductQuery,
+//!     interface::query::Order,
+//!     shutdown::Shutdown,
+//!     watch::{WatchEvent, WatchRule, Watcher},
+//!     DlsiteClient,
+//! };
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = DlsiteClient::default();
+//!     let mut watcher = Watcher::load("watcher.json").unwrap();
+//!     watcher.add(WatchRule::sales_milestones("RJ403038"));
+//!     watcher.add(WatchRule::price_drop("RJ403038"));
+//!     watcher.add(WatchRule::query(
+//!         "asmr",
+//!         &SearchProductQuery {
+//!             keyword: Some("ASMR".to_string()),
+//!             order: Some(Order::Release),
+//!             ..Default::default()
+//!         },
+//!     ));
+//!     watcher
+//!         .run(&client, "watcher.json", Duration::from_secs(3600), &Shutdown::new(), |event| {
+//!             match event {
+//!                 WatchEvent::SalesMilestone { product_id, milestone, delta, .. } => {
+//!                     println!("{product_id} passed {milestone} downloads (+{delta})")
+//!                 }
+//!                 WatchEvent::NewWork { query, product_id, title, .. } => {
+//!                     println!("New work for {query}: {title} ({product_id})")
+//!                 }
+//!                 event => println!("{event:?}"),
+//!             }
+//!         })
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    path::Path,
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::Stream;
+
+use crate::{
+    client::{product::ajax::ProductAjax, search::SearchProductQuery},
+    error::Result,
+    interface::product_id::ProductId,
+    persist::{self, Persisted},
+    shutdown::Shutdown,
+    DlsiteClient,
+};
+
+/// Milestones used by [`WatchRule::sales_milestones`].
+pub const DEFAULT_MILESTONES: [i64; 3] = [1_000, 10_000, 100_000];
+
+/// Condition on a watched work or search.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatchRule {
+    /// Fire [`WatchEvent::SalesMilestone`] when the download count of a work crosses one of
+    /// `milestones`.
+    SalesMilestones {
+        product_id: ProductId,
+        milestones: Vec<i64>,
+    },
+    /// Fire [`WatchEvent::PriceDrop`] when the price of a work goes down.
+    PriceDrop { product_id: ProductId },
+    /// Fire [`WatchEvent::Delisted`] when a work is no longer on sale.
+    Delisting { product_id: ProductId },
+    /// Fire [`WatchEvent::Release`] at each state change of an announced work until it is
+    /// released, and keep its [`Watcher::timeline`].
+    Release { product_id: ProductId },
+    /// Fire [`WatchEvent::NewWork`] when a work appears in the first page of a search.
+    Query {
+        /// Name of the search, given back in the events
+        name: String,
+        /// Path of the search, see [`SearchProductQuery::to_path`]
+        query_path: String,
+    },
+}
+
+impl WatchRule {
+    /// Watch the download count of a work for [`DEFAULT_MILESTONES`].
+    pub fn sales_milestones(product_id: impl Into<ProductId>) -> Self {
+        Self::sales_milestones_at(product_id, &DEFAULT_MILESTONES)
+    }
+
+    /// Watch the download count of a work for the given milestones.
+    pub fn sales_milestones_at(product_id: impl Into<ProductId>, milestones: &[i64]) -> Self {
+        let mut milestones = milestones.to_vec();
+        milestones.sort_unstable();
+        milestones.dedup();
+        WatchRule::SalesMilestones {
+            product_id: product_id.into(),
+            milestones,
+        }
+    }
+
+    /// Watch the price of a work.
+    pub fn price_drop(product_id: impl Into<ProductId>) -> Self {
+        WatchRule::PriceDrop {
+            product_id: product_id.into(),
+        }
+    }
+
+    /// Watch a work being taken down.
+    pub fn delisting(product_id: impl Into<ProductId>) -> Self {
+        WatchRule::Delisting {
+            product_id: product_id.into(),
+        }
+    }
+
+    /// Watch an announced work until its release.
+    pub fn release(product_id: impl Into<ProductId>) -> Self {
+        WatchRule::Release {
+            product_id: product_id.into(),
+        }
+    }
+
+    /// Watch the works matching a search. Only the first page is fetched at each poll, so
+    /// the search should be sorted by release date ([`crate::interface::query::Order::Release`]).
+    pub fn query(name: impl Into<String>, query: &SearchProductQuery) -> Self {
+        WatchRule::Query {
+            name: name.into(),
+            query_path: query.to_path(),
+        }
+    }
+
+    /// Work the rule applies to, `None` for searches.
+    pub fn product_id(&self) -> Option<&ProductId> {
+        match self {
+            WatchRule::SalesMilestones { product_id, .. }
+            | WatchRule::PriceDrop { product_id }
+            | WatchRule::Delisting { product_id }
+            | WatchRule::Release { product_id } => Some(product_id),
+            WatchRule::Query { .. } => None,
+        }
+    }
+}
+
+/// Change detected by a [`Watcher`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatchEvent {
+    /// The download count of a work reached a milestone. Crossing several milestones at once
+    /// fires one event per milestone.
+    SalesMilestone {
+        product_id: ProductId,
+        milestone: i64,
+        dl_count: i64,
+        /// Downloads since the previous poll
+        delta: i64,
+        at: DateTime<Utc>,
+    },
+    /// The price of a work went down.
+    PriceDrop {
+        product_id: ProductId,
+        previous_price: i64,
+        price: i64,
+        at: DateTime<Utc>,
+    },
+    /// A work on sale at the previous poll is not found anymore.
+    Delisted {
+        product_id: ProductId,
+        at: DateTime<Utc>,
+    },
+    /// A work appeared in a watched search.
+    NewWork {
+        /// Name of the [`WatchRule::Query`]
+        query: String,
+        product_id: ProductId,
+        title: String,
+        at: DateTime<Utc>,
+    },
+    /// The state of a work watched with [`WatchRule::Release`] changed.
+    Release {
+        product_id: ProductId,
+        transition: ReleaseTransition,
+        at: DateTime<Utc>,
+    },
+}
+
+/// State of a work before its release, as compared by [`WatchRule::Release`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReleaseStatus {
+    /// Whether the work is announced and not released yet
+    pub announced: bool,
+    /// Planned release date while announced, release date after
+    pub release_date: Option<NaiveDate>,
+    /// List price, before discounts
+    pub price: i64,
+    /// Discount rate in percent, `None` without discount
+    pub discount_rate: Option<i64>,
+}
+
+impl From<&ProductAjax> for ReleaseStatus {
+    fn from(ajax: &ProductAjax) -> Self {
+        Self {
+            announced: ajax.is_ana,
+            release_date: ajax
+                .regist_date
+                .get(..10)
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()),
+            price: i64::from(ajax.official_price),
+            discount_rate: ajax.discount_rate.filter(|&rate| rate > 0).map(i64::from),
+        }
+    }
+}
+
+/// Change in the [`ReleaseStatus`] of a work.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReleaseTransition {
+    /// The work was first seen announced. Only recorded in the timeline.
+    Announced {
+        release_date: Option<NaiveDate>,
+        price: i64,
+    },
+    /// The planned release date was set or moved.
+    ReleaseDateChanged {
+        previous: Option<NaiveDate>,
+        release_date: Option<NaiveDate>,
+    },
+    /// The price was set or changed before the release.
+    PriceChanged { previous: i64, price: i64 },
+    /// A preorder discount appeared.
+    PreorderDiscount { rate: i64 },
+    /// The work is released.
+    Released { release_date: Option<NaiveDate> },
+}
+
+/// Entry of a [`Watcher::timeline`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TimelineEntry {
+    pub at: DateTime<Utc>,
+    pub transition: ReleaseTransition,
+}
+
+/// State of a watched work at the last poll.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WorkSnapshot {
+    pub at: DateTime<Utc>,
+    pub dl_count: i64,
+}
+
+/// Rules and the state they are evaluated against, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Watcher {
+    rules: Vec<WatchRule>,
+    snapshots: BTreeMap<ProductId, WorkSnapshot>,
+    #[serde(default)]
+    prices: BTreeMap<ProductId, i64>,
+    /// Works found on sale at the last poll
+    #[serde(default)]
+    listed: BTreeSet<ProductId>,
+    /// Works seen in each search, by name
+    #[serde(default)]
+    seen: BTreeMap<String, BTreeSet<ProductId>>,
+    /// Events fired but not handed out yet
+    #[serde(default)]
+    pending: VecDeque<WatchEvent>,
+    /// Status of the works watched with [`WatchRule::Release`] at the last poll
+    #[serde(default)]
+    releases: BTreeMap<ProductId, ReleaseStatus>,
+    #[serde(default)]
+    timelines: BTreeMap<ProductId, Vec<TimelineEntry>>,
+}
+
+impl Persisted for Watcher {
+    const KIND: &'static str = "watcher";
+    const VERSION: u32 = 1;
+}
+
+/// Result of fetching a watched work or search, applied to the state once every fetch is done.
+enum Observation {
+    Work {
+        product_id: ProductId,
+        dl_count: Option<i64>,
+        price: i64,
+        release: ReleaseStatus,
+    },
+    Missing(ProductId),
+    Query {
+        name: String,
+        works: Vec<(ProductId, String)>,
+    },
+}
+
+impl Watcher {
+    /// Load the watcher saved at `path`, or an empty one if the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(persist::load(path)?.unwrap_or_default())
+    }
+
+    /// Save the rules and state to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        persist::save(path, self)
+    }
+
+    /// Add a rule. Does nothing if the same rule is already set.
+    pub fn add(&mut self, rule: WatchRule) {
+        if !self.rules.contains(&rule) {
+            self.rules.push(rule);
+        }
+    }
+
+    /// Remove every rule of a work and forget its state.
+    pub fn remove(&mut self, product_id: impl Into<ProductId>) {
+        let product_id = product_id.into();
+        self.rules
+            .retain(|rule| rule.product_id() != Some(&product_id));
+        self.snapshots.remove(&product_id);
+        self.prices.remove(&product_id);
+        self.listed.remove(&product_id);
+        self.releases.remove(&product_id);
+        self.timelines.remove(&product_id);
+    }
+
+    /// Remove a search and forget the works seen in it.
+    pub fn remove_query(&mut self, name: &str) {
+        self.rules
+            .retain(|rule| !matches!(rule, WatchRule::Query { name: n, .. } if n == name));
+        self.seen.remove(name);
+    }
+
+    /// Current rules.
+    pub fn rules(&self) -> &[WatchRule] {
+        &self.rules
+    }
+
+    /// State of a work at the last poll.
+    pub fn snapshot(&self, product_id: impl Into<ProductId>) -> Option<&WorkSnapshot> {
+        self.snapshots.get(&product_id.into())
+    }
+
+    /// State changes of a work watched with [`WatchRule::Release`], oldest first.
+    pub fn timeline(&self, product_id: impl Into<ProductId>) -> &[TimelineEntry] {
+        self.timelines
+            .get(&product_id.into())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn rules_of<'s>(&'s self, product_id: &'s ProductId) -> impl Iterator<Item = &'s WatchRule> {
+        self.rules
+            .iter()
+            .filter(move |r| r.product_id() == Some(product_id))
+    }
+
+    /// Evaluate the rules of a work against its current download count, and remember it for
+    /// the next poll.
+    ///
+    /// The first observation of a work only records it: milestones already passed when a
+    /// work starts being watched don't fire.
+    pub fn observe(
+        &mut self,
+        product_id: impl Into<ProductId>,
+        at: DateTime<Utc>,
+        dl_count: i64,
+    ) -> Vec<WatchEvent> {
+        let product_id = product_id.into();
+        let mut events = vec![];
+        if let Some(previous) = self.snapshots.get(&product_id) {
+            for rule in self.rules_of(&product_id) {
+                if let WatchRule::SalesMilestones { milestones, .. } = rule {
+                    events.extend(
+                        milestones
+                            .iter()
+                            .filter(|&&m| previous.dl_count < m && m <= dl_count)
+                            .map(|&milestone| WatchEvent::SalesMilestone {
+                                product_id: product_id.clone(),
+                                milestone,
+                                dl_count,
+                                delta: dl_count - previous.dl_count,
+                                at,
+                            }),
+                    );
+                }
+            }
+        }
+        self.snapshots
+            .insert(product_id, WorkSnapshot { at, dl_count });
+        events
+    }
+
+    /// Evaluate the rules of a work against its current price, and remember it for the next
+    /// poll. The work is also remembered as on sale for [`WatchRule::Delisting`].
+    pub fn observe_price(
+        &mut self,
+        product_id: impl Into<ProductId>,
+        at: DateTime<Utc>,
+        price: i64,
+    ) -> Vec<WatchEvent> {
+        let product_id = product_id.into();
+        let mut events = vec![];
+        if let Some(&previous_price) = self.prices.get(&product_id) {
+            let watched = self
+                .rules_of(&product_id)
+                .any(|r| matches!(r, WatchRule::PriceDrop { .. }));
+            if watched && price < previous_price {
+                events.push(WatchEvent::PriceDrop {
+                    product_id: product_id.clone(),
+                    previous_price,
+                    price,
+                    at,
+                });
+            }
+        }
+        self.listed.insert(product_id.clone());
+        self.prices.insert(product_id, price);
+        events
+    }
+
+    /// Record that a work is not found anymore. Fires [`WatchEvent::Delisted`] once, if the
+    /// work was on sale at the previous poll.
+    pub fn observe_delisted(
+        &mut self,
+        product_id: impl Into<ProductId>,
+        at: DateTime<Utc>,
+    ) -> Vec<WatchEvent> {
+        let product_id = product_id.into();
+        let watched = self
+            .rules_of(&product_id)
+            .any(|r| matches!(r, WatchRule::Delisting { .. }));
+        if self.listed.remove(&product_id) && watched {
+            vec![WatchEvent::Delisted { product_id, at }]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Compare the status of a work watched with [`WatchRule::Release`] with the previous one,
+    /// add the transitions to its timeline and fire them. Does nothing for other works.
+    ///
+    /// The first observation only starts the timeline, with [`ReleaseTransition::Announced`]
+    /// if the work is announced. Once the work is released, it isn't followed anymore.
+    pub fn observe_release(
+        &mut self,
+        product_id: impl Into<ProductId>,
+        at: DateTime<Utc>,
+        status: ReleaseStatus,
+    ) -> Vec<WatchEvent> {
+        let product_id = product_id.into();
+        if !self
+            .rules_of(&product_id)
+            .any(|r| matches!(r, WatchRule::Release { .. }))
+        {
+            return vec![];
+        }
+        let transitions = match self.releases.get(&product_id) {
+            None if status.announced => {
+                self.timelines
+                    .entry(product_id.clone())
+                    .or_default()
+                    .push(TimelineEntry {
+                        at,
+                        transition: ReleaseTransition::Announced {
+                            release_date: status.release_date,
+                            price: status.price,
+                        },
+                    });
+                vec![]
+            }
+            Some(previous) if previous.announced => release_transitions(previous, &status),
+            _ => vec![],
+        };
+        self.releases.insert(product_id.clone(), status);
+
+        let timeline = self.timelines.entry(product_id.clone()).or_default();
+        transitions
+            .into_iter()
+            .map(|transition| {
+                timeline.push(TimelineEntry {
+                    at,
+                    transition: transition.clone(),
+                });
+                WatchEvent::Release {
+                    product_id: product_id.clone(),
+                    transition,
+                    at,
+                }
+            })
+            .collect()
+    }
+
+    /// Evaluate a search against the works it currently returns (IDs and titles), and
+    /// remember them.
+    ///
+    /// The first observation of a search only records it: works already listed when a search
+    /// starts being watched don't fire.
+    pub fn observe_query(
+        &mut self,
+        name: &str,
+        at: DateTime<Utc>,
+        works: impl IntoIterator<Item = (ProductId, String)>,
+    ) -> Vec<WatchEvent> {
+        let first = !self.seen.contains_key(name);
+        let seen = self.seen.entry(name.to_string()).or_default();
+        let mut events = vec![];
+        for (product_id, title) in works {
+            if seen.insert(product_id.clone()) && !first {
+                events.push(WatchEvent::NewWork {
+                    query: name.to_string(),
+                    product_id,
+                    title,
+                    at,
+                });
+            }
+        }
+        events
+    }
+
+    /// Fetch every watched work and search. Doesn't touch the state, so it can be cancelled
+    /// at any point.
+    async fn fetch(&self, client: &DlsiteClient) -> Vec<Observation> {
+        let mut product_ids: Vec<ProductId> = self
+            .rules
+            .iter()
+            .filter_map(|r| r.product_id().cloned())
+            .collect();
+        product_ids.sort();
+        product_ids.dedup();
+
+        let mut observations = vec![];
+        for product_id in product_ids {
+            match client.product().get_ajax(&product_id).await {
+                Ok(ajax) => observations.push(Observation::Work {
+                    product_id,
+                    dl_count: ajax.dl_count.map(i64::from),
+                    price: i64::from(ajax.price),
+                    release: ReleaseStatus::from(&ajax),
+                }),
+                Err(e) if e.is_not_found() => observations.push(Observation::Missing(product_id)),
+                Err(e) => tracing::warn!("Failed to get {product_id}: {e}"),
+            }
+        }
+        for rule in &self.rules {
+            let WatchRule::Query { name, query_path } = rule else {
+                continue;
+            };
+            match client.search().search_fresh(query_path).await {
+                Ok(items) => observations.push(Observation::Query {
+                    name: name.clone(),
+                    works: items
+                        .into_iter()
+                        .map(|item| (ProductId::from(item.id), item.title))
+                        .collect(),
+                }),
+                Err(e) => tracing::warn!("Failed to search {name}: {e}"),
+            }
+        }
+        observations
+    }
+
+    /// Apply fetched observations and queue the events they fire.
+    fn apply(&mut self, observations: Vec<Observation>, at: DateTime<Utc>) {
+        for observation in observations {
+            let events = match observation {
+                Observation::Work {
+                    product_id,
+                    dl_count,
+                    price,
+                    release,
+                } => {
+                    let mut events = self.observe_price(product_id.clone(), at, price);
+                    events.extend(self.observe_release(product_id.clone(), at, release));
+                    match dl_count {
+                        Some(dl_count) => events.extend(self.observe(product_id, at, dl_count)),
+                        // Not on sale yet, or the circle hides it
+                        None => tracing::debug!("{product_id} has no download count"),
+                    }
+                    events
+                }
+                Observation::Missing(product_id) => self.observe_delisted(product_id, at),
+                Observation::Query { name, works } => self.observe_query(&name, at, works),
+            };
+            self.pending.extend(events);
+        }
+    }
+
+    /// Fetch every watched work and search, and return the events fired since the last poll.
+    ///
+    /// Works and searches which fail to load are logged and skipped; they are compared with
+    /// their last known state at the next poll. The state is only updated once everything is
+    /// fetched, so cancelling a poll loses nothing.
+    pub async fn poll(&mut self, client: &DlsiteClient) -> Vec<WatchEvent> {
+        let observations = self.fetch(client).await;
+        self.apply(observations, Utc::now());
+        self.pending.drain(..).collect()
+    }
+
+    /// Poll every `interval` and yield the events as they fire. The first poll is immediate.
+    ///
+    /// The stream never ends. It is cancel-safe: dropping it at any point loses no event, as
+    /// events not yielded yet are kept in the watcher (and saved with it) and given first by
+    /// the next [`Watcher::poll`] or stream.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::{pin::pin, time::Duration};
+    ///
+    /// use dlsite_gamebox::{watch::{WatchRule, Watcher}, DlsiteClient};
+    /// use futures::StreamExt as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = DlsiteClient::default();
+    ///     let mut watcher = Watcher::load("watcher.json").unwrap();
+    ///     watcher.add(WatchRule::delisting("RJ403038"));
+    ///     {
+    ///         let mut events = pin!(watcher.stream(&client, Duration::from_secs(600)));
+    ///         while let Some(event) = events.next().await {
+    ///             println!("{event:?}");
+    ///         }
+    ///     }
+    ///     watcher.save("watcher.json").unwrap();
+    /// }
+    /// ```
+    pub fn stream<'w>(
+        &'w mut self,
+        client: &'w DlsiteClient,
+        interval: Duration,
+    ) -> impl Stream<Item = WatchEvent> + 'w {
+        let ticker = crate::runtime::interval(interval);
+        futures::stream::unfold((self, ticker), move |(watcher, mut ticker)| async move {
+            loop {
+                if let Some(event) = watcher.pending.pop_front() {
+                    return Some((event, (watcher, ticker)));
+                }
+                ticker.tick().await;
+                let observations = watcher.fetch(client).await;
+                watcher.apply(observations, Utc::now());
+            }
+        })
+    }
+
+    /// Poll every `interval`, pass the events to `on_event` and save the state to `path`
+    /// after each poll.
+    ///
+    /// Runs until saving fails or `shutdown` is triggered, in which case the state is saved
+    /// one last time.
+    pub async fn run<F>(
+        &mut self,
+        client: &DlsiteClient,
+        path: impl AsRef<Path>,
+        interval: Duration,
+        shutdown: &Shutdown,
+        mut on_event: F,
+    ) -> Result<()>
+    where
+        F: FnMut(WatchEvent),
+    {
+        let mut signal = shutdown.signal();
+        let mut ticker = crate::runtime::interval(interval);
+        loop {
+            tokio::select! {
+                biased;
+                _ = signal.triggered() => return self.save(path.as_ref()),
+                _ = ticker.tick() => {}
+            }
+            for event in self.poll(client).await {
+                on_event(event);
+            }
+            self.save(path.as_ref())?;
+        }
+    }
+}
+
+/// Transitions from `previous`, an announced status, to `status`.
+fn release_transitions(previous: &ReleaseStatus, status: &ReleaseStatus) -> Vec<ReleaseTransition> {
+    let mut transitions = vec![];
+    if status.release_date != previous.release_date && status.announced {
+        transitions.push(ReleaseTransition::ReleaseDateChanged {
+            previous: previous.release_date,
+            release_date: status.release_date,
+        });
+    }
+    if status.price != previous.price {
+        transitions.push(ReleaseTransition::PriceChanged {
+            previous: previous.price,
+            price: status.price,
+        });
+    }
+    if let (None, Some(rate), true) = (
+        previous.discount_rate,
+        status.discount_rate,
+        status.announced,
+    ) {
+        transitions.push(ReleaseTransition::PreorderDiscount { rate });
+    }
+    if !status.announced {
+        transitions.push(ReleaseTransition::Released {
+            release_date: status.release_date,
+        });
+    }
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Days, NaiveDate, Utc};
+
+    use super::{ReleaseStatus, ReleaseTransition, WatchEvent, WatchRule, Watcher};
+    use crate::interface::product_id::ProductId;
+
+    #[test]
+    fn sales_milestones() {
+        let start: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let mut watcher = Watcher::default();
+        watcher.add(WatchRule::sales_milestones("RJ403038"));
+        watcher.add(WatchRule::sales_milestones("RJ403038"));
+        assert_eq!(watcher.rules().len(), 1);
+
+        // Milestones passed before watching don't fire
+        assert!(watcher.observe("RJ403038", start, 1_500).is_empty());
+        assert!(watcher
+            .observe("RJ403038", start + Days::new(1), 9_000)
+            .is_empty());
+
+        let at = start + Days::new(2);
+        let events = watcher.observe("RJ403038", at, 120_000);
+        let milestones: Vec<(i64, i64)> = events
+            .iter()
+            .filter_map(|e| match e {
+                WatchEvent::SalesMilestone {
+                    milestone, delta, ..
+                } => Some((*milestone, *delta)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(milestones, vec![(10_000, 111_000), (100_000, 111_000)]);
+        assert_eq!(watcher.snapshot("rj403038").unwrap().dl_count, 120_000);
+
+        // Works without rules are only recorded
+        assert!(watcher.observe("RJ01014447", at, 0).is_empty());
+        assert!(watcher
+            .observe("RJ01014447", at + Days::new(1), 5_000)
+            .is_empty());
+
+        watcher.remove("RJ403038");
+        assert!(watcher.rules().is_empty());
+        assert!(watcher.snapshot("RJ403038").is_none());
+    }
+
+    #[test]
+    fn price_drop_and_delisting() {
+        let at: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let mut watcher = Watcher::default();
+        watcher.add(WatchRule::price_drop("RJ403038"));
+        watcher.add(WatchRule::delisting("RJ403038"));
+
+        // Delisted works never seen on sale don't fire
+        assert!(watcher.observe_delisted("RJ01014447", at).is_empty());
+
+        assert!(watcher.observe_price("RJ403038", at, 1_100).is_empty());
+        assert!(watcher.observe_price("RJ403038", at, 1_320).is_empty());
+        assert_eq!(
+            watcher.observe_price("RJ403038", at, 660),
+            vec![WatchEvent::PriceDrop {
+                product_id: "RJ403038".into(),
+                previous_price: 1_320,
+                price: 660,
+                at,
+            }]
+        );
+        assert!(watcher.observe_price("RJ403038", at, 660).is_empty());
+
+        assert_eq!(
+            watcher.observe_delisted("RJ403038", at),
+            vec![WatchEvent::Delisted {
+                product_id: "RJ403038".into(),
+                at,
+            }]
+        );
+        assert!(watcher.observe_delisted("RJ403038", at).is_empty());
+    }
+
+    #[test]
+    fn new_works() {
+        let at: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let work = |id: &str| (ProductId::from(id), format!("title of {id}"));
+        let mut watcher = Watcher::default();
+
+        // Works listed when the search starts being watched don't fire
+        assert!(watcher
+            .observe_query("asmr", at, [work("RJ01000001"), work("RJ01000002")])
+            .is_empty());
+        let events = watcher.observe_query("asmr", at, [work("RJ01000003"), work("RJ01000001")]);
+        assert_eq!(
+            events,
+            vec![WatchEvent::NewWork {
+                query: "asmr".to_string(),
+                product_id: "RJ01000003".into(),
+                title: "title of RJ01000003".to_string(),
+                at,
+            }]
+        );
+        assert!(watcher
+            .observe_query("asmr", at, [work("RJ01000003")])
+            .is_empty());
+
+        // Each search has its own state
+        assert!(watcher
+            .observe_query("rpg", at, [work("RJ01000003")])
+            .is_empty());
+    }
+
+    #[test]
+    fn release_timeline() {
+        let start: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let date = |d: &str| Some(d.parse::<NaiveDate>().unwrap());
+        let announced = ReleaseStatus {
+            announced: true,
+            release_date: date("2024-07-01"),
+            price: 1_100,
+            discount_rate: None,
+        };
+        let mut watcher = Watcher::default();
+
+        // Works without the rule are not followed
+        assert!(watcher
+            .observe_release("RJ01000001", start, announced.clone())
+            .is_empty());
+        assert!(watcher.timeline("RJ01000001").is_empty());
+
+        watcher.add(WatchRule::release("RJ01000001"));
+        assert!(watcher
+            .observe_release("RJ01000001", start, announced.clone())
+            .is_empty());
+        assert!(watcher
+            .observe_release("RJ01000001", start + Days::new(1), announced.clone())
+            .is_empty());
+
+        let at = start + Days::new(2);
+        let delayed = ReleaseStatus {
+            release_date: date("2024-07-15"),
+            price: 1_320,
+            discount_rate: Some(20),
+            ..announced.clone()
+        };
+        let events = watcher.observe_release("RJ01000001", at, delayed.clone());
+        assert_eq!(
+            events,
+            vec![
+                WatchEvent::Release {
+                    product_id: "RJ01000001".into(),
+                    transition: ReleaseTransition::ReleaseDateChanged {
+                        previous: date("2024-07-01"),
+                        release_date: date("2024-07-15"),
+                    },
+                    at,
+                },
+                WatchEvent::Release {
+                    product_id: "RJ01000001".into(),
+                    transition: ReleaseTransition::PriceChanged {
+                        previous: 1_100,
+                        price: 1_320,
+                    },
+                    at,
+                },
+                WatchEvent::Release {
+                    product_id: "RJ01000001".into(),
+                    transition: ReleaseTransition::PreorderDiscount { rate: 20 },
+                    at,
+                },
+            ]
+        );
+
+        let released = ReleaseStatus {
+            announced: false,
+            ..delayed
+        };
+        let at = start + Days::new(45);
+        assert_eq!(
+            watcher.observe_release("RJ01000001", at, released.clone()),
+            vec![WatchEvent::Release {
+                product_id: "RJ01000001".into(),
+                transition: ReleaseTransition::Released {
+                    release_date: date("2024-07-15"),
+                },
+                at,
+            }]
+        );
+        // Released works aren't followed anymore
+        let discounted = ReleaseStatus {
+            price: 660,
+            ..released
+        };
+        assert!(watcher
+            .observe_release("RJ01000001", at, discounted)
+            .is_empty());
+
+        let timeline: Vec<_> = watcher
+            .timeline("RJ01000001")
+            .iter()
+            .map(|entry| &entry.transition)
+            .collect();
+        assert_eq!(timeline.len(), 5);
+        assert_eq!(
+            timeline[0],
+            &ReleaseTransition::Announced {
+                release_date: date("2024-07-01"),
+                price: 1_100,
+            }
+        );
+        assert!(matches!(timeline[4], ReleaseTransition::Released { .. }));
+
+        watcher.remove("RJ01000001");
+        assert!(watcher.timeline("RJ01000001").is_empty());
+    }
+
+    #[test]
+    fn load_without_new_fields() {
+        let watcher: Watcher = serde_json::from_str(
+            r#"{"rules": [{"kind": "sales_milestones", "product_id": "RJ403038", "milestones": [1000]}],
+                "snapshots": {}}"#,
+        )
+        .unwrap();
+        assert_eq!(watcher.rules().len(), 1);
+    }
+}